@@ -118,6 +118,21 @@ impl DiffSummaryData {
         }
     }
 
+    /// Build a summary from precomputed file statistics
+    ///
+    /// Used when only aggregated statistics are available (e.g. task-level
+    /// change tracking); files can't be opened since there are no tool calls.
+    pub fn from_file_stats(stats: impl IntoIterator<Item = FileChangeStats>) -> Self {
+        Self {
+            files: stats
+                .into_iter()
+                .map(|stats| (stats.path.clone(), stats))
+                .collect(),
+            tool_calls: Vec::new(),
+            merged_states: HashMap::new(),
+        }
+    }
+
     /// Find or create a ToolCall for the given file path
     /// For files edited multiple times, returns a synthetic ToolCall with merged diff (initial -> final)
     /// For files edited once, returns the original ToolCall
@@ -5,12 +5,14 @@
 
 use std::{
     collections::HashMap,
+    path::PathBuf,
     rc::Rc,
     sync::{
         Arc,
         atomic::{AtomicU64, AtomicUsize, Ordering},
     },
     thread,
+    time::Duration,
 };
 
 use agent_client_protocol::{self as acp, Agent as _};
@@ -24,16 +26,36 @@ use tokio::{
 };
 
 use agentx_event_bus::{EventHub, PermissionRequestEvent, SessionUpdateEvent};
-use agentx_types::{AgentProcessConfig, ProxyConfig};
+use agentx_types::{AgentProcessConfig, CwdPolicy, NodeRuntimeConfig, ProxyConfig};
+
+use crate::nodejs::NodeRuntime;
 
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
+/// How long a probed agent may take to answer `initialize` (npx may have to
+/// download it first)
+const PROBE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Where agents whose config names no Node.js get it from, looked up each
+/// time one starts
+#[derive(Clone, Debug, Default)]
+pub struct AgentNodejs {
+    /// Folder the managed runtimes are installed into
+    pub runtimes_dir: PathBuf,
+    /// Managed runtime, used once its pinned version is installed
+    pub runtime: NodeRuntimeConfig,
+    /// Node.js set in the settings
+    pub settings_path: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct AgentManager {
     agents: Arc<RwLock<HashMap<String, Arc<AgentHandle>>>>,
     permission_store: Arc<PermissionStore>,
     event_hub: EventHub,
     proxy_config: Arc<RwLock<ProxyConfig>>,
+    /// Node.js for agents whose config does not name one
+    nodejs: Arc<RwLock<AgentNodejs>>,
 }
 
 impl AgentManager {
@@ -50,14 +72,20 @@ impl AgentManager {
             permission_store,
             event_hub,
             proxy_config: Arc::new(RwLock::new(proxy_config)),
+            nodejs: Arc::new(RwLock::new(AgentNodejs::default())),
         }
     }
 
+    /// Start the agents of `configs` in the background
+    ///
+    /// When the managed Node.js runtime still has to be installed, agents
+    /// that need Node.js start once it is, the others right away.
     pub async fn initialize(
         configs: HashMap<String, AgentProcessConfig>,
         permission_store: Arc<PermissionStore>,
         event_hub: EventHub,
         proxy_config: ProxyConfig,
+        nodejs: AgentNodejs,
     ) -> Result<Arc<Self>> {
         if configs.is_empty() {
             return Err(anyhow!("no agents defined in config"));
@@ -68,21 +96,48 @@ impl AgentManager {
             permission_store,
             event_hub,
             proxy_config,
+            nodejs: Arc::new(RwLock::new(nodejs)),
         });
         let remaining = Arc::new(AtomicUsize::new(configs.len()));
 
+        // Agents that need Node.js wait for the managed runtime, unless it is
+        // pinned to a version too old for them: they start (and fail) now
+        let mut after_install = HashMap::new();
+        let mut now = HashMap::new();
+        match manager.managed_runtime().await {
+            Some(runtime) if runtime.installed_node().is_none() => {
+                for (name, cfg) in configs {
+                    let waits = cfg.requires_nodejs()
+                        && cfg.nodejs_path.is_none()
+                        && runtime
+                            .check_required_version(&name, cfg.node_version)
+                            .is_ok();
+                    if waits {
+                        after_install.insert(name, cfg);
+                    } else {
+                        now.insert(name, cfg);
+                    }
+                }
+            }
+            _ => now = configs,
+        }
+
         // Initialize agents in parallel and insert them as soon as each is ready.
-        for (name, cfg) in configs {
+        for (name, cfg) in now {
             let manager = manager.clone();
             let remaining = remaining.clone();
             smol::spawn(async move {
-                if let Err(e) = manager.add_agent(name.clone(), cfg).await {
-                    warn!("Failed to initialize agent '{}': {}", name, e);
-                }
-                if remaining.fetch_sub(1, Ordering::SeqCst) == 1
-                    && manager.list_agents().await.is_empty()
-                {
-                    warn!("No agents could be initialized, continuing without agents");
+                manager.start_agent(name, cfg, &remaining).await;
+            })
+            .detach();
+        }
+
+        if !after_install.is_empty() {
+            let manager = manager.clone();
+            smol::spawn(async move {
+                manager.install_node_runtime().await;
+                for (name, cfg) in after_install {
+                    manager.start_agent(name, cfg, &remaining).await;
                 }
             })
             .detach();
@@ -91,6 +146,22 @@ impl AgentManager {
         Ok(manager)
     }
 
+    /// Start an agent given to `initialize`, restarting it if it was added
+    /// in the meantime; `remaining` counts the agents still to start
+    async fn start_agent(&self, name: String, config: AgentProcessConfig, remaining: &AtomicUsize) {
+        let result = if self.get(&name).await.is_some() {
+            self.restart_agent(&name, config).await
+        } else {
+            self.add_agent(name.clone(), config).await
+        };
+        if let Err(e) = result {
+            warn!("Failed to initialize agent '{}': {}", name, e);
+        }
+        if remaining.fetch_sub(1, Ordering::SeqCst) == 1 && self.list_agents().await.is_empty() {
+            warn!("No agents could be initialized, continuing without agents");
+        }
+    }
+
     pub async fn list_agents(&self) -> Vec<String> {
         let agents = self.agents.read().await;
         let mut list = agents.keys().cloned().collect::<Vec<_>>();
@@ -122,6 +193,85 @@ impl AgentManager {
         agents.get(name).cloned()
     }
 
+    /// Configuration a running agent was started with
+    pub async fn agent_config(&self, name: &str) -> Option<AgentProcessConfig> {
+        let agents = self.agents.read().await;
+        agents.get(name).map(|handle| handle.config.clone())
+    }
+
+    /// Set the Node.js from the settings, used by agents started from now on
+    /// while the managed runtime is off or not installed
+    pub async fn set_nodejs_path(&self, nodejs_path: Option<String>) {
+        self.nodejs.write().await.settings_path = nodejs_path;
+    }
+
+    /// Switch to another managed runtime config, then install its pinned
+    /// version in the background
+    ///
+    /// Running agents keep their Node.js until they are restarted.
+    pub async fn set_node_runtime(self: &Arc<Self>, runtime: NodeRuntimeConfig) {
+        self.nodejs.write().await.runtime = runtime;
+        let manager = self.clone();
+        smol::spawn(async move { manager.install_node_runtime().await }).detach();
+    }
+
+    /// The managed runtime, when it is enabled
+    async fn managed_runtime(&self) -> Option<NodeRuntime> {
+        let nodejs = self.nodejs.read().await;
+        nodejs
+            .runtime
+            .managed
+            .then(|| NodeRuntime::new(nodejs.runtimes_dir.clone(), nodejs.runtime.clone()))
+    }
+
+    /// Install the pinned version of the managed runtime unless it is
+    /// installed already; failures are logged, agents then fall back to the
+    /// Node.js from the settings
+    async fn install_node_runtime(&self) {
+        let Some(runtime) = self.managed_runtime().await else {
+            return;
+        };
+        if runtime.installed_node().is_some() {
+            return;
+        }
+        log::info!("Installing Node.js {}...", runtime.version());
+        let result = smol::unblock(move || {
+            runtime.install_blocking(|progress| log::debug!("[node runtime] {:?}", progress))
+        })
+        .await;
+        if let Err(e) = result {
+            error!("Failed to install the managed Node.js runtime: {}", e);
+        }
+    }
+
+    /// `config` with the manager's Node.js filled in: the managed runtime
+    /// when it is installed, otherwise the one from the settings
+    ///
+    /// `nodejs_path` is never persisted, so configs coming from the config
+    /// file or the settings get it here before every start. An agent that
+    /// needs a newer Node.js than the managed runtime is pinned to fails
+    /// here, before anything is installed or started.
+    async fn with_nodejs_path(
+        &self,
+        name: &str,
+        mut config: AgentProcessConfig,
+    ) -> Result<AgentProcessConfig> {
+        if config.nodejs_path.is_some() {
+            return Ok(config);
+        }
+        let runtime = self.managed_runtime().await;
+        if let Some(runtime) = &runtime
+            && config.requires_nodejs()
+        {
+            runtime.check_required_version(name, config.node_version)?;
+        }
+        config.nodejs_path = match runtime.and_then(|runtime| runtime.installed_node()) {
+            Some(node) => Some(node.to_string_lossy().to_string()),
+            None => self.nodejs.read().await.settings_path.clone(),
+        };
+        Ok(config)
+    }
+
     /// Add a new agent to the manager
     pub async fn add_agent(&self, name: String, config: AgentProcessConfig) -> Result<()> {
         // Check if agent already exists
@@ -135,7 +285,7 @@ impl AgentManager {
         // Spawn new agent
         let handle = AgentHandle::spawn(
             name.clone(),
-            config,
+            self.with_nodejs_path(&name, config).await?,
             self.permission_store.clone(),
             self.event_hub.clone(),
             self.proxy_config.read().await.clone(),
@@ -149,6 +299,43 @@ impl AgentManager {
         Ok(())
     }
 
+    /// Start an agent without registering it, to check that it completes
+    /// the `initialize` handshake
+    ///
+    /// The process is shut down once the agent has answered, or when it
+    /// times out. Its events go to a hub of its own, so nothing it sends
+    /// reaches the app.
+    pub async fn probe_agent(
+        &self,
+        name: &str,
+        config: AgentProcessConfig,
+    ) -> Result<acp::InitializeResponse> {
+        // Dropping the spawn future on timeout makes the worker stop the process
+        let spawn = AgentHandle::spawn(
+            format!("{name}-probe"),
+            self.with_nodejs_path(name, config).await?,
+            self.permission_store.clone(),
+            EventHub::new(),
+            self.proxy_config.read().await.clone(),
+        );
+        let timeout = async {
+            smol::Timer::after(PROBE_TIMEOUT).await;
+            Err(anyhow!(
+                "agent {name} did not answer initialize within {}s",
+                PROBE_TIMEOUT.as_secs()
+            ))
+        };
+        let handle = smol::future::or(spawn, timeout).await?;
+
+        let response = handle
+            .get_init_response()
+            .ok_or_else(|| anyhow!("agent {name} sent no initialize response"))?;
+        if let Err(e) = handle.shutdown().await {
+            warn!("Failed to stop probe of agent '{}': {}", name, e);
+        }
+        Ok(response)
+    }
+
     /// Remove an agent from the manager
     pub async fn remove_agent(&self, name: &str) -> Result<()> {
         let handle = {
@@ -183,6 +370,8 @@ impl AgentManager {
 
     /// Restart an agent with new configuration
     pub async fn restart_agent(&self, name: &str, config: AgentProcessConfig) -> Result<()> {
+        let config = self.with_nodejs_path(name, config).await?;
+
         // Remove old agent
         let old_handle = {
             let mut agents = self.agents.write().await;
@@ -248,6 +437,10 @@ pub struct AgentHandle {
     sender: mpsc::Sender<AgentCommand>,
     /// Initialize response from the agent
     init_response: Arc<std::sync::RwLock<Option<acp::InitializeResponse>>>,
+    /// Where the agent may start sessions
+    cwd_policy: CwdPolicy,
+    /// Configuration the process was started with
+    config: AgentProcessConfig,
 }
 
 impl AgentHandle {
@@ -262,6 +455,8 @@ impl AgentHandle {
         let (ready_tx, ready_rx) = oneshot::channel();
         let init_response = Arc::new(std::sync::RwLock::new(None));
         let init_response_clone = init_response.clone();
+        let cwd_policy = config.cwd_policy;
+        let started_config = config.clone();
         let thread_name = format!("agent-worker-{name}");
         let worker_name = name.clone();
         thread::Builder::new()
@@ -291,6 +486,8 @@ impl AgentHandle {
             name,
             sender,
             init_response,
+            cwd_policy,
+            config: started_config,
         })
     }
 
@@ -450,6 +647,11 @@ impl AgentHandle {
     pub fn get_init_response(&self) -> Option<acp::InitializeResponse> {
         self.init_response.read().unwrap().clone()
     }
+
+    /// Where the agent may start sessions
+    pub fn cwd_policy(&self) -> CwdPolicy {
+        self.cwd_policy
+    }
 }
 
 enum AgentCommand {
@@ -533,17 +735,13 @@ async fn agent_event_loop(
     permission_store: Arc<PermissionStore>,
     event_hub: EventHub,
     mut command_rx: mpsc::Receiver<AgentCommand>,
-    ready_tx: oneshot::Sender<Result<agent_client_protocol::InitializeResponse>>,
+    mut ready_tx: oneshot::Sender<Result<agent_client_protocol::InitializeResponse>>,
     init_response: Arc<std::sync::RwLock<Option<acp::InitializeResponse>>>,
     proxy_config: ProxyConfig,
 ) -> Result<()> {
     // Node.js environment validation
-    let requires_nodejs = config.command.ends_with(".js")
-        || config.command.ends_with(".ts")
-        || config.command.contains("node")
-        || config.command.contains("npx");
-
-    if requires_nodejs {
+    let mut nodejs_dir = None;
+    if config.requires_nodejs() {
         log::info!(
             "Agent '{}' requires Node.js, validating environment...",
             agent_name
@@ -557,12 +755,32 @@ async fn agent_event_loop(
 
         match nodejs_checker.check_nodejs_available().await {
             Ok(result) if result.available => {
+                let path = result.path.unwrap_or_default();
+                let version = result.version.unwrap_or_default();
                 log::info!(
                     "Node.js found for '{}': {} ({})",
                     agent_name,
-                    result.path.unwrap().display(),
-                    result.version.unwrap()
+                    path.display(),
+                    version
                 );
+
+                if let Some(required) = config.node_version
+                    && crate::installer::node_major_version(&version)
+                        .is_some_and(|major| major < required)
+                {
+                    let error_msg = format!(
+                        "Agent '{}' requires Node.js {} or newer, found {} at {}.\n\n\
+                         Set a newer Node.js in the settings, or pin one with \"node_runtime\" in config.json.",
+                        agent_name,
+                        required,
+                        version,
+                        path.display()
+                    );
+                    log::error!("{}", error_msg);
+                    let _ = ready_tx.send(Err(anyhow!(error_msg.clone())));
+                    return Err(anyhow!(error_msg));
+                }
+                nodejs_dir = path.parent().map(PathBuf::from);
             }
             Ok(result) => {
                 let error_msg = format!(
@@ -603,8 +821,13 @@ async fn agent_event_loop(
         command.creation_flags(CREATE_NO_WINDOW);
     }
 
+    // Put the validated Node.js first on PATH, so `npx` and `node` resolve to it
+    if let Some(dir) = nodejs_dir.filter(|dir| !dir.as_os_str().is_empty()) {
+        command.env("PATH", crate::installer::prepend_path(&dir));
+    }
+
     // Set environment variables from config
-    command.envs(&config.env);
+    command.envs(agentx_types::secrets::resolve_env(&config.env));
 
     // Set proxy environment variables if enabled
     let proxy_envs = proxy_config.env_vars();
@@ -656,7 +879,21 @@ async fn agent_event_loop(
     init_request.client_capabilities = acp::ClientCapabilities::default();
     init_request.client_info = Some(client_info);
     init_request.meta = None;
-    let init_result = conn.initialize(init_request).await;
+    let init_result = tokio::select! {
+        result = conn.initialize(init_request) => result,
+        // Whoever started the agent stopped waiting, e.g. a probe timed out
+        _ = ready_tx.closed() => {
+            log::warn!(
+                "Agent {} was abandoned before it initialized, stopping it",
+                agent_name
+            );
+            io_handle.abort();
+            if let Err(e) = child.kill().await {
+                log::error!("Failed to kill agent {} process: {}", agent_name, e);
+            }
+            return Err(anyhow!("agent {agent_name} was abandoned before it initialized"));
+        }
+    };
     log::info!(
         "Agent {} initialized  === >>> {:?}",
         agent_name,
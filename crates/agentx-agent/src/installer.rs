@@ -0,0 +1,473 @@
+//! Installs agents of the agent catalog
+//!
+//! Each agent gets its own folder under the agents directory, so installs
+//! need no admin rights and never touch global npm or cargo state:
+//!
+//! - npm packages go to `<agent>/node_modules`, using the Node.js found by
+//!   [`NodeJsChecker`] and the `npm` next to it
+//! - binaries are downloaded to `<agent>/<command>` and checked against the
+//!   catalog's SHA-256
+//! - crates are built with `cargo install --root <agent>`
+//! - manual agents are only looked up on `PATH`
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{Context, Result, anyhow};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use agentx_types::AgentProcessConfig;
+use agentx_types::schemas::agent_catalog::{BinaryChecksum, CatalogAgent, InstallMethod};
+use sha2::{Digest, Sha256};
+
+use crate::nodejs::{NodeJsChecker, PackageManager, detect_package_manager};
+
+/// What an install is doing, reported while it runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallProgress {
+    /// A new install step started
+    Step(String),
+    /// A line printed by the package manager or downloader
+    Output(String),
+}
+
+/// Installer for catalog agents
+pub struct AgentInstaller {
+    /// Folder holding one subfolder per installed agent
+    agents_dir: PathBuf,
+    /// Node.js executable from the settings, auto-detected if unset
+    nodejs_path: Option<PathBuf>,
+}
+
+impl AgentInstaller {
+    pub fn new(agents_dir: PathBuf) -> Self {
+        Self {
+            agents_dir,
+            nodejs_path: None,
+        }
+    }
+
+    /// Use the Node.js executable configured in the settings
+    pub fn with_nodejs_path(mut self, nodejs_path: Option<PathBuf>) -> Self {
+        self.nodejs_path = nodejs_path;
+        self
+    }
+
+    /// Folder an agent is installed into
+    pub fn install_dir(&self, agent: &CatalogAgent) -> PathBuf {
+        let slug: String = agent
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        self.agents_dir.join(slug)
+    }
+
+    /// Install an agent, returning the config that launches it
+    pub async fn install(
+        &self,
+        agent: &CatalogAgent,
+        progress: impl Fn(InstallProgress),
+    ) -> Result<AgentProcessConfig> {
+        let dir = self.install_dir(agent);
+        let command = match &agent.install {
+            InstallMethod::Npm { package } => {
+                let nodejs = self.check_nodejs(agent, &progress).await?;
+                self.install_npm(agent, package, &nodejs, &dir, &progress)
+                    .await?
+            }
+            InstallMethod::Binary { url, sha256 } => {
+                self.install_binary(agent, url, sha256, &dir, &progress)
+                    .await?
+            }
+            InstallMethod::Cargo { crate_name, git } => {
+                self.install_cargo(agent, crate_name, git.as_deref(), &dir, &progress)
+                    .await?
+            }
+            InstallMethod::Manual { instructions } => {
+                progress(InstallProgress::Step(format!(
+                    "Looking for {} on PATH",
+                    agent.command
+                )));
+                which::which(&agent.command).map_err(|_| {
+                    anyhow!(
+                        "'{}' was not found on PATH. {}",
+                        agent.command,
+                        instructions
+                    )
+                })?;
+                agent.command.clone()
+            }
+        };
+
+        log::info!("Installed agent '{}': {}", agent.name, command);
+        Ok(agent.installed_config(command))
+    }
+
+    /// Install on a runtime of its own, for callers outside of Tokio
+    pub fn install_blocking(
+        &self,
+        agent: &CatalogAgent,
+        progress: impl Fn(InstallProgress),
+    ) -> Result<AgentProcessConfig> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Failed to start installer runtime")?
+            .block_on(self.install(agent, progress))
+    }
+
+    /// Find Node.js and check it is recent enough, returning its path
+    async fn check_nodejs(
+        &self,
+        agent: &CatalogAgent,
+        progress: &impl Fn(InstallProgress),
+    ) -> Result<PathBuf> {
+        progress(InstallProgress::Step("Checking Node.js".to_string()));
+        let result = NodeJsChecker::new(self.nodejs_path.clone())
+            .check_nodejs_available()
+            .await?;
+        let (Some(path), true) = (result.path, result.available) else {
+            return Err(anyhow!(
+                "{} requires Node.js, which was not found.\n\n{}",
+                agent.name,
+                result.install_hint.unwrap_or_default()
+            ));
+        };
+
+        let version = result.version.unwrap_or_default();
+        if let Some(required) = agent.node_version
+            && node_major_version(&version).is_some_and(|major| major < required)
+        {
+            let hint = match detect_package_manager().await {
+                PackageManager::Unknown => "Download it from https://nodejs.org/".to_string(),
+                pm => format!("Upgrade it with {}: {}", pm.name(), pm.install_command()),
+            };
+            return Err(anyhow!(
+                "{} requires Node.js {} or newer, found {}. {}",
+                agent.name,
+                required,
+                version,
+                hint
+            ));
+        }
+
+        progress(InstallProgress::Output(format!(
+            "Node.js {} at {}",
+            version,
+            path.display()
+        )));
+        Ok(path)
+    }
+
+    async fn install_npm(
+        &self,
+        agent: &CatalogAgent,
+        package: &str,
+        nodejs: &Path,
+        dir: &Path,
+        progress: &impl Fn(InstallProgress),
+    ) -> Result<String> {
+        progress(InstallProgress::Step(format!("Installing {}", package)));
+        create_dir(dir).await?;
+
+        // Use the npm shipped with this Node.js, and put it first on PATH so
+        // install scripts run with it too
+        let node_dir = nodejs.parent().unwrap_or(Path::new(""));
+        let npm_name = if cfg!(windows) { "npm.cmd" } else { "npm" };
+        let npm = Some(node_dir.join(npm_name))
+            .filter(|npm| npm.exists())
+            .unwrap_or_else(|| PathBuf::from(npm_name));
+
+        let mut command = new_command(npm);
+        command
+            .args(["install", "--no-fund", "--no-audit", "--prefix"])
+            .arg(dir)
+            .arg(package)
+            .env("PATH", prepend_path(node_dir));
+        run(command, progress).await?;
+
+        let bin_name = if cfg!(windows) {
+            format!("{}.cmd", agent.command)
+        } else {
+            agent.command.clone()
+        };
+        installed_command(dir.join("node_modules").join(".bin").join(bin_name))
+    }
+
+    async fn install_binary(
+        &self,
+        agent: &CatalogAgent,
+        url: &str,
+        sha256: &BinaryChecksum,
+        dir: &Path,
+        progress: &impl Fn(InstallProgress),
+    ) -> Result<String> {
+        let command = plain_file_name(&agent.command)?;
+        let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+        let expected = sha256.for_platform(os, arch).ok_or_else(|| {
+            anyhow!(
+                "The catalog has no checksum of {} for {}-{}",
+                agent.name,
+                os,
+                arch
+            )
+        })?;
+        let url = binary_url(url);
+        progress(InstallProgress::Step(format!("Downloading {}", url)));
+        create_dir(dir).await?;
+
+        // Downloaded next to the target, which only appears once verified
+        let path = dir.join(executable_name(command));
+        let partial = path.with_extension("download");
+        download(&url, &partial, progress).await?;
+
+        progress(InstallProgress::Step("Verifying checksum".to_string()));
+        let actual = sha256_file(partial.clone()).await?;
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(anyhow!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                url,
+                expected.trim(),
+                actual
+            ));
+        }
+        tokio::fs::rename(&partial, &path)
+            .await
+            .with_context(|| format!("Failed to move {:?} into place", partial))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+                .await
+                .with_context(|| format!("Failed to make {:?} executable", path))?;
+        }
+        installed_command(path)
+    }
+
+    async fn install_cargo(
+        &self,
+        agent: &CatalogAgent,
+        crate_name: &str,
+        git: Option<&str>,
+        dir: &Path,
+        progress: &impl Fn(InstallProgress),
+    ) -> Result<String> {
+        progress(InstallProgress::Step(format!("Building {}", crate_name)));
+        which::which("cargo").map_err(|_| {
+            anyhow!(
+                "{} is built with cargo, which was not found. Install Rust from https://rustup.rs/",
+                agent.name
+            )
+        })?;
+        create_dir(dir).await?;
+
+        let mut command = new_command("cargo");
+        command.args(["install", "--locked", "--root"]).arg(dir);
+        if let Some(git) = git {
+            command.args(["--git", git]);
+        }
+        command.arg(crate_name);
+        run(command, progress).await?;
+
+        installed_command(dir.join("bin").join(executable_name(&agent.command)))
+    }
+}
+
+/// Major version of a `node --version` output such as `v20.11.1`
+pub fn node_major_version(version: &str) -> Option<u32> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Download URL for the current platform
+fn binary_url(url: &str) -> String {
+    url.replace("{os}", std::env::consts::OS)
+        .replace("{arch}", std::env::consts::ARCH)
+}
+
+/// Check that a catalog command is a file name, so a downloaded binary
+/// cannot be written outside the agent's folder
+fn plain_file_name(command: &str) -> Result<&str> {
+    if Path::new(command).file_name() != Some(std::ffi::OsStr::new(command)) {
+        return Err(anyhow!(
+            "Invalid agent command {:?}: binaries need a plain file name",
+            command
+        ));
+    }
+    Ok(command)
+}
+
+fn executable_name(command: &str) -> String {
+    if cfg!(windows) && Path::new(command).extension().is_none() {
+        format!("{}.exe", command)
+    } else {
+        command.to_string()
+    }
+}
+
+fn installed_command(path: PathBuf) -> Result<String> {
+    if !path.exists() {
+        return Err(anyhow!(
+            "Install finished, but {} was not found",
+            path.display()
+        ));
+    }
+    Ok(path.to_string_lossy().to_string())
+}
+
+pub(crate) async fn create_dir(dir: &Path) -> Result<()> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("Failed to create {:?}", dir))
+}
+
+/// `PATH` with `dir` in front
+pub(crate) fn prepend_path(dir: &Path) -> std::ffi::OsString {
+    let mut paths = vec![dir.to_path_buf()];
+    if let Some(path) = std::env::var_os("PATH") {
+        paths.extend(std::env::split_paths(&path));
+    }
+    std::env::join_paths(paths).unwrap_or_default()
+}
+
+/// Download `url` to `path` with curl
+pub(crate) async fn download(
+    url: &str,
+    path: &Path,
+    progress: &impl Fn(InstallProgress),
+) -> Result<()> {
+    let mut command = new_command("curl");
+    command
+        .args([
+            "--fail",
+            "--location",
+            "--silent",
+            "--show-error",
+            "--output",
+        ])
+        .arg(path)
+        .arg(url);
+    run(command, progress).await
+}
+
+/// Hex SHA-256 of a file
+pub(crate) async fn sha256_file(path: PathBuf) -> Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let mut file =
+            std::fs::File::open(&path).with_context(|| format!("Failed to open {:?}", path))?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await?
+}
+
+/// Create a Command with console window hidden on Windows
+pub(crate) fn new_command(program: impl AsRef<std::ffi::OsStr>) -> Command {
+    #[allow(unused_mut)]
+    let mut command = Command::new(program);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+/// Run a command, reporting its output line by line
+pub(crate) async fn run(mut command: Command, progress: &impl Fn(InstallProgress)) -> Result<()> {
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    let (stdout_last, stderr_last) = tokio::join!(
+        forward_lines(child.stdout.take(), progress),
+        forward_lines(child.stderr.take(), progress)
+    );
+
+    let status = child.wait().await?;
+    if !status.success() {
+        let detail = stderr_last.or(stdout_last).unwrap_or_default();
+        return Err(anyhow!("{} failed ({}): {}", program, status, detail));
+    }
+    Ok(())
+}
+
+/// Report the lines of an output stream, returning the last one
+async fn forward_lines(
+    stream: Option<impl AsyncRead + Unpin>,
+    progress: &impl Fn(InstallProgress),
+) -> Option<String> {
+    let mut lines = BufReader::new(stream?).lines();
+    let mut last_line = None;
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        log::debug!("[installer] {}", line);
+        progress(InstallProgress::Output(line.clone()));
+        last_line = Some(line);
+    }
+    last_line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_major_version() {
+        assert_eq!(node_major_version("v20.11.1"), Some(20));
+        assert_eq!(node_major_version("18.0.0\n"), Some(18));
+        assert_eq!(node_major_version("unknown"), None);
+    }
+
+    #[test]
+    fn test_install_dir() {
+        let installer = AgentInstaller::new(PathBuf::from("/agents"));
+        let agent = CatalogAgent {
+            name: "Kimi Code".to_string(),
+            description: String::new(),
+            homepage: None,
+            install: InstallMethod::Manual {
+                instructions: String::new(),
+            },
+            command: "kimi".to_string(),
+            args: Vec::new(),
+            env: Default::default(),
+            node_version: None,
+        };
+        assert_eq!(
+            installer.install_dir(&agent),
+            PathBuf::from("/agents/kimi-code")
+        );
+    }
+
+    #[test]
+    fn test_plain_file_name() {
+        assert_eq!(plain_file_name("kimi").unwrap(), "kimi");
+        for command in ["", "..", "../kimi", "bin/kimi", "/usr/bin/kimi"] {
+            assert!(plain_file_name(command).is_err(), "{:?}", command);
+        }
+    }
+}
@@ -1,4 +1,6 @@
 pub mod client;
+pub mod installer;
 pub mod nodejs;
 
-pub use client::{AgentHandle, AgentManager, PermissionStore};
+pub use client::{AgentHandle, AgentManager, AgentNodejs, PermissionStore};
+pub use installer::{AgentInstaller, InstallProgress};
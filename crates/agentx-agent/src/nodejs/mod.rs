@@ -1,8 +1,10 @@
 mod detector;
 mod error;
 mod installer_hint;
+mod runtime;
 
-pub use installer_hint::{PackageManager, generate_install_hint};
+pub use installer_hint::{PackageManager, detect_package_manager, generate_install_hint};
+pub use runtime::NodeRuntime;
 
 use anyhow::Result;
 use std::path::PathBuf;
@@ -0,0 +1,399 @@
+//! A private Node.js runtime, installed into the user data directory
+//!
+//! Releases are downloaded from a mirror laid out like
+//! `https://nodejs.org/dist` and checked against the `SHASUMS256.txt` of the
+//! release before they are extracted. A local archive can be installed
+//! instead, e.g. on machines without internet access: it is checked against
+//! the configured SHA-256, or a `SHASUMS256.txt` next to it.
+//!
+//! The `SHASUMS256.txt` of a release comes from the same mirror as the
+//! archive, so it only catches corrupted or truncated downloads: a mirror
+//! that serves a tampered archive can serve matching checksums. Set
+//! `node_runtime.sha256` to pin the checksum of the archive instead.
+//!
+//! Each version gets its own folder, `<runtimes>/v<version>`, so pinning
+//! another version leaves the installed ones untouched. Installs run one at
+//! a time, in this process and across processes.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+
+use agentx_types::NodeRuntimeConfig;
+
+use crate::installer::{
+    InstallProgress, create_dir, download, new_command, node_major_version, run, sha256_file,
+};
+
+/// Checksum list published with every Node.js release
+const SHASUMS_FILE: &str = "SHASUMS256.txt";
+/// Lock file in the runtimes folder, held while a runtime is installed
+const LOCK_FILE: &str = ".install.lock";
+
+/// Serializes the installs of this process; the lock file those of others
+static INSTALL_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Installer and locator of the managed Node.js runtime
+pub struct NodeRuntime {
+    /// Folder holding one subfolder per installed version
+    runtimes_dir: PathBuf,
+    config: NodeRuntimeConfig,
+}
+
+impl NodeRuntime {
+    pub fn new(runtimes_dir: PathBuf, config: NodeRuntimeConfig) -> Self {
+        Self {
+            runtimes_dir,
+            config,
+        }
+    }
+
+    /// Pinned version, without the `v` prefix
+    pub fn version(&self) -> &str {
+        self.config.version.trim().trim_start_matches('v')
+    }
+
+    /// Folder the pinned version is installed into
+    pub fn install_dir(&self) -> PathBuf {
+        self.runtimes_dir.join(format!("v{}", self.version()))
+    }
+
+    /// The `node` executable of the pinned version, if it is installed
+    pub fn installed_node(&self) -> Option<PathBuf> {
+        Some(node_executable(&self.install_dir())).filter(|node| node.is_file())
+    }
+
+    /// Fail when the pinned version is older than the major version an
+    /// agent requires, so the mismatch shows before anything is installed
+    pub fn check_required_version(&self, agent_name: &str, required: Option<u32>) -> Result<()> {
+        if let Some(required) = required
+            && node_major_version(self.version()).is_some_and(|major| major < required)
+        {
+            return Err(anyhow!(
+                "Agent '{}' requires Node.js {} or newer, but the managed Node.js runtime is pinned to {}.\n\n\
+                 Pin a newer \"node_runtime.version\" in config.json.",
+                agent_name,
+                required,
+                self.version()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Install the pinned version unless it is installed, returning its `node`
+    pub async fn install(&self, progress: impl Fn(InstallProgress)) -> Result<PathBuf> {
+        if let Some(node) = self.installed_node() {
+            return Ok(node);
+        }
+
+        let _guard = INSTALL_LOCK.lock().await;
+        create_dir(&self.runtimes_dir).await?;
+        let _lock = lock_file(self.runtimes_dir.join(LOCK_FILE)).await?;
+        // Another install may have finished while this one waited
+        if let Some(node) = self.installed_node() {
+            return Ok(node);
+        }
+
+        // Downloads and extraction happen next to the install folder, which
+        // only appears once the runtime is complete
+        let staging = self
+            .runtimes_dir
+            .join(format!(".v{}.partial", self.version()));
+        let _ = tokio::fs::remove_dir_all(&staging).await;
+        create_dir(&staging).await?;
+        let result = self.install_into(&staging, &progress).await;
+        let _ = tokio::fs::remove_dir_all(&staging).await;
+
+        let node = result?;
+        log::info!("Installed Node.js {} at {}", self.version(), node.display());
+        Ok(node)
+    }
+
+    /// Install on a runtime of its own, for callers outside of Tokio
+    pub fn install_blocking(&self, progress: impl Fn(InstallProgress)) -> Result<PathBuf> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Failed to start installer runtime")?
+            .block_on(self.install(progress))
+    }
+
+    async fn install_into(
+        &self,
+        staging: &Path,
+        progress: &impl Fn(InstallProgress),
+    ) -> Result<PathBuf> {
+        let (archive, expected) = match &self.config.archive {
+            Some(archive) => {
+                progress(InstallProgress::Step(format!(
+                    "Using {}",
+                    archive.display()
+                )));
+                (archive.clone(), self.local_checksum(archive).await?)
+            }
+            None => self.download_release(staging, progress).await?,
+        };
+
+        progress(InstallProgress::Step("Verifying checksum".to_string()));
+        let actual = sha256_file(archive.clone()).await?;
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(anyhow!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                archive.display(),
+                expected.trim(),
+                actual
+            ));
+        }
+
+        progress(InstallProgress::Step(format!(
+            "Extracting Node.js {}",
+            self.version()
+        )));
+        let extract_dir = staging.join("extract");
+        create_dir(&extract_dir).await?;
+        let mut command = new_command("tar");
+        command.arg("-xf").arg(&archive).arg("-C").arg(&extract_dir);
+        run(command, progress).await?;
+
+        // A folder without `node` is left over from a broken install
+        let install_dir = self.install_dir();
+        let _ = tokio::fs::remove_dir_all(&install_dir).await;
+        tokio::fs::rename(extracted_root(&extract_dir).await?, &install_dir)
+            .await
+            .with_context(|| format!("Failed to move Node.js to {:?}", install_dir))?;
+
+        self.installed_node().ok_or_else(|| {
+            anyhow!(
+                "Node.js was extracted, but {} was not found",
+                node_executable(&install_dir).display()
+            )
+        })
+    }
+
+    /// Download the release archive of this platform and its checksum
+    async fn download_release(
+        &self,
+        staging: &Path,
+        progress: &impl Fn(InstallProgress),
+    ) -> Result<(PathBuf, String)> {
+        let version = self.version();
+        let file = dist_file_name(version, std::env::consts::OS, std::env::consts::ARCH)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Node.js has no release for {} {}",
+                    std::env::consts::OS,
+                    std::env::consts::ARCH
+                )
+            })?;
+        let release_url = format!("{}/v{}", self.config.mirror_url(), version);
+
+        let expected = match &self.config.sha256 {
+            Some(sha256) => sha256.clone(),
+            None => {
+                let url = format!("{}/{}", release_url, SHASUMS_FILE);
+                progress(InstallProgress::Step(format!("Downloading {}", url)));
+                let path = staging.join(SHASUMS_FILE);
+                download(&url, &path, progress).await?;
+                let sums = tokio::fs::read_to_string(&path).await?;
+                shasum_for(&sums, &file)
+                    .ok_or_else(|| anyhow!("{} is not listed in {}", file, url))?
+            }
+        };
+
+        let url = format!("{}/{}", release_url, file);
+        progress(InstallProgress::Step(format!("Downloading {}", url)));
+        let archive = staging.join(&file);
+        download(&url, &archive, progress).await?;
+        Ok((archive, expected))
+    }
+
+    /// Checksum of a local archive, from the config or its `SHASUMS256.txt`
+    async fn local_checksum(&self, archive: &Path) -> Result<String> {
+        if let Some(sha256) = &self.config.sha256 {
+            return Ok(sha256.clone());
+        }
+
+        let file = archive
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let sums = tokio::fs::read_to_string(archive.with_file_name(SHASUMS_FILE))
+            .await
+            .map_err(|_| {
+                anyhow!(
+                    "No checksum for {}: set node_runtime.sha256, or put the {} of the release next to it",
+                    archive.display(),
+                    SHASUMS_FILE
+                )
+            })?;
+        shasum_for(&sums, &file)
+            .ok_or_else(|| anyhow!("{} is not listed in {}", file, SHASUMS_FILE))
+    }
+}
+
+/// Take an exclusive lock on `path`, held until the returned file is dropped
+async fn lock_file(path: PathBuf) -> Result<std::fs::File> {
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {:?}", path))?;
+        file.lock()
+            .with_context(|| format!("Failed to lock {:?}", path))?;
+        Ok(file)
+    })
+    .await?
+}
+
+/// Name of the release archive for a platform, as published on nodejs.org
+fn dist_file_name(version: &str, os: &str, arch: &str) -> Option<String> {
+    let os = match os {
+        "linux" => "linux",
+        "macos" => "darwin",
+        "windows" => "win",
+        _ => return None,
+    };
+    let arch = match arch {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        _ => return None,
+    };
+    let extension = if os == "win" { "zip" } else { "tar.gz" };
+    Some(format!("node-v{}-{}-{}.{}", version, os, arch, extension))
+}
+
+/// Checksum of `file` in a `SHASUMS256.txt` (`<sha256>  <file>` lines)
+fn shasum_for(sums: &str, file: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (sha256, name) = line.trim().split_once(char::is_whitespace)?;
+        (name.trim().trim_start_matches('*') == file).then(|| sha256.to_ascii_lowercase())
+    })
+}
+
+fn node_executable(install_dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        install_dir.join("node.exe")
+    } else {
+        install_dir.join("bin").join("node")
+    }
+}
+
+/// The `node-v<version>-<os>-<arch>` folder every release archive holds
+async fn extracted_root(dir: &Path) -> Result<PathBuf> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let mut folders = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            folders.push(entry.path());
+        }
+    }
+    match folders.as_slice() {
+        [root] => Ok(root.clone()),
+        _ => Err(anyhow!("Unexpected layout of the Node.js archive")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dist_file_name() {
+        assert_eq!(
+            dist_file_name("24.11.0", "linux", "x86_64").as_deref(),
+            Some("node-v24.11.0-linux-x64.tar.gz")
+        );
+        assert_eq!(
+            dist_file_name("24.11.0", "macos", "aarch64").as_deref(),
+            Some("node-v24.11.0-darwin-arm64.tar.gz")
+        );
+        assert_eq!(
+            dist_file_name("24.11.0", "windows", "x86_64").as_deref(),
+            Some("node-v24.11.0-win-x64.zip")
+        );
+        assert_eq!(dist_file_name("24.11.0", "freebsd", "x86_64"), None);
+    }
+
+    #[test]
+    fn test_shasum_for() {
+        let sums = "\
+            0a1b2c  node-v24.11.0-darwin-arm64.tar.gz\n\
+            3D4E5F  node-v24.11.0-linux-x64.tar.gz\n\
+            6a7b8c *node-v24.11.0-win-x64.zip\n";
+        assert_eq!(
+            shasum_for(sums, "node-v24.11.0-linux-x64.tar.gz").as_deref(),
+            Some("3d4e5f")
+        );
+        assert_eq!(
+            shasum_for(sums, "node-v24.11.0-win-x64.zip").as_deref(),
+            Some("6a7b8c")
+        );
+        assert_eq!(shasum_for(sums, "node-v24.11.0-linux-arm64.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_install_dir() {
+        let config = NodeRuntimeConfig {
+            version: "v22.12.0".to_string(),
+            ..Default::default()
+        };
+        let runtime = NodeRuntime::new(PathBuf::from("/runtimes/node"), config);
+        assert_eq!(
+            runtime.install_dir(),
+            PathBuf::from("/runtimes/node/v22.12.0")
+        );
+        assert_eq!(runtime.installed_node(), None);
+    }
+
+    #[test]
+    fn test_check_required_version() {
+        let config = NodeRuntimeConfig {
+            version: "18.20.0".to_string(),
+            ..Default::default()
+        };
+        let runtime = NodeRuntime::new(PathBuf::from("/runtimes/node"), config);
+        assert!(runtime.check_required_version("qwen", None).is_ok());
+        assert!(runtime.check_required_version("qwen", Some(18)).is_ok());
+
+        let error = runtime
+            .check_required_version("qwen", Some(20))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("managed Node.js runtime is pinned to 18.20.0"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_concurrent_installs() {
+        let dir = std::env::temp_dir().join(format!("agentx-node-install-{}", std::process::id()));
+        let root = dir.join("src").join("node-v1.0.0-linux-x64");
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+        std::fs::write(root.join("bin").join("node"), "#!/bin/sh\n").unwrap();
+        let archive = dir.join("node-v1.0.0-linux-x64.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("-czf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(dir.join("src"))
+            .arg("node-v1.0.0-linux-x64")
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let config = NodeRuntimeConfig {
+            managed: true,
+            version: "1.0.0".to_string(),
+            sha256: Some(sha256_file(archive.clone()).await.unwrap()),
+            archive: Some(archive),
+            ..Default::default()
+        };
+        let first = NodeRuntime::new(dir.join("runtimes"), config.clone());
+        let second = NodeRuntime::new(dir.join("runtimes"), config);
+        let (first, second) = tokio::join!(first.install(|_| {}), second.install(|_| {}));
+        assert_eq!(first.unwrap(), second.unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
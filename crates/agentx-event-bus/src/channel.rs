@@ -0,0 +1,394 @@
+//! Channel-based Event Delivery
+//!
+//! An alternative to callback subscribers: each channel subscriber gets a
+//! bounded queue that the publisher only pushes to, and reads events at its
+//! own pace through an [`EventReceiver`], which is also an async `Stream`.
+//! A slow subscriber therefore never runs on the publishing thread; what
+//! happens when it falls behind is set by its [`OverflowPolicy`].
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+
+use crate::core::SubscriptionId;
+
+/// Decides whether a newer event replaces a queued one, `(queued, new)`
+pub type CoalesceFn<T> = Arc<dyn Fn(&T, &T) -> bool + Send + Sync>;
+
+/// What a full queue does with a new event
+pub enum OverflowPolicy<T> {
+    /// Drop the oldest queued event to make room
+    DropOldest,
+    /// Replace the newest queued event the new one supersedes, dropping
+    /// the oldest event if there is none
+    Coalesce(CoalesceFn<T>),
+    /// Wait until the subscriber makes room; events are never lost, but the
+    /// publisher is stalled by the subscriber
+    Block,
+}
+
+impl<T> OverflowPolicy<T> {
+    pub fn coalesce<F>(supersedes: F) -> Self
+    where
+        F: Fn(&T, &T) -> bool + Send + Sync + 'static,
+    {
+        Self::Coalesce(Arc::new(supersedes))
+    }
+}
+
+impl<T> Clone for OverflowPolicy<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::DropOldest => Self::DropOldest,
+            Self::Coalesce(supersedes) => Self::Coalesce(supersedes.clone()),
+            Self::Block => Self::Block,
+        }
+    }
+}
+
+impl<T> fmt::Debug for OverflowPolicy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DropOldest => f.write_str("DropOldest"),
+            Self::Coalesce(_) => f.write_str("Coalesce"),
+            Self::Block => f.write_str("Block"),
+        }
+    }
+}
+
+/// Queue metrics of a channel subscriber
+#[derive(Debug, Clone)]
+pub struct SubscriberStats {
+    pub id: SubscriptionId,
+    pub capacity: usize,
+    /// Events waiting to be received, i.e. how far the subscriber lags behind
+    pub queued: usize,
+    /// Most events the queue held at once
+    pub max_queued: usize,
+    /// Events taken from the queue by the subscriber
+    pub received: usize,
+    /// Events dropped because the queue was full
+    pub dropped: usize,
+    /// Queued events replaced by a newer one because the queue was full
+    pub coalesced: usize,
+    /// Publishes that had to wait for room in the queue
+    pub blocked: usize,
+}
+
+struct QueueState<T> {
+    events: VecDeque<T>,
+    closed: bool,
+    waker: Option<Waker>,
+    max_queued: usize,
+    received: usize,
+    dropped: usize,
+    coalesced: usize,
+    blocked: usize,
+}
+
+/// Bounded queue shared by the bus and an [`EventReceiver`]
+pub(crate) struct SubscriberQueue<T> {
+    id: SubscriptionId,
+    capacity: usize,
+    overflow: OverflowPolicy<T>,
+    filter: Option<Box<dyn Fn(&T) -> bool + Send + Sync>>,
+    state: Mutex<QueueState<T>>,
+    space: Condvar,
+}
+
+impl<T> SubscriberQueue<T> {
+    pub(crate) fn new(
+        id: SubscriptionId,
+        capacity: usize,
+        overflow: OverflowPolicy<T>,
+        filter: Option<Box<dyn Fn(&T) -> bool + Send + Sync>>,
+    ) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            id,
+            capacity,
+            overflow,
+            filter,
+            state: Mutex::new(QueueState {
+                events: VecDeque::with_capacity(capacity),
+                closed: false,
+                waker: None,
+                max_queued: 0,
+                received: 0,
+                dropped: 0,
+                coalesced: 0,
+                blocked: 0,
+            }),
+            space: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn id(&self) -> SubscriptionId {
+        self.id
+    }
+
+    pub(crate) fn should_notify(&self, event: &T) -> bool {
+        match &self.filter {
+            Some(filter) => filter(event),
+            None => true,
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    /// Queue an event, applying the overflow policy when the queue is full
+    pub(crate) fn push(&self, event: T) {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return;
+        }
+
+        if state.events.len() >= self.capacity {
+            match &self.overflow {
+                OverflowPolicy::DropOldest => {
+                    state.events.pop_front();
+                    state.dropped += 1;
+                }
+                OverflowPolicy::Coalesce(supersedes) => {
+                    if let Some(queued) = state
+                        .events
+                        .iter_mut()
+                        .rev()
+                        .find(|queued| supersedes(queued, &event))
+                    {
+                        *queued = event;
+                        state.coalesced += 1;
+                        return;
+                    }
+                    state.events.pop_front();
+                    state.dropped += 1;
+                }
+                OverflowPolicy::Block => {
+                    state.blocked += 1;
+                    while state.events.len() >= self.capacity && !state.closed {
+                        state = self.space.wait(state).unwrap();
+                    }
+                    if state.closed {
+                        return;
+                    }
+                }
+            }
+        }
+
+        state.events.push_back(event);
+        state.max_queued = state.max_queued.max(state.events.len());
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn pop(&self, cx: Option<&mut Context<'_>>) -> Poll<Option<T>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(event) = state.events.pop_front() {
+            state.received += 1;
+            drop(state);
+            self.space.notify_one();
+            return Poll::Ready(Some(event));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        if let Some(cx) = cx {
+            state.waker = Some(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    /// Stop accepting events; queued ones can still be received
+    pub(crate) fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        let waker = state.waker.take();
+        drop(state);
+        self.space.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn stats(&self) -> SubscriberStats {
+        let state = self.state.lock().unwrap();
+        SubscriberStats {
+            id: self.id,
+            capacity: self.capacity,
+            queued: state.events.len(),
+            max_queued: state.max_queued,
+            received: state.received,
+            dropped: state.dropped,
+            coalesced: state.coalesced,
+            blocked: state.blocked,
+        }
+    }
+}
+
+/// Receiving end of a channel subscription
+///
+/// Yields events in publish order and ends once the subscription is removed
+/// from the bus and the queue is drained. Dropping the receiver unsubscribes.
+///
+/// With [`OverflowPolicy::Block`], don't receive on the thread that
+/// publishes: a full queue would wait for itself.
+pub struct EventReceiver<T> {
+    queue: Arc<SubscriberQueue<T>>,
+}
+
+impl<T> EventReceiver<T> {
+    pub(crate) fn new(queue: Arc<SubscriberQueue<T>>) -> Self {
+        Self { queue }
+    }
+
+    /// Subscription ID, to unsubscribe from the bus
+    pub fn id(&self) -> SubscriptionId {
+        self.queue.id()
+    }
+
+    /// Take the next queued event without waiting
+    pub fn try_recv(&self) -> Option<T> {
+        match self.queue.pop(None) {
+            Poll::Ready(event) => event,
+            Poll::Pending => None,
+        }
+    }
+
+    /// Wait for the next event, `None` once the subscription has ended
+    pub async fn recv(&mut self) -> Option<T> {
+        std::future::poll_fn(|cx| self.queue.pop(Some(cx))).await
+    }
+
+    /// Queue metrics of this subscriber
+    pub fn stats(&self) -> SubscriberStats {
+        self.queue.stats()
+    }
+}
+
+impl<T> Stream for EventReceiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.queue.pop(Some(cx))
+    }
+}
+
+impl<T> Drop for EventReceiver<T> {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn queue(
+        capacity: usize,
+        overflow: OverflowPolicy<(u32, u32)>,
+    ) -> Arc<SubscriberQueue<(u32, u32)>> {
+        Arc::new(SubscriberQueue::new(
+            SubscriptionId::new(),
+            capacity,
+            overflow,
+            None,
+        ))
+    }
+
+    fn drain(receiver: &EventReceiver<(u32, u32)>) -> Vec<(u32, u32)> {
+        std::iter::from_fn(|| receiver.try_recv()).collect()
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let queue = queue(2, OverflowPolicy::DropOldest);
+        let receiver = EventReceiver::new(queue.clone());
+        for event in [(1, 1), (2, 1), (3, 1)] {
+            queue.push(event);
+        }
+
+        assert_eq!(drain(&receiver), vec![(2, 1), (3, 1)]);
+        let stats = receiver.stats();
+        assert_eq!(stats.dropped, 1);
+        assert_eq!(stats.max_queued, 2);
+        assert_eq!(stats.received, 2);
+        assert_eq!(stats.queued, 0);
+    }
+
+    #[test]
+    fn test_coalesce() {
+        // Events are (key, revision); a newer revision supersedes the same key
+        let queue = queue(
+            2,
+            OverflowPolicy::coalesce(|queued: &(u32, u32), new: &(u32, u32)| queued.0 == new.0),
+        );
+        let receiver = EventReceiver::new(queue.clone());
+        for event in [(1, 1), (2, 1), (1, 2), (3, 1)] {
+            queue.push(event);
+        }
+
+        assert_eq!(drain(&receiver), vec![(2, 1), (3, 1)]);
+        let stats = receiver.stats();
+        assert_eq!(stats.coalesced, 1);
+        assert_eq!(stats.dropped, 1);
+    }
+
+    #[test]
+    fn test_block() {
+        let queue = queue(1, OverflowPolicy::Block);
+        let receiver = EventReceiver::new(queue.clone());
+        queue.push((1, 1));
+
+        let publisher = std::thread::spawn({
+            let queue = queue.clone();
+            move || queue.push((2, 1))
+        });
+        while receiver.stats().blocked == 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(receiver.stats().queued, 1);
+
+        assert_eq!(receiver.try_recv(), Some((1, 1)));
+        publisher.join().unwrap();
+        assert_eq!(receiver.try_recv(), Some((2, 1)));
+    }
+
+    #[test]
+    fn test_receiver_ends_when_closed() {
+        let queue = queue(4, OverflowPolicy::DropOldest);
+        let mut receiver = EventReceiver::new(queue.clone());
+        queue.push((1, 1));
+        queue.close();
+        queue.push((2, 1));
+
+        let events: Vec<_> = block_on(async {
+            let mut events = Vec::new();
+            while let Some(event) = receiver.recv().await {
+                events.push(event);
+            }
+            events
+        });
+        assert_eq!(events, vec![(1, 1)]);
+    }
+
+    /// Poll a future that never waits to completion
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+}
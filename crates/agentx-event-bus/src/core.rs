@@ -5,18 +5,21 @@
 //! - Filtering capabilities
 //! - Performance metrics
 //! - Automatic cleanup
+//! - Channel subscribers with bounded queues (see [`crate::channel`])
 
 use std::sync::{
     Arc, Mutex,
     atomic::{AtomicUsize, Ordering},
 };
 
+use crate::channel::{EventReceiver, OverflowPolicy, SubscriberQueue, SubscriberStats};
+
 /// Unique identifier for event subscriptions
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SubscriptionId(usize);
 
 impl SubscriptionId {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         static COUNTER: AtomicUsize = AtomicUsize::new(0);
         Self(COUNTER.fetch_add(1, Ordering::Relaxed))
     }
@@ -33,6 +36,8 @@ pub struct EventBusStats {
     pub active_subscriptions: usize,
     /// Total number of subscriptions created
     pub total_subscriptions: usize,
+    /// Queue metrics of each channel subscriber
+    pub channels: Vec<SubscriberStats>,
 }
 
 /// Subscriber callback with filtering support
@@ -58,6 +63,7 @@ impl<T> Subscriber<T> {
 /// Core event bus implementation with advanced features
 pub struct EventBus<T> {
     subscribers: Vec<Subscriber<T>>,
+    channels: Vec<Arc<SubscriberQueue<T>>>,
     stats: EventBusStats,
 }
 
@@ -69,6 +75,7 @@ where
     pub fn new() -> Self {
         Self {
             subscribers: Vec::new(),
+            channels: Vec::new(),
             stats: EventBusStats::default(),
         }
     }
@@ -141,6 +148,28 @@ where
         })
     }
 
+    /// Subscribe with a bounded queue instead of a callback
+    ///
+    /// Events are queued for the returned receiver, which reads them at its
+    /// own pace. When `capacity` events are waiting, `overflow` decides what
+    /// happens to the next one. Only events matching `filter` are queued.
+    pub fn subscribe_channel(
+        &mut self,
+        capacity: usize,
+        overflow: OverflowPolicy<T>,
+        filter: Option<Box<dyn Fn(&T) -> bool + Send + Sync>>,
+    ) -> EventReceiver<T> {
+        let id = SubscriptionId::new();
+        let queue = Arc::new(SubscriberQueue::new(id, capacity, overflow, filter));
+
+        self.channels.push(queue.clone());
+        self.stats.active_subscriptions += 1;
+        self.stats.total_subscriptions += 1;
+
+        log::trace!("[EventBus] New channel subscription: {:?}", id);
+        EventReceiver::new(queue)
+    }
+
     /// Unsubscribe using a subscription ID
     ///
     /// Returns true if the subscription was found and removed.
@@ -150,6 +179,12 @@ where
             self.stats.active_subscriptions = self.stats.active_subscriptions.saturating_sub(1);
             log::trace!("[EventBus] Unsubscribed: {:?}", id);
             true
+        } else if let Some(pos) = self.channels.iter().position(|q| q.id() == id) {
+            // The receiver still gets the queued events, then ends
+            self.channels.remove(pos).close();
+            self.stats.active_subscriptions = self.stats.active_subscriptions.saturating_sub(1);
+            log::trace!("[EventBus] Unsubscribed channel: {:?}", id);
+            true
         } else {
             log::warn!("[EventBus] Subscription not found: {:?}", id);
             false
@@ -160,16 +195,27 @@ where
     ///
     /// Automatically removes one-shot subscribers that return false.
     pub fn publish(&mut self, event: T) {
+        for queue in self.dispatch(&event) {
+            queue.push(event.clone());
+        }
+    }
+
+    /// Run the callbacks for an event, returning the channel queues it
+    /// should be pushed to
+    ///
+    /// Queues are pushed to by the caller, so that [`EventBusContainer`] can
+    /// release its lock first: a [`OverflowPolicy::Block`] queue may wait.
+    pub(crate) fn dispatch(&mut self, event: &T) -> Vec<Arc<SubscriberQueue<T>>> {
         self.stats.events_published += 1;
 
         let mut to_remove = Vec::new();
 
         for subscriber in &self.subscribers {
-            if subscriber.should_notify(&event) {
+            if subscriber.should_notify(event) {
                 self.stats.events_delivered += 1;
 
                 // If callback returns false, mark for removal
-                if !subscriber.notify(&event) {
+                if !subscriber.notify(event) {
                     to_remove.push(subscriber.id);
                 }
             }
@@ -180,26 +226,48 @@ where
             self.unsubscribe(id);
         }
 
+        // Remove channel subscribers whose receiver was dropped
+        let before = self.channels.len();
+        self.channels.retain(|queue| !queue.is_closed());
+        self.stats.active_subscriptions = self
+            .stats
+            .active_subscriptions
+            .saturating_sub(before - self.channels.len());
+
+        let queues: Vec<_> = self
+            .channels
+            .iter()
+            .filter(|queue| queue.should_notify(event))
+            .cloned()
+            .collect();
+        self.stats.events_delivered += queues.len();
+
         log::trace!(
             "[EventBus] Published event to {} subscribers",
-            self.subscribers.len()
+            self.subscribers.len() + self.channels.len()
         );
+        queues
     }
 
     /// Get current statistics
     pub fn stats(&self) -> EventBusStats {
-        self.stats.clone()
+        let mut stats = self.stats.clone();
+        stats.channels = self.channels.iter().map(|queue| queue.stats()).collect();
+        stats
     }
 
     /// Get the number of active subscriptions
     pub fn subscriber_count(&self) -> usize {
-        self.subscribers.len()
+        self.subscribers.len() + self.channels.len()
     }
 
     /// Clear all subscriptions
     pub fn clear(&mut self) {
-        let count = self.subscribers.len();
+        let count = self.subscribers.len() + self.channels.len();
         self.subscribers.clear();
+        for queue in self.channels.drain(..) {
+            queue.close();
+        }
         self.stats.active_subscriptions = 0;
         log::info!("[EventBus] Cleared {} subscriptions", count);
     }
@@ -259,6 +327,30 @@ where
         bus.subscribe_once(callback)
     }
 
+    /// Subscribe with a bounded queue instead of a callback
+    pub fn subscribe_channel(
+        &self,
+        capacity: usize,
+        overflow: OverflowPolicy<T>,
+    ) -> EventReceiver<T> {
+        let mut bus = self.inner.lock().unwrap();
+        bus.subscribe_channel(capacity, overflow, None)
+    }
+
+    /// Subscribe with a bounded queue, only queueing events matching `filter`
+    pub fn subscribe_channel_with_filter<P>(
+        &self,
+        capacity: usize,
+        overflow: OverflowPolicy<T>,
+        filter: P,
+    ) -> EventReceiver<T>
+    where
+        P: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        let mut bus = self.inner.lock().unwrap();
+        bus.subscribe_channel(capacity, overflow, Some(Box::new(filter)))
+    }
+
     /// Unsubscribe using a subscription ID
     pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
         let mut bus = self.inner.lock().unwrap();
@@ -266,9 +358,17 @@ where
     }
 
     /// Publish an event
+    ///
+    /// Callbacks run under the bus lock, channel subscribers are pushed to
+    /// after it is released.
     pub fn publish(&self, event: T) {
-        let mut bus = self.inner.lock().unwrap();
-        bus.publish(event);
+        let queues = {
+            let mut bus = self.inner.lock().unwrap();
+            bus.dispatch(&event)
+        };
+        for queue in queues {
+            queue.push(event.clone());
+        }
     }
 
     /// Get current statistics
@@ -451,6 +551,42 @@ mod tests {
         assert_eq!(stats.active_subscriptions, 2);
     }
 
+    #[test]
+    fn test_subscribe_channel() {
+        let bus = EventBusContainer::new();
+        let receiver = bus.subscribe_channel_with_filter(
+            1,
+            OverflowPolicy::DropOldest,
+            |event: &TestEvent| event.id > 1,
+        );
+        for id in 1..=3 {
+            bus.publish(TestEvent {
+                id,
+                message: "test".to_string(),
+            });
+        }
+
+        let stats = bus.stats();
+        assert_eq!(stats.events_delivered, 2);
+        assert_eq!(stats.channels[0].queued, 1);
+        assert_eq!(stats.channels[0].dropped, 1);
+        assert_eq!(receiver.try_recv().map(|event| event.id), Some(3));
+
+        // Unsubscribing ends the receiver
+        let other = bus.subscribe_channel(4, OverflowPolicy::Block);
+        assert!(bus.unsubscribe(other.id()));
+        assert_eq!(other.try_recv(), None);
+
+        // Dropping the receiver unsubscribes on the next publish
+        drop(receiver);
+        bus.publish(TestEvent {
+            id: 4,
+            message: "test".to_string(),
+        });
+        assert_eq!(bus.subscriber_count(), 0);
+        assert_eq!(bus.stats().active_subscriptions, 0);
+    }
+
     #[test]
     fn test_clear() {
         let bus = EventBusContainer::new();
@@ -1,10 +1,12 @@
+use crate::channel::{EventReceiver, OverflowPolicy};
 use crate::core::{EventBusContainer, EventBusStats, SubscriptionId};
 use agentx_types::{
     AgentConfigEvent, CodeSelectionEvent, Config, PermissionRequestEvent, SessionStatus,
     SessionUpdateEvent, WorkspaceUpdateEvent,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AppEvent {
     AgentConfig(AgentConfigEvent),
     CodeSelection(CodeSelectionEvent),
@@ -13,6 +15,62 @@ pub enum AppEvent {
     WorkspaceUpdate(WorkspaceUpdateEvent),
 }
 
+/// Variant of an [`AppEvent`], without its payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    AgentConfig,
+    CodeSelection,
+    PermissionRequest,
+    SessionUpdate,
+    WorkspaceUpdate,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 5] = [
+        EventKind::SessionUpdate,
+        EventKind::PermissionRequest,
+        EventKind::WorkspaceUpdate,
+        EventKind::AgentConfig,
+        EventKind::CodeSelection,
+    ];
+
+    /// Stable identifier, e.g. for locale keys
+    pub fn id(self) -> &'static str {
+        match self {
+            EventKind::AgentConfig => "agent_config",
+            EventKind::CodeSelection => "code_selection",
+            EventKind::PermissionRequest => "permission_request",
+            EventKind::SessionUpdate => "session_update",
+            EventKind::WorkspaceUpdate => "workspace_update",
+        }
+    }
+}
+
+impl AppEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            AppEvent::AgentConfig(_) => EventKind::AgentConfig,
+            AppEvent::CodeSelection(_) => EventKind::CodeSelection,
+            AppEvent::PermissionRequest(_) => EventKind::PermissionRequest,
+            AppEvent::SessionUpdate(_) => EventKind::SessionUpdate,
+            AppEvent::WorkspaceUpdate(_) => EventKind::WorkspaceUpdate,
+        }
+    }
+
+    /// Session the event belongs to, if any
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            AppEvent::PermissionRequest(event) => Some(&event.session_id),
+            AppEvent::SessionUpdate(event) => Some(&event.session_id),
+            AppEvent::WorkspaceUpdate(WorkspaceUpdateEvent::SessionStatusUpdated {
+                session_id,
+                ..
+            }) => Some(session_id),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct EventHub {
     bus: EventBusContainer<AppEvent>,
@@ -47,6 +105,31 @@ impl EventHub {
         self.bus.subscribe_once(callback)
     }
 
+    /// Subscribe with a bounded queue, read as an async `Stream`
+    ///
+    /// Use this for subscribers that do slow work, such as I/O: publishers
+    /// (e.g. the agent I/O thread) only push to the queue.
+    pub fn subscribe_channel(
+        &self,
+        capacity: usize,
+        overflow: OverflowPolicy<AppEvent>,
+    ) -> EventReceiver<AppEvent> {
+        self.bus.subscribe_channel(capacity, overflow)
+    }
+
+    pub fn subscribe_channel_with_filter<P>(
+        &self,
+        capacity: usize,
+        overflow: OverflowPolicy<AppEvent>,
+        filter: P,
+    ) -> EventReceiver<AppEvent>
+    where
+        P: Fn(&AppEvent) -> bool + Send + Sync + 'static,
+    {
+        self.bus
+            .subscribe_channel_with_filter(capacity, overflow, filter)
+    }
+
     pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
         self.bus.unsubscribe(id)
     }
@@ -236,6 +319,9 @@ impl EventHub {
                             | WorkspaceUpdateEvent::TaskRemoved { workspace_id: wid, .. }
                             | WorkspaceUpdateEvent::WorkspaceAdded { workspace_id: wid }
                             | WorkspaceUpdateEvent::WorkspaceRemoved { workspace_id: wid }
+                            | WorkspaceUpdateEvent::TemplatesUpdated { workspace_id: wid }
+                            | WorkspaceUpdateEvent::ComparisonUpdated { workspace_id: wid, .. }
+                            | WorkspaceUpdateEvent::PipelineUpdated { workspace_id: wid, .. }
                     ) if wid == &workspace_id
                 )
             },
@@ -1,9 +1,13 @@
 pub mod batching;
+pub mod channel;
 pub mod core;
 pub mod hub;
+pub mod journal;
 
+pub use channel::{EventReceiver, OverflowPolicy, SubscriberStats};
 pub use core::{EventBus, EventBusContainer, EventBusStats, SubscriptionId};
-pub use hub::{AppEvent, EventHub};
+pub use hub::{AppEvent, EventHub, EventKind};
+pub use journal::{EventJournal, JournalEntry, JournalFilter, ReplayState, SessionReplay, Speaker};
 
 // Re-export types for convenience
 pub use agentx_types::{
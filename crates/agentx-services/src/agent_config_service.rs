@@ -3,14 +3,22 @@
 //! This service manages agent configuration CRUD operations, validation,
 //! persistence, and hot-reload functionality.
 
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
-use crate::AgentService;
+use crate::config_diff::same;
+use crate::{AgentService, ConfigDiff, SecretStore};
 use agentx_agent::AgentManager;
 use agentx_event_bus::{AgentConfigEvent, EventHub};
-use agentx_types::{AgentProcessConfig, Config};
+use agentx_types::schemas::pipeline::PipelineDefinition;
+use agentx_types::schemas::task_template::TaskTemplate;
+use agentx_types::schemas::workspace::Workspace;
+use agentx_types::{
+    AgentProcessConfig, Config, ConfigError, ConfigLayer, EffectiveConfig, LanguageServerConfig,
+    SessionStatus, UntrustedFolder, split_profile_agent_name,
+};
 use anyhow::{Context, Result, anyhow};
 
 /// Agent Configuration Service
@@ -27,6 +35,14 @@ pub struct AgentConfigService {
     agent_service: Option<Arc<AgentService>>,
     /// Event hub for publishing configuration changes
     event_hub: EventHub,
+    /// Vault receiving plaintext secrets on save
+    secret_store: Option<Arc<SecretStore>>,
+    /// Agents whose new configuration waits for their sessions to end
+    pending_restarts: RwLock<HashSet<String>>,
+    /// Why the config file was last rejected (None while it is valid)
+    config_error: RwLock<Option<ConfigError>>,
+    /// Keeps saves in order, so an older snapshot never overwrites a newer one
+    save_lock: Mutex<()>,
 }
 
 impl AgentConfigService {
@@ -43,6 +59,10 @@ impl AgentConfigService {
             agent_manager,
             agent_service: None,
             event_hub,
+            secret_store: None,
+            pending_restarts: RwLock::new(HashSet::new()),
+            config_error: RwLock::new(None),
+            save_lock: Mutex::new(()),
         }
     }
 
@@ -51,6 +71,28 @@ impl AgentConfigService {
         self.agent_service = Some(agent_service);
     }
 
+    /// Set the SecretStore (secrets are moved out of config.json on save)
+    pub fn set_secret_store(&mut self, secret_store: Arc<SecretStore>) {
+        self.secret_store = Some(secret_store);
+    }
+
+    /// Subscribe to session status changes, restarting agents with a deferred
+    /// restart once their last running turn is over
+    pub fn init(self: &Arc<Self>) {
+        let service = self.clone();
+        self.event_hub
+            .subscribe_workspace_session_status(move |_session_id, status| {
+                if *status == SessionStatus::InProgress {
+                    return;
+                }
+                let service = service.clone();
+                smol::spawn(async move {
+                    service.apply_pending_restarts().await;
+                })
+                .detach();
+            });
+    }
+
     // ========== Query Operations ==========
 
     /// List all configured agents
@@ -65,6 +107,15 @@ impl AgentConfigService {
         agents
     }
 
+    /// Names of all configured agents and their profiles, sorted
+    pub async fn list_agent_variants(&self) -> Vec<String> {
+        self.list_agents()
+            .await
+            .iter()
+            .flat_map(|(name, config)| config.variant_names(name))
+            .collect()
+    }
+
     /// Get a specific agent's configuration
     pub async fn get_agent(&self, name: &str) -> Option<AgentProcessConfig> {
         let config = self.config.read().await;
@@ -82,11 +133,93 @@ impl AgentConfigService {
         self.config.blocking_read().proxy.clone()
     }
 
+    /// Get the managed Node.js runtime configuration
+    pub async fn node_runtime_config(&self) -> agentx_types::config::NodeRuntimeConfig {
+        self.config.read().await.node_runtime.clone()
+    }
+
     /// Get the config file path
     pub fn config_path(&self) -> &PathBuf {
         &self.config_path
     }
 
+    /// Agents with a deferred restart, sorted by name
+    pub async fn pending_restarts(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.pending_restarts.read().await.iter().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Why the config file was rejected, if the last load or reload failed
+    pub async fn config_error(&self) -> Option<ConfigError> {
+        self.config_error.read().await.clone()
+    }
+
+    /// Record a validation error found while loading the config at startup
+    pub async fn report_config_error(&self, error: ConfigError) {
+        self.set_config_error(Some(error)).await;
+    }
+
+    /// Store the validation state, publishing it when there is news
+    async fn set_config_error(&self, error: Option<ConfigError>) {
+        {
+            let mut current = self.config_error.write().await;
+            if current.is_none() && error.is_none() {
+                return;
+            }
+            *current = error.clone();
+        }
+        self.event_hub
+            .publish_agent_config_update(AgentConfigEvent::ConfigValidated { error });
+    }
+
+    /// Number of the agent's sessions with a turn in progress
+    fn running_sessions(&self, agent_name: &str) -> usize {
+        self.agent_service
+            .as_ref()
+            .map(|service| {
+                service
+                    .list_workspace_sessions_for_agent(agent_name)
+                    .iter()
+                    .filter(|session| session.status == SessionStatus::InProgress)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Number of the agent's sessions that are not closed
+    fn open_sessions(&self, agent_name: &str) -> usize {
+        self.agent_service
+            .as_ref()
+            .map(|service| {
+                service
+                    .list_workspace_sessions_for_agent(agent_name)
+                    .iter()
+                    .filter(|session| session.status != SessionStatus::Closed)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Restart the agents with a deferred restart that have no running turn
+    async fn apply_pending_restarts(&self) {
+        for name in self.pending_restarts().await {
+            if self.running_sessions(&name) > 0 {
+                continue;
+            }
+            match self.restart_agent(&name).await {
+                Ok(()) => {
+                    if let Some(config) = self.get_agent(&name).await {
+                        self.event_hub.publish_agent_config_update(
+                            AgentConfigEvent::AgentUpdated { name, config },
+                        );
+                    }
+                }
+                Err(e) => log::error!("Failed to apply deferred restart of '{}': {}", name, e),
+            }
+        }
+    }
+
     /// Check if an agent has active sessions
     pub async fn has_active_sessions(&self, agent_name: &str) -> bool {
         if let Some(agent_service) = &self.agent_service {
@@ -133,6 +266,227 @@ impl AgentConfigService {
         commands
     }
 
+    // ========== Layered Configuration ==========
+
+    /// Effective configuration for a workspace
+    ///
+    /// Applies the workspace folder's `.agentx/config.json` and, if the
+    /// session runs elsewhere (e.g. a task worktree), the one in `working_dir`.
+    /// Overlay files are read on every call, so edits apply to new sessions
+    /// without a reload.
+    pub async fn effective_config(
+        &self,
+        workspace: &Workspace,
+        working_dir: Option<&Path>,
+    ) -> EffectiveConfig {
+        self.effective_config_at(&workspace.path, working_dir).await
+    }
+
+    /// Effective configuration for a folder, registered as a workspace or not
+    pub async fn effective_config_at(
+        &self,
+        workspace_dir: &Path,
+        working_dir: Option<&Path>,
+    ) -> EffectiveConfig {
+        let global = self.config.read().await.clone();
+        EffectiveConfig::resolve(global, workspace_dir, working_dir)
+    }
+
+    /// Make sure an agent of an effective configuration can take sessions
+    ///
+    /// Agents defined by a workspace or project overlay, and profile agents
+    /// such as `codex@offline`, are started on first use. An overlay agent
+    /// only starts once its folder is trusted, failing with
+    /// [`UntrustedFolder`] until then.
+    ///
+    /// Agent processes are shared by name: a process running with another
+    /// configuration (another folder's overlay, or the global one) is
+    /// restarted with this one only when it has no open session, as the
+    /// restart would end them; otherwise the definition is refused.
+    pub async fn prepare_agent(&self, effective: &EffectiveConfig, name: &str) -> Result<()> {
+        let (agent, profile) = split_profile_agent_name(name);
+        let Some(config) = effective.config.agent_servers.get(agent) else {
+            if self.config.read().await.agent_servers.contains_key(agent) {
+                return Err(anyhow!("Agent '{}' is disabled in this workspace", agent));
+            }
+            // Not configured at all: let session creation report it
+            return Ok(());
+        };
+        let layer = effective
+            .sources
+            .agent_servers
+            .get(agent)
+            .copied()
+            .unwrap_or(ConfigLayer::Global);
+        if layer != ConfigLayer::Global
+            && let Some(folder) = effective.overlay_dir(layer)
+            && !self.config.read().await.is_trusted_folder(folder)
+        {
+            return Err(UntrustedFolder {
+                agent: agent.to_string(),
+                folder: folder.to_path_buf(),
+            }
+            .into());
+        }
+        let config = match profile {
+            Some(profile) => config
+                .with_profile(profile)
+                .ok_or_else(|| anyhow!("Agent '{}' has no profile '{}'", agent, profile))?,
+            None => config.clone(),
+        };
+
+        match self.agent_manager.agent_config(name).await {
+            Some(running) if same(&running, &config) => {
+                log::info!(
+                    "Agent '{}' from the {} config is already running, reusing it",
+                    name,
+                    layer
+                );
+                Ok(())
+            }
+            Some(_) => {
+                let open_sessions = self.open_sessions(name);
+                if open_sessions > 0 {
+                    return Err(anyhow!(
+                        "Agent '{}' has {} open session(s) with another folder's configuration; \
+                         close them, or give this folder's agent another name",
+                        name,
+                        open_sessions
+                    ));
+                }
+                log::info!("Restarting agent '{}' with the {} config", name, layer);
+                self.validate_command(&config.command)?;
+                self.agent_manager.restart_agent(name, config).await
+            }
+            // Global agents are started with the app
+            None if layer == ConfigLayer::Global && profile.is_none() => Ok(()),
+            None => {
+                log::info!("Starting agent '{}' from the {} config", name, layer);
+                self.validate_command(&config.command)?;
+                self.agent_manager.add_agent(name.to_string(), config).await
+            }
+        }
+    }
+
+    /// Trust the overlay of `folder`, letting the agents it defines start
+    pub async fn trust_folder(&self, folder: PathBuf) -> Result<()> {
+        {
+            let mut config = self.config.write().await;
+            if config.is_trusted_folder(&folder) {
+                return Ok(());
+            }
+            config.trusted_folders.push(folder.clone());
+        }
+        self.save_to_file().await?;
+
+        log::info!("Trusted folder {:?}", folder);
+        Ok(())
+    }
+
+    /// Switch a new session to the default mode and model of its profile
+    ///
+    /// Failures are logged: the session stays usable with the agent's
+    /// defaults.
+    pub async fn apply_profile_defaults(
+        &self,
+        effective: &EffectiveConfig,
+        agent_name: &str,
+        session_id: &str,
+    ) {
+        let (Some(profile), Some(agent_service)) =
+            (effective.agent_profile(agent_name), &self.agent_service)
+        else {
+            return;
+        };
+
+        if let Some(mode) = &profile.mode
+            && let Err(e) = agent_service
+                .set_session_mode(agent_name, session_id, mode)
+                .await
+        {
+            log::warn!(
+                "Failed to set profile mode '{}' for {}: {}",
+                mode,
+                agent_name,
+                e
+            );
+        }
+        if let Some(model) = &profile.model
+            && let Err(e) = agent_service
+                .set_session_model(agent_name, session_id, model)
+                .await
+        {
+            log::warn!(
+                "Failed to set profile model '{}' for {}: {}",
+                model,
+                agent_name,
+                e
+            );
+        }
+    }
+
+    /// Stop the running profile processes of an agent
+    ///
+    /// They start again with the agent's current configuration on next use.
+    async fn stop_profile_agents(&self, agent: &str) {
+        for name in self.agent_manager.list_agents().await {
+            if !matches!(split_profile_agent_name(&name), (base, Some(_)) if base == agent) {
+                continue;
+            }
+            if let Err(e) = self.agent_manager.remove_agent_if_present(&name).await {
+                log::error!("Failed to stop profile agent '{}': {}", name, e);
+            }
+        }
+    }
+
+    /// List all global task templates
+    pub async fn list_task_templates(&self) -> Vec<(String, TaskTemplate)> {
+        let config = self.config.read().await;
+        let mut templates: Vec<_> = config
+            .task_templates
+            .iter()
+            .map(|(name, template)| (name.clone(), template.clone()))
+            .collect();
+        templates.sort_by(|a, b| a.0.cmp(&b.0));
+        templates
+    }
+
+    /// List all configured pipelines
+    pub async fn list_pipelines(&self) -> Vec<(String, PipelineDefinition)> {
+        let config = self.config.read().await;
+        let mut pipelines: Vec<_> = config
+            .pipelines
+            .iter()
+            .map(|(name, pipeline)| (name.clone(), pipeline.clone()))
+            .collect();
+        pipelines.sort_by(|a, b| a.0.cmp(&b.0));
+        pipelines
+    }
+
+    /// Get a pipeline by name
+    pub async fn get_pipeline(&self, name: &str) -> Option<PipelineDefinition> {
+        let config = self.config.read().await;
+        config.pipelines.get(name).cloned()
+    }
+
+    /// Find the enabled language server handling an editor language
+    pub async fn language_server_for(
+        &self,
+        language: &str,
+    ) -> Option<(String, LanguageServerConfig)> {
+        let config = self.config.read().await;
+        let mut servers: Vec<_> = config
+            .language_servers
+            .iter()
+            .filter(|(_, server)| server.enabled && server.languages.iter().any(|l| l == language))
+            .collect();
+        // Pick deterministically when several servers claim the language
+        servers.sort_by(|a, b| a.0.cmp(b.0));
+        servers
+            .first()
+            .map(|(name, server)| ((*name).clone(), (*server).clone()))
+    }
+
     // ========== Validation ==========
 
     /// Validate that a command exists and is executable
@@ -255,6 +609,7 @@ impl AgentConfigService {
         self.agent_manager
             .restart_agent(name, config.clone())
             .await?;
+        self.stop_profile_agents(name).await;
 
         // Update config
         {
@@ -296,16 +651,21 @@ impl AgentConfigService {
             }
             Err(err) => return Err(err),
         }
+        self.stop_profile_agents(name).await;
 
         // Update config
-        {
-            let mut current_config = self.config.write().await;
-            current_config.agent_servers.remove(name);
-        }
+        let removed = self.config.write().await.agent_servers.remove(name);
 
         // Save to file
         self.save_to_file().await?;
 
+        if let Some(secret_store) = &self.secret_store
+            && let Some(agent) = &removed
+            && let Err(e) = secret_store.remove_agent(name, agent)
+        {
+            log::warn!("Failed to remove secrets of agent '{}': {}", name, e);
+        }
+
         // Publish event
         self.event_hub
             .publish_agent_config_update(AgentConfigEvent::AgentRemoved {
@@ -428,6 +788,12 @@ impl AgentConfigService {
         // Save to file
         self.save_to_file().await?;
 
+        if let Some(secret_store) = &self.secret_store
+            && let Err(e) = secret_store.remove(&format!("model.{}.api_key", name))
+        {
+            log::warn!("Failed to remove API key of model '{}': {}", name, e);
+        }
+
         // Publish event
         self.event_hub
             .publish_agent_config_update(AgentConfigEvent::ModelRemoved {
@@ -523,14 +889,18 @@ impl AgentConfigService {
         }
 
         // Update config
-        {
-            let mut current_config = self.config.write().await;
-            current_config.mcp_servers.remove(name);
-        }
+        let removed = self.config.write().await.mcp_servers.remove(name);
 
         // Save to file
         self.save_to_file().await?;
 
+        if let Some(secret_store) = &self.secret_store
+            && let Some(server) = &removed
+            && let Err(e) = secret_store.remove_mcp_server(name, server)
+        {
+            log::warn!("Failed to remove secrets of MCP server '{}': {}", name, e);
+        }
+
         // Publish event
         self.event_hub
             .publish_agent_config_update(AgentConfigEvent::McpServerRemoved {
@@ -642,6 +1012,101 @@ impl AgentConfigService {
         Ok(())
     }
 
+    // ========== Task Template Operations ==========
+
+    /// Add a new global task template
+    pub async fn add_task_template(&self, name: String, template: TaskTemplate) -> Result<()> {
+        // Check for duplicate
+        {
+            let current_config = self.config.read().await;
+            if current_config.task_templates.contains_key(&name) {
+                return Err(anyhow!("Task template '{}' already exists", name));
+            }
+        }
+
+        // Update config
+        {
+            let mut current_config = self.config.write().await;
+            current_config
+                .task_templates
+                .insert(name.clone(), template.clone());
+        }
+
+        // Save to file
+        self.save_to_file().await?;
+
+        // Publish event
+        self.event_hub
+            .publish_agent_config_update(AgentConfigEvent::TaskTemplateAdded {
+                name: name.clone(),
+                template,
+            });
+
+        log::info!("Successfully added task template '{}'", name);
+        Ok(())
+    }
+
+    /// Update an existing global task template
+    pub async fn update_task_template(&self, name: &str, template: TaskTemplate) -> Result<()> {
+        // Check if template exists
+        {
+            let current_config = self.config.read().await;
+            if !current_config.task_templates.contains_key(name) {
+                return Err(anyhow!("Task template '{}' not found", name));
+            }
+        }
+
+        // Update config
+        {
+            let mut current_config = self.config.write().await;
+            current_config
+                .task_templates
+                .insert(name.to_string(), template.clone());
+        }
+
+        // Save to file
+        self.save_to_file().await?;
+
+        // Publish event
+        self.event_hub
+            .publish_agent_config_update(AgentConfigEvent::TaskTemplateUpdated {
+                name: name.to_string(),
+                template,
+            });
+
+        log::info!("Successfully updated task template '{}'", name);
+        Ok(())
+    }
+
+    /// Remove a global task template
+    pub async fn remove_task_template(&self, name: &str) -> Result<()> {
+        // Check if template exists
+        {
+            let current_config = self.config.read().await;
+            if !current_config.task_templates.contains_key(name) {
+                return Err(anyhow!("Task template '{}' not found", name));
+            }
+        }
+
+        // Update config
+        {
+            let mut current_config = self.config.write().await;
+            current_config.task_templates.remove(name);
+        }
+
+        // Save to file
+        self.save_to_file().await?;
+
+        // Publish event
+        self.event_hub
+            .publish_agent_config_update(AgentConfigEvent::TaskTemplateRemoved {
+                name: name.to_string(),
+            });
+
+        log::info!("Successfully removed task template '{}'", name);
+        Ok(())
+    }
+
     /// Restart an agent with its current configuration
     pub async fn restart_agent(&self, name: &str) -> Result<()> {
         let config = {
@@ -660,6 +1125,8 @@ impl AgentConfigService {
         self.agent_manager
             .restart_agent(name, config.clone())
             .await?;
+        self.stop_profile_agents(name).await;
+        self.pending_restarts.write().await.remove(name);
 
         log::info!("Successfully restarted agent '{}'", name);
         Ok(())
@@ -708,57 +1175,240 @@ impl AgentConfigService {
     // ========== Persistence ==========
 
     /// Save configuration to file
+    ///
+    /// With a SecretStore set, plaintext secrets are moved into the vault
+    /// first, so neither the file nor its backup holds them.
+    ///
+    /// A snapshot of the configuration is written off the async runtime, so
+    /// the config lock is not held during the file IO.
     async fn save_to_file(&self) -> Result<()> {
-        let config = self.config.read().await;
+        // The file holds edits that failed validation; don't replace them
+        // with the last good configuration
+        if let Some(error) = self.config_error().await {
+            return Err(anyhow!(
+                "Configuration file has errors, fix them before saving: {}",
+                error
+            ));
+        }
+
+        let _save = self.save_lock.lock().await;
+        let config = self.config.read().await.clone();
+        let secret_store = self.secret_store.clone();
+        let config_path = self.config_path.clone();
+        smol::unblock(move || Self::write_config(config, &config_path, secret_store.as_deref()))
+            .await?;
+
+        // Keep the references in memory too
+        if let Some(secret_store) = &self.secret_store {
+            secret_store.reference_stored(&mut *self.config.write().await);
+        }
+
+        log::info!("Configuration saved to: {:?}", self.config_path);
+        Ok(())
+    }
+
+    fn write_config(
+        mut config: Config,
+        config_path: &Path,
+        secret_store: Option<&SecretStore>,
+    ) -> Result<()> {
+        if let Some(secret_store) = secret_store {
+            secret_store
+                .externalize(&mut config)
+                .context("Failed to store secrets in the vault")?;
+        }
 
         // Create backup before saving
-        if self.config_path.exists() {
-            let backup_path = self.config_path.with_extension("json.backup");
-            if let Err(e) = std::fs::copy(&self.config_path, &backup_path) {
+        if config_path.exists() {
+            let backup_path = config_path.with_extension("json.backup");
+            if let Err(e) = Self::write_backup(config_path, &backup_path, secret_store) {
                 log::warn!("Failed to create backup: {}", e);
             }
         }
 
         // Serialize config
         let json =
-            serde_json::to_string_pretty(&*config).context("Failed to serialize configuration")?;
+            serde_json::to_string_pretty(&config).context("Failed to serialize configuration")?;
 
         // Write to file (atomic write using temp file)
-        let temp_path = self.config_path.with_extension("json.tmp");
+        let temp_path = config_path.with_extension("json.tmp");
         std::fs::write(&temp_path, json).context("Failed to write configuration to temp file")?;
 
-        std::fs::rename(&temp_path, &self.config_path)
-            .context("Failed to replace configuration file")?;
+        std::fs::rename(&temp_path, config_path).context("Failed to replace configuration file")
+    }
+
+    /// Move plaintext secrets left in the config file into the vault
+    ///
+    /// Rewrites the file only if something moved, so once migrated later
+    /// starts leave it alone; a migration is announced with
+    /// `SecretsExternalized`.
+    pub async fn externalize_secrets(&self) -> Result<bool> {
+        let Some(secret_store) = &self.secret_store else {
+            return Ok(false);
+        };
 
-        log::info!("Configuration saved to: {:?}", self.config_path);
+        let mut config = self.config.read().await.clone();
+        let store = secret_store.clone();
+        let moved = smol::unblock(move || store.externalize(&mut config)).await?;
+        if moved {
+            self.save_to_file().await?;
+            log::info!("Moved plaintext secrets from config file into the vault");
+            self.event_hub
+                .publish_agent_config_update(AgentConfigEvent::SecretsExternalized {
+                    vault_path: secret_store.path().display().to_string(),
+                });
+        }
+        Ok(moved)
+    }
+
+    /// Copy the current file to `backup_path`, with its secrets externalized
+    fn write_backup(
+        config_path: &Path,
+        backup_path: &Path,
+        secret_store: Option<&SecretStore>,
+    ) -> Result<()> {
+        let Some(secret_store) = secret_store else {
+            std::fs::copy(config_path, backup_path)?;
+            return Ok(());
+        };
+
+        // Never copy a file that may still hold plaintext secrets
+        let json = std::fs::read_to_string(config_path)?;
+        let mut previous: Config =
+            serde_json::from_str(&json).context("Current configuration is not valid")?;
+        secret_store.externalize(&mut previous)?;
+        std::fs::write(backup_path, serde_json::to_string_pretty(&previous)?)?;
         Ok(())
     }
 
     /// Reload configuration from file
+    ///
+    /// Publishes one event per changed agent, model, MCP server, command and
+    /// task template, plus `ConfigReloaded` if other settings changed. Agents
+    /// are started, stopped or restarted to match; an agent with a turn in
+    /// progress keeps running its old configuration until its running turns
+    /// are over (or it is restarted by hand), and `AgentRestartDeferred` is
+    /// published.
+    ///
+    /// An invalid file leaves the current configuration in place and
+    /// publishes `ConfigValidated` with the error.
     pub async fn reload_from_file(&self) -> Result<()> {
         // Read file
         let json = std::fs::read_to_string(&self.config_path)
             .with_context(|| format!("Failed to read config file: {:?}", self.config_path))?;
 
-        // Parse config
-        let new_config: Config =
-            serde_json::from_str(&json).context("Failed to parse configuration file")?;
+        // Parse config, keeping the last good one if the file is invalid
+        let new_config = match agentx_types::parse_config(&json) {
+            Ok(config) => config,
+            Err(error) => {
+                log::error!(
+                    "Invalid configuration file {:?}: {}",
+                    self.config_path,
+                    error
+                );
+                self.set_config_error(Some(error.clone())).await;
+                return Err(anyhow::Error::new(error).context("Invalid configuration file"));
+            }
+        };
+        self.set_config_error(None).await;
 
         // Update internal config
-        {
+        let (diff, node_runtime_changed) = {
             let mut config = self.config.write().await;
+            let diff = ConfigDiff::between(&config, &new_config);
+            let node_runtime_changed = config.node_runtime != new_config.node_runtime;
             *config = new_config.clone();
+            (diff, node_runtime_changed)
+        };
+
+        if diff.is_empty() {
+            log::info!("Configuration file changed, but nothing to reload");
+            return Ok(());
         }
 
-        // Publish reload event with full config
-        self.event_hub
-            .publish_agent_config_update(AgentConfigEvent::ConfigReloaded {
-                config: Box::new(new_config),
-            });
+        if node_runtime_changed {
+            self.agent_manager
+                .set_node_runtime(new_config.node_runtime.clone())
+                .await;
+        }
+
+        self.apply_agent_changes(&diff).await;
+        for event in diff.events() {
+            self.event_hub.publish_agent_config_update(event);
+        }
+        if diff.settings_changed {
+            self.event_hub
+                .publish_agent_config_update(AgentConfigEvent::ConfigReloaded {
+                    config: Box::new(new_config),
+                });
+        }
 
         log::info!("Configuration reloaded from: {:?}", self.config_path);
         Ok(())
     }
+
+    /// Start, stop and restart agent processes for a reloaded configuration
+    async fn apply_agent_changes(&self, diff: &ConfigDiff) {
+        for name in &diff.agents.removed {
+            self.pending_restarts.write().await.remove(name);
+            if let Err(e) = self.agent_manager.remove_agent_if_present(name).await {
+                log::error!("Failed to stop removed agent '{}': {}", name, e);
+            }
+            self.stop_profile_agents(name).await;
+            self.event_hub
+                .publish_agent_config_update(AgentConfigEvent::AgentRemoved { name: name.clone() });
+        }
+
+        for (name, config) in &diff.agents.added {
+            if let Err(e) = self
+                .agent_manager
+                .add_agent(name.clone(), config.clone())
+                .await
+            {
+                log::error!("Failed to start added agent '{}': {}", name, e);
+                continue;
+            }
+            self.event_hub
+                .publish_agent_config_update(AgentConfigEvent::AgentAdded {
+                    name: name.clone(),
+                    config: config.clone(),
+                });
+        }
+
+        for (name, config) in &diff.agents.updated {
+            let active_sessions = self.running_sessions(name);
+
+            if active_sessions > 0 {
+                log::warn!(
+                    "Agent '{}' has {} running sessions; deferring restart",
+                    name,
+                    active_sessions
+                );
+                self.pending_restarts.write().await.insert(name.clone());
+            } else if let Err(e) = self.agent_manager.restart_agent(name, config.clone()).await {
+                log::error!("Failed to restart agent '{}': {}", name, e);
+                continue;
+            } else {
+                self.stop_profile_agents(name).await;
+                self.pending_restarts.write().await.remove(name);
+            }
+
+            self.event_hub
+                .publish_agent_config_update(AgentConfigEvent::AgentUpdated {
+                    name: name.clone(),
+                    config: config.clone(),
+                });
+            // Published after the update, which clears a pending restart
+            if active_sessions > 0 {
+                self.event_hub.publish_agent_config_update(
+                    AgentConfigEvent::AgentRestartDeferred {
+                        name: name.clone(),
+                        active_sessions,
+                    },
+                );
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -803,6 +1453,9 @@ mod tests {
             },
             args: vec![],
             env: HashMap::new(),
+            profiles: HashMap::new(),
+            cwd_policy: Default::default(),
+            node_version: None,
             nodejs_path: None,
         };
 
@@ -811,21 +1464,128 @@ mod tests {
         // Note: This test requires mocking AgentManager for full coverage
     }
 
+    #[tokio::test]
+    async fn test_invalid_reload_keeps_config() {
+        let config_path =
+            std::env::temp_dir().join(format!("agentx-invalid-config-{}.json", std::process::id()));
+        std::fs::write(&config_path, r#"{ "agent_servers": {}, "upload_dir": 42 }"#).unwrap();
+        let service = create_test_service_at(config_path.clone());
+
+        let result = service.reload_from_file().await;
+        assert!(result.is_err());
+        let error = service.config_error().await.unwrap();
+        assert_eq!(error.path.as_deref(), Some("upload_dir"));
+        assert_eq!(service.get_upload_dir().await, PathBuf::from("."));
+
+        // Saving would replace the user's edits with the last good config
+        assert!(service.save_to_file().await.is_err());
+
+        std::fs::write(
+            &config_path,
+            r#"{ "agent_servers": {}, "upload_dir": "/tmp" }"#,
+        )
+        .unwrap();
+        service.reload_from_file().await.unwrap();
+        assert!(service.config_error().await.is_none());
+        assert_eq!(service.get_upload_dir().await, PathBuf::from("/tmp"));
+
+        let _ = std::fs::remove_file(&config_path);
+    }
+
+    #[tokio::test]
+    async fn test_overlay_agents_need_trusted_folder() {
+        let root = std::env::temp_dir().join(format!("agentx-trust-{}", uuid::Uuid::new_v4()));
+        let folder = root.join("project");
+        std::fs::create_dir_all(folder.join(".agentx")).unwrap();
+        std::fs::write(
+            folder.join(".agentx").join("config.json"),
+            r#"{ "agent_servers": { "local": { "command": "/nonexistent/local-acp" } } }"#,
+        )
+        .unwrap();
+        let config_path = root.join("config.json");
+        std::fs::write(&config_path, r#"{ "agent_servers": {} }"#).unwrap();
+        let service = create_test_service_at(config_path.clone());
+
+        let effective = service.effective_config_at(&folder, None).await;
+        let error = service
+            .prepare_agent(&effective, "local")
+            .await
+            .unwrap_err();
+        let untrusted = error.downcast_ref::<UntrustedFolder>().unwrap();
+        assert_eq!(untrusted.folder, folder);
+
+        service.trust_folder(folder.clone()).await.unwrap();
+        let saved = std::fs::read_to_string(&config_path).unwrap();
+        assert!(saved.contains("trusted_folders"));
+
+        // Trusted: it gets as far as checking the command
+        let error = service
+            .prepare_agent(&effective, "local")
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<UntrustedFolder>().is_none());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_remove_mcp_server_drops_its_secrets() {
+        let root = std::env::temp_dir().join(format!("agentx-secrets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let config_path = root.join("config.json");
+        let secret_store =
+            Arc::new(SecretStore::open(root.join("secrets.vault"), "passphrase").unwrap());
+        let mut service = create_test_service_at(config_path.clone());
+        service.set_secret_store(secret_store.clone());
+
+        let server = agentx_types::config::McpServerConfig {
+            enabled: true,
+            command: "github-mcp".to_string(),
+            args: Vec::new(),
+            env: HashMap::from([("GITHUB_TOKEN".to_string(), "ghp_abc".to_string())]),
+        };
+        service
+            .add_mcp_server("github".to_string(), server)
+            .await
+            .unwrap();
+        let saved = std::fs::read_to_string(&config_path).unwrap();
+        assert!(!saved.contains("ghp_abc"));
+        let servers = service.list_mcp_servers().await;
+        assert_eq!(
+            servers[0].1.env["GITHUB_TOKEN"],
+            "secret:mcp.github.env.GITHUB_TOKEN"
+        );
+
+        service.remove_mcp_server("github").await.unwrap();
+        assert!(secret_store.names().is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
     fn create_test_service() -> AgentConfigService {
+        create_test_service_at(std::env::temp_dir().join("test-config.json"))
+    }
+
+    fn create_test_service_at(config_path: PathBuf) -> AgentConfigService {
         // Create test dependencies
         let config = Config {
+            schema: None,
             agent_servers: HashMap::new(),
             upload_dir: PathBuf::from("."),
             models: HashMap::new(),
             mcp_servers: HashMap::new(),
             commands: HashMap::new(),
+            task_templates: HashMap::new(),
+            pipelines: HashMap::new(),
+            language_servers: HashMap::new(),
             system_prompts: HashMap::new(),
             tool_call_preview_max_lines: 10,
             proxy: ProxyConfig::default(),
+            node_runtime: Default::default(),
+            trusted_folders: Vec::new(),
         };
 
         let event_hub = EventHub::new();
-        let config_path = std::env::temp_dir().join("test-config.json");
 
         // Mock agent manager for testing
         let agent_manager = Arc::new(agentx_agent::AgentManager::new(
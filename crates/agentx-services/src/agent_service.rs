@@ -6,6 +6,7 @@
 
 use std::{
     collections::{HashMap, HashSet, hash_map::Entry},
+    path::Path,
     sync::{Arc, RwLock},
     time::Duration,
 };
@@ -17,7 +18,9 @@ use serde::{Deserialize, Serialize};
 
 use agentx_agent::{AgentHandle, AgentManager};
 use agentx_event_bus::{EventHub, WorkspaceUpdateEvent};
-use agentx_types::SessionStatus;
+use agentx_types::{AgentFeatures, CwdPolicy, SessionStatus};
+
+use crate::{WorkspaceService, git};
 
 /// Agent service - manages agents and their sessions
 pub struct AgentService {
@@ -28,6 +31,8 @@ pub struct AgentService {
     loading_sessions: Arc<RwLock<HashSet<String>>>,
     /// Event hub for publishing status updates
     event_hub: Option<EventHub>,
+    /// Registered workspaces, for agents restricted to their folders
+    workspace_service: Option<Arc<WorkspaceService>>,
 }
 
 /// Agent session information
@@ -51,6 +56,7 @@ impl AgentService {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             loading_sessions: Arc::new(RwLock::new(HashSet::new())),
             event_hub: None,
+            workspace_service: None,
         }
     }
 
@@ -60,6 +66,11 @@ impl AgentService {
         self.event_hub = Some(hub);
     }
 
+    /// Set the WorkspaceService (for agents with a workspace cwd policy)
+    pub fn set_workspace_service(&mut self, workspace_service: Arc<WorkspaceService>) {
+        self.workspace_service = Some(workspace_service);
+    }
+
     // ========== Agent Operations ==========
 
     /// List all available agents
@@ -80,6 +91,38 @@ impl AgentService {
         self.agent_manager.list_agents_with_info().await
     }
 
+    /// Get the features an agent supports, once it is initialized
+    pub async fn get_agent_features(&self, agent_name: &str) -> Option<AgentFeatures> {
+        self.get_agent_init_response(agent_name)
+            .await
+            .map(|response| AgentFeatures::from_init_response(&response))
+    }
+
+    /// Drop the MCP servers using a transport the agent doesn't support
+    fn supported_mcp_servers(
+        agent_name: &str,
+        agent_handle: &AgentHandle,
+        mcp_servers: Vec<acp::McpServer>,
+    ) -> Vec<acp::McpServer> {
+        let Some(response) = agent_handle.get_init_response() else {
+            return mcp_servers;
+        };
+        let features = AgentFeatures::from_init_response(&response);
+        mcp_servers
+            .into_iter()
+            .filter(|server| {
+                let supported = features.supports_mcp_server(server);
+                if !supported {
+                    log::warn!(
+                        "Agent '{}' does not support the transport of an MCP server, skipping it",
+                        agent_name
+                    );
+                }
+                supported
+            })
+            .collect()
+    }
+
     /// Get agent handle (internal use)
     async fn get_agent_handle(&self, name: &str) -> Result<Arc<AgentHandle>> {
         self.agent_manager
@@ -88,6 +131,40 @@ impl AgentService {
             .ok_or_else(|| anyhow!("Agent not found: {}", name))
     }
 
+    /// Refuse a working directory the agent's cwd policy doesn't allow
+    ///
+    /// Task worktrees, which live next to their workspace folder, count as
+    /// part of the workspace.
+    async fn check_cwd_policy(
+        &self,
+        agent_name: &str,
+        agent_handle: &AgentHandle,
+        cwd: &Path,
+    ) -> Result<()> {
+        let policy = agent_handle.cwd_policy();
+        if policy == CwdPolicy::Any {
+            return Ok(());
+        }
+
+        let mut allowed_dirs = Vec::new();
+        if let Some(workspace_service) = &self.workspace_service {
+            for workspace in workspace_service.list_workspaces().await {
+                allowed_dirs.extend(git::worktrees_dir(&workspace.path));
+                allowed_dirs.push(workspace.path);
+            }
+        }
+
+        if policy.allows(cwd, &allowed_dirs) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Agent '{}' may only start sessions inside a registered workspace, not in {}",
+                agent_name,
+                cwd.display()
+            ))
+        }
+    }
+
     // ========== Session Operations ==========
     /// Mark a session as loading (used for session/load persistence behavior)
     pub fn set_session_loading(&self, session_id: &str, is_loading: bool) {
@@ -131,10 +208,12 @@ impl AgentService {
         cwd: std::path::PathBuf,
     ) -> Result<String> {
         let agent_handle = self.get_agent_handle(agent_name).await?;
+        self.check_cwd_policy(agent_name, &agent_handle, &cwd)
+            .await?;
 
         let mut request = acp::NewSessionRequest::new(cwd.clone());
         request.cwd = cwd;
-        request.mcp_servers = mcp_servers;
+        request.mcp_servers = Self::supported_mcp_servers(agent_name, &agent_handle, mcp_servers);
         request.meta = None;
 
         let new_session_response: acp::NewSessionResponse = agent_handle
@@ -213,13 +292,23 @@ impl AgentService {
         cwd: std::path::PathBuf,
     ) -> Result<String> {
         let agent_handle = self.get_agent_handle(agent_name).await?;
+        if let Some(response) = agent_handle.get_init_response()
+            && !AgentFeatures::from_init_response(&response).resume_session
+        {
+            return Err(anyhow!(
+                "Agent '{}' does not support session/resume",
+                agent_name
+            ));
+        }
+        self.check_cwd_policy(agent_name, &agent_handle, &cwd)
+            .await?;
 
         let mut request = acp::ResumeSessionRequest::new(
             acp::SessionId::from(session_id.to_string()),
             cwd.clone(),
         );
         request.cwd = cwd;
-        request.mcp_servers = mcp_servers;
+        request.mcp_servers = Self::supported_mcp_servers(agent_name, &agent_handle, mcp_servers);
         request.meta = None;
 
         let resume_session_response: acp::ResumeSessionResponse = agent_handle
@@ -314,11 +403,13 @@ impl AgentService {
         }
 
         let agent_handle = self.get_agent_handle(agent_name).await?;
+        self.check_cwd_policy(agent_name, &agent_handle, &cwd)
+            .await?;
 
         let mut request =
             acp::LoadSessionRequest::new(acp::SessionId::from(session_id.to_string()), cwd.clone());
         request.cwd = cwd;
-        request.mcp_servers = mcp_servers;
+        request.mcp_servers = Self::supported_mcp_servers(agent_name, &agent_handle, mcp_servers);
         request.meta = None;
 
         self.set_session_loading(session_id, true);
@@ -382,6 +473,121 @@ impl AgentService {
             .cloned()
     }
 
+    /// Switch a session to one of the modes it advertised on creation
+    ///
+    /// Returns `false` without contacting the agent if the session does not
+    /// offer the mode or is already using it.
+    pub async fn set_session_mode(
+        &self,
+        agent_name: &str,
+        session_id: &str,
+        mode_id: &str,
+    ) -> Result<bool> {
+        let supported = self
+            .get_session_info(agent_name, session_id)
+            .and_then(|info| info.new_session_response)
+            .and_then(|response| response.modes)
+            .is_some_and(|modes| {
+                modes.current_mode_id.to_string() != mode_id
+                    && modes
+                        .available_modes
+                        .iter()
+                        .any(|mode| mode.id.to_string() == mode_id)
+            });
+        if !supported {
+            return Ok(false);
+        }
+
+        let agent_handle = self.get_agent_handle(agent_name).await?;
+        let request = acp::SetSessionModeRequest::new(
+            acp::SessionId::from(session_id.to_string()),
+            mode_id.to_string(),
+        );
+        agent_handle
+            .set_session_mode(request)
+            .await
+            .map_err(|e| anyhow!("Failed to set session mode: {}", e))?;
+        self.update_session_response(agent_name, session_id, |response| {
+            if let Some(modes) = response.modes.as_mut() {
+                modes.current_mode_id = acp::SessionModeId::from(mode_id.to_string());
+            }
+        });
+
+        log::info!(
+            "Set mode '{}' for agent {} session {}",
+            mode_id,
+            agent_name,
+            session_id
+        );
+        Ok(true)
+    }
+
+    /// Switch a session to one of the models it advertised on creation
+    ///
+    /// Returns `false` without contacting the agent if the session does not
+    /// offer the model or is already using it.
+    pub async fn set_session_model(
+        &self,
+        agent_name: &str,
+        session_id: &str,
+        model_id: &str,
+    ) -> Result<bool> {
+        let supported = self
+            .get_session_info(agent_name, session_id)
+            .and_then(|info| info.new_session_response)
+            .and_then(|response| response.models)
+            .is_some_and(|models| {
+                models.current_model_id.to_string() != model_id
+                    && models
+                        .available_models
+                        .iter()
+                        .any(|model| model.model_id.to_string() == model_id)
+            });
+        if !supported {
+            return Ok(false);
+        }
+
+        let agent_handle = self.get_agent_handle(agent_name).await?;
+        let request = acp::SetSessionModelRequest::new(
+            acp::SessionId::from(session_id.to_string()),
+            model_id.to_string(),
+        );
+        agent_handle
+            .set_session_model(request)
+            .await
+            .map_err(|e| anyhow!("Failed to set session model: {}", e))?;
+        self.update_session_response(agent_name, session_id, |response| {
+            if let Some(models) = response.models.as_mut() {
+                models.current_model_id = acp::ModelId::from(model_id.to_string());
+            }
+        });
+
+        log::info!(
+            "Set model '{}' for agent {} session {}",
+            model_id,
+            agent_name,
+            session_id
+        );
+        Ok(true)
+    }
+
+    /// Keep the stored session metadata in sync with changes made to it
+    fn update_session_response(
+        &self,
+        agent_name: &str,
+        session_id: &str,
+        update: impl FnOnce(&mut acp::NewSessionResponse),
+    ) {
+        let mut sessions = self.sessions.write().unwrap();
+        if let Some(response) = sessions
+            .get_mut(agent_name)
+            .and_then(|agent_sessions| agent_sessions.get_mut(session_id))
+            .and_then(|info| info.new_session_response.as_mut())
+        {
+            update(response);
+        }
+    }
+
     /// Close an agent's session
     pub async fn close_session(&self, agent_name: &str, session_id: &str) -> Result<()> {
         let mut sessions = self.sessions.write().unwrap();
@@ -595,6 +801,20 @@ impl AgentService {
         prompt: Vec<acp::ContentBlock>,
     ) -> Result<PromptResponse> {
         let agent_handle = self.get_agent_handle(agent_name).await?;
+        // Convert content the agent doesn't accept, or refuse the prompt
+        // before the session is marked as running
+        let prompt = match agent_handle.get_init_response() {
+            Some(response) => AgentFeatures::from_init_response(&response)
+                .adapt_prompt(prompt)
+                .map_err(|feature| {
+                    anyhow!(
+                        "Agent '{}' does not support {} in prompts",
+                        agent_name,
+                        feature.id()
+                    )
+                })?,
+            None => prompt,
+        };
         self.update_session_status(agent_name, session_id, SessionStatus::InProgress);
         let request = acp::PromptRequest::new(acp::SessionId::from(session_id.to_string()), prompt);
 
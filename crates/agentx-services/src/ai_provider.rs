@@ -0,0 +1,440 @@
+//! AI Providers - Wire formats of the supported model APIs
+//!
+//! `ModelConfig.provider` selects the adapter:
+//! - OpenAI-compatible `/chat/completions` (the default for unknown names)
+//! - Anthropic Messages `/messages`
+//! - Google Gemini `/models/{model}:streamGenerateContent`
+//! - Ollama `/api/chat` (local, newline-delimited JSON)
+//!
+//! Each adapter builds the streaming request with its own auth headers, parses
+//! the streamed events and maps error responses to readable messages.
+
+use anyhow::{Context, Result, anyhow};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// API family of a configured model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiProvider {
+    OpenAi,
+    Anthropic,
+    Gemini,
+    Ollama,
+}
+
+/// Default `max_tokens` for Anthropic, where the field is required
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 1024;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// A prompt ready to be sent to a provider
+pub(crate) struct PromptRequest<'a> {
+    pub base_url: &'a str,
+    pub model: &'a str,
+    pub api_key: &'a str,
+    pub system: &'a str,
+    pub user: &'a str,
+    pub max_tokens: Option<u32>,
+    pub temperature: f32,
+}
+
+/// What a streamed event carried
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum StreamEvent {
+    Delta(String),
+    Done,
+    /// Metadata, pings and empty deltas
+    Skip,
+}
+
+impl AiProvider {
+    /// Adapter for a `ModelConfig.provider` value (case-insensitive)
+    pub fn from_name(name: &str) -> Self {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "anthropic" | "claude" => Self::Anthropic,
+            "gemini" | "google" | "google gemini" => Self::Gemini,
+            "ollama" => Self::Ollama,
+            _ => Self::OpenAi,
+        }
+    }
+
+    /// Whether the response is newline-delimited JSON rather than server-sent events
+    pub(crate) fn is_ndjson(self) -> bool {
+        self == Self::Ollama
+    }
+
+    /// Build the streaming request
+    pub(crate) fn request(
+        self,
+        client: &reqwest::Client,
+        prompt: &PromptRequest,
+    ) -> Result<RequestBuilder> {
+        let base_url = prompt.base_url.trim_end_matches('/');
+
+        let request = match self {
+            Self::OpenAi => {
+                let body = json!({
+                    "model": prompt.model,
+                    "messages": [
+                        { "role": "system", "content": prompt.system },
+                        { "role": "user", "content": prompt.user },
+                    ],
+                    "max_tokens": prompt.max_tokens,
+                    "temperature": prompt.temperature,
+                    "stream": true,
+                });
+                client
+                    .post(format!("{}/chat/completions", base_url))
+                    .header("Authorization", format!("Bearer {}", prompt.api_key))
+                    .body(without_nulls(body).to_string())
+            }
+            Self::Anthropic => {
+                let body = json!({
+                    "model": prompt.model,
+                    "system": prompt.system,
+                    "messages": [{ "role": "user", "content": prompt.user }],
+                    "max_tokens": prompt.max_tokens.unwrap_or(ANTHROPIC_DEFAULT_MAX_TOKENS),
+                    "temperature": prompt.temperature,
+                    "stream": true,
+                });
+                client
+                    .post(format!("{}/messages", base_url))
+                    .header("x-api-key", prompt.api_key)
+                    .header("anthropic-version", ANTHROPIC_VERSION)
+                    .body(body.to_string())
+            }
+            Self::Gemini => {
+                let body = json!({
+                    "systemInstruction": { "parts": [{ "text": prompt.system }] },
+                    "contents": [{ "role": "user", "parts": [{ "text": prompt.user }] }],
+                    "generationConfig": {
+                        "maxOutputTokens": prompt.max_tokens,
+                        "temperature": prompt.temperature,
+                    },
+                });
+                client
+                    .post(format!(
+                        "{}/models/{}:streamGenerateContent?alt=sse",
+                        base_url, prompt.model
+                    ))
+                    .header("x-goog-api-key", prompt.api_key)
+                    .body(without_nulls(body).to_string())
+            }
+            Self::Ollama => {
+                let body = json!({
+                    "model": prompt.model,
+                    "messages": [
+                        { "role": "system", "content": prompt.system },
+                        { "role": "user", "content": prompt.user },
+                    ],
+                    "options": {
+                        "num_predict": prompt.max_tokens,
+                        "temperature": prompt.temperature,
+                    },
+                    "stream": true,
+                });
+                let request = client
+                    .post(format!("{}/api/chat", base_url))
+                    .body(without_nulls(body).to_string());
+                // Local servers need no key; proxies in front of one may
+                if prompt.api_key.is_empty() {
+                    request
+                } else {
+                    request.header("Authorization", format!("Bearer {}", prompt.api_key))
+                }
+            }
+        };
+
+        Ok(request.header("Content-Type", "application/json"))
+    }
+
+    /// Parse one event payload (an SSE `data:` value or an NDJSON line)
+    pub(crate) fn parse_event(self, data: &str) -> Result<StreamEvent> {
+        if data == "[DONE]" {
+            return Ok(StreamEvent::Done);
+        }
+        let value: Value =
+            serde_json::from_str(data).context("Failed to parse AI service response")?;
+
+        let text = match self {
+            Self::OpenAi => value.pointer("/choices/0/delta/content"),
+            Self::Anthropic => match value["type"].as_str() {
+                Some("content_block_delta") => value.pointer("/delta/text"),
+                Some("message_stop") => return Ok(StreamEvent::Done),
+                Some("error") => {
+                    return Err(anyhow!("AI service error: {}", error_message(&value)));
+                }
+                _ => None,
+            },
+            Self::Gemini => {
+                if value.get("error").is_some() {
+                    return Err(anyhow!("AI service error: {}", error_message(&value)));
+                }
+                value.pointer("/candidates/0/content/parts/0/text")
+            }
+            Self::Ollama => {
+                if value.get("error").is_some() {
+                    return Err(anyhow!("AI service error: {}", error_message(&value)));
+                }
+                let text = value.pointer("/message/content");
+                if value["done"].as_bool() == Some(true) {
+                    // The final line may still carry text
+                    return Ok(match text.and_then(Value::as_str) {
+                        Some(text) if !text.is_empty() => StreamEvent::Delta(text.to_string()),
+                        _ => StreamEvent::Done,
+                    });
+                }
+                text
+            }
+        };
+
+        Ok(match text.and_then(Value::as_str) {
+            Some(text) if !text.is_empty() => StreamEvent::Delta(text.to_string()),
+            _ => StreamEvent::Skip,
+        })
+    }
+
+    /// Parse a complete (non-streamed) response, from servers that ignore streaming
+    pub(crate) fn parse_response(self, body: &str) -> Result<String> {
+        let value: Value =
+            serde_json::from_str(body).context("Failed to parse AI service response")?;
+
+        let text = match self {
+            Self::OpenAi => value.pointer("/choices/0/message/content"),
+            Self::Anthropic => value.pointer("/content/0/text"),
+            Self::Gemini => value.pointer("/candidates/0/content/parts/0/text"),
+            Self::Ollama => value.pointer("/message/content"),
+        };
+
+        text.and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("No response from AI service"))
+    }
+
+    /// Build the request listing the models available to the key
+    pub(crate) fn models_request(
+        self,
+        client: &reqwest::Client,
+        base_url: &str,
+        api_key: &str,
+    ) -> RequestBuilder {
+        let base_url = base_url.trim_end_matches('/');
+        match self {
+            Self::OpenAi => client
+                .get(format!("{}/models", base_url))
+                .header("Authorization", format!("Bearer {}", api_key)),
+            Self::Anthropic => client
+                .get(format!("{}/models?limit=1000", base_url))
+                .header("x-api-key", api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION),
+            Self::Gemini => client
+                .get(format!("{}/models?pageSize=1000", base_url))
+                .header("x-goog-api-key", api_key),
+            Self::Ollama => client.get(format!("{}/api/tags", base_url)),
+        }
+    }
+
+    /// Model names from a list-models response, sorted
+    pub(crate) fn parse_models(self, body: &str) -> Result<Vec<String>> {
+        let value: Value = serde_json::from_str(body).context("Failed to parse the model list")?;
+
+        let (list, field) = match self {
+            Self::OpenAi | Self::Anthropic => ("data", "id"),
+            Self::Gemini | Self::Ollama => ("models", "name"),
+        };
+        let entries = value[list]
+            .as_array()
+            .ok_or_else(|| anyhow!("Unexpected model list response"))?;
+
+        let mut models: Vec<String> = entries
+            .iter()
+            // Gemini also lists embedding-only models
+            .filter(|entry| {
+                self != Self::Gemini
+                    || entry["supportedGenerationMethods"]
+                        .as_array()
+                        .is_none_or(|methods| {
+                            methods
+                                .iter()
+                                .any(|m| m.as_str() == Some("generateContent"))
+                        })
+            })
+            .filter_map(|entry| entry[field].as_str())
+            .map(|name| name.strip_prefix("models/").unwrap_or(name).to_string())
+            .collect();
+        models.sort();
+        models.dedup();
+        Ok(models)
+    }
+
+    /// Map an error response to a readable message
+    pub(crate) fn error(self, status: StatusCode, body: &str) -> anyhow::Error {
+        let message = serde_json::from_str::<Value>(body)
+            .map(|value| error_message(&value))
+            .unwrap_or_else(|_| body.trim().to_string());
+        let message = if message.is_empty() {
+            "Unknown error".to_string()
+        } else {
+            message
+        };
+
+        match (self, status.as_u16()) {
+            (_, 401) | (Self::Anthropic | Self::Gemini, 403) => {
+                anyhow!("Invalid API key. Please check your config.json")
+            }
+            // Gemini reports a bad key as a 400 with an `API_KEY_INVALID` reason
+            (Self::Gemini, 400) if body.contains("API_KEY_INVALID") => {
+                anyhow!("Invalid API key. Please check your config.json")
+            }
+            (Self::Ollama, 404) => anyhow!(
+                "Model not found on the Ollama server ({}). Pull it with `ollama pull`",
+                message
+            ),
+            // A wrong model name: 404 for most providers, 400 for some
+            // OpenAI-compatible servers
+            (_, 404) => anyhow!("Model not found: {}", message),
+            (Self::OpenAi, 400) if body.contains("model_not_found") => {
+                anyhow!("Model not found: {}", message)
+            }
+            (_, 429) => anyhow!("API rate limit reached. Please try again later"),
+            (Self::Anthropic, 529) => anyhow!("AI service is overloaded. Please try again later"),
+            (_, 500..=599) => anyhow!("AI service error: {}", message),
+            _ => anyhow!("API request failed ({}): {}", status, message),
+        }
+    }
+}
+
+/// Error shapes used by the providers
+///
+/// - OpenAI / Gemini / Anthropic: `{"error": {"message": "..."}}`
+/// - Ollama: `{"error": "..."}`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorField {
+    Object { message: String },
+    Text(String),
+}
+
+fn error_message(value: &Value) -> String {
+    value
+        .get("error")
+        .and_then(|error| serde_json::from_value::<ErrorField>(error.clone()).ok())
+        .map(|error| match error {
+            ErrorField::Object { message } | ErrorField::Text(message) => message,
+        })
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// Drop `null` fields, so unset limits fall back to the provider defaults
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, without_nulls(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(AiProvider::from_name("OpenAI"), AiProvider::OpenAi);
+        assert_eq!(AiProvider::from_name("DeepSeek"), AiProvider::OpenAi);
+        assert_eq!(AiProvider::from_name(" Anthropic "), AiProvider::Anthropic);
+        assert_eq!(AiProvider::from_name("google"), AiProvider::Gemini);
+        assert_eq!(AiProvider::from_name("ollama"), AiProvider::Ollama);
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let openai = r#"{"choices":[{"delta":{"content":"Hi"}}]}"#;
+        assert_eq!(
+            AiProvider::OpenAi.parse_event(openai).unwrap(),
+            StreamEvent::Delta("Hi".into())
+        );
+        let role_only = r#"{"choices":[{"delta":{"role":"assistant"}}]}"#;
+        assert_eq!(
+            AiProvider::OpenAi.parse_event(role_only).unwrap(),
+            StreamEvent::Skip
+        );
+
+        let anthropic =
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#;
+        assert_eq!(
+            AiProvider::Anthropic.parse_event(anthropic).unwrap(),
+            StreamEvent::Delta("Hi".into())
+        );
+        assert_eq!(
+            AiProvider::Anthropic
+                .parse_event(r#"{"type":"message_stop"}"#)
+                .unwrap(),
+            StreamEvent::Done
+        );
+        let overloaded =
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert!(AiProvider::Anthropic.parse_event(overloaded).is_err());
+
+        let gemini = r#"{"candidates":[{"content":{"parts":[{"text":"Hi"}],"role":"model"}}]}"#;
+        assert_eq!(
+            AiProvider::Gemini.parse_event(gemini).unwrap(),
+            StreamEvent::Delta("Hi".into())
+        );
+
+        let ollama_done = r#"{"message":{"role":"assistant","content":""},"done":true}"#;
+        assert_eq!(
+            AiProvider::Ollama.parse_event(ollama_done).unwrap(),
+            StreamEvent::Done
+        );
+    }
+
+    #[test]
+    fn test_parse_models() {
+        let openai = r#"{"object":"list","data":[{"id":"gpt-4o-mini"},{"id":"gpt-4o"}]}"#;
+        assert_eq!(
+            AiProvider::OpenAi.parse_models(openai).unwrap(),
+            vec!["gpt-4o", "gpt-4o-mini"]
+        );
+
+        let gemini = r#"{"models":[
+            {"name":"models/gemini-pro","supportedGenerationMethods":["generateContent"]},
+            {"name":"models/embedding-001","supportedGenerationMethods":["embedContent"]}
+        ]}"#;
+        assert_eq!(
+            AiProvider::Gemini.parse_models(gemini).unwrap(),
+            vec!["gemini-pro"]
+        );
+
+        let ollama = r#"{"models":[{"name":"llama3:latest","size":1}]}"#;
+        assert_eq!(
+            AiProvider::Ollama.parse_models(ollama).unwrap(),
+            vec!["llama3:latest"]
+        );
+        assert!(AiProvider::OpenAi.parse_models("{}").is_err());
+    }
+
+    #[test]
+    fn test_error_mapping() {
+        let body = r#"{"type":"error","error":{"type":"invalid_request_error","message":"max_tokens: too large"}}"#;
+        let error = AiProvider::Anthropic.error(StatusCode::BAD_REQUEST, body);
+        assert!(error.to_string().contains("max_tokens: too large"));
+
+        let body = r#"{"error":{"code":400,"message":"API key not valid","status":"INVALID_ARGUMENT","details":[{"reason":"API_KEY_INVALID"}]}}"#;
+        let error = AiProvider::Gemini.error(StatusCode::BAD_REQUEST, body);
+        assert!(error.to_string().contains("Invalid API key"));
+
+        let body =
+            r#"{"error":{"message":"The model `gpt-9` does not exist","code":"model_not_found"}}"#;
+        let error = AiProvider::OpenAi.error(StatusCode::NOT_FOUND, body);
+        assert!(error.to_string().starts_with("Model not found"));
+
+        let error =
+            AiProvider::Ollama.error(StatusCode::NOT_FOUND, r#"{"error":"model 'x' not found"}"#);
+        assert!(error.to_string().contains("model 'x' not found"));
+    }
+}
@@ -4,18 +4,29 @@
 //! - Code documentation generation
 //! - Code explanation
 //! - Optimization suggestions
+//!
+//! Responses are streamed (server-sent events), so callers can show the text
+//! as it arrives and cancel a request that is no longer needed.
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow};
-use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
 use agentx_types::ModelConfig;
 
+use crate::ai_provider::{AiProvider, PromptRequest, StreamEvent};
+
 /// Global Tokio runtime for HTTP requests
 static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
 
+/// Time to wait for the response to start, and between streamed chunks,
+/// unless the model sets `timeout_secs`
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
 /// AI service for code annotation and analysis
 pub struct AiService {
     /// Shared HTTP client for making API requests
@@ -47,37 +58,114 @@ pub enum CommentStyle {
     Inline,
 }
 
-/// Request body for OpenAI Chat Completions API
-#[derive(Debug, Serialize)]
-struct ChatCompletionRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+/// Endpoint and limits of the model a request is sent to
+struct RequestTarget {
+    provider: AiProvider,
+    base_url: String,
+    model_name: String,
+    api_key: String,
     max_tokens: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    temperature: Option<f32>,
+    timeout: Duration,
 }
 
-#[derive(Debug, Serialize)]
-struct ChatMessage {
-    role: String,
-    content: String,
+impl RequestTarget {
+    fn from_model(model: &ModelConfig, default_max_tokens: Option<u32>) -> Self {
+        Self {
+            provider: AiProvider::from_name(&model.provider),
+            base_url: model.base_url.clone(),
+            model_name: model.model_name.clone(),
+            api_key: model.resolved_api_key(),
+            max_tokens: model.max_tokens.or(default_max_tokens),
+            timeout: Duration::from_secs(model.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+        }
+    }
 }
 
-/// Response from OpenAI Chat Completions API
-#[derive(Debug, Deserialize)]
-struct ChatCompletionResponse {
-    choices: Vec<ChatChoice>,
+/// Cancels a streaming request; cheap to clone and share with the UI
+#[derive(Clone)]
+pub struct AiCancelHandle {
+    abort: tokio::task::AbortHandle,
+    cancelled: Arc<AtomicBool>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ChatChoice {
-    message: ChatMessageResponse,
+impl AiCancelHandle {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.abort.abort();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct ChatMessageResponse {
-    content: String,
+/// The text of a response, delivered as it is generated
+///
+/// Dropping the stream cancels the request.
+pub struct AiStream {
+    deltas: mpsc::UnboundedReceiver<Result<String>>,
+    cancel: AiCancelHandle,
+}
+
+impl AiStream {
+    /// The next piece of text; `None` once the response is complete or cancelled
+    pub async fn next_delta(&mut self) -> Option<Result<String>> {
+        self.deltas.recv().await
+    }
+
+    pub fn cancel_handle(&self) -> AiCancelHandle {
+        self.cancel.clone()
+    }
+
+    /// Wait for the whole response
+    pub async fn collect(mut self) -> Result<String> {
+        let mut text = String::new();
+        while let Some(delta) = self.next_delta().await {
+            text.push_str(&delta?);
+        }
+        if self.cancel.is_cancelled() {
+            return Err(anyhow!("Request cancelled"));
+        }
+        Ok(text.trim().to_string())
+    }
+}
+
+impl Drop for AiStream {
+    fn drop(&mut self) {
+        self.cancel.abort.abort();
+    }
+}
+
+/// Splits a response body into complete lines, across chunk boundaries
+#[derive(Default)]
+struct LineBuffer {
+    /// Bytes of the current, incomplete line
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            lines.push(line.trim_end_matches(['\n', '\r']).to_string());
+        }
+        lines
+    }
+}
+
+/// The event payload of a line: the value of an SSE `data:` line, or a
+/// whole NDJSON line
+fn event_payload(line: &str, ndjson: bool) -> Option<&str> {
+    if ndjson {
+        let line = line.trim();
+        return (!line.is_empty()).then_some(line);
+    }
+    // Comments, `event:` and `id:` lines carry no text
+    line.strip_prefix("data:").map(str::trim_start)
 }
 
 impl AiService {
@@ -99,9 +187,9 @@ impl AiService {
             runtime.handle().clone()
         });
 
-        // Build HTTP client with timeout
+        // Streamed responses can take long; timeouts apply per chunk instead
         let http_client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
+            .connect_timeout(Duration::from_secs(10))
             .build()
             .expect("Failed to create HTTP client");
 
@@ -162,122 +250,140 @@ impl AiService {
         default_prompt.to_string()
     }
 
-    /// Call OpenAI-compatible API with system and user prompts
-    async fn call_api(
-        &self,
-        system_prompt: &str,
-        user_prompt: &str,
-        max_tokens: Option<u32>,
-    ) -> Result<String> {
-        // Extract config data and release lock immediately
-        let (url, model_name, api_key) = {
-            let config = self.config.read().unwrap();
-
-            let model_name = config
-                .default_model
-                .as_ref()
-                .ok_or_else(|| anyhow!("No default AI model configured"))?;
-
-            let model_config = config
-                .models
-                .get(model_name)
-                .ok_or_else(|| anyhow!("Model '{}' not found in configuration", model_name))?;
-
-            if !model_config.enabled {
-                return Err(anyhow!("Model '{}' is disabled", model_name));
-            }
+    /// Resolve the default model, applying its token and timeout settings
+    fn request_target(&self, default_max_tokens: Option<u32>) -> Result<RequestTarget> {
+        let config = self.config.read().unwrap();
 
-            let url = format!(
-                "{}/chat/completions",
-                model_config.base_url.trim_end_matches('/')
-            );
+        let model_name = config
+            .default_model
+            .as_ref()
+            .ok_or_else(|| anyhow!("No default AI model configured"))?;
 
-            (
-                url,
-                model_config.model_name.clone(),
-                model_config.api_key.clone(),
-            )
-        }; // Lock is released here
-
-        let request = ChatCompletionRequest {
-            model: model_name.clone(),
-            messages: vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: system_prompt.to_string(),
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: user_prompt.to_string(),
-                },
-            ],
-            max_tokens,
-            temperature: Some(0.3),
-        };
+        let model_config = config
+            .models
+            .get(model_name)
+            .ok_or_else(|| anyhow!("Model '{}' not found in configuration", model_name))?;
 
-        log::debug!("Calling AI API: {} (model: {})", url, model_name);
+        if !model_config.enabled {
+            return Err(anyhow!("Model '{}' is disabled", model_name));
+        }
 
-        let body = serde_json::to_string(&request).context("Failed to serialize request")?;
+        Ok(RequestTarget::from_model(model_config, default_max_tokens))
+    }
 
-        // Execute HTTP request in Tokio runtime
-        let http_client = self.http_client.clone();
+    /// Start a streaming call to the OpenAI-compatible API
+    ///
+    /// `default_max_tokens` applies unless the model configures `max_tokens`.
+    fn stream_api(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        default_max_tokens: Option<u32>,
+    ) -> Result<AiStream> {
+        let target = self.request_target(default_max_tokens)?;
+        self.stream_target(&target, system_prompt, user_prompt)
+    }
 
-        let response = self
-            .runtime_handle
-            .spawn(async move {
-                http_client
-                    .post(&url)
-                    .header("Content-Type", "application/json")
-                    .header("Authorization", format!("Bearer {}", api_key))
-                    .body(body)
-                    .send()
-                    .await
-            })
-            .await
-            .context("Failed to spawn HTTP request task")?
-            .context("Failed to send request to AI service")?;
+    /// Start a streaming call to `target`
+    fn stream_target(
+        &self,
+        target: &RequestTarget,
+        system_prompt: &str,
+        user_prompt: &str,
+    ) -> Result<AiStream> {
+        log::debug!(
+            "Calling AI API: {} ({:?}, model: {})",
+            target.base_url,
+            target.provider,
+            target.model_name
+        );
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
+        let request = target.provider.request(
+            &self.http_client,
+            &PromptRequest {
+                base_url: &target.base_url,
+                model: &target.model_name,
+                api_key: &target.api_key,
+                system: system_prompt,
+                user: user_prompt,
+                max_tokens: target.max_tokens,
+                temperature: 0.3,
+            },
+        )?;
+        let provider = target.provider;
+        let timeout = target.timeout;
+
+        let (tx, deltas) = mpsc::unbounded_channel();
+        let task = self.runtime_handle.spawn(async move {
+            if let Err(e) = read_stream(provider, request, timeout, &tx).await {
+                let _ = tx.send(Err(e));
+            }
+        });
 
-            return Err(match status.as_u16() {
-                401 => anyhow!("Invalid API key. Please check your config.json"),
-                429 => anyhow!("API rate limit reached. Please try again later"),
-                500..=599 => anyhow!("AI service error: {}", error_text),
-                _ => anyhow!("API request failed ({}): {}", status, error_text),
-            });
+        Ok(AiStream {
+            deltas,
+            cancel: AiCancelHandle {
+                abort: task.abort_handle(),
+                cancelled: Arc::new(AtomicBool::new(false)),
+            },
+        })
+    }
+
+    /// Send a minimal prompt to `model`, returning the time to the first token
+    ///
+    /// Works for models not yet saved or enabled, so settings can be checked
+    /// before they are applied. Auth failures, unknown model names and rate
+    /// limits surface as the usual provider errors.
+    pub async fn test_connection(&self, model: &ModelConfig) -> Result<Duration> {
+        if model.model_name.trim().is_empty() {
+            return Err(anyhow!("Model name is empty"));
         }
 
-        let response_text = response
-            .text()
-            .await
-            .context("Failed to read AI service response")?;
+        let mut target = RequestTarget::from_model(model, None);
+        // One token is enough to prove the model answers
+        target.max_tokens = Some(1);
+
+        let started = Instant::now();
+        let mut stream = self.stream_target(&target, "Reply with OK.", "ping")?;
+        match stream.next_delta().await {
+            Some(Err(e)) => Err(e),
+            Some(Ok(_)) | None => Ok(started.elapsed()),
+        }
+    }
 
-        let completion: ChatCompletionResponse =
-            serde_json::from_str(&response_text).context("Failed to parse AI service response")?;
+    /// Names of the models `model`'s key can use, from the provider's model list
+    pub async fn list_models(&self, model: &ModelConfig) -> Result<Vec<String>> {
+        let target = RequestTarget::from_model(model, None);
+        let request =
+            target
+                .provider
+                .models_request(&self.http_client, &target.base_url, &target.api_key);
+
+        let task = self.runtime_handle.spawn(async move {
+            let timed_out = || anyhow!("AI service timed out after {}s", target.timeout.as_secs());
+            let response = tokio::time::timeout(target.timeout, request.send())
+                .await
+                .map_err(|_| timed_out())?
+                .context("Failed to send request to AI service")?;
 
-        let content = completion
-            .choices
-            .first()
-            .map(|choice| choice.message.content.trim().to_string())
-            .ok_or_else(|| anyhow!("No response from AI service"))?;
+            let status = response.status();
+            let body = tokio::time::timeout(target.timeout, response.text())
+                .await
+                .map_err(|_| timed_out())?
+                .context("Failed to read AI service response")?;
+            if !status.is_success() {
+                return Err(target.provider.error(status, &body));
+            }
+            target.provider.parse_models(&body)
+        });
 
-        Ok(content)
+        task.await.context("Model list request was aborted")?
     }
 
-    /// Generate code comment in the specified style
-    ///
-    /// # Arguments
-    /// * `code` - The code to document
-    /// * `style` - Comment style (FunctionDoc or Inline)
+    /// Stream a code comment in the specified style
     ///
-    /// # Returns
-    /// Raw comment text without formatting (formatting is done by caller)
-    pub async fn generate_comment(&self, code: &str, style: CommentStyle) -> Result<String> {
+    /// The text is the raw comment without formatting (formatting is done by caller).
+    pub fn stream_comment(&self, code: &str, style: CommentStyle) -> Result<AiStream> {
         let (prompt_key, default_system, user_prompt, max_tokens) = match style {
             CommentStyle::FunctionDoc => (
                 "doc_comment",
@@ -285,32 +391,38 @@ impl AiService {
                  Focus on what the code does, parameters, return values, and any important notes. \
                  Return ONLY the comment text without any formatting markers (no ///, /**, etc.).",
                 format!("Generate documentation for the following code:\n\n{}", code),
-                Some(500),
+                Some(1024),
             ),
             CommentStyle::Inline => (
                 "inline_comment",
                 "You are a code documentation expert. Generate brief, single-line inline comments that explain code. \
                  Be concise and clear. Return ONLY the comment text without any formatting markers (no //, #, etc.).",
                 format!("Generate a brief inline comment for:\n\n{}", code),
-                Some(100),
+                Some(200),
             ),
         };
 
         let system_prompt = self.get_system_prompt(prompt_key, default_system);
-
-        self.call_api(&system_prompt, &user_prompt, max_tokens)
-            .await
-            .context("Failed to generate code comment")
+        self.stream_api(&system_prompt, &user_prompt, max_tokens)
     }
 
-    /// Explain what a piece of code does
+    /// Generate code comment in the specified style
     ///
     /// # Arguments
-    /// * `code` - The code to explain
+    /// * `code` - The code to document
+    /// * `style` - Comment style (FunctionDoc or Inline)
     ///
     /// # Returns
-    /// Natural language explanation of the code
-    pub async fn explain_code(&self, code: &str) -> Result<String> {
+    /// Raw comment text without formatting (formatting is done by caller)
+    pub async fn generate_comment(&self, code: &str, style: CommentStyle) -> Result<String> {
+        self.stream_comment(code, style)?
+            .collect()
+            .await
+            .context("Failed to generate code comment")
+    }
+
+    /// Stream a natural language explanation of a piece of code
+    pub fn stream_explanation(&self, code: &str) -> Result<AiStream> {
         let default_system = "You are a code explanation expert. Explain code clearly and concisely \
                             in natural language. Focus on what the code does, why it works that way, \
                             and any important concepts.";
@@ -318,19 +430,25 @@ impl AiService {
         let system_prompt = self.get_system_prompt("explain", default_system);
         let user_prompt = format!("Explain what this code does:\n\n{}", code);
 
-        self.call_api(&system_prompt, &user_prompt, Some(500))
-            .await
-            .context("Failed to explain code")
+        self.stream_api(&system_prompt, &user_prompt, Some(2048))
     }
 
-    /// Suggest improvements for code
+    /// Explain what a piece of code does
     ///
     /// # Arguments
-    /// * `code` - The code to analyze
+    /// * `code` - The code to explain
     ///
     /// # Returns
-    /// List of improvement suggestions as numbered list
-    pub async fn suggest_improvements(&self, code: &str) -> Result<String> {
+    /// Natural language explanation of the code
+    pub async fn explain_code(&self, code: &str) -> Result<String> {
+        self.stream_explanation(code)?
+            .collect()
+            .await
+            .context("Failed to explain code")
+    }
+
+    /// Stream improvement suggestions for a piece of code, as a numbered list
+    pub fn stream_improvements(&self, code: &str) -> Result<AiStream> {
         let default_system = "You are a code review expert. Analyze code and suggest improvements \
                             focusing on: readability, performance, best practices, potential bugs, \
                             and maintainability. Format your response as a numbered list.";
@@ -341,12 +459,89 @@ impl AiService {
             code
         );
 
-        self.call_api(&system_prompt, &user_prompt, Some(800))
+        self.stream_api(&system_prompt, &user_prompt, Some(2048))
+    }
+
+    /// Suggest improvements for code
+    ///
+    /// # Arguments
+    /// * `code` - The code to analyze
+    ///
+    /// # Returns
+    /// List of improvement suggestions as numbered list
+    pub async fn suggest_improvements(&self, code: &str) -> Result<String> {
+        self.stream_improvements(code)?
+            .collect()
             .await
             .context("Failed to generate improvement suggestions")
     }
 }
 
+/// Send a request and forward the streamed text to `tx`
+///
+/// Servers that ignore streaming and answer with a single JSON body are
+/// handled as one delta.
+async fn read_stream(
+    provider: AiProvider,
+    request: reqwest::RequestBuilder,
+    timeout: Duration,
+    tx: &mpsc::UnboundedSender<Result<String>>,
+) -> Result<()> {
+    let timed_out = || anyhow!("AI service timed out after {}s", timeout.as_secs());
+
+    let mut response = tokio::time::timeout(timeout, request.send())
+        .await
+        .map_err(|_| timed_out())?
+        .context("Failed to send request to AI service")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = tokio::time::timeout(timeout, response.text())
+            .await
+            .map_err(|_| timed_out())?
+            .unwrap_or_default();
+        return Err(provider.error(status, &error_text));
+    }
+
+    let is_json = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if is_json && !provider.is_ndjson() {
+        let response_text = tokio::time::timeout(timeout, response.text())
+            .await
+            .map_err(|_| timed_out())?
+            .context("Failed to read AI service response")?;
+        let _ = tx.send(provider.parse_response(&response_text));
+        return Ok(());
+    }
+
+    let mut lines = LineBuffer::default();
+    while let Some(chunk) = tokio::time::timeout(timeout, response.chunk())
+        .await
+        .map_err(|_| timed_out())?
+        .context("Failed to read AI service response")?
+    {
+        for line in lines.push(&chunk) {
+            let Some(data) = event_payload(&line, provider.is_ndjson()) else {
+                continue;
+            };
+            match provider.parse_event(data)? {
+                StreamEvent::Delta(delta) => {
+                    if tx.send(Ok(delta)).is_err() {
+                        // The stream was dropped
+                        return Ok(());
+                    }
+                }
+                StreamEvent::Done => return Ok(()),
+                StreamEvent::Skip => {}
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -361,6 +556,8 @@ mod tests {
                 base_url: "https://api.openai.com/v1".to_string(),
                 api_key: "test-key".to_string(),
                 model_name: "gpt-3.5-turbo".to_string(),
+                max_tokens: None,
+                timeout_secs: None,
             },
         );
         models
@@ -390,6 +587,8 @@ mod tests {
                 base_url: "https://api.openai.com/v1".to_string(),
                 api_key: "new-key".to_string(),
                 model_name: "gpt-4".to_string(),
+                max_tokens: Some(4096),
+                timeout_secs: Some(120),
             },
         );
 
@@ -410,6 +609,8 @@ mod tests {
                 base_url: "https://api.openai.com/v1".to_string(),
                 api_key: "test-key".to_string(),
                 model_name: "gpt-3.5-turbo".to_string(),
+                max_tokens: None,
+                timeout_secs: None,
             },
         );
 
@@ -417,4 +618,240 @@ mod tests {
         let config = service.config.read().unwrap();
         assert!(config.default_model.is_none());
     }
+
+    #[test]
+    fn test_request_target_limits() {
+        let service = AiService::new(create_test_config(), HashMap::new());
+        let target = service.request_target(Some(500)).unwrap();
+        assert_eq!(target.max_tokens, Some(500));
+        assert_eq!(target.timeout, Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+
+        let mut models = create_test_config();
+        let model = models.get_mut("test-model").unwrap();
+        model.max_tokens = Some(4096);
+        model.timeout_secs = Some(5);
+        service.update_config(models, HashMap::new());
+
+        let target = service.request_target(Some(500)).unwrap();
+        assert_eq!(target.max_tokens, Some(4096));
+        assert_eq!(target.timeout, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_line_buffer_handles_split_events() {
+        let mut lines = LineBuffer::default();
+        assert_eq!(
+            lines.push(b": keep-alive\ndata: {\"a\""),
+            vec![": keep-alive"]
+        );
+        assert_eq!(
+            lines.push(b":1}\r\n\ndata: [DONE]\n"),
+            vec!["data: {\"a\":1}", "", "data: [DONE]"]
+        );
+        assert_eq!(event_payload("data: [DONE]", false), Some("[DONE]"));
+        assert_eq!(event_payload(": keep-alive", false), None);
+        assert_eq!(
+            event_payload("{\"done\":true}", true),
+            Some("{\"done\":true}")
+        );
+        assert_eq!(event_payload("", true), None);
+
+        // A multi-byte character split across chunks
+        let bytes = "data: é\n".as_bytes();
+        assert!(lines.push(&bytes[..7]).is_empty());
+        assert_eq!(lines.push(&bytes[7..]), vec!["data: é"]);
+    }
+
+    /// Serve one canned HTTP response on a local port, returning the base URL
+    /// and a receiver for the raw request
+    fn mock_server(
+        status: &'static str,
+        content_type: &'static str,
+        body: &'static str,
+    ) -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read the headers, then the body announced by Content-Length
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                // The client closed the connection
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text
+                        .lines()
+                        .find_map(|line| {
+                            line.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            tx.send(String::from_utf8_lossy(&request).to_string())
+                .unwrap();
+
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nConnection: close\r\n\r\n{}",
+                status, content_type, body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        (url, rx)
+    }
+
+    fn mock_service(provider: &str, base_url: String) -> AiService {
+        let mut models = HashMap::new();
+        models.insert(
+            "mock".to_string(),
+            ModelConfig {
+                enabled: true,
+                provider: provider.to_string(),
+                base_url,
+                api_key: "secret".to_string(),
+                model_name: "mock-model".to_string(),
+                max_tokens: None,
+                timeout_secs: Some(5),
+            },
+        );
+        AiService::new(models, HashMap::new())
+    }
+
+    fn run(service: &AiService) -> Result<String> {
+        let stream = service.stream_api("system", "user", Some(100))?;
+        service.runtime_handle.block_on(stream.collect())
+    }
+
+    #[test]
+    fn test_openai_streaming() {
+        let (url, requests) = mock_server(
+            "200 OK",
+            "text/event-stream",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{\"content\":\" world\"}}]}\n\n\
+             data: [DONE]\n\n",
+        );
+        let service = mock_service("OpenAI", url);
+
+        assert_eq!(run(&service).unwrap(), "Hello world");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /chat/completions "));
+        assert!(request.contains("authorization: Bearer secret"));
+        assert!(request.contains("\"stream\":true"));
+    }
+
+    #[test]
+    fn test_anthropic_streaming() {
+        let (url, requests) = mock_server(
+            "200 OK",
+            "text/event-stream",
+            "event: message_start\ndata: {\"type\":\"message_start\"}\n\n\
+             event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n\
+             event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        );
+        let service = mock_service("Anthropic", url);
+
+        assert_eq!(run(&service).unwrap(), "Hi");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /messages "));
+        assert!(request.contains("x-api-key: secret"));
+        assert!(request.contains("anthropic-version: 2023-06-01"));
+        assert!(request.contains("\"system\":\"system\""));
+    }
+
+    #[test]
+    fn test_gemini_streaming() {
+        let (url, requests) = mock_server(
+            "200 OK",
+            "text/event-stream",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hi\"}]}}]}\r\n\r\n",
+        );
+        let service = mock_service("Gemini", url);
+
+        assert_eq!(run(&service).unwrap(), "Hi");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /models/mock-model:streamGenerateContent?alt=sse "));
+        assert!(request.contains("x-goog-api-key: secret"));
+        assert!(request.contains("\"maxOutputTokens\":100"));
+    }
+
+    #[test]
+    fn test_ollama_streaming() {
+        let (url, requests) = mock_server(
+            "200 OK",
+            "application/x-ndjson",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"done\":false}\n\
+             {\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n",
+        );
+        let service = mock_service("Ollama", url);
+
+        assert_eq!(run(&service).unwrap(), "Hi");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /api/chat "));
+        assert!(request.contains("\"num_predict\":100"));
+    }
+
+    #[test]
+    fn test_list_models() {
+        let (url, requests) = mock_server(
+            "200 OK",
+            "application/json",
+            "{\"models\":[{\"name\":\"qwen2:7b\"},{\"name\":\"llama3:latest\"}]}",
+        );
+        let service = mock_service("Ollama", url);
+        let model = service.config.read().unwrap().models["mock"].clone();
+
+        let models = service
+            .runtime_handle
+            .block_on(service.list_models(&model))
+            .unwrap();
+        assert_eq!(models, vec!["llama3:latest", "qwen2:7b"]);
+        assert!(requests.recv().unwrap().starts_with("GET /api/tags "));
+    }
+
+    #[test]
+    fn test_connection_reports_unknown_model() {
+        let (url, requests) = mock_server(
+            "404 Not Found",
+            "application/json",
+            "{\"error\":{\"message\":\"The model `mock-model` does not exist\",\"code\":\"model_not_found\"}}",
+        );
+        let service = mock_service("OpenAI", url);
+        let model = service.config.read().unwrap().models["mock"].clone();
+
+        let error = service
+            .runtime_handle
+            .block_on(service.test_connection(&model))
+            .unwrap_err();
+        assert!(error.to_string().starts_with("Model not found"));
+        assert!(requests.recv().unwrap().contains("\"max_tokens\":1"));
+    }
+
+    #[test]
+    fn test_provider_error_response() {
+        let (url, _requests) = mock_server(
+            "401 Unauthorized",
+            "application/json",
+            "{\"type\":\"error\",\"error\":{\"type\":\"authentication_error\",\"message\":\"invalid x-api-key\"}}",
+        );
+        let service = mock_service("Anthropic", url);
+
+        let error = run(&service).unwrap_err();
+        assert!(error.to_string().contains("Invalid API key"));
+    }
 }
@@ -0,0 +1,484 @@
+//! Change Tracker Service - Aggregates per-task file change statistics
+//!
+//! This service listens to session updates and accumulates every `Diff`
+//! reported by tool calls across the whole session (initial old text to latest
+//! new text per file). The resulting statistics are stored on the task bound to
+//! the session, and confirmed against `git diff` in the task's workspace once a
+//! prompt turn completes.
+//!
+//! Tracking starts from the statistics the task already has, so edits made
+//! before a restart or in an earlier prompt turn are kept.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use agent_client_protocol::{Diff, SessionUpdate, ToolCallContent};
+use anyhow::{Context, Result, anyhow};
+use similar::{ChangeTag, TextDiff};
+
+use agentx_event_bus::EventHub;
+use agentx_types::SessionStatus;
+use agentx_types::schemas::workspace::{TaskChangeSummary, TaskFileChange};
+
+use super::workspace_service::WorkspaceService;
+
+/// Initial and latest content of a file edited during a session
+#[derive(Clone, Debug)]
+struct FileState {
+    /// Content before the first edit (`None` if the file was created)
+    initial: Option<String>,
+    /// Content after the latest edit
+    latest: String,
+}
+
+/// Files edited during a session
+#[derive(Clone, Debug, Default)]
+struct SessionChanges {
+    /// Statistics the task had when tracking started (None until loaded)
+    baseline: Option<TaskChangeSummary>,
+    files: HashMap<PathBuf, FileState>,
+}
+
+/// Change tracker service - keeps task-level diff statistics up to date
+pub struct ChangeTrackerService {
+    workspace_service: Arc<WorkspaceService>,
+    /// Stores session_id -> tracked changes
+    sessions: Arc<Mutex<HashMap<String, SessionChanges>>>,
+    /// Serializes loading baselines and writing statistics to tasks
+    persist_lock: smol::lock::Mutex<()>,
+}
+
+impl ChangeTrackerService {
+    pub fn new(workspace_service: Arc<WorkspaceService>) -> Self {
+        Self {
+            workspace_service,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            persist_lock: smol::lock::Mutex::new(()),
+        }
+    }
+
+    /// Subscribe to session and workspace events
+    ///
+    /// Diffs are recorded synchronously; persisting the statistics and running
+    /// `git diff` happen on background tasks so the publisher is never blocked.
+    pub fn init(self: &Arc<Self>, event_hub: &EventHub) {
+        let tracker = self.clone();
+        event_hub.subscribe_session_updates(move |event| {
+            let diffs = diffs_from_update(&event.update);
+            if diffs.is_empty() {
+                return;
+            }
+
+            if tracker.record_diffs(&event.session_id, &diffs).is_none() {
+                return;
+            }
+
+            let tracker = tracker.clone();
+            let session_id = event.session_id.clone();
+            smol::spawn(async move {
+                if let Err(e) = tracker.persist_session(&session_id).await {
+                    log::error!(
+                        "Failed to update task changes for session {}: {}",
+                        session_id,
+                        e
+                    );
+                }
+            })
+            .detach();
+        });
+
+        let tracker = self.clone();
+        event_hub.subscribe_workspace_session_status(move |session_id, status| {
+            let confirm = match status {
+                SessionStatus::Completed | SessionStatus::Idle => true,
+                SessionStatus::Closed | SessionStatus::Failed => false,
+                _ => return,
+            };
+
+            // The turn is over: the next one starts from the stored statistics
+            let tracker = tracker.clone();
+            let session_id = session_id.clone();
+            smol::spawn(async move {
+                if confirm && let Err(e) = tracker.confirm_session(&session_id).await {
+                    log::debug!(
+                        "Skipping git confirmation for session {}: {}",
+                        session_id,
+                        e
+                    );
+                }
+                let _guard = tracker.persist_lock.lock().await;
+                tracker.clear_session(&session_id);
+            })
+            .detach();
+        });
+
+        log::info!("ChangeTrackerService subscriptions initialized (event_hub)");
+    }
+
+    /// Record diffs for a session
+    ///
+    /// Returns the new summary if any file state changed.
+    pub fn record_diffs(&self, session_id: &str, diffs: &[&Diff]) -> Option<TaskChangeSummary> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let changes = sessions.entry(session_id.to_string()).or_default();
+        let files = &mut changes.files;
+
+        let mut changed = false;
+        for diff in diffs {
+            match files.get_mut(&diff.path) {
+                Some(state) => {
+                    if state.latest != diff.new_text {
+                        state.latest = diff.new_text.clone();
+                        changed = true;
+                    }
+                }
+                None => {
+                    files.insert(
+                        diff.path.clone(),
+                        FileState {
+                            initial: diff.old_text.clone(),
+                            latest: diff.new_text.clone(),
+                        },
+                    );
+                    changed = true;
+                }
+            }
+        }
+
+        changed.then(|| summarize(changes))
+    }
+
+    /// Get the current summary for a session
+    pub fn summary_for_session(&self, session_id: &str) -> Option<TaskChangeSummary> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions.get(session_id).map(summarize)
+    }
+
+    /// Forget the tracked state of a session
+    pub fn clear_session(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+
+    /// Summary of a session, seeded with the statistics its task already has
+    async fn load_summary(&self, session_id: &str) -> Option<TaskChangeSummary> {
+        let has_baseline = {
+            let sessions = self.sessions.lock().unwrap();
+            sessions.get(session_id)?.baseline.is_some()
+        };
+        if !has_baseline {
+            let baseline = self
+                .workspace_service
+                .get_task_by_session(session_id)
+                .await
+                .map(|task| task.changes)
+                .unwrap_or_default();
+            let mut sessions = self.sessions.lock().unwrap();
+            let changes = sessions.get_mut(session_id)?;
+            if changes.baseline.is_none() {
+                changes.baseline = Some(baseline);
+            }
+        }
+        self.summary_for_session(session_id)
+    }
+
+    /// Store a session's statistics on its task
+    async fn persist_session(&self, session_id: &str) -> Result<()> {
+        let _guard = self.persist_lock.lock().await;
+        let Some(summary) = self.load_summary(session_id).await else {
+            return Ok(());
+        };
+        self.workspace_service
+            .update_task_changes(session_id, summary)
+            .await?;
+        Ok(())
+    }
+
+    /// Confirm a session's statistics against `git diff` in its workspace
+    pub async fn confirm_session(&self, session_id: &str) -> Result<()> {
+        let _guard = self.persist_lock.lock().await;
+        let summary = self
+            .load_summary(session_id)
+            .await
+            .ok_or_else(|| anyhow!("No tracked changes"))?;
+
+        let task = self
+            .workspace_service
+            .get_task_by_session(session_id)
+            .await
+            .ok_or_else(|| anyhow!("No task for session"))?;
+        let workspace = self
+            .workspace_service
+            .get_workspace(&task.workspace_id)
+            .await
+            .ok_or_else(|| anyhow!("Workspace not found: {}", task.workspace_id))?;
+
+        let cwd = task.working_dir.unwrap_or(workspace.path);
+        let confirmed = confirm_with_git(&cwd, summary).await?;
+        self.workspace_service
+            .update_task_changes(session_id, confirmed)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Extract all diffs carried by a tool call or tool call update
+fn diffs_from_update(update: &SessionUpdate) -> Vec<&Diff> {
+    let content: &[ToolCallContent] = match update {
+        SessionUpdate::ToolCall(tool_call) => &tool_call.content,
+        SessionUpdate::ToolCallUpdate(update) => update.fields.content.as_deref().unwrap_or(&[]),
+        _ => return Vec::new(),
+    };
+
+    content
+        .iter()
+        .filter_map(|content| match content {
+            ToolCallContent::Diff(diff) => Some(diff),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Build a summary from tracked file states (initial -> latest), added to
+/// the baseline statistics
+fn summarize(changes: &SessionChanges) -> TaskChangeSummary {
+    let mut files: HashMap<PathBuf, TaskFileChange> = changes
+        .baseline
+        .iter()
+        .flat_map(|baseline| baseline.files.iter())
+        .map(|file| (file.path.clone(), file.clone()))
+        .collect();
+    for (path, state) in &changes.files {
+        let change = file_change(path.clone(), state.initial.as_deref(), &state.latest);
+        match files.get_mut(path) {
+            Some(file) => {
+                file.additions += change.additions;
+                file.deletions += change.deletions;
+            }
+            None => {
+                files.insert(path.clone(), change);
+            }
+        }
+    }
+
+    let mut files: Vec<TaskFileChange> = files
+        .into_values()
+        .filter(|change| change.is_new_file || change.total_changes() > 0)
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    TaskChangeSummary {
+        files,
+        confirmed_by_git: false,
+    }
+}
+
+/// Calculate line statistics from old and new text
+fn file_change(path: PathBuf, old_text: Option<&str>, new_text: &str) -> TaskFileChange {
+    let (additions, deletions, is_new_file) = match old_text {
+        Some(old) => {
+            let diff = TextDiff::from_lines(old, new_text);
+            let (mut adds, mut dels) = (0, 0);
+            for change in diff.iter_all_changes() {
+                match change.tag() {
+                    ChangeTag::Insert => adds += 1,
+                    ChangeTag::Delete => dels += 1,
+                    ChangeTag::Equal => {}
+                }
+            }
+            (adds, dels, false)
+        }
+        None => (new_text.lines().count(), 0, true),
+    };
+
+    TaskFileChange {
+        path,
+        additions,
+        deletions,
+        is_new_file,
+    }
+}
+
+/// Replace tracked statistics with `git diff --numstat HEAD` for files git knows about
+///
+/// `--relative` reports paths relative to `cwd`, which may be a subdirectory
+/// of the repository.
+///
+/// Files missing from the git output (untracked new files, or changes that were
+/// already committed by the agent) keep their tracked statistics.
+async fn confirm_with_git(cwd: &Path, summary: TaskChangeSummary) -> Result<TaskChangeSummary> {
+    if summary.files.is_empty() {
+        return Ok(summary);
+    }
+
+    let relative_paths: Vec<PathBuf> = summary
+        .files
+        .iter()
+        .map(|file| relative_to(&file.path, cwd))
+        .collect();
+
+    let output = smol::process::Command::new("git")
+        .arg("diff")
+        .arg("--numstat")
+        .arg("--relative")
+        .arg("HEAD")
+        .arg("--")
+        .args(&relative_paths)
+        .current_dir(cwd)
+        .output()
+        .await
+        .context("Failed to run git diff")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let numstat = parse_numstat(&String::from_utf8_lossy(&output.stdout));
+
+    let files = summary
+        .files
+        .into_iter()
+        .zip(relative_paths)
+        .map(|(mut file, relative)| {
+            if let Some((additions, deletions)) = numstat.get(&relative) {
+                file.additions = *additions;
+                file.deletions = *deletions;
+            }
+            file
+        })
+        .collect();
+
+    Ok(TaskChangeSummary {
+        files,
+        confirmed_by_git: true,
+    })
+}
+
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    path.strip_prefix(base)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Parse `git diff --numstat` output into path -> (additions, deletions)
+///
+/// Binary files (reported as `-`) count as zero lines.
+fn parse_numstat(output: &str) -> HashMap<PathBuf, (usize, usize)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let additions = parts.next()?.parse().unwrap_or(0);
+            let deletions = parts.next()?.parse().unwrap_or(0);
+            let path = parts.next()?;
+            Some((PathBuf::from(path), (additions, deletions)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_tracker() -> ChangeTrackerService {
+        // The workspace config is never written by these tests
+        let config_path = std::env::temp_dir().join("agentx-change-tracker-test.json");
+        ChangeTrackerService::new(Arc::new(WorkspaceService::new(config_path)))
+    }
+
+    #[test]
+    fn test_record_diffs_merges_multiple_edits() {
+        let tracker = create_test_tracker();
+        let path = PathBuf::from("src/lib.rs");
+
+        let first = Diff::new(path.clone(), "a\nb\n".to_string()).old_text("a\n".to_string());
+        let second =
+            Diff::new(path.clone(), "a\nb\nc\n".to_string()).old_text("a\nb\n".to_string());
+
+        tracker.record_diffs("session-1", &[&first]).unwrap();
+        let summary = tracker.record_diffs("session-1", &[&second]).unwrap();
+
+        assert_eq!(summary.total_files(), 1);
+        assert_eq!(summary.total_additions(), 2);
+        assert_eq!(summary.total_deletions(), 0);
+    }
+
+    #[test]
+    fn test_record_diffs_ignores_repeated_content() {
+        let tracker = create_test_tracker();
+        let diff = Diff::new(PathBuf::from("new.txt"), "one\ntwo\n".to_string());
+
+        let summary = tracker.record_diffs("session-1", &[&diff]).unwrap();
+        assert!(summary.files[0].is_new_file);
+        assert_eq!(summary.total_additions(), 2);
+
+        assert!(tracker.record_diffs("session-1", &[&diff]).is_none());
+    }
+
+    #[test]
+    fn test_reverted_file_is_dropped() {
+        let tracker = create_test_tracker();
+        let path = PathBuf::from("file.txt");
+
+        let edit = Diff::new(path.clone(), "changed\n".to_string()).old_text("orig\n".to_string());
+        let revert =
+            Diff::new(path.clone(), "orig\n".to_string()).old_text("changed\n".to_string());
+
+        tracker.record_diffs("session-1", &[&edit]).unwrap();
+        let summary = tracker.record_diffs("session-1", &[&revert]).unwrap();
+
+        assert!(!summary.has_changes());
+    }
+
+    #[test]
+    fn test_baseline_is_kept() {
+        let tracker = create_test_tracker();
+        let baseline = TaskChangeSummary {
+            files: vec![
+                TaskFileChange {
+                    path: PathBuf::from("a.rs"),
+                    additions: 4,
+                    deletions: 1,
+                    is_new_file: false,
+                },
+                TaskFileChange {
+                    path: PathBuf::from("b.rs"),
+                    additions: 2,
+                    deletions: 0,
+                    is_new_file: true,
+                },
+            ],
+            confirmed_by_git: true,
+        };
+        tracker.sessions.lock().unwrap().insert(
+            "session-1".to_string(),
+            SessionChanges {
+                baseline: Some(baseline),
+                files: HashMap::new(),
+            },
+        );
+
+        let edit =
+            Diff::new(PathBuf::from("a.rs"), "x\ny\n".to_string()).old_text("x\n".to_string());
+        let summary = tracker.record_diffs("session-1", &[&edit]).unwrap();
+
+        assert_eq!(summary.total_files(), 2);
+        assert_eq!(summary.files[0].additions, 5);
+        assert_eq!(summary.files[0].deletions, 1);
+        assert!(summary.files[1].is_new_file);
+        assert!(!summary.confirmed_by_git);
+    }
+
+    #[test]
+    fn test_parse_numstat() {
+        let stats = parse_numstat("3\t1\tsrc/main.rs\n-\t-\tassets/logo.png\n");
+
+        assert_eq!(stats.get(Path::new("src/main.rs")), Some(&(3, 1)));
+        assert_eq!(stats.get(Path::new("assets/logo.png")), Some(&(0, 0)));
+    }
+}
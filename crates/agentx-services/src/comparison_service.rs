@@ -0,0 +1,406 @@
+//! Comparison Service - Runs one prompt against several agents side by side
+//!
+//! A comparison creates one queued task per selected agent, each with its own
+//! session and (optionally) its own git worktree. Once the candidates are done,
+//! the winner's changes are committed in its worktree and its branch is merged
+//! into the workspace; the other worktrees and branches are removed.
+
+use std::sync::Arc;
+
+use agent_client_protocol as acp;
+use anyhow::{Result, anyhow};
+
+use agentx_types::SessionStatus;
+use agentx_types::schemas::workspace::{TaskComparison, WorkspaceTask};
+
+use super::git::{self, TaskWorktree};
+use super::workspace_service::WorkspaceService;
+
+/// Default mode recorded on candidate tasks when none is given
+const DEFAULT_TASK_MODE: &str = "default";
+
+/// Maximum length of a comparison name derived from the prompt
+const MAX_NAME_LENGTH: usize = 50;
+
+/// Parameters of a new comparison
+#[derive(Clone, Debug, Default)]
+pub struct ComparisonRequest {
+    pub workspace_id: String,
+    pub prompt: String,
+    /// Extra content sent with the prompt (images, code selections)
+    pub attachments: Vec<acp::ContentBlock>,
+    /// Agents to compare, in display order
+    pub agents: Vec<String>,
+    /// Session mode for all candidates (None = default mode)
+    pub mode: Option<String>,
+    /// Run each candidate in its own git worktree
+    pub use_worktrees: bool,
+}
+
+/// Comparison service - creates comparisons and merges their winner
+pub struct ComparisonService {
+    workspace_service: Arc<WorkspaceService>,
+}
+
+impl ComparisonService {
+    pub fn new(workspace_service: Arc<WorkspaceService>) -> Self {
+        Self { workspace_service }
+    }
+
+    /// Create a comparison and enqueue one candidate task per agent
+    pub async fn start(&self, request: ComparisonRequest) -> Result<TaskComparison> {
+        let agents = unique_agents(&request.agents);
+        if agents.len() < 2 {
+            return Err(anyhow!("Select at least two agents to compare"));
+        }
+        if request.prompt.trim().is_empty() {
+            return Err(anyhow!("Comparison prompt cannot be empty"));
+        }
+
+        let workspace = self
+            .workspace_service
+            .get_workspace(&request.workspace_id)
+            .await
+            .ok_or_else(|| anyhow!("Workspace not found: {}", request.workspace_id))?;
+
+        let name = comparison_name(&request.prompt);
+        let mode = request
+            .mode
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TASK_MODE.to_string());
+
+        let mut tasks = Vec::new();
+        let mut worktrees = Vec::new();
+        for agent_name in agents {
+            let mut task = WorkspaceTask::new_queued(
+                request.workspace_id.clone(),
+                format!("{} ({})", name, agent_name),
+                agent_name.clone(),
+                mode.clone(),
+                request.prompt.clone(),
+                None,
+            );
+            task.attachments = request.attachments.clone();
+
+            if request.use_worktrees {
+                let worktree = match git::create_worktree(
+                    &workspace.path,
+                    &format!("{}-{}", name, agent_name),
+                )
+                .await
+                {
+                    Ok(worktree) => worktree,
+                    Err(e) => {
+                        // Don't leave half of the candidates' worktrees behind
+                        for worktree in &worktrees {
+                            if let Err(e) =
+                                git::remove_worktree(&workspace.path, worktree, true).await
+                            {
+                                log::warn!("Failed to clean up worktree: {}", e);
+                            }
+                        }
+                        return Err(e);
+                    }
+                };
+                task.working_dir = Some(worktree.path.clone());
+                task.branch = Some(worktree.branch.clone());
+                worktrees.push(worktree);
+            }
+
+            tasks.push(task);
+        }
+
+        self.workspace_service
+            .add_comparison(
+                TaskComparison::new(request.workspace_id.clone(), name),
+                tasks,
+            )
+            .await
+    }
+
+    /// Pick the winning candidate of a comparison
+    ///
+    /// Every candidate must have finished. The winner is recorded first, so a
+    /// second pick fails instead of merging another candidate. If the winner
+    /// ran in a worktree, its changes are committed and its branch is merged
+    /// into the workspace; a failed merge clears the winner again. All
+    /// candidate worktrees are then removed, along with the branches of the
+    /// other candidates.
+    pub async fn pick_winner(&self, comparison_id: &str, task_id: &str) -> Result<()> {
+        let comparison = self
+            .workspace_service
+            .get_comparison(comparison_id)
+            .await
+            .ok_or_else(|| anyhow!("Comparison not found: {}", comparison_id))?;
+        if comparison.winner_task_id.is_some() {
+            return Err(anyhow!("A winner was already picked for this comparison"));
+        }
+
+        let tasks = self
+            .workspace_service
+            .get_comparison_tasks(comparison_id)
+            .await;
+        let winner = tasks
+            .iter()
+            .find(|t| t.id == task_id)
+            .ok_or_else(|| anyhow!("Task {} is not part of this comparison", task_id))?;
+        if !is_finished(winner.status) {
+            return Err(anyhow!("The agent has not finished this task yet"));
+        }
+        // Losing worktrees are force-removed, so no agent may still be using one
+        if let Some(running) = tasks.iter().find(|t| !is_finished(t.status)) {
+            return Err(anyhow!(
+                "Wait for {} to finish before picking a winner",
+                running.agent_name
+            ));
+        }
+
+        let workspace = self
+            .workspace_service
+            .get_workspace(&comparison.workspace_id)
+            .await
+            .ok_or_else(|| anyhow!("Workspace not found: {}", comparison.workspace_id))?;
+
+        self.workspace_service
+            .set_comparison_winner(comparison_id, task_id)
+            .await?;
+
+        if let Some(worktree) = task_worktree(winner) {
+            let merged: Result<()> = async {
+                git::commit_all(&worktree.path, &winner.name).await?;
+                git::merge_branch(
+                    &workspace.path,
+                    &worktree.branch,
+                    &format!("Merge {} ({})", comparison.name, winner.agent_name),
+                )
+                .await
+            }
+            .await;
+            if let Err(e) = merged {
+                if let Err(clear_error) = self
+                    .workspace_service
+                    .clear_comparison_winner(comparison_id)
+                    .await
+                {
+                    log::warn!(
+                        "Failed to clear the winner of comparison {}: {}",
+                        comparison_id,
+                        clear_error
+                    );
+                }
+                return Err(e);
+            }
+        }
+
+        for task in &tasks {
+            let Some(worktree) = task_worktree(task) else {
+                continue;
+            };
+            if let Err(e) =
+                git::remove_worktree(&workspace.path, &worktree, task.id != task_id).await
+            {
+                log::warn!("Failed to remove worktree of task {}: {}", task.id, e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a candidate is done running (queued or running candidates are not)
+fn is_finished(status: SessionStatus) -> bool {
+    !matches!(status, SessionStatus::Pending | SessionStatus::InProgress)
+}
+
+/// Get the worktree a task runs in, if it was created with one
+fn task_worktree(task: &WorkspaceTask) -> Option<TaskWorktree> {
+    Some(TaskWorktree {
+        path: task.working_dir.clone()?,
+        branch: task.branch.clone()?,
+    })
+}
+
+/// Remove empty and duplicate agent names, keeping the selection order
+fn unique_agents(agents: &[String]) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for agent in agents.iter().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+        if !unique.iter().any(|a| a == agent) {
+            unique.push(agent.to_string());
+        }
+    }
+    unique
+}
+
+/// Derive a short comparison name from the first line of the prompt
+fn comparison_name(prompt: &str) -> String {
+    let first_line = prompt.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    let first_line = first_line.trim();
+
+    if first_line.chars().count() > MAX_NAME_LENGTH {
+        let truncated: String = first_line.chars().take(MAX_NAME_LENGTH).collect();
+        format!("{}...", truncated.trim_end())
+    } else {
+        first_line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a workspace with a comparison of two candidates without worktrees
+    async fn create_comparison() -> (ComparisonService, Arc<WorkspaceService>, TaskComparison) {
+        let dir = std::env::temp_dir().join(format!("agentx-comparison-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let workspace_service = Arc::new(WorkspaceService::new(dir.join("workspace.json")));
+        let workspace = workspace_service.add_workspace(dir).await.unwrap();
+
+        let service = ComparisonService::new(workspace_service.clone());
+        let comparison = service
+            .start(ComparisonRequest {
+                workspace_id: workspace.id,
+                prompt: "Fix the login bug".to_string(),
+                agents: vec!["claude".to_string(), "codex".to_string()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        (service, workspace_service, comparison)
+    }
+
+    async fn set_statuses(
+        workspace_service: &WorkspaceService,
+        comparison: &TaskComparison,
+        statuses: [SessionStatus; 2],
+    ) {
+        for (task_id, status) in comparison.task_ids.iter().zip(statuses) {
+            workspace_service
+                .update_task_status(task_id, status)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pick_winner_waits_for_all_candidates() {
+        let (service, workspace_service, comparison) = create_comparison().await;
+        let winner_id = &comparison.task_ids[0];
+
+        // Neither a queued winner nor a loser that is still running can be settled
+        assert!(
+            service
+                .pick_winner(&comparison.id, winner_id)
+                .await
+                .is_err()
+        );
+        set_statuses(
+            &workspace_service,
+            &comparison,
+            [SessionStatus::Completed, SessionStatus::InProgress],
+        )
+        .await;
+        assert!(
+            service
+                .pick_winner(&comparison.id, winner_id)
+                .await
+                .is_err()
+        );
+        set_statuses(
+            &workspace_service,
+            &comparison,
+            [SessionStatus::Completed, SessionStatus::Pending],
+        )
+        .await;
+        assert!(
+            service
+                .pick_winner(&comparison.id, winner_id)
+                .await
+                .is_err()
+        );
+
+        let stored = workspace_service
+            .get_comparison(&comparison.id)
+            .await
+            .unwrap();
+        assert_eq!(stored.winner_task_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_pick_winner_records_winner_once() {
+        let (service, workspace_service, comparison) = create_comparison().await;
+        let winner_id = &comparison.task_ids[1];
+        set_statuses(
+            &workspace_service,
+            &comparison,
+            [SessionStatus::Failed, SessionStatus::Completed],
+        )
+        .await;
+
+        service
+            .pick_winner(&comparison.id, winner_id)
+            .await
+            .unwrap();
+        let stored = workspace_service
+            .get_comparison(&comparison.id)
+            .await
+            .unwrap();
+        assert_eq!(stored.winner_task_id.as_deref(), Some(winner_id.as_str()));
+
+        assert!(
+            service
+                .pick_winner(&comparison.id, winner_id)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_picks_merge_one_winner() {
+        let (service, workspace_service, comparison) = create_comparison().await;
+        set_statuses(
+            &workspace_service,
+            &comparison,
+            [SessionStatus::Completed, SessionStatus::Completed],
+        )
+        .await;
+
+        let (first, second) = tokio::join!(
+            service.pick_winner(&comparison.id, &comparison.task_ids[0]),
+            service.pick_winner(&comparison.id, &comparison.task_ids[1]),
+        );
+        assert!(first.is_ok() != second.is_ok());
+
+        let stored = workspace_service
+            .get_comparison(&comparison.id)
+            .await
+            .unwrap();
+        let winner = if first.is_ok() { 0 } else { 1 };
+        assert_eq!(
+            stored.winner_task_id.as_deref(),
+            Some(comparison.task_ids[winner].as_str())
+        );
+    }
+
+    #[test]
+    fn test_unique_agents_keeps_order() {
+        let agents = vec![
+            "codex".to_string(),
+            " claude ".to_string(),
+            "codex".to_string(),
+            "".to_string(),
+        ];
+
+        assert_eq!(unique_agents(&agents), vec!["codex", "claude"]);
+    }
+
+    #[test]
+    fn test_comparison_name() {
+        assert_eq!(
+            comparison_name("\n  Fix the login bug  \nDetails"),
+            "Fix the login bug"
+        );
+
+        let long = "a".repeat(80);
+        assert_eq!(comparison_name(&long), format!("{}...", "a".repeat(50)));
+    }
+}
@@ -0,0 +1,239 @@
+//! Configuration Diff
+//!
+//! Works out what changed between two versions of `config.json`, so a reload
+//! can publish fine-grained events and restart only the agents it has to.
+
+use std::collections::HashMap;
+
+use agentx_event_bus::AgentConfigEvent;
+use agentx_types::schemas::task_template::TaskTemplate;
+use agentx_types::{AgentProcessConfig, CommandConfig, Config, McpServerConfig, ModelConfig};
+use serde::Serialize;
+
+/// Entries added, updated and removed in one section of the config
+#[derive(Debug, Clone)]
+pub struct MapDiff<T> {
+    pub added: Vec<(String, T)>,
+    pub updated: Vec<(String, T)>,
+    pub removed: Vec<String>,
+}
+
+impl<T: Clone + Serialize> MapDiff<T> {
+    /// Compare two maps by their serialized form
+    ///
+    /// Runtime-only fields (`#[serde(skip)]`) don't count as changes.
+    fn between(old: &HashMap<String, T>, new: &HashMap<String, T>) -> Self {
+        let mut added = Vec::new();
+        let mut updated = Vec::new();
+        for (name, value) in new {
+            match old.get(name) {
+                None => added.push((name.clone(), value.clone())),
+                Some(previous) if !same(previous, value) => {
+                    updated.push((name.clone(), value.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        let mut removed: Vec<String> = old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .cloned()
+            .collect();
+
+        added.sort_by(|a, b| a.0.cmp(&b.0));
+        updated.sort_by(|a, b| a.0.cmp(&b.0));
+        removed.sort();
+        Self {
+            added,
+            updated,
+            removed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Structured difference between two configurations
+#[derive(Debug, Clone)]
+pub struct ConfigDiff {
+    pub agents: MapDiff<AgentProcessConfig>,
+    pub models: MapDiff<ModelConfig>,
+    pub mcp_servers: MapDiff<McpServerConfig>,
+    pub commands: MapDiff<CommandConfig>,
+    pub task_templates: MapDiff<TaskTemplate>,
+    /// Whether anything outside the sections above changed (proxy, prompts,
+    /// language servers, pipelines, ...)
+    pub settings_changed: bool,
+}
+
+impl ConfigDiff {
+    pub fn between(old: &Config, new: &Config) -> Self {
+        Self {
+            agents: MapDiff::between(&old.agent_servers, &new.agent_servers),
+            models: MapDiff::between(&old.models, &new.models),
+            mcp_servers: MapDiff::between(&old.mcp_servers, &new.mcp_servers),
+            commands: MapDiff::between(&old.commands, &new.commands),
+            task_templates: MapDiff::between(&old.task_templates, &new.task_templates),
+            settings_changed: !same(&other_settings(old), &other_settings(new)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+            && self.models.is_empty()
+            && self.mcp_servers.is_empty()
+            && self.commands.is_empty()
+            && self.task_templates.is_empty()
+            && !self.settings_changed
+    }
+
+    /// Events describing the diff, except for agents (published once their
+    /// processes have been updated)
+    pub fn events(&self) -> Vec<AgentConfigEvent> {
+        let mut events = Vec::new();
+
+        for (name, config) in &self.models.added {
+            events.push(AgentConfigEvent::ModelAdded {
+                name: name.clone(),
+                config: config.clone(),
+            });
+        }
+        for (name, config) in &self.models.updated {
+            events.push(AgentConfigEvent::ModelUpdated {
+                name: name.clone(),
+                config: config.clone(),
+            });
+        }
+        for name in &self.models.removed {
+            events.push(AgentConfigEvent::ModelRemoved { name: name.clone() });
+        }
+
+        for (name, config) in &self.mcp_servers.added {
+            events.push(AgentConfigEvent::McpServerAdded {
+                name: name.clone(),
+                config: config.clone(),
+            });
+        }
+        for (name, config) in &self.mcp_servers.updated {
+            events.push(AgentConfigEvent::McpServerUpdated {
+                name: name.clone(),
+                config: config.clone(),
+            });
+        }
+        for name in &self.mcp_servers.removed {
+            events.push(AgentConfigEvent::McpServerRemoved { name: name.clone() });
+        }
+
+        for (name, config) in &self.commands.added {
+            events.push(AgentConfigEvent::CommandAdded {
+                name: name.clone(),
+                config: config.clone(),
+            });
+        }
+        for (name, config) in &self.commands.updated {
+            events.push(AgentConfigEvent::CommandUpdated {
+                name: name.clone(),
+                config: config.clone(),
+            });
+        }
+        for name in &self.commands.removed {
+            events.push(AgentConfigEvent::CommandRemoved { name: name.clone() });
+        }
+
+        for (name, template) in &self.task_templates.added {
+            events.push(AgentConfigEvent::TaskTemplateAdded {
+                name: name.clone(),
+                template: template.clone(),
+            });
+        }
+        for (name, template) in &self.task_templates.updated {
+            events.push(AgentConfigEvent::TaskTemplateUpdated {
+                name: name.clone(),
+                template: template.clone(),
+            });
+        }
+        for name in &self.task_templates.removed {
+            events.push(AgentConfigEvent::TaskTemplateRemoved { name: name.clone() });
+        }
+
+        events
+    }
+}
+
+pub(crate) fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// The config with the diffed sections cleared
+fn other_settings(config: &Config) -> Config {
+    Config {
+        agent_servers: HashMap::new(),
+        models: HashMap::new(),
+        mcp_servers: HashMap::new(),
+        commands: HashMap::new(),
+        task_templates: HashMap::new(),
+        ..config.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(json: &str) -> Config {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let old = config(
+            r#"{
+                "agent_servers": {
+                    "a": { "command": "agent-a" },
+                    "b": { "command": "agent-b" }
+                },
+                "mcpServers": { "fs": { "command": "npx" } }
+            }"#,
+        );
+        let new = config(
+            r#"{
+                "agent_servers": {
+                    "a": { "command": "agent-a" },
+                    "b": { "command": "agent-b", "args": ["--acp"] },
+                    "c": { "command": "agent-c" }
+                },
+                "mcpServers": {}
+            }"#,
+        );
+
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(diff.agents.added.len(), 1);
+        assert_eq!(diff.agents.added[0].0, "c");
+        assert_eq!(diff.agents.updated.len(), 1);
+        assert_eq!(diff.agents.updated[0].0, "b");
+        assert!(diff.agents.removed.is_empty());
+        assert_eq!(diff.mcp_servers.removed, vec!["fs"]);
+        assert!(!diff.settings_changed);
+        assert!(matches!(
+            diff.events().as_slice(),
+            [AgentConfigEvent::McpServerRemoved { name }] if name == "fs"
+        ));
+
+        assert!(ConfigDiff::between(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_settings_change() {
+        let old = config(r#"{ "agent_servers": {} }"#);
+        let new = config(r#"{ "agent_servers": {}, "system_prompts": { "explain": "Be brief" } }"#);
+
+        let diff = ConfigDiff::between(&old, &new);
+        assert!(diff.settings_changed);
+        assert!(diff.events().is_empty());
+    }
+}
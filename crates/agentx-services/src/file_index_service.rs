@@ -0,0 +1,482 @@
+//! File Index Service
+//!
+//! Keeps an index of the files of each workspace folder for the file picker,
+//! `@` mentions and quick-open. Indexes are built in the background, honor
+//! `.gitignore`, and are kept current by a file watcher.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    time::Duration,
+};
+
+use ignore::WalkBuilder;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher, event::ModifyKind};
+use smol::channel;
+
+/// Quiet period before a burst of file system events triggers a rescan
+const RESCAN_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// An indexed file or folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub path: PathBuf,
+    /// Path relative to the index root, with `/` separators
+    pub relative_path: String,
+    pub is_dir: bool,
+}
+
+impl IndexEntry {
+    pub fn name(&self) -> &str {
+        self.relative_path
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.relative_path)
+    }
+}
+
+/// A search hit with its score and the matched character positions
+#[derive(Debug, Clone)]
+pub struct FileMatch {
+    pub entry: IndexEntry,
+    pub score: i64,
+    /// Char indices of the matched characters in `entry.relative_path`
+    pub positions: Vec<usize>,
+}
+
+/// File Index Service
+///
+/// Holds one index per workspace folder, created on first use.
+pub struct FileIndexService {
+    indexes: Mutex<HashMap<PathBuf, Arc<FileIndex>>>,
+}
+
+impl FileIndexService {
+    pub fn new() -> Self {
+        Self {
+            indexes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The index of a folder, starting to build it if needed
+    pub fn index(&self, root: &Path) -> Arc<FileIndex> {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        self.indexes
+            .lock()
+            .unwrap()
+            .entry(root.clone())
+            .or_insert_with(|| FileIndex::start(root))
+            .clone()
+    }
+}
+
+impl Default for FileIndexService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The files of one folder
+pub struct FileIndex {
+    root: PathBuf,
+    entries: RwLock<Vec<IndexEntry>>,
+    ready: AtomicBool,
+    /// Closed once the first scan completes
+    ready_rx: channel::Receiver<()>,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl FileIndex {
+    fn start(root: PathBuf) -> Arc<Self> {
+        let (ready_tx, ready_rx) = channel::bounded(1);
+        let (rescan_tx, rescan_rx) = mpsc::channel::<()>();
+
+        let watcher = Self::watch(&root, rescan_tx)
+            .inspect_err(|e| {
+                log::warn!(
+                    "[FileIndexService] Not watching {}, the index will not update: {}",
+                    root.display(),
+                    e
+                )
+            })
+            .ok();
+
+        let index = Arc::new(Self {
+            root,
+            entries: RwLock::new(Vec::new()),
+            ready: AtomicBool::new(false),
+            ready_rx,
+            _watcher: watcher,
+        });
+
+        let weak = Arc::downgrade(&index);
+        std::thread::Builder::new()
+            .name("file-index".to_string())
+            .spawn(move || {
+                let Some(index) = weak.upgrade() else {
+                    return;
+                };
+                index.rescan();
+                index.ready.store(true, Ordering::SeqCst);
+                drop(ready_tx);
+                drop(index);
+
+                // Rescan after each burst of changes, until the index is dropped
+                while rescan_rx.recv().is_ok() {
+                    while rescan_rx.recv_timeout(RESCAN_DEBOUNCE).is_ok() {}
+                    let Some(index) = weak.upgrade() else {
+                        return;
+                    };
+                    index.rescan();
+                }
+            })
+            .expect("Failed to spawn file index thread");
+
+        index
+    }
+
+    fn watch(root: &Path, rescan_tx: mpsc::Sender<()>) -> notify::Result<RecommendedWatcher> {
+        let watch_root = root.to_path_buf();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+                Ok(event) => {
+                    if Self::affects_index(&watch_root, &event) {
+                        let _ = rescan_tx.send(());
+                    }
+                }
+                Err(e) => log::error!("File index watch error: {:?}", e),
+            })?;
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        Ok(watcher)
+    }
+
+    /// Whether an event may add, remove or re-ignore files
+    ///
+    /// Content changes are skipped, and so are events inside `.git`. Changes
+    /// inside ignored folders still trigger a (cheap) rescan, which is the
+    /// price of not re-evaluating ignore rules here.
+    fn affects_index(root: &Path, event: &Event) -> bool {
+        let is_ignore_file = |path: &PathBuf| {
+            path.file_name()
+                .is_some_and(|name| name == ".gitignore" || name == ".ignore")
+        };
+
+        match event.kind {
+            EventKind::Access(_) => return false,
+            EventKind::Modify(ModifyKind::Data(_)) | EventKind::Modify(ModifyKind::Metadata(_)) => {
+                return event.paths.iter().any(is_ignore_file);
+            }
+            _ => {}
+        }
+
+        event.paths.iter().any(|path| {
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            !relative.starts_with(".git")
+        })
+    }
+
+    fn rescan(&self) {
+        let entries = scan(&self.root);
+        log::debug!(
+            "[FileIndexService] Indexed {} entries in {}",
+            entries.len(),
+            self.root.display()
+        );
+        *self.entries.write().unwrap() = entries;
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the first scan has completed
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    /// Wait for the first scan to complete
+    pub async fn wait_until_ready(&self) {
+        // The sender is dropped after the scan, which closes the channel
+        let _ = self.ready_rx.recv().await;
+    }
+
+    /// The indexed files (folders excluded)
+    pub fn files(&self) -> Vec<IndexEntry> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|entry| !entry.is_dir)
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fuzzy-search the index, best matches first
+    ///
+    /// An empty query returns the first `limit` entries in path order.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<FileMatch> {
+        let entries = self.entries.read().unwrap();
+        let query = query.trim();
+        if query.is_empty() {
+            return entries
+                .iter()
+                .take(limit)
+                .map(|entry| FileMatch {
+                    entry: entry.clone(),
+                    score: 0,
+                    positions: Vec::new(),
+                })
+                .collect();
+        }
+
+        let mut matches: Vec<FileMatch> = entries
+            .iter()
+            .filter_map(|entry| {
+                let (score, positions) = fuzzy_match(query, &entry.relative_path)?;
+                Some(FileMatch {
+                    entry: entry.clone(),
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| {
+                    a.entry
+                        .relative_path
+                        .len()
+                        .cmp(&b.entry.relative_path.len())
+                })
+                .then_with(|| a.entry.relative_path.cmp(&b.entry.relative_path))
+        });
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// Walk a folder, honoring `.gitignore`, `.ignore` and `.git/info/exclude`
+fn scan(root: &Path) -> Vec<IndexEntry> {
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .require_git(false)
+        .follow_links(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut entries: Vec<IndexEntry> = walker
+        .flatten()
+        .filter(|entry| entry.depth() > 0)
+        .map(|entry| {
+            let path = entry.path().to_path_buf();
+            let relative_path = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            IndexEntry {
+                is_dir: entry.file_type().is_some_and(|t| t.is_dir()),
+                path,
+                relative_path,
+            }
+        })
+        .collect();
+
+    entries.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    entries
+}
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_SEGMENT_START: i64 = 12;
+const BONUS_WORD_START: i64 = 8;
+const BONUS_CONSECUTIVE: i64 = 6;
+const BONUS_FILE_NAME: i64 = 4;
+const PENALTY_GAP: i64 = 4;
+
+/// Score a path against a query
+///
+/// The query must be a case-insensitive subsequence of the path. Among all
+/// the ways to match it, the best one is picked: matches at the start of a
+/// path segment or word, consecutive matches and matches in the file name
+/// score higher, gaps between matches score lower. Returns the score and the
+/// char indices of the matched characters.
+pub fn fuzzy_match(query: &str, path: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let chars: Vec<char> = path.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    // Cheap rejection before the full alignment
+    let mut rest = query.iter().peekable();
+    for c in &lower {
+        if rest.peek() == Some(&c) {
+            rest.next();
+        }
+    }
+    if rest.peek().is_some() {
+        return None;
+    }
+
+    let n = chars.len();
+    let file_name_start = path
+        .rfind('/')
+        .map_or(0, |ix| path[..ix].chars().count() + 1);
+    let base = |j: usize| {
+        let mut score = SCORE_MATCH + boundary_bonus(&chars, j);
+        if j >= file_name_start {
+            score += BONUS_FILE_NAME;
+        }
+        score
+    };
+
+    // scores[i][j]: best score with query[i] matched at j; from[i][j]: where
+    // query[i - 1] was matched in that case
+    let mut scores = vec![vec![None::<i64>; n]; query.len()];
+    let mut from = vec![vec![0usize; n]; query.len()];
+    for j in 0..n {
+        if lower[j] == query[0] {
+            scores[0][j] = Some(base(j));
+        }
+    }
+    for i in 1..query.len() {
+        // Best score of query[i - 1] strictly before j - 1, for gapped matches
+        let mut best_before: Option<(i64, usize)> = None;
+        for j in 1..n {
+            if j >= 2
+                && let Some(score) = scores[i - 1][j - 2]
+                && best_before.is_none_or(|(best, _)| score > best)
+            {
+                best_before = Some((score, j - 2));
+            }
+            if lower[j] != query[i] {
+                continue;
+            }
+
+            let consecutive = scores[i - 1][j - 1].map(|s| (s + BONUS_CONSECUTIVE, j - 1));
+            let gapped = best_before.map(|(s, k)| (s - PENALTY_GAP, k));
+            let best = match (consecutive, gapped) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + base(j));
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let last = query.len() - 1;
+    let (mut j, score) = (0..n)
+        .filter_map(|j| scores[last][j].map(|score| (j, score)))
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+
+    let mut positions = vec![0; query.len()];
+    for i in (0..query.len()).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+
+    // Prefer shorter paths a little
+    Some((score - n as i64 / 16, positions))
+}
+
+fn boundary_bonus(chars: &[char], ix: usize) -> i64 {
+    let Some(prev) = ix.checked_sub(1).map(|p| chars[p]) else {
+        return BONUS_SEGMENT_START;
+    };
+    let current = chars[ix];
+    if prev == '/' {
+        BONUS_SEGMENT_START
+    } else if matches!(prev, '_' | '-' | '.' | ' ')
+        || (prev.is_lowercase() && current.is_uppercase())
+    {
+        BONUS_WORD_START
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn best<'a>(query: &str, paths: &[&'a str]) -> Vec<&'a str> {
+        let mut scored: Vec<(i64, &str)> = paths
+            .iter()
+            .filter_map(|path| fuzzy_match(query, path).map(|(score, _)| (score, *path)))
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, path)| path).collect()
+    }
+
+    #[test]
+    fn test_fuzzy_match_requires_subsequence() {
+        assert!(fuzzy_match("fpk", "src/components/file_picker.rs").is_some());
+        assert!(fuzzy_match("FILEPICK", "src/components/file_picker.rs").is_some());
+        assert!(fuzzy_match("pkf", "src/components/file_picker.rs").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_match_positions() {
+        let (_, positions) = fuzzy_match("mod", "src/mod.rs").unwrap();
+        assert_eq!(positions, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn test_fuzzy_match_prefers_segments_and_file_names() {
+        let ranked = best(
+            "picker",
+            &[
+                "src/pick/er.rs",
+                "src/components/file_picker.rs",
+                "crates/deep/nested/path/with/a/picker.rs",
+            ],
+        );
+        assert_eq!(ranked[0], "crates/deep/nested/path/with/a/picker.rs");
+        assert_eq!(ranked[2], "src/pick/er.rs");
+
+        let ranked = best("ws", &["crates/awesome.rs", "src/workspace/mod.rs"]);
+        assert_eq!(ranked[0], "src/workspace/mod.rs");
+    }
+
+    #[test]
+    fn test_scan_honors_gitignore() {
+        let root = std::env::temp_dir().join(format!("file-index-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("a/b/c/d/e")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(root.join("a/b/c/d/e/deep.rs"), "").unwrap();
+        std::fs::write(root.join("target/out.rs"), "").unwrap();
+
+        let paths: Vec<String> = scan(&root).into_iter().map(|e| e.relative_path).collect();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert!(paths.contains(&"a/b/c/d/e/deep.rs".to_string()));
+        assert!(!paths.iter().any(|p| p.starts_with("target")));
+    }
+}
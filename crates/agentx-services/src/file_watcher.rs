@@ -0,0 +1,115 @@
+//! File Watcher
+//!
+//! Watches individual files (e.g. the files open in the code editor) and
+//! reports when they are rewritten on disk, for example by an agent.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use smol::channel;
+
+/// Watched files: canonical path -> path as passed to `watch`
+type WatchedFiles = Arc<Mutex<HashMap<PathBuf, PathBuf>>>;
+
+/// Watches a set of files for changes
+///
+/// The parent directories are watched rather than the files themselves, so
+/// files replaced by a rename (atomic writes) keep being tracked.
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    files: WatchedFiles,
+    /// Watched directories with the number of watched files they contain
+    directories: HashMap<PathBuf, usize>,
+}
+
+impl FileWatcher {
+    /// Create a watcher and the channel receiving the paths of changed files
+    pub fn new() -> Result<(Self, channel::Receiver<PathBuf>)> {
+        let (tx, rx) = channel::unbounded();
+        let files: WatchedFiles = Arc::new(Mutex::new(HashMap::new()));
+
+        let watcher = notify::recommended_watcher({
+            let files = files.clone();
+            move |res: notify::Result<Event>| match res {
+                Ok(event) => {
+                    if !Self::is_content_change(&event) {
+                        return;
+                    }
+                    let files = files.lock().unwrap();
+                    for path in &event.paths {
+                        if let Some(watched) = files.get(path) {
+                            let _ = tx.try_send(watched.clone());
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::error!("File watch error: {:?}", e);
+                }
+            }
+        })
+        .context("Failed to create file watcher")?;
+
+        Ok((
+            Self {
+                watcher,
+                files,
+                directories: HashMap::new(),
+            },
+            rx,
+        ))
+    }
+
+    /// Start watching a file
+    pub fn watch(&mut self, path: &Path) -> Result<()> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.files.lock().unwrap().contains_key(&canonical) {
+            return Ok(());
+        }
+
+        let directory = canonical
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .to_path_buf();
+        if !self.directories.contains_key(&directory) {
+            self.watcher
+                .watch(&directory, RecursiveMode::NonRecursive)
+                .with_context(|| format!("Failed to watch {}", directory.display()))?;
+        }
+        *self.directories.entry(directory).or_insert(0) += 1;
+
+        self.files
+            .lock()
+            .unwrap()
+            .insert(canonical, path.to_path_buf());
+        Ok(())
+    }
+
+    /// Stop watching a file
+    pub fn unwatch(&mut self, path: &Path) {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.files.lock().unwrap().remove(&canonical).is_none() {
+            return;
+        }
+
+        let Some(directory) = canonical.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        if let Some(count) = self.directories.get_mut(&directory) {
+            *count -= 1;
+            if *count == 0 {
+                self.directories.remove(&directory);
+                let _ = self.watcher.unwatch(&directory);
+            }
+        }
+    }
+
+    /// Whether the event may have changed the content of a file
+    fn is_content_change(event: &Event) -> bool {
+        matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+    }
+}
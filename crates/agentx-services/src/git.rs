@@ -0,0 +1,186 @@
+//! Git helpers shared by services
+//!
+//! Queries like the current branch or a diff run the `git` command line in a
+//! workspace folder, so they work with whatever git setup the user already
+//! has. Task worktrees are managed through `git-worktree-manager`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use git_worktree_manager::{
+    CreateOptions, MergeOptions, MergeOutcome, WorktreeBranch, WorktreeManager,
+};
+
+/// Prefix of branches created for task worktrees
+pub const WORKTREE_BRANCH_PREFIX: &str = "agentx/";
+
+/// A git worktree created for a task
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskWorktree {
+    /// Folder the worktree is checked out in
+    pub path: PathBuf,
+    /// Branch checked out in the worktree
+    pub branch: String,
+}
+
+/// Run git in `cwd` and return its trimmed stdout
+pub async fn run_git(cwd: &Path, args: &[&str]) -> Result<String> {
+    let output = smol::process::Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the branch currently checked out in `cwd` (None for a detached HEAD)
+pub async fn current_branch(cwd: &Path) -> Result<Option<String>> {
+    let branch = run_git(cwd, &["rev-parse", "--abbrev-ref", "HEAD"]).await?;
+    Ok((branch != "HEAD").then_some(branch))
+}
+
+/// Create a worktree on a new branch for a task
+///
+/// Worktrees are placed next to the repository in `<repo>-worktrees/<slug>`
+/// and branch off the repository's current `HEAD`.
+pub async fn create_worktree(repo: &Path, task_name: &str) -> Result<TaskWorktree> {
+    let slug = worktree_slug(task_name);
+    let dir =
+        worktrees_dir(repo).ok_or_else(|| anyhow!("Workspace has no parent folder: {:?}", repo))?;
+    let path = dir.join(&slug);
+    let branch = format!("{}{}", WORKTREE_BRANCH_PREFIX, slug);
+
+    let manager = WorktreeManager::new(repo);
+    let worktree = TaskWorktree {
+        path: path.clone(),
+        branch: branch.clone(),
+    };
+    smol::unblock(move || {
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        manager.create(
+            &path,
+            WorktreeBranch::New {
+                name: branch,
+                start_point: None,
+            },
+            CreateOptions::default(),
+        )
+    })
+    .await?;
+
+    log::info!(
+        "Created worktree {:?} on branch {}",
+        worktree.path,
+        worktree.branch
+    );
+    Ok(worktree)
+}
+
+/// Commit all changes in `cwd` (including untracked files)
+///
+/// Returns `false` if there was nothing to commit.
+pub async fn commit_all(cwd: &Path, message: &str) -> Result<bool> {
+    let manager = WorktreeManager::new(cwd);
+    let cwd = cwd.to_path_buf();
+    let message = message.to_string();
+    smol::unblock(move || manager.commit_all(&cwd, &message)).await
+}
+
+/// Merge a branch into the branch checked out in `repo`
+///
+/// A merge commit is always created so the merged task stays visible in the
+/// history. On conflicts the merge is aborted and an error listing the
+/// conflicting files is returned.
+pub async fn merge_branch(repo: &Path, branch: &str, message: &str) -> Result<()> {
+    let target = current_branch(repo)
+        .await?
+        .ok_or_else(|| anyhow!("Cannot merge {} into a detached HEAD", branch))?;
+
+    let manager = WorktreeManager::new(repo);
+    let outcome = {
+        let repo = repo.to_path_buf();
+        let branch = branch.to_string();
+        let options = MergeOptions {
+            no_ff: true,
+            message: Some(message.to_string()),
+        };
+        smol::unblock(move || manager.merge(&repo, &target, &branch, options)).await?
+    };
+
+    if let MergeOutcome::Conflicts(conflicts) = outcome {
+        if let Err(abort_error) = run_git(repo, &["merge", "--abort"]).await {
+            log::warn!("Failed to abort merge of {}: {}", branch, abort_error);
+        }
+        let files = conflicts
+            .iter()
+            .map(|conflict| conflict.path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(anyhow!("Merging {} conflicts in: {}", branch, files));
+    }
+
+    log::info!("Merged branch {} in {:?}", branch, repo);
+    Ok(())
+}
+
+/// Folder holding the task worktrees of a repository, `<repo>-worktrees`
+pub fn worktrees_dir(repo: &Path) -> Option<PathBuf> {
+    let repo_name = repo
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("workspace");
+    let parent = repo.parent()?;
+    Some(parent.join(format!("{}-worktrees", repo_name)))
+}
+
+/// Remove a worktree and, optionally, the branch checked out in it
+pub async fn remove_worktree(
+    repo: &Path,
+    worktree: &TaskWorktree,
+    delete_branch: bool,
+) -> Result<()> {
+    let manager = WorktreeManager::new(repo);
+    let worktree = worktree.clone();
+    smol::unblock(move || {
+        manager.delete(&worktree.path, true)?;
+        if delete_branch {
+            manager.delete_branch(&worktree.branch)?;
+        }
+        log::info!("Removed worktree {:?}", worktree.path);
+        Ok(())
+    })
+    .await
+}
+
+/// Build a unique, filesystem and branch safe name from a task name
+fn worktree_slug(task_name: &str) -> String {
+    let mut slug = String::new();
+    for c in task_name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= 40 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+
+    let suffix = &uuid::Uuid::new_v4().simple().to_string()[..8];
+    if slug.is_empty() {
+        format!("task-{}", suffix)
+    } else {
+        format!("{}-{}", slug, suffix)
+    }
+}
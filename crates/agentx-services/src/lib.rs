@@ -1,17 +1,41 @@
 pub mod agent_config_service;
 pub mod agent_service;
+pub mod ai_provider;
 pub mod ai_service;
+pub mod change_tracker_service;
+pub mod comparison_service;
+pub mod config_diff;
 pub mod config_watcher;
+pub mod file_index_service;
+pub mod file_watcher;
+pub mod git;
+pub mod lsp_client;
+pub mod lsp_service;
 pub mod message_service;
 pub mod persistence_service;
+pub mod pipeline_service;
+pub mod secret_store;
+pub mod task_queue_service;
+pub mod task_template_service;
 pub mod workspace_service;
 
 pub use agent_config_service::AgentConfigService;
 pub use agent_service::{AgentService, AgentSessionInfo};
-pub use ai_service::{AiService, AiServiceConfig, CommentStyle};
+pub use ai_provider::AiProvider;
+pub use ai_service::{AiCancelHandle, AiService, AiServiceConfig, AiStream, CommentStyle};
+pub use change_tracker_service::ChangeTrackerService;
+pub use comparison_service::{ComparisonRequest, ComparisonService};
+pub use config_diff::{ConfigDiff, MapDiff};
 pub use config_watcher::ConfigWatcher;
+pub use file_index_service::{FileIndex, FileIndexService, FileMatch, IndexEntry, fuzzy_match};
+pub use file_watcher::FileWatcher;
+pub use lsp_service::{LspDocument, LspService};
 pub use message_service::MessageService;
 pub use persistence_service::PersistenceService;
+pub use pipeline_service::PipelineService;
+pub use secret_store::SecretStore;
+pub use task_queue_service::TaskQueueService;
+pub use task_template_service::{ScopedTemplate, TaskTemplateService, TemplateInput};
 pub use workspace_service::WorkspaceService;
 
 // Re-export SessionStatus from types for convenience
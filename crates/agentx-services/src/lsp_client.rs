@@ -0,0 +1,675 @@
+//! Language Server Protocol client
+//!
+//! Speaks JSON-RPC over the stdio of a language server process. Requests and
+//! notifications are typed with `lsp-types`; diagnostics published by the
+//! server are routed to every editor view that has the document open.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use agentx_types::LanguageServerConfig;
+use anyhow::{Context, Result, anyhow};
+use lsp_types::{
+    ClientCapabilities, DidCloseTextDocumentParams, DidOpenTextDocumentParams, InitializeParams,
+    InitializedParams, PublishDiagnosticsParams, ServerCapabilities, TextDocumentIdentifier,
+    TextDocumentItem, Uri, WorkspaceFolder,
+    notification::{self, Notification},
+    request::{self, Request},
+};
+use serde_json::{Value, json};
+use smol::channel;
+use smol::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+/// How long to wait for the response of a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+type PendingRequests = Mutex<HashMap<i64, channel::Sender<Result<Value>>>>;
+type OpenDocuments = Mutex<HashMap<Uri, OpenDocument>>;
+
+/// A document opened on the server by one or more editor views
+#[derive(Default)]
+struct OpenDocument {
+    /// Number of views holding the document open
+    views: usize,
+    /// Version of the last change sent for the document
+    version: i32,
+    subscribers: Vec<channel::Sender<Vec<lsp_types::Diagnostic>>>,
+}
+
+/// State shared between the client and its I/O tasks
+struct Connection {
+    name: String,
+    outgoing: channel::Sender<Vec<u8>>,
+    pending: PendingRequests,
+    documents: OpenDocuments,
+    next_id: AtomicI64,
+    running: AtomicBool,
+}
+
+/// A running language server
+pub struct LspClient {
+    connection: Arc<Connection>,
+    root: PathBuf,
+    capabilities: RwLock<ServerCapabilities>,
+    _child: Mutex<smol::process::Child>,
+}
+
+impl LspClient {
+    /// Spawn a language server for `root` and run the `initialize` handshake
+    pub async fn start(
+        name: &str,
+        config: &LanguageServerConfig,
+        root: &Path,
+    ) -> Result<Arc<Self>> {
+        let mut child = smol::process::Command::new(&config.command)
+            .args(&config.args)
+            .envs(agentx_types::secrets::resolve_env(&config.env))
+            .current_dir(root)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to spawn language server '{}'", name))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Language server '{}' missing stdin", name))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("Language server '{}' missing stdout", name))?;
+        let stderr = child.stderr.take();
+
+        let (outgoing, outgoing_rx) = channel::unbounded::<Vec<u8>>();
+        let connection = Arc::new(Connection {
+            name: name.to_string(),
+            outgoing,
+            pending: Mutex::new(HashMap::new()),
+            documents: Mutex::new(HashMap::new()),
+            next_id: AtomicI64::new(1),
+            running: AtomicBool::new(true),
+        });
+
+        // Writer: the only owner of stdin
+        smol::spawn(async move {
+            let mut stdin = stdin;
+            while let Ok(bytes) = outgoing_rx.recv().await {
+                if stdin.write_all(&bytes).await.is_err() || stdin.flush().await.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+
+        // Reader: dispatches responses, server requests and notifications
+        smol::spawn({
+            let connection = connection.clone();
+            async move {
+                let mut reader = BufReader::new(stdout);
+                loop {
+                    match read_message(&mut reader).await {
+                        Ok(Some(message)) => connection.handle_message(message),
+                        Ok(None) => break,
+                        Err(e) => {
+                            log::warn!(
+                                "Language server '{}' sent an invalid message: {}",
+                                connection.name,
+                                e
+                            );
+                        }
+                    }
+                }
+
+                log::info!("Language server '{}' exited", connection.name);
+                connection.running.store(false, Ordering::SeqCst);
+                // Dropping the senders fails the requests still waiting
+                connection.pending.lock().unwrap().clear();
+                connection.documents.lock().unwrap().clear();
+            }
+        })
+        .detach();
+
+        if let Some(stderr) = stderr {
+            let name = name.to_string();
+            smol::spawn(async move {
+                let mut reader = BufReader::new(stderr);
+                let mut line = String::new();
+                while matches!(reader.read_line(&mut line).await, Ok(n) if n > 0) {
+                    log::debug!("[{}] {}", name, line.trim_end());
+                    line.clear();
+                }
+            })
+            .detach();
+        }
+
+        let client = Arc::new(Self {
+            connection,
+            root: root.to_path_buf(),
+            capabilities: RwLock::new(ServerCapabilities::default()),
+            _child: Mutex::new(child),
+        });
+
+        client.initialize(config).await?;
+        log::info!("Started language server '{}' for {}", name, root.display());
+
+        Ok(client)
+    }
+
+    async fn initialize(&self, config: &LanguageServerConfig) -> Result<()> {
+        let root_uri = path_to_uri(&self.root)?;
+        let folder_name = self
+            .root
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("workspace")
+            .to_string();
+
+        #[allow(deprecated)]
+        let params = InitializeParams {
+            process_id: Some(std::process::id()),
+            root_uri: Some(root_uri.clone()),
+            initialization_options: config.initialization_options.clone(),
+            capabilities: client_capabilities(),
+            workspace_folders: Some(vec![WorkspaceFolder {
+                uri: root_uri,
+                name: folder_name,
+            }]),
+            client_info: Some(lsp_types::ClientInfo {
+                name: "agentx".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+            ..Default::default()
+        };
+
+        let result = self
+            .request::<request::Initialize>(params)
+            .await
+            .with_context(|| format!("Failed to initialize language server '{}'", self.name()))?;
+        *self.capabilities.write().unwrap() = result.capabilities;
+
+        self.notify::<notification::Initialized>(InitializedParams {})
+    }
+
+    /// Name of the server in the configuration
+    pub fn name(&self) -> &str {
+        &self.connection.name
+    }
+
+    /// Folder the server was started for
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the server process is still running
+    pub fn is_running(&self) -> bool {
+        self.connection.running.load(Ordering::SeqCst)
+    }
+
+    /// Capabilities reported by the server in the `initialize` response
+    pub fn capabilities(&self) -> ServerCapabilities {
+        self.capabilities.read().unwrap().clone()
+    }
+
+    /// Send a request and wait for its response
+    pub async fn request<R: Request>(&self, params: R::Params) -> Result<R::Result> {
+        if !self.is_running() {
+            return Err(anyhow!("Language server '{}' is not running", self.name()));
+        }
+
+        let id = self.connection.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = channel::bounded(1);
+        self.connection.pending.lock().unwrap().insert(id, tx);
+
+        self.connection.send(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": R::METHOD,
+            "params": params,
+        }))?;
+
+        let response = smol::future::or(
+            async {
+                rx.recv()
+                    .await
+                    .map_err(|_| anyhow!("Language server '{}' exited", self.name()))
+                    .and_then(|result| result)
+            },
+            async {
+                smol::Timer::after(REQUEST_TIMEOUT).await;
+                Err(anyhow!("{} request timed out", R::METHOD))
+            },
+        )
+        .await;
+
+        if response.is_err()
+            && self
+                .connection
+                .pending
+                .lock()
+                .unwrap()
+                .remove(&id)
+                .is_some()
+        {
+            let _ = self.notify::<notification::Cancel>(lsp_types::CancelParams {
+                id: lsp_types::NumberOrString::Number(id as i32),
+            });
+        }
+
+        let value = response?;
+        serde_json::from_value(value)
+            .with_context(|| format!("Invalid {} response from '{}'", R::METHOD, self.name()))
+    }
+
+    /// Send a notification
+    pub fn notify<N: Notification>(&self, params: N::Params) -> Result<()> {
+        self.connection.send(&json!({
+            "jsonrpc": "2.0",
+            "method": N::METHOD,
+            "params": params,
+        }))
+    }
+
+    /// Open a document for an editor view and receive its diagnostics
+    ///
+    /// The server is only told about the first view of a document; later
+    /// views share it until the last one is closed.
+    pub fn open_document(
+        &self,
+        uri: Uri,
+        language_id: &str,
+        text: String,
+    ) -> Result<channel::Receiver<Vec<lsp_types::Diagnostic>>> {
+        // Subscribe first: servers publish diagnostics right after didOpen
+        let (is_first, diagnostics) = self.connection.open_document(uri.clone());
+        if is_first {
+            self.notify::<notification::DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(uri, language_id.to_string(), 0, text),
+            })?;
+        }
+        Ok(diagnostics)
+    }
+
+    /// Next version number of a change to an open document
+    pub fn next_version(&self, uri: &Uri) -> i32 {
+        let mut documents = self.connection.documents.lock().unwrap();
+        documents.get_mut(uri).map_or(0, |document| {
+            document.version += 1;
+            document.version
+        })
+    }
+
+    /// Close a document for an editor view, closing it on the server with
+    /// its last view
+    pub fn close_document(&self, uri: &Uri) -> Result<()> {
+        if !self.connection.close_document(uri) {
+            return Ok(());
+        }
+        self.notify::<notification::DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+        })
+    }
+
+    /// Ask the server to shut down, then exit
+    pub async fn shutdown(&self) {
+        if !self.is_running() {
+            return;
+        }
+        if let Err(e) = self.request::<request::Shutdown>(()).await {
+            log::warn!("Language server '{}' did not shut down: {}", self.name(), e);
+        }
+        let _ = self.notify::<notification::Exit>(());
+    }
+}
+
+impl Connection {
+    /// Add a view of a document, returning whether it is the first one
+    fn open_document(&self, uri: Uri) -> (bool, channel::Receiver<Vec<lsp_types::Diagnostic>>) {
+        let (tx, rx) = channel::unbounded();
+        let mut documents = self.documents.lock().unwrap();
+        let document = documents.entry(uri).or_default();
+        document.views += 1;
+        document.subscribers.push(tx);
+        (document.views == 1, rx)
+    }
+
+    /// Remove a view of a document, returning whether it was the last one
+    fn close_document(&self, uri: &Uri) -> bool {
+        let mut documents = self.documents.lock().unwrap();
+        let Some(document) = documents.get_mut(uri) else {
+            return false;
+        };
+        document.views = document.views.saturating_sub(1);
+        if document.views > 0 {
+            return false;
+        }
+        documents.remove(uri);
+        true
+    }
+
+    /// Send diagnostics to the views of a document, dropping closed ones
+    fn publish_diagnostics(&self, uri: &Uri, diagnostics: Vec<lsp_types::Diagnostic>) {
+        if let Some(document) = self.documents.lock().unwrap().get_mut(uri) {
+            document
+                .subscribers
+                .retain(|tx| tx.try_send(diagnostics.clone()).is_ok());
+        }
+    }
+
+    fn send(&self, message: &Value) -> Result<()> {
+        self.outgoing
+            .try_send(encode_message(message))
+            .map_err(|_| anyhow!("Language server '{}' is not running", self.name))
+    }
+
+    fn respond(&self, id: Value, result: Result<Value, (i64, String)>) {
+        let message = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        let _ = self.send(&message);
+    }
+
+    fn handle_message(&self, message: Value) {
+        let method = message.get("method").and_then(Value::as_str);
+        let id = message.get("id").cloned();
+
+        match (method, id) {
+            // Response to one of our requests
+            (None, Some(id)) => {
+                let Some(id) = id.as_i64() else {
+                    return;
+                };
+                let Some(tx) = self.pending.lock().unwrap().remove(&id) else {
+                    return;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(anyhow!(
+                        "{}",
+                        error
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .unwrap_or("Unknown error")
+                    )),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = tx.try_send(result);
+            }
+            // Request from the server
+            (Some(method), Some(id)) => {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                self.respond(id, self.handle_server_request(method, params));
+            }
+            (Some(method), None) => self.handle_notification(method, message.get("params")),
+            (None, None) => {}
+        }
+    }
+
+    fn handle_server_request(&self, method: &str, params: Value) -> Result<Value, (i64, String)> {
+        match method {
+            // No client-side settings: let the server use its defaults
+            request::WorkspaceConfiguration::METHOD => {
+                let count = params
+                    .get("items")
+                    .and_then(Value::as_array)
+                    .map(Vec::len)
+                    .unwrap_or(0);
+                Ok(Value::Array(vec![Value::Null; count]))
+            }
+            request::WorkDoneProgressCreate::METHOD
+            | request::RegisterCapability::METHOD
+            | request::UnregisterCapability::METHOD => Ok(Value::Null),
+            request::ApplyWorkspaceEdit::METHOD => Ok(json!({ "applied": false })),
+            _ => {
+                log::debug!("Unhandled request from '{}': {}", self.name, method);
+                Err((-32601, format!("Method not found: {}", method)))
+            }
+        }
+    }
+
+    fn handle_notification(&self, method: &str, params: Option<&Value>) {
+        match method {
+            notification::PublishDiagnostics::METHOD => {
+                let Some(params) = params.and_then(|p| {
+                    serde_json::from_value::<PublishDiagnosticsParams>(p.clone()).ok()
+                }) else {
+                    return;
+                };
+                self.publish_diagnostics(&params.uri, params.diagnostics);
+            }
+            notification::LogMessage::METHOD | notification::ShowMessage::METHOD => {
+                let message = params
+                    .and_then(|p| p.get("message"))
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                log::debug!("[{}] {}", self.name, message);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// What the editor supports, announced in the `initialize` request
+fn client_capabilities() -> ClientCapabilities {
+    use lsp_types::*;
+
+    ClientCapabilities {
+        text_document: Some(TextDocumentClientCapabilities {
+            synchronization: Some(TextDocumentSyncClientCapabilities::default()),
+            completion: Some(CompletionClientCapabilities {
+                completion_item: Some(CompletionItemCapability {
+                    documentation_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            hover: Some(HoverClientCapabilities {
+                content_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                ..Default::default()
+            }),
+            definition: Some(GotoCapability {
+                link_support: Some(true),
+                ..Default::default()
+            }),
+            code_action: Some(CodeActionClientCapabilities {
+                code_action_literal_support: Some(CodeActionLiteralSupport {
+                    code_action_kind: CodeActionKindLiteralSupport {
+                        value_set: vec![
+                            CodeActionKind::QUICKFIX.as_str().to_string(),
+                            CodeActionKind::REFACTOR.as_str().to_string(),
+                            CodeActionKind::REFACTOR_EXTRACT.as_str().to_string(),
+                            CodeActionKind::REFACTOR_INLINE.as_str().to_string(),
+                            CodeActionKind::REFACTOR_REWRITE.as_str().to_string(),
+                            CodeActionKind::SOURCE.as_str().to_string(),
+                        ],
+                    },
+                }),
+                data_support: Some(true),
+                resolve_support: Some(CodeActionCapabilityResolveSupport {
+                    properties: vec!["edit".to_string()],
+                }),
+                ..Default::default()
+            }),
+            publish_diagnostics: Some(PublishDiagnosticsClientCapabilities::default()),
+            ..Default::default()
+        }),
+        workspace: Some(WorkspaceClientCapabilities {
+            configuration: Some(true),
+            workspace_folders: Some(true),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Frame a JSON-RPC message with its `Content-Length` header
+fn encode_message(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    let mut bytes = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    bytes.extend_from_slice(body.as_bytes());
+    bytes
+}
+
+/// Read one framed JSON-RPC message (None at end of stream)
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+
+    let length = content_length.ok_or_else(|| anyhow!("Missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Convert an absolute path to a `file://` URI
+pub fn path_to_uri(path: &Path) -> Result<Uri> {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        format!("/{}", path)
+    };
+
+    let mut uri = String::from("file://");
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    Uri::from_str(&uri).map_err(|e| anyhow!("Invalid file URI {}: {}", uri, e))
+}
+
+/// Convert a `file://` URI back to a path (None for other schemes)
+pub fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    let path = uri.as_str().strip_prefix("file://")?;
+
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+
+    // "/C:/dir" on Windows
+    if cfg!(windows) {
+        return Some(PathBuf::from(path.trim_start_matches('/')));
+    }
+    Some(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_connection() -> Connection {
+        let (outgoing, _) = channel::unbounded();
+        Connection {
+            name: "test".to_string(),
+            outgoing,
+            pending: Mutex::new(HashMap::new()),
+            documents: Mutex::new(HashMap::new()),
+            next_id: AtomicI64::new(1),
+            running: AtomicBool::new(true),
+        }
+    }
+
+    #[test]
+    fn test_documents_are_shared_between_views() {
+        let connection = test_connection();
+        let uri = Uri::from_str("file:///project/src/main.rs").unwrap();
+        let diagnostic = lsp_types::Diagnostic {
+            message: "unused variable".to_string(),
+            ..Default::default()
+        };
+
+        let (first, first_rx) = connection.open_document(uri.clone());
+        let (second, second_rx) = connection.open_document(uri.clone());
+        assert!(first);
+        assert!(!second);
+
+        // Closing one view keeps the document open for the other
+        assert!(!connection.close_document(&uri));
+        drop(first_rx);
+        connection.publish_diagnostics(&uri, vec![diagnostic.clone()]);
+        assert_eq!(second_rx.try_recv().unwrap(), vec![diagnostic]);
+
+        assert!(connection.close_document(&uri));
+        assert!(!connection.close_document(&uri));
+    }
+
+    #[test]
+    fn test_message_roundtrip() {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "result": { "value": "héllo" } });
+        let mut bytes = encode_message(&message);
+        bytes.extend(encode_message(
+            &json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ));
+
+        smol::block_on(async {
+            let mut reader = BufReader::new(bytes.as_slice());
+            assert_eq!(read_message(&mut reader).await.unwrap(), Some(message));
+            let second = read_message(&mut reader).await.unwrap().unwrap();
+            assert_eq!(second["method"], "exit");
+            assert_eq!(read_message(&mut reader).await.unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn test_read_message_with_extra_headers() {
+        let body = r#"{"jsonrpc":"2.0","method":"initialized"}"#;
+        let raw = format!(
+            "content-length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}",
+            body.len(),
+            body
+        );
+
+        smol::block_on(async {
+            let mut reader = BufReader::new(raw.as_bytes());
+            let message = read_message(&mut reader).await.unwrap().unwrap();
+            assert_eq!(message["method"], "initialized");
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_path_uri_roundtrip() {
+        let path = Path::new("/tmp/my project/src/main.rs");
+        let uri = path_to_uri(path).unwrap();
+        assert_eq!(uri.as_str(), "file:///tmp/my%20project/src/main.rs");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+    }
+}
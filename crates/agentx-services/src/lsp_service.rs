@@ -0,0 +1,341 @@
+//! Language Server Service
+//!
+//! Starts the language servers configured in `config.json` on demand, one per
+//! workspace folder and server, and opens editor documents on them.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Result;
+use lsp_types::{
+    CodeAction, CodeActionContext, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, CompletionContext, CompletionParams, CompletionResponse,
+    CompletionTriggerKind, DidChangeTextDocumentParams, DidSaveTextDocumentParams,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams, LocationLink, Position,
+    Range, TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentPositionParams, Uri,
+    VersionedTextDocumentIdentifier, notification, request,
+};
+use smol::channel;
+use smol::lock::OnceCell;
+use tokio::sync::Mutex;
+
+use crate::AgentConfigService;
+use crate::lsp_client::{LspClient, path_to_uri};
+
+/// Slot of a server, filled once the server has started
+type ServerSlot = Arc<OnceCell<Arc<LspClient>>>;
+
+/// Language Server Service
+///
+/// Keeps the running servers keyed by workspace folder and server name.
+pub struct LspService {
+    agent_config_service: Arc<AgentConfigService>,
+    servers: Mutex<HashMap<(PathBuf, String), ServerSlot>>,
+}
+
+impl LspService {
+    pub fn new(agent_config_service: Arc<AgentConfigService>) -> Self {
+        Self {
+            agent_config_service,
+            servers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Open a document on the language server configured for its language
+    ///
+    /// Returns `None` when no server handles the language. The document is
+    /// closed on the server when the returned handle is dropped.
+    pub async fn open_document(
+        &self,
+        root: &Path,
+        language: &str,
+        path: &Path,
+        text: String,
+    ) -> Result<Option<LspDocument>> {
+        let Some((name, config)) = self
+            .agent_config_service
+            .language_server_for(language)
+            .await
+        else {
+            return Ok(None);
+        };
+
+        // Reserve the slot under the lock, but start the server outside of it
+        // so other folders and servers aren't blocked by the handshake
+        let slot = {
+            let mut servers = self.servers.lock().await;
+            let slot = servers
+                .entry((root.to_path_buf(), name.clone()))
+                .or_insert_with(|| Arc::new(OnceCell::new()));
+            if slot.get().is_some_and(|client| !client.is_running()) {
+                *slot = Arc::new(OnceCell::new());
+            }
+            slot.clone()
+        };
+        let client = slot
+            .get_or_try_init(|| LspClient::start(&name, &config, root))
+            .await?
+            .clone();
+
+        LspDocument::open(client, path, language_id(language), text).map(Some)
+    }
+
+    /// Shut down the servers started for a workspace folder
+    pub async fn shutdown_workspace(&self, root: &Path) {
+        let clients: Vec<_> = {
+            let mut servers = self.servers.lock().await;
+            let keys: Vec<_> = servers
+                .keys()
+                .filter(|(server_root, _)| server_root == root)
+                .cloned()
+                .collect();
+            keys.iter()
+                .filter_map(|key| servers.remove(key))
+                .filter_map(|slot| slot.get().cloned())
+                .collect()
+        };
+
+        for client in clients {
+            client.shutdown().await;
+        }
+    }
+
+    /// Shut down all running servers
+    pub async fn shutdown_all(&self) {
+        let clients: Vec<_> = self
+            .servers
+            .lock()
+            .await
+            .drain()
+            .filter_map(|(_, slot)| slot.get().cloned())
+            .collect();
+        for client in clients {
+            client.shutdown().await;
+        }
+    }
+}
+
+/// A document opened on a language server by an editor view
+///
+/// Views of the same file share the document on the server, which is closed
+/// when the last of them is dropped. Forwards editor requests to the server.
+pub struct LspDocument {
+    client: Arc<LspClient>,
+    uri: Uri,
+    diagnostics: channel::Receiver<Vec<lsp_types::Diagnostic>>,
+}
+
+impl LspDocument {
+    fn open(client: Arc<LspClient>, path: &Path, language_id: &str, text: String) -> Result<Self> {
+        let uri = path_to_uri(path)?;
+        let diagnostics = client.open_document(uri.clone(), language_id, text)?;
+
+        Ok(Self {
+            client,
+            uri,
+            diagnostics,
+        })
+    }
+
+    /// URI of the document
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Name of the language server the document is open on
+    pub fn server_name(&self) -> &str {
+        self.client.name()
+    }
+
+    /// Send the new content of the document (full sync)
+    pub fn change(&self, text: String) -> Result<()> {
+        let version = self.client.next_version(&self.uri);
+        self.client
+            .notify::<notification::DidChangeTextDocument>(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(self.uri.clone(), version),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text,
+                }],
+            })
+    }
+
+    /// Tell the server the document was written to disk
+    pub fn save(&self) -> Result<()> {
+        self.client
+            .notify::<notification::DidSaveTextDocument>(DidSaveTextDocumentParams {
+                text_document: TextDocumentIdentifier::new(self.uri.clone()),
+                text: None,
+            })
+    }
+
+    /// Wait for the next diagnostics published for the document
+    ///
+    /// Returns `None` once the server has exited.
+    pub async fn next_diagnostics(&self) -> Option<Vec<lsp_types::Diagnostic>> {
+        self.diagnostics.recv().await.ok()
+    }
+
+    /// Whether the server treats `text` as a completion trigger character
+    pub fn is_trigger_character(&self, text: &str) -> bool {
+        self.client
+            .capabilities()
+            .completion_provider
+            .and_then(|options| options.trigger_characters)
+            .is_some_and(|chars| chars.iter().any(|c| c == text))
+    }
+
+    /// Request completions at a position
+    pub async fn completion(
+        &self,
+        position: Position,
+        trigger_character: Option<String>,
+    ) -> Result<CompletionResponse> {
+        let trigger_character = trigger_character.filter(|c| self.is_trigger_character(c));
+        let context = CompletionContext {
+            trigger_kind: if trigger_character.is_some() {
+                CompletionTriggerKind::TRIGGER_CHARACTER
+            } else {
+                CompletionTriggerKind::INVOKED
+            },
+            trigger_character,
+        };
+
+        let response = self
+            .client
+            .request::<request::Completion>(CompletionParams {
+                text_document_position: self.position_params(position),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: Some(context),
+            })
+            .await?;
+
+        Ok(response.unwrap_or(CompletionResponse::Array(vec![])))
+    }
+
+    /// Request hover information at a position
+    pub async fn hover(&self, position: Position) -> Result<Option<Hover>> {
+        self.client
+            .request::<request::HoverRequest>(HoverParams {
+                text_document_position_params: self.position_params(position),
+                work_done_progress_params: Default::default(),
+            })
+            .await
+    }
+
+    /// Request the definitions of the symbol at a position
+    pub async fn definition(&self, position: Position) -> Result<Vec<LocationLink>> {
+        let response = self
+            .client
+            .request::<request::GotoDefinition>(GotoDefinitionParams {
+                text_document_position_params: self.position_params(position),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .await?;
+
+        let location_link = |location: lsp_types::Location| LocationLink {
+            origin_selection_range: None,
+            target_uri: location.uri,
+            target_range: location.range,
+            target_selection_range: location.range,
+        };
+
+        Ok(match response {
+            None => vec![],
+            Some(GotoDefinitionResponse::Scalar(location)) => vec![location_link(location)],
+            Some(GotoDefinitionResponse::Array(locations)) => {
+                locations.into_iter().map(location_link).collect()
+            }
+            Some(GotoDefinitionResponse::Link(links)) => links,
+        })
+    }
+
+    /// Request the code actions for a range
+    ///
+    /// Bare commands are skipped since the editor can only apply edits.
+    pub async fn code_actions(
+        &self,
+        range: Range,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    ) -> Result<Vec<CodeAction>> {
+        if self.client.capabilities().code_action_provider.is_none() {
+            return Ok(vec![]);
+        }
+
+        let response = self
+            .client
+            .request::<request::CodeActionRequest>(CodeActionParams {
+                text_document: TextDocumentIdentifier::new(self.uri.clone()),
+                range,
+                context: CodeActionContext {
+                    diagnostics,
+                    only: None,
+                    trigger_kind: None,
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .await?;
+
+        Ok(response
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => Some(action),
+                CodeActionOrCommand::Command(_) => None,
+            })
+            .collect())
+    }
+
+    /// Fill in the edit of a code action the server left unresolved
+    pub async fn resolve_code_action(&self, action: CodeAction) -> Result<CodeAction> {
+        let can_resolve = matches!(
+            self.client.capabilities().code_action_provider,
+            Some(CodeActionProviderCapability::Options(ref options))
+                if options.resolve_provider == Some(true)
+        );
+        if action.edit.is_some() || !can_resolve {
+            return Ok(action);
+        }
+
+        self.client
+            .request::<request::CodeActionResolveRequest>(action)
+            .await
+    }
+
+    fn position_params(&self, position: Position) -> TextDocumentPositionParams {
+        TextDocumentPositionParams::new(TextDocumentIdentifier::new(self.uri.clone()), position)
+    }
+}
+
+impl Drop for LspDocument {
+    fn drop(&mut self) {
+        let _ = self.client.close_document(&self.uri);
+    }
+}
+
+/// LSP language identifier of an editor language
+fn language_id(language: &str) -> &str {
+    match language {
+        "tsx" => "typescriptreact",
+        "jsx" => "javascriptreact",
+        "bash" => "shellscript",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_id() {
+        assert_eq!(language_id("rust"), "rust");
+        assert_eq!(language_id("tsx"), "typescriptreact");
+        assert_eq!(language_id("bash"), "shellscript");
+    }
+}
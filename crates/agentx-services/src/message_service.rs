@@ -14,7 +14,7 @@ use agent_client_protocol::{
 };
 use anyhow::{Result, anyhow};
 
-use agentx_event_bus::{EventHub, SessionUpdateEvent, WorkspaceUpdateEvent};
+use agentx_event_bus::{AppEvent, EventHub, SessionUpdateEvent, WorkspaceUpdateEvent};
 use agentx_types::SessionStatus;
 
 use super::agent_service::AgentService;
@@ -43,116 +43,144 @@ impl MessageService {
     /// Initialize persistence subscription
     ///
     /// This should be called after the MessageService is created.
-    /// Subscribes to session and workspace events.
+    /// Session updates and status changes are persisted from an unbounded
+    /// queue in publish order, so disk I/O never runs on (or stalls) the
+    /// thread that publishes them, no update is dropped, and a session is
+    /// flushed after its updates.
     pub fn init_persistence(&self) {
         let persistence_service = self.persistence_service.clone();
-        let event_hub = self.event_hub.clone();
         let agent_service = self.agent_service.clone();
-        let load_persist_policy: Arc<Mutex<HashMap<String, bool>>> =
-            Arc::new(Mutex::new(HashMap::new()));
-
-        // Subscribe to session updates
-        event_hub.subscribe_session_updates(move |event| {
-            let session_id = event.session_id.clone();
-            let update = (*event.update).clone();
-            let agent_name = event.agent_name.clone();
-            let service = persistence_service.clone();
-            let agent_svc = agent_service.clone();
-            let load_policy = load_persist_policy.clone();
-            let is_agent_event = agent_name.is_some();
 
-            // Handle AvailableCommandsUpdate to store in AgentService
-            if let SessionUpdate::AvailableCommandsUpdate(ref commands_update) = update {
+        // Store available commands in AgentService right away
+        self.event_hub.subscribe_session_updates(move |event| {
+            if let SessionUpdate::AvailableCommandsUpdate(commands_update) = event.update.as_ref()
+            {
                 log::debug!(
                     "Received AvailableCommandsUpdate for session {}: {} commands",
-                    session_id,
+                    event.session_id,
                     commands_update.available_commands.len()
                 );
 
                 // Get agent name for this session (prefer event metadata if available)
-                let agent_name_for_update =
-                    agent_name.clone().or_else(|| agent_svc.get_agent_for_session(&session_id));
+                let agent_name = event
+                    .agent_name
+                    .clone()
+                    .or_else(|| agent_service.get_agent_for_session(&event.session_id));
 
-                if let Some(agent_name) = agent_name_for_update {
-                    agent_svc.update_session_commands(
+                if let Some(agent_name) = agent_name {
+                    agent_service.update_session_commands(
                         &agent_name,
-                        &session_id,
+                        &event.session_id,
                         commands_update.available_commands.clone(),
                     );
                 } else {
                     log::warn!(
                         "Could not find agent for session {} when processing AvailableCommandsUpdate",
-                        session_id
+                        event.session_id
                     );
                 }
             }
+        });
 
-            let is_loading = is_agent_event && agent_svc.is_session_loading(&session_id);
-            let should_persist = if is_loading {
-                let mut policy_map = load_policy.lock().unwrap();
-                let entry = policy_map.entry(session_id.clone()).or_insert_with(|| {
-                    !service.session_file_exists(&session_id)
-                });
-                *entry
-            } else {
-                if is_agent_event {
-                    let mut policy_map = load_policy.lock().unwrap();
-                    policy_map.remove(&session_id);
+        // The filter runs when an update is published, so it sees whether
+        // the session is still loading its history
+        let filter = {
+            let service = persistence_service.clone();
+            let agent_svc = self.agent_service.clone();
+            let load_policy: Mutex<HashMap<String, bool>> = Mutex::new(HashMap::new());
+            move |event: &AppEvent| match event {
+                AppEvent::SessionUpdate(event) => {
+                    Self::should_persist(&service, &agent_svc, &load_policy, event)
                 }
-                true
-            };
-
-            // Spawn async task using smol to save message
-            if should_persist {
-                smol::spawn(async move {
-                    if let Err(e) = service.save_update(&session_id, update).await {
-                        log::error!(
-                            "Failed to persist message for session {}: {}",
-                            session_id,
-                            e
-                        );
-                    }
-                })
-                .detach();
-            } else {
-                log::debug!(
-                    "Skipping persistence for session {} (history already loaded)",
-                    session_id
-                );
+                AppEvent::WorkspaceUpdate(WorkspaceUpdateEvent::SessionStatusUpdated {
+                    ..
+                }) => true,
+                _ => false,
             }
-        });
-
-        // Subscribe to workspace bus for session status changes
-        let persistence_service_ws = self.persistence_service.clone();
-        let event_hub = self.event_hub.clone();
-
-        event_hub.subscribe_workspace_updates(move |event| {
-            if let WorkspaceUpdateEvent::SessionStatusUpdated {
-                session_id, status, ..
-            } = event
-            {
-                // Flush accumulator when session completes or becomes idle
-                if matches!(status, SessionStatus::Completed | SessionStatus::Idle) {
-                    let service = persistence_service_ws.clone();
-                    let session_id = session_id.clone();
-
-                    smol::spawn(async move {
-                        if let Err(e) = service.flush_session(&session_id).await {
+        };
+        // Publishers are the GPUI and agent I/O threads, which must never
+        // wait for the disk, and saved history must not lose updates: they
+        // only push to an unbounded queue
+        let (tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+        self.event_hub
+            .subscribe_with_filter(move |event| tx.send(event.clone()).is_ok(), filter);
+
+        smol::spawn(async move {
+            while let Some(event) = events.recv().await {
+                match event {
+                    AppEvent::SessionUpdate(event) => {
+                        let update = (*event.update).clone();
+                        if let Err(e) = persistence_service
+                            .save_update(&event.session_id, update)
+                            .await
+                        {
+                            log::error!(
+                                "Failed to persist message for session {}: {}",
+                                event.session_id,
+                                e
+                            );
+                        }
+                    }
+                    AppEvent::WorkspaceUpdate(WorkspaceUpdateEvent::SessionStatusUpdated {
+                        session_id,
+                        status,
+                        ..
+                    }) => {
+                        // Flush accumulator when session completes or becomes idle
+                        if matches!(status, SessionStatus::Completed | SessionStatus::Idle)
+                            && let Err(e) = persistence_service.flush_session(&session_id).await
+                        {
                             log::error!(
                                 "Failed to flush session {} on status change: {}",
                                 session_id,
                                 e
                             );
                         }
-                    })
-                    .detach();
+                    }
+                    _ => {}
                 }
             }
-        });
+        })
+        .detach();
 
         log::info!("MessageService persistence subscriptions initialized (event_hub)");
     }
 
+    /// Whether a session update is persisted
+    ///
+    /// History replayed while a session loads is only persisted if the
+    /// session has no file yet, as decided at the first replayed update.
+    fn should_persist(
+        service: &PersistenceService,
+        agent_svc: &AgentService,
+        load_policy: &Mutex<HashMap<String, bool>>,
+        event: &SessionUpdateEvent,
+    ) -> bool {
+        let session_id = &event.session_id;
+        let is_agent_event = event.agent_name.is_some();
+        let is_loading = is_agent_event && agent_svc.is_session_loading(session_id);
+
+        let should_persist = if is_loading {
+            let mut policy_map = load_policy.lock().unwrap();
+            *policy_map
+                .entry(session_id.clone())
+                .or_insert_with(|| !service.session_file_exists(session_id))
+        } else {
+            if is_agent_event {
+                load_policy.lock().unwrap().remove(session_id);
+            }
+            true
+        };
+
+        if !should_persist {
+            log::debug!(
+                "Skipping persistence for session {} (history already loaded)",
+                session_id
+            );
+        }
+        should_persist
+    }
+
     /// Send a user message to an existing session
     ///
     /// This method performs the following steps:
@@ -306,3 +334,56 @@ impl MessageService {
             .get_session_commands(&agent_name, session_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_persists_burst_in_full() {
+        let event_hub = EventHub::new();
+        let dir = std::env::temp_dir().join(format!("agentx-messages-{}", uuid::Uuid::new_v4()));
+        let persistence_service = Arc::new(PersistenceService::new(dir.clone()));
+        let agent_manager = Arc::new(agentx_agent::AgentManager::new(
+            HashMap::new(),
+            Arc::new(Default::default()),
+            event_hub.clone(),
+            Default::default(),
+        ));
+        let service = MessageService::new(
+            event_hub.clone(),
+            Arc::new(AgentService::new(agent_manager)),
+            persistence_service.clone(),
+        );
+        service.init_persistence();
+
+        // More updates than a bounded queue of 1024 would hold
+        const BURST: usize = 4096;
+        for i in 0..BURST {
+            event_hub.publish(AppEvent::SessionUpdate(SessionUpdateEvent {
+                session_id: "session-1".to_string(),
+                agent_name: None,
+                update: Arc::new(SessionUpdate::UserMessageChunk(ContentChunk::new(
+                    format!("message {}", i).into(),
+                ))),
+            }));
+        }
+
+        let mut persisted = 0;
+        for _ in 0..200 {
+            persisted = persistence_service
+                .load_messages("session-1")
+                .await
+                .unwrap()
+                .len();
+            if persisted == BURST {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(persisted, BURST);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
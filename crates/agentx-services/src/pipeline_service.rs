@@ -0,0 +1,657 @@
+//! Pipeline Service - Hands the output of one agent task over to the next
+//!
+//! A pipeline (see `PipelineDefinition` in the config file) is a list of
+//! stages. Each stage is run as one or more queued tasks, so sessions are
+//! created and prompted by the task queue through `AgentService` and
+//! `MessageService`. When all tasks of a stage are completed, their output
+//! (final agent message, plan entries or the resulting `git diff`) is rendered
+//! into the prompt of the next stage.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use agent_client_protocol::{ContentBlock, SessionUpdate};
+use anyhow::{Result, anyhow};
+
+use agentx_event_bus::{EventHub, WorkspaceUpdateEvent};
+use agentx_types::SessionStatus;
+use agentx_types::schemas::pipeline::{
+    PipelineDefinition, PipelineRun, PipelineStatus, StageInput, StageTaskOutput,
+};
+use agentx_types::schemas::workspace::{TaskChangeSummary, WorkspaceTask};
+
+use super::agent_config_service::AgentConfigService;
+use super::git;
+use super::message_service::MessageService;
+use super::workspace_service::WorkspaceService;
+
+/// Default mode recorded on stage tasks whose stage does not set one
+const DEFAULT_TASK_MODE: &str = "default";
+
+/// Maximum length of a diff handed to the next stage
+const MAX_DIFF_LENGTH: usize = 50_000;
+
+/// Separator between the outputs of a fanned-out stage
+const OUTPUT_SEPARATOR: &str = "\n\n---\n\n";
+
+/// Output of a session, built from its updates
+#[derive(Debug, Default)]
+struct SessionOutput {
+    output: StageTaskOutput,
+    /// Whether the last update was part of an agent message
+    in_message: bool,
+}
+
+impl SessionOutput {
+    /// Apply a session update
+    ///
+    /// Only the last agent message is kept: a message interrupted by tool
+    /// calls or a new user prompt starts over.
+    fn apply(&mut self, update: &SessionUpdate) {
+        match update {
+            SessionUpdate::AgentMessageChunk(chunk) => {
+                if !self.in_message {
+                    self.output.final_message.clear();
+                    self.in_message = true;
+                }
+                if let ContentBlock::Text(text) = &chunk.content {
+                    self.output.final_message.push_str(&text.text);
+                }
+            }
+            SessionUpdate::Plan(plan) => {
+                self.output.plan_entries = plan
+                    .entries
+                    .iter()
+                    .map(|entry| entry.content.clone())
+                    .collect();
+            }
+            SessionUpdate::AgentThoughtChunk(_) => {}
+            _ => self.in_message = false,
+        }
+    }
+}
+
+/// Pipeline service - starts pipeline runs and advances them stage by stage
+pub struct PipelineService {
+    workspace_service: Arc<WorkspaceService>,
+    agent_config_service: Arc<AgentConfigService>,
+    message_service: Arc<MessageService>,
+    /// Stores session_id -> output recorded from live session updates of
+    /// running stage tasks
+    sessions: Arc<Mutex<HashMap<String, SessionOutput>>>,
+    /// Serializes stage transitions
+    advancing: tokio::sync::Mutex<()>,
+}
+
+impl PipelineService {
+    pub fn new(
+        workspace_service: Arc<WorkspaceService>,
+        agent_config_service: Arc<AgentConfigService>,
+        message_service: Arc<MessageService>,
+    ) -> Self {
+        Self {
+            workspace_service,
+            agent_config_service,
+            message_service,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            advancing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Subscribe to session and task events, and resume unfinished runs
+    pub fn init(self: &Arc<Self>, event_hub: &EventHub) {
+        let sessions = self.sessions.clone();
+        event_hub.subscribe_session_updates(move |event| {
+            // Only sessions of stage tasks are tracked (see `track_session`)
+            let mut sessions = sessions.lock().unwrap();
+            if let Some(session) = sessions.get_mut(&event.session_id) {
+                session.apply(&event.update);
+            }
+        });
+
+        let service = self.clone();
+        event_hub.subscribe_workspace_task_events(move |event| {
+            let WorkspaceUpdateEvent::TaskUpdated { task_id } = event else {
+                return;
+            };
+
+            let service = service.clone();
+            let task_id = task_id.clone();
+            smol::spawn(async move {
+                let Some(task) = service.workspace_service.get_task(&task_id).await else {
+                    return;
+                };
+                let Some(run_id) = &task.pipeline_run_id else {
+                    return;
+                };
+                service.track_session(&task);
+                if let Err(e) = service.advance(run_id).await {
+                    log::error!("Failed to advance pipeline run {}: {}", run_id, e);
+                }
+            })
+            .detach();
+        });
+
+        // Stage tasks may have finished while the app was closed
+        let service = self.clone();
+        smol::spawn(async move {
+            for run in service.workspace_service.get_running_pipeline_runs().await {
+                if let Err(e) = service.advance(&run.id).await {
+                    log::error!("Failed to resume pipeline run {}: {}", run.id, e);
+                }
+            }
+        })
+        .detach();
+
+        log::info!("PipelineService subscriptions initialized (event_hub)");
+    }
+
+    /// Start recording the output of a running stage task's session
+    ///
+    /// Called on the `TaskUpdated` that `set_task_session` publishes, before
+    /// the session is prompted.
+    fn track_session(&self, task: &WorkspaceTask) {
+        if task.status != SessionStatus::InProgress {
+            return;
+        }
+        if let Some(session_id) = &task.session_id {
+            self.sessions
+                .lock()
+                .unwrap()
+                .entry(session_id.clone())
+                .or_default();
+        }
+    }
+
+    /// Stop recording the sessions of a stage's tasks
+    async fn forget_sessions(&self, run: &PipelineRun, stage: usize) {
+        for task_id in &run.stage_tasks[stage] {
+            if let Some(session_id) = self
+                .workspace_service
+                .get_task(task_id)
+                .await
+                .and_then(|task| task.session_id)
+            {
+                self.sessions.lock().unwrap().remove(&session_id);
+            }
+        }
+    }
+
+    /// List the configured pipelines
+    pub async fn list_pipelines(&self) -> Vec<(String, PipelineDefinition)> {
+        self.agent_config_service.list_pipelines().await
+    }
+
+    /// Start a pipeline in a workspace with the given input
+    pub async fn start(
+        &self,
+        workspace_id: &str,
+        pipeline_name: &str,
+        input: String,
+    ) -> Result<PipelineRun> {
+        let definition = self
+            .agent_config_service
+            .get_pipeline(pipeline_name)
+            .await
+            .ok_or_else(|| anyhow!("Pipeline not found: {}", pipeline_name))?;
+        self.validate(&definition).await?;
+
+        let run = self
+            .workspace_service
+            .add_pipeline_run(PipelineRun::new(
+                workspace_id.to_string(),
+                pipeline_name.to_string(),
+                definition,
+                input,
+            ))
+            .await?;
+
+        self.advance(&run.id).await?;
+        Ok(run)
+    }
+
+    /// Check that a pipeline has stages and that their agents exist
+    async fn validate(&self, definition: &PipelineDefinition) -> Result<()> {
+        if definition.stages.is_empty() {
+            return Err(anyhow!("Pipeline has no stages"));
+        }
+
+        let agents = self.agent_config_service.list_agent_variants().await;
+        for stage in &definition.stages {
+            if !agents.contains(&stage.agent_name) {
+                return Err(anyhow!(
+                    "Agent '{}' of stage '{}' is not configured",
+                    stage.agent_name,
+                    stage.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Start the next stage of a run once all tasks of the current stage are done
+    ///
+    /// Fails the run if a stage task failed, and completes it after the last
+    /// stage. Does nothing while the current stage is still running.
+    pub async fn advance(&self, run_id: &str) -> Result<()> {
+        let _guard = self.advancing.lock().await;
+
+        let run = self
+            .workspace_service
+            .get_pipeline_run(run_id)
+            .await
+            .ok_or_else(|| anyhow!("Pipeline run not found: {}", run_id))?;
+        if run.status != PipelineStatus::Running {
+            return Ok(());
+        }
+
+        let next_stage = match run.current_stage() {
+            None => 0,
+            Some(stage) => {
+                if !self.collect_stage_outputs(&run, stage).await? {
+                    return Ok(());
+                }
+                stage + 1
+            }
+        };
+
+        if next_stage >= run.definition.stages.len() {
+            return self
+                .workspace_service
+                .finish_pipeline_run(run_id, PipelineStatus::Completed, None)
+                .await;
+        }
+
+        // Outputs may have been recorded above
+        let run = self
+            .workspace_service
+            .get_pipeline_run(run_id)
+            .await
+            .ok_or_else(|| anyhow!("Pipeline run not found: {}", run_id))?;
+        let tasks = self.stage_tasks(&run, next_stage).await?;
+        let base_commit = self.snapshot_workspace(&run.workspace_id).await;
+        self.workspace_service
+            .add_pipeline_stage(run_id, next_stage, tasks, base_commit)
+            .await?;
+        Ok(())
+    }
+
+    /// Record the outputs of the finished tasks of a stage
+    ///
+    /// Returns whether every task of the stage is completed. A failed task
+    /// fails the whole run.
+    async fn collect_stage_outputs(&self, run: &PipelineRun, stage: usize) -> Result<bool> {
+        let mut done = true;
+
+        for task_id in &run.stage_tasks[stage] {
+            let task = self
+                .workspace_service
+                .get_task(task_id)
+                .await
+                .ok_or_else(|| anyhow!("Stage task was removed: {}", task_id))?;
+
+            match task.status {
+                SessionStatus::Completed => {
+                    if !run.outputs.contains_key(task_id) {
+                        let output = self.task_output(&task).await;
+                        self.workspace_service
+                            .record_pipeline_output(&run.id, task_id, output)
+                            .await?;
+                    }
+                }
+                SessionStatus::Failed => {
+                    let error = format!(
+                        "Stage '{}' failed: {}",
+                        run.definition.stages[stage].name,
+                        task.last_error.as_deref().unwrap_or("unknown error")
+                    );
+                    self.workspace_service
+                        .finish_pipeline_run(&run.id, PipelineStatus::Failed, Some(error))
+                        .await?;
+                    self.forget_sessions(run, stage).await;
+                    return Ok(false);
+                }
+                _ => done = false,
+            }
+        }
+
+        Ok(done)
+    }
+
+    /// Get the output of a completed task's session
+    ///
+    /// Uses the updates recorded live, falling back to the persisted history
+    /// (e.g. when the task finished before a restart).
+    async fn task_output(&self, task: &WorkspaceTask) -> StageTaskOutput {
+        let Some(session_id) = &task.session_id else {
+            return StageTaskOutput::default();
+        };
+
+        if let Some(session) = self.sessions.lock().unwrap().remove(session_id) {
+            return session.output;
+        }
+
+        match self.message_service.load_history(session_id).await {
+            Ok(messages) => {
+                let mut session = SessionOutput::default();
+                for message in &messages {
+                    session.apply(&message.update);
+                }
+                session.output
+            }
+            Err(e) => {
+                log::warn!("Failed to load history of session {}: {}", session_id, e);
+                StageTaskOutput::default()
+            }
+        }
+    }
+
+    /// Build the queued tasks of a stage from the previous stage's output
+    async fn stage_tasks(&self, run: &PipelineRun, stage: usize) -> Result<Vec<WorkspaceTask>> {
+        let definition = &run.definition.stages[stage];
+        let inputs = match (stage.checked_sub(1), definition.input) {
+            (None, _) | (_, StageInput::PipelineInput) => vec![run.input.clone()],
+            (Some(previous), StageInput::FinalMessage) => {
+                vec![final_messages(&run.stage_outputs(previous))]
+            }
+            (Some(previous), StageInput::PlanEntries) => {
+                let outputs = run.stage_outputs(previous);
+                let entries: Vec<String> = outputs
+                    .iter()
+                    .flat_map(|output| output.plan_entries.iter().cloned())
+                    .collect();
+                if entries.is_empty() {
+                    log::warn!(
+                        "Stage before '{}' reported no plan, using its final message",
+                        definition.name
+                    );
+                    vec![final_messages(&outputs)]
+                } else {
+                    entries
+                }
+            }
+            (Some(previous), StageInput::Diff) => vec![self.stage_diff(run, previous).await?],
+        };
+
+        let mode = definition
+            .mode
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TASK_MODE.to_string());
+        let count = inputs.len();
+
+        Ok(inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| {
+                WorkspaceTask::new_queued(
+                    run.workspace_id.clone(),
+                    stage_task_name(&run.pipeline_name, &definition.name, idx, count),
+                    definition.agent_name.clone(),
+                    mode.clone(),
+                    definition.render_prompt(input, &run.input),
+                    None,
+                )
+            })
+            .collect())
+    }
+
+    /// Snapshot the workspace before a stage starts, so its diff only holds
+    /// the stage's own changes
+    ///
+    /// Returns None when the workspace is not a git repository.
+    async fn snapshot_workspace(&self, workspace_id: &str) -> Option<String> {
+        let workspace = self.workspace_service.get_workspace(workspace_id).await?;
+        match snapshot_commit(&workspace.path).await {
+            Ok(commit) => Some(commit),
+            Err(e) => {
+                log::debug!("No git snapshot for workspace {:?}: {}", workspace.path, e);
+                None
+            }
+        }
+    }
+
+    /// Get the changes made by a stage as a diff
+    ///
+    /// Uses `git diff` against the snapshot taken when the stage started,
+    /// falling back to the change statistics tracked on the stage tasks when
+    /// the workspace is not a git repository.
+    async fn stage_diff(&self, run: &PipelineRun, stage: usize) -> Result<String> {
+        let workspace = self
+            .workspace_service
+            .get_workspace(&run.workspace_id)
+            .await
+            .ok_or_else(|| anyhow!("Workspace not found: {}", run.workspace_id))?;
+
+        let base = run.stage_base_commit(stage).unwrap_or("HEAD");
+        match workspace_diff(&workspace.path, base).await {
+            Ok(diff) if !diff.trim().is_empty() => return Ok(truncate_diff(diff)),
+            Ok(_) => {}
+            Err(e) => log::debug!("No git diff for workspace {:?}: {}", workspace.path, e),
+        }
+
+        let mut summary = String::new();
+        for task_id in &run.stage_tasks[stage] {
+            if let Some(task) = self.workspace_service.get_task(task_id).await {
+                summary.push_str(&change_summary(&task.changes));
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// Commit holding the current state of a repository's tracked files
+///
+/// `git stash create` records uncommitted changes without touching the
+/// working tree or the stash list; it prints nothing for a clean tree.
+async fn snapshot_commit(path: &Path) -> Result<String> {
+    let stash = git::run_git(path, &["stash", "create"]).await?;
+    if !stash.is_empty() {
+        return Ok(stash);
+    }
+    git::run_git(path, &["rev-parse", "HEAD"]).await
+}
+
+/// Get the changes of a repository since `base`, listing new files after the diff
+async fn workspace_diff(path: &Path, base: &str) -> Result<String> {
+    let mut diff = git::run_git(path, &["diff", base]).await?;
+    let untracked = git::run_git(path, &["ls-files", "--others", "--exclude-standard"]).await?;
+    for file in untracked.lines().filter(|l| !l.trim().is_empty()) {
+        diff.push_str(&format!("\nNew file: {}", file));
+    }
+    Ok(diff)
+}
+
+/// Cut a diff down to `MAX_DIFF_LENGTH` characters
+fn truncate_diff(diff: String) -> String {
+    if diff.chars().count() <= MAX_DIFF_LENGTH {
+        return diff;
+    }
+    let truncated: String = diff.chars().take(MAX_DIFF_LENGTH).collect();
+    format!("{}\n... (diff truncated)", truncated)
+}
+
+/// Describe tracked file changes, one file per line
+fn change_summary(changes: &TaskChangeSummary) -> String {
+    changes
+        .files
+        .iter()
+        .map(|file| {
+            format!(
+                "{} (+{} -{})\n",
+                file.path.display(),
+                file.additions,
+                file.deletions
+            )
+        })
+        .collect()
+}
+
+/// Join the final messages of a stage's tasks
+fn final_messages(outputs: &[&StageTaskOutput]) -> String {
+    outputs
+        .iter()
+        .map(|output| output.final_message.trim())
+        .filter(|message| !message.is_empty())
+        .collect::<Vec<_>>()
+        .join(OUTPUT_SEPARATOR)
+}
+
+/// Name of a stage task, numbered when the stage fans out
+fn stage_task_name(pipeline_name: &str, stage_name: &str, idx: usize, count: usize) -> String {
+    if count > 1 {
+        format!("{}: {} ({}/{})", pipeline_name, stage_name, idx + 1, count)
+    } else {
+        format!("{}: {}", pipeline_name, stage_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_client_protocol::{
+        ContentChunk, Plan, PlanEntry, PlanEntryPriority, PlanEntryStatus, ToolCall,
+    };
+
+    fn message(text: &str) -> SessionUpdate {
+        SessionUpdate::AgentMessageChunk(ContentChunk::new(text.to_string().into()))
+    }
+
+    #[test]
+    fn test_session_output_keeps_last_message_and_plan() {
+        let mut session = SessionOutput::default();
+        session.apply(&message("Let me look "));
+        session.apply(&message("at the code."));
+        session.apply(&SessionUpdate::ToolCall(ToolCall::new(
+            "call-1",
+            "Read file",
+        )));
+        session.apply(&SessionUpdate::Plan(Plan::new(vec![
+            PlanEntry::new(
+                "Add tests",
+                PlanEntryPriority::High,
+                PlanEntryStatus::Pending,
+            ),
+            PlanEntry::new(
+                "Fix bug",
+                PlanEntryPriority::Medium,
+                PlanEntryStatus::Pending,
+            ),
+        ])));
+        session.apply(&message("Done: "));
+        session.apply(&message("fixed it."));
+
+        assert_eq!(session.output.final_message, "Done: fixed it.");
+        assert_eq!(session.output.plan_entries, vec!["Add tests", "Fix bug"]);
+    }
+
+    #[test]
+    fn test_stage_task_name() {
+        assert_eq!(stage_task_name("ship", "plan", 0, 1), "ship: plan");
+        assert_eq!(stage_task_name("ship", "code", 1, 3), "ship: code (2/3)");
+    }
+
+    #[tokio::test]
+    async fn test_records_plan_sent_before_first_edit() {
+        use crate::agent_service::AgentService;
+        use crate::persistence_service::PersistenceService;
+        use agentx_event_bus::{AppEvent, SessionUpdateEvent};
+        use std::time::Duration;
+
+        let dir = std::env::temp_dir().join(format!("agentx-pipeline-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let event_hub = EventHub::new();
+        let mut workspace_service = WorkspaceService::new(dir.join("workspace.json"));
+        workspace_service.set_event_hub(event_hub.clone());
+        let workspace_service = Arc::new(workspace_service);
+        let agent_manager = Arc::new(agentx_agent::AgentManager::new(
+            HashMap::new(),
+            Arc::new(Default::default()),
+            event_hub.clone(),
+            Default::default(),
+        ));
+        let config = agentx_types::parse_config(
+            r#"{
+                "agent_servers": { "planner": { "command": "planner-acp" } },
+                "pipelines": {
+                    "plan": {
+                        "stages": [{ "name": "plan", "agent": "planner", "prompt": "{{input}}" }]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let agent_config_service = Arc::new(AgentConfigService::new(
+            config,
+            dir.join("config.json"),
+            agent_manager.clone(),
+            event_hub.clone(),
+        ));
+        let message_service = Arc::new(MessageService::new(
+            event_hub.clone(),
+            Arc::new(AgentService::new(agent_manager)),
+            Arc::new(PersistenceService::new(dir.join("sessions"))),
+        ));
+        let pipeline = Arc::new(PipelineService::new(
+            workspace_service.clone(),
+            agent_config_service,
+            message_service,
+        ));
+        pipeline.init(&event_hub);
+
+        let workspace = workspace_service.add_workspace(dir.clone()).await.unwrap();
+        let run = pipeline
+            .start(&workspace.id, "plan", "Fix the bug".to_string())
+            .await
+            .unwrap();
+        let task = workspace_service.claim_runnable_tasks().await.unwrap()[0].clone();
+        workspace_service
+            .set_task_session(&task.id, "session-1".to_string())
+            .await
+            .unwrap();
+        for _ in 0..100 {
+            if pipeline.sessions.lock().unwrap().contains_key("session-1") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // The plan comes before any file is edited
+        let publish = |update: SessionUpdate| {
+            event_hub.publish(AppEvent::SessionUpdate(SessionUpdateEvent {
+                session_id: "session-1".to_string(),
+                agent_name: Some("planner".to_string()),
+                update: Arc::new(update),
+            }));
+        };
+        publish(SessionUpdate::Plan(Plan::new(vec![PlanEntry::new(
+            "Add tests",
+            PlanEntryPriority::High,
+            PlanEntryStatus::Pending,
+        )])));
+        publish(message("Planned."));
+        workspace_service
+            .finish_queued_task(&task.id, Ok(()))
+            .await
+            .unwrap();
+
+        let mut output = None;
+        for _ in 0..100 {
+            output = workspace_service
+                .get_pipeline_run(&run.id)
+                .await
+                .and_then(|run| run.outputs.get(&task.id).cloned());
+            if output.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let output = output.unwrap();
+        assert_eq!(output.plan_entries, vec!["Add tests"]);
+        assert_eq!(output.final_message, "Planned.");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
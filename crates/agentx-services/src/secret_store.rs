@@ -0,0 +1,565 @@
+//! Secret Store
+//!
+//! An encrypted vault for API keys, tokens and proxy passwords, kept next to
+//! `config.json` so the config file (and its backups) only hold `secret:`
+//! references.
+//!
+//! The vault is encrypted with XChaCha20-Poly1305 under a key derived with
+//! Argon2id from a passphrase. The passphrase comes from
+//! `AGENTX_VAULT_PASSPHRASE`, or from a key file created on first use in a
+//! per-user key directory outside the config directory.
+//!
+//! The key file only keeps secrets out of the config directory: a copied,
+//! synced or committed config directory leaks nothing, but anyone who can
+//! read the user's files can read the key too. Set `AGENTX_VAULT_PASSPHRASE`
+//! for protection beyond that.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use agentx_types::secrets::{SECRET_PREFIX, SecretSource, is_secret_ref, is_sensitive_env_key};
+use agentx_types::{AgentProcessConfig, Config, McpServerConfig};
+use anyhow::{Context, Result, anyhow};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+
+/// Environment variable holding the vault passphrase
+pub const PASSPHRASE_ENV: &str = "AGENTX_VAULT_PASSPHRASE";
+
+const VAULT_FILE: &str = "secrets.vault";
+const KEY_FILE: &str = "secrets.key";
+const VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// On-disk vault layout
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secret Store
+///
+/// Secrets are held decrypted in memory and written back on every change.
+pub struct SecretStore {
+    path: PathBuf,
+    key: [u8; 32],
+    salt: [u8; SALT_LEN],
+    secrets: RwLock<BTreeMap<String, String>>,
+}
+
+impl SecretStore {
+    /// Open the vault at `path` with `passphrase`, creating an empty one if missing
+    pub fn open(path: PathBuf, passphrase: &str) -> Result<Self> {
+        if !path.exists() {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            let store = Self {
+                key: derive_key(passphrase, &salt)?,
+                salt,
+                path,
+                secrets: RwLock::new(BTreeMap::new()),
+            };
+            log::info!("Created secret vault: {:?}", store.path);
+            return Ok(store);
+        }
+
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read secret vault: {:?}", path))?;
+        let file: VaultFile =
+            serde_json::from_str(&json).context("Failed to parse secret vault")?;
+        if file.version != VAULT_VERSION {
+            return Err(anyhow!("Unsupported secret vault version {}", file.version));
+        }
+
+        let salt: [u8; SALT_LEN] = decode(&file.salt)?
+            .try_into()
+            .map_err(|_| anyhow!("Corrupt secret vault salt"))?;
+        let nonce = decode(&file.nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(anyhow!("Corrupt secret vault nonce"));
+        }
+        let key = derive_key(passphrase, &salt)?;
+
+        let plaintext = XChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                decode(&file.ciphertext)?.as_ref(),
+            )
+            .map_err(|_| anyhow!("Failed to decrypt secret vault (wrong passphrase?)"))?;
+        let secrets: BTreeMap<String, String> =
+            serde_json::from_slice(&plaintext).context("Failed to parse secret vault contents")?;
+
+        log::info!("Opened secret vault with {} secrets", secrets.len());
+        Ok(Self {
+            path,
+            key,
+            salt,
+            secrets: RwLock::new(secrets),
+        })
+    }
+
+    /// Open `secrets.vault` in `dir`, with the passphrase from the environment
+    /// or from `secrets.key` in `key_dir` (created on first use)
+    ///
+    /// `key_dir` must not be the vault directory: a key stored next to the
+    /// vault would travel with it.
+    pub fn open_in(dir: &Path, key_dir: &Path) -> Result<Self> {
+        let passphrase = match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) if !passphrase.is_empty() => passphrase,
+            _ => {
+                if same_dir(dir, key_dir) {
+                    return Err(anyhow!(
+                        "Refusing to keep the vault key next to the vault in {:?}; set {}",
+                        dir,
+                        PASSPHRASE_ENV
+                    ));
+                }
+                read_or_create_key_file(&key_dir.join(KEY_FILE))?
+            }
+        };
+        Self::open(dir.join(VAULT_FILE), &passphrase)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.secrets.read().unwrap().get(name).cloned()
+    }
+
+    /// Names of the stored secrets
+    pub fn names(&self) -> Vec<String> {
+        self.secrets.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Store a secret, returning the reference to put in the config
+    pub fn set(&self, name: &str, value: &str) -> Result<String> {
+        {
+            let mut secrets = self.secrets.write().unwrap();
+            if secrets.get(name).map(String::as_str) != Some(value) {
+                secrets.insert(name.to_string(), value.to_string());
+                self.save(&secrets)?;
+            }
+        }
+        Ok(format!("{}{}", SECRET_PREFIX, name))
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        self.remove_all([name.to_string()])
+    }
+
+    /// Remove the secrets externalized from the `env` of a removed agent and
+    /// of its profiles
+    pub fn remove_agent(&self, name: &str, agent: &AgentProcessConfig) -> Result<()> {
+        let mut names = env_secret_names(&format!("agent.{}", name), &agent.env);
+        for (profile_name, profile) in &agent.profiles {
+            names.extend(env_secret_names(
+                &format!("agent.{}.profiles.{}", name, profile_name),
+                &profile.env,
+            ));
+        }
+        self.remove_all(names)
+    }
+
+    /// Remove the secrets externalized from the `env` of a removed MCP server
+    pub fn remove_mcp_server(&self, name: &str, server: &McpServerConfig) -> Result<()> {
+        self.remove_all(env_secret_names(&format!("mcp.{}", name), &server.env))
+    }
+
+    fn remove_all(&self, names: impl IntoIterator<Item = String>) -> Result<()> {
+        let mut secrets = self.secrets.write().unwrap();
+        let mut removed = false;
+        for name in names {
+            removed |= secrets.remove(&name).is_some();
+        }
+        if removed {
+            self.save(&secrets)?;
+        }
+        Ok(())
+    }
+
+    /// Move plaintext secrets of `config` into the vault, replacing them with
+    /// references
+    ///
+    /// Covers model API keys, the proxy password and credential-like `env`
+    /// entries of agents, agent profiles, MCP servers and language servers.
+    /// Returns whether anything moved.
+    pub fn externalize(&self, config: &mut Config) -> Result<bool> {
+        let mut moved = false;
+        for_each_secret(config, |name, value| {
+            if !value.is_empty() && !is_secret_ref(value) {
+                *value = self.set(&name, value)?;
+                moved = true;
+            }
+            Ok(())
+        })?;
+        Ok(moved)
+    }
+
+    /// Replace the plaintext secrets of `config` that the vault already
+    /// holds with their references, without writing the vault
+    pub fn reference_stored(&self, config: &mut Config) {
+        let secrets = self.secrets.read().unwrap();
+        let _ = for_each_secret(config, |name, value| {
+            if secrets.get(&name) == Some(value) {
+                *value = format!("{}{}", SECRET_PREFIX, name);
+            }
+            Ok(())
+        });
+    }
+
+    /// Encrypt `secrets` and atomically replace the vault file
+    fn save(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let plaintext = serde_json::to_vec(secrets).context("Failed to serialize secrets")?;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&self.key))
+            .encrypt(&nonce, plaintext.as_ref())
+            .map_err(|_| anyhow!("Failed to encrypt secret vault"))?;
+
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            salt: BASE64.encode(self.salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        let json = serde_json::to_string_pretty(&file).context("Failed to serialize vault")?;
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create vault directory: {:?}", parent))?;
+        }
+        let temp_path = self.path.with_extension("vault.tmp");
+        write_private(&temp_path, json.as_bytes())?;
+        std::fs::rename(&temp_path, &self.path).context("Failed to replace secret vault")?;
+        Ok(())
+    }
+}
+
+impl SecretSource for SecretStore {
+    fn get_secret(&self, name: &str) -> Option<String> {
+        self.get(name)
+    }
+}
+
+/// Call `f` with the vault name and value of every secret field of `config`
+fn for_each_secret(
+    config: &mut Config,
+    mut f: impl FnMut(String, &mut String) -> Result<()>,
+) -> Result<()> {
+    for (name, model) in config.models.iter_mut() {
+        f(format!("model.{}.api_key", name), &mut model.api_key)?;
+    }
+    f("proxy.password".to_string(), &mut config.proxy.password)?;
+    for (name, server) in config.mcp_servers.iter_mut() {
+        env_secrets(&format!("mcp.{}", name), &mut server.env, &mut f)?;
+    }
+    for (name, agent) in config.agent_servers.iter_mut() {
+        env_secrets(&format!("agent.{}", name), &mut agent.env, &mut f)?;
+        for (profile_name, profile) in agent.profiles.iter_mut() {
+            env_secrets(
+                &format!("agent.{}.profiles.{}", name, profile_name),
+                &mut profile.env,
+                &mut f,
+            )?;
+        }
+    }
+    for (name, server) in config.language_servers.iter_mut() {
+        env_secrets(&format!("lsp.{}", name), &mut server.env, &mut f)?;
+    }
+    Ok(())
+}
+
+fn env_secrets(
+    prefix: &str,
+    env: &mut HashMap<String, String>,
+    f: &mut impl FnMut(String, &mut String) -> Result<()>,
+) -> Result<()> {
+    for (key, value) in env.iter_mut() {
+        if is_sensitive_env_key(key) {
+            f(format!("{}.env.{}", prefix, key), value)?;
+        }
+    }
+    Ok(())
+}
+
+fn env_secret_names(prefix: &str, env: &HashMap<String, String>) -> Vec<String> {
+    env.keys()
+        .filter(|key| is_sensitive_env_key(key))
+        .map(|key| format!("{}.env.{}", prefix, key))
+        .collect()
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive vault key: {}", e))?;
+    Ok(key)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(value)
+        .context("Corrupt secret vault encoding")
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn read_or_create_key_file(path: &Path) -> Result<String> {
+    if path.exists() {
+        let key = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read vault key: {:?}", path))?;
+        return Ok(key.trim().to_string());
+    }
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let key = BASE64.encode(bytes);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create key directory: {:?}", parent))?;
+    }
+    write_private(path, key.as_bytes())?;
+    log::info!("Created vault key file: {:?}", path);
+    Ok(key)
+}
+
+/// Write a file readable only by the current user
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("Failed to write {:?}", path))?;
+        file.write_all(contents)
+            .with_context(|| format!("Failed to write {:?}", path))?;
+    }
+
+    #[cfg(not(unix))]
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {:?}", path))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentx_types::{McpServerConfig, ModelConfig};
+    use std::collections::HashMap;
+
+    fn temp_vault() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("agentx-vault-{}", uuid::Uuid::new_v4()))
+            .join("secrets.vault")
+    }
+
+    #[test]
+    fn test_vault_round_trip() {
+        let path = temp_vault();
+        let store = SecretStore::open(path.clone(), "passphrase").unwrap();
+        assert_eq!(store.set("openai", "sk-123").unwrap(), "secret:openai");
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("sk-123"));
+
+        let reopened = SecretStore::open(path.clone(), "passphrase").unwrap();
+        assert_eq!(reopened.get("openai").as_deref(), Some("sk-123"));
+        assert!(SecretStore::open(path.clone(), "wrong").is_err());
+
+        reopened.remove("openai").unwrap();
+        let reopened = SecretStore::open(path.clone(), "passphrase").unwrap();
+        assert!(reopened.names().is_empty());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_externalize_config() {
+        let path = temp_vault();
+        let store = SecretStore::open(path.clone(), "passphrase").unwrap();
+
+        let mut config: Config = serde_json::from_str(r#"{"agent_servers": {}}"#).unwrap();
+        config.models.insert(
+            "gpt".to_string(),
+            ModelConfig {
+                enabled: true,
+                provider: "OpenAI".to_string(),
+                base_url: "https://api.openai.com/v1".to_string(),
+                api_key: "sk-123".to_string(),
+                model_name: "gpt-4o".to_string(),
+                max_tokens: None,
+                timeout_secs: None,
+            },
+        );
+        config.mcp_servers.insert(
+            "github".to_string(),
+            McpServerConfig {
+                enabled: true,
+                command: "npx".to_string(),
+                args: Vec::new(),
+                env: HashMap::from([
+                    ("GITHUB_TOKEN".to_string(), "ghp_abc".to_string()),
+                    ("NODE_ENV".to_string(), "production".to_string()),
+                    ("GITHUB_API_KEY".to_string(), "${env:GH_KEY}".to_string()),
+                ]),
+            },
+        );
+        config.proxy.password = "hunter2".to_string();
+
+        assert!(store.externalize(&mut config).unwrap());
+        assert_eq!(config.models["gpt"].api_key, "secret:model.gpt.api_key");
+        assert_eq!(config.proxy.password, "secret:proxy.password");
+        let env = &config.mcp_servers["github"].env;
+        assert_eq!(env["GITHUB_TOKEN"], "secret:mcp.github.env.GITHUB_TOKEN");
+        assert_eq!(env["NODE_ENV"], "production");
+        assert_eq!(env["GITHUB_API_KEY"], "${env:GH_KEY}");
+        assert_eq!(store.get("model.gpt.api_key").as_deref(), Some("sk-123"));
+
+        // Already externalized values stay put
+        assert!(!store.externalize(&mut config).unwrap());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_key_file_stays_out_of_vault_dir() {
+        if std::env::var(PASSPHRASE_ENV).is_ok() {
+            return;
+        }
+        let root = std::env::temp_dir().join(format!("agentx-vault-{}", uuid::Uuid::new_v4()));
+        let config_dir = root.join("project");
+        let key_dir = root.join("keys");
+        std::fs::create_dir_all(&config_dir).unwrap();
+
+        assert!(SecretStore::open_in(&config_dir, &config_dir).is_err());
+        assert!(!config_dir.join(KEY_FILE).exists());
+
+        let store = SecretStore::open_in(&config_dir, &key_dir).unwrap();
+        store.set("openai", "sk-123").unwrap();
+        assert!(!config_dir.join(KEY_FILE).exists());
+
+        let key = std::fs::read_to_string(key_dir.join(KEY_FILE)).unwrap();
+        let reopened = SecretStore::open(config_dir.join(VAULT_FILE), &key).unwrap();
+        assert_eq!(reopened.get("openai").as_deref(), Some("sk-123"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_externalize_profile_env() {
+        let path = temp_vault();
+        let store = SecretStore::open(path.clone(), "passphrase").unwrap();
+
+        let mut config: Config = serde_json::from_str(
+            r#"{
+                "agent_servers": {
+                    "codex": {
+                        "command": "codex-acp",
+                        "profiles": {
+                            "work": {
+                                "env": { "OPENAI_API_KEY": "sk-work", "CODEX_HOME": "/work" }
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(store.externalize(&mut config).unwrap());
+        let env = &config.agent_servers["codex"].profiles["work"].env;
+        assert_eq!(
+            env["OPENAI_API_KEY"],
+            "secret:agent.codex.profiles.work.env.OPENAI_API_KEY"
+        );
+        assert_eq!(env["CODEX_HOME"], "/work");
+        assert_eq!(
+            store
+                .get("agent.codex.profiles.work.env.OPENAI_API_KEY")
+                .as_deref(),
+            Some("sk-work")
+        );
+
+        // Removing the agent drops the secrets of its profiles too
+        store
+            .remove_agent("codex", &config.agent_servers["codex"])
+            .unwrap();
+        assert!(store.names().is_empty());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_externalize_language_server_env() {
+        let path = temp_vault();
+        let store = SecretStore::open(path.clone(), "passphrase").unwrap();
+
+        let mut config: Config = serde_json::from_str(
+            r#"{
+                "agent_servers": {},
+                "language_servers": {
+                    "deno": {
+                        "command": "deno",
+                        "args": ["lsp"],
+                        "env": { "DENO_AUTH_TOKENS": "abc@deno.land", "DENO_DIR": "/cache" },
+                        "languages": ["typescript"]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(store.externalize(&mut config).unwrap());
+        let env = &config.language_servers["deno"].env;
+        assert_eq!(
+            env["DENO_AUTH_TOKENS"],
+            "secret:lsp.deno.env.DENO_AUTH_TOKENS"
+        );
+        assert_eq!(env["DENO_DIR"], "/cache");
+
+        // A copy still holding the plaintext gets the references, without a
+        // vault write
+        let mut copy: Config = serde_json::from_str(
+            r#"{
+                "agent_servers": {},
+                "language_servers": {
+                    "deno": {
+                        "command": "deno",
+                        "env": { "DENO_AUTH_TOKENS": "abc@deno.land" },
+                        "languages": ["typescript"]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        store.reference_stored(&mut copy);
+        assert_eq!(
+            copy.language_servers["deno"].env["DENO_AUTH_TOKENS"],
+            "secret:lsp.deno.env.DENO_AUTH_TOKENS"
+        );
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,401 @@
+//! Task Queue Service - Runs queued workspace tasks in the background
+//!
+//! Queued tasks are stored by `WorkspaceService` (see `enqueue_task` and
+//! `enqueue_batch`). This service periodically claims the tasks that may start,
+//! honoring the per-agent concurrency limits and schedules, creates a session
+//! for each one in its workspace and sends the queued prompt. Task status is
+//! reported through `SessionStatus` and `WorkspaceUpdateEvent::TaskUpdated`.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use agent_client_protocol as acp;
+use anyhow::{Result, anyhow};
+
+use agentx_types::schemas::workspace::WorkspaceTask;
+use agentx_types::{EffectiveConfig, SessionStatus};
+
+use super::agent_config_service::AgentConfigService;
+use super::agent_service::AgentService;
+use super::message_service::MessageService;
+use super::workspace_service::WorkspaceService;
+
+/// How often the queue checks for runnable tasks
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Task queue service - drives queued tasks through agent sessions
+pub struct TaskQueueService {
+    workspace_service: Arc<WorkspaceService>,
+    agent_service: Arc<AgentService>,
+    message_service: Arc<MessageService>,
+    /// Used to attach the enabled MCP servers to new sessions
+    agent_config_service: Option<Arc<AgentConfigService>>,
+    running: AtomicBool,
+}
+
+impl TaskQueueService {
+    pub fn new(
+        workspace_service: Arc<WorkspaceService>,
+        agent_service: Arc<AgentService>,
+        message_service: Arc<MessageService>,
+    ) -> Self {
+        Self {
+            workspace_service,
+            agent_service,
+            message_service,
+            agent_config_service: None,
+            running: AtomicBool::new(false),
+        }
+    }
+
+    /// Set the agent config service (for MCP server configuration)
+    pub fn set_agent_config_service(&mut self, service: Arc<AgentConfigService>) {
+        self.agent_config_service = Some(service);
+    }
+
+    /// Start the background queue loop (no-op if already running)
+    pub fn start(self: &Arc<Self>) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let queue = self.clone();
+        smol::spawn(async move {
+            match queue.workspace_service.requeue_interrupted_tasks().await {
+                Ok(0) => {}
+                Ok(count) => log::info!("Re-queued {} interrupted task(s)", count),
+                Err(e) => log::error!("Failed to re-queue interrupted tasks: {}", e),
+            }
+
+            while queue.running.load(Ordering::SeqCst) {
+                queue.tick().await;
+                smol::Timer::after(QUEUE_POLL_INTERVAL).await;
+            }
+            log::info!("Task queue stopped");
+        })
+        .detach();
+
+        log::info!("Task queue started");
+    }
+
+    /// Stop the background queue loop after the current tick
+    ///
+    /// Tasks that are already running are not cancelled.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the queue loop is running
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Record the session status of a task started by hand
+    ///
+    /// Queued tasks are skipped: the queue sets their status once the run is
+    /// over (see `WorkspaceService::finish_queued_task`), and a session status
+    /// arriving later must not overwrite it.
+    pub async fn update_session_status(
+        &self,
+        session_id: &str,
+        status: SessionStatus,
+    ) -> Result<()> {
+        let Some(task) = self.workspace_service.get_task_by_session(session_id).await else {
+            return Ok(());
+        };
+        if task.is_queued() || task.status == status {
+            return Ok(());
+        }
+        self.workspace_service
+            .update_task_status(&task.id, status)
+            .await
+    }
+
+    /// Claim runnable tasks and start them
+    async fn tick(self: &Arc<Self>) {
+        let tasks = match self.workspace_service.claim_runnable_tasks().await {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                log::error!("Failed to claim queued tasks: {}", e);
+                return;
+            }
+        };
+
+        for task in tasks {
+            let queue = self.clone();
+            smol::spawn(async move {
+                let task_id = task.id.clone();
+                let result = queue.run_task(task).await.map_err(|e| e.to_string());
+                if let Err(error) = &result {
+                    log::warn!("Queued task {} failed: {}", task_id, error);
+                }
+
+                match queue
+                    .workspace_service
+                    .finish_queued_task(&task_id, result)
+                    .await
+                {
+                    Ok(SessionStatus::Pending) => {
+                        log::info!("Queued task {} will be retried", task_id)
+                    }
+                    Ok(status) => log::info!("Queued task {} finished: {:?}", task_id, status),
+                    Err(e) => log::error!("Failed to record result of task {}: {}", task_id, e),
+                }
+            })
+            .detach();
+        }
+    }
+
+    /// Run a single queued task: create a session in its workspace and send the prompt
+    async fn run_task(&self, task: WorkspaceTask) -> Result<()> {
+        let prompt = task
+            .prompt
+            .clone()
+            .ok_or_else(|| anyhow!("Task {} has no queued prompt", task.id))?;
+        let workspace = self
+            .workspace_service
+            .get_workspace(&task.workspace_id)
+            .await
+            .ok_or_else(|| anyhow!("Workspace not found: {}", task.workspace_id))?;
+
+        let effective = match &self.agent_config_service {
+            Some(service) => {
+                // Apply the workspace and worktree `.agentx/config.json` overrides
+                let effective = service
+                    .effective_config(&workspace, task.working_dir.as_deref())
+                    .await;
+                service.prepare_agent(&effective, &task.agent_name).await?;
+                Some(effective)
+            }
+            None => None,
+        };
+        let mcp_servers = match &effective {
+            Some(effective) => {
+                let profile = effective.agent_profile(&task.agent_name);
+                let mut servers: Vec<_> = effective
+                    .config
+                    .mcp_servers
+                    .iter()
+                    .filter(|(name, config)| match &task.mcp_servers {
+                        Some(selected) => selected.contains(name),
+                        None => config.enabled,
+                    })
+                    .filter(|(name, _)| profile.is_none_or(|p| p.allows_mcp_server(name)))
+                    .collect();
+                servers.sort_by(|a, b| a.0.cmp(b.0));
+                servers
+                    .into_iter()
+                    .map(|(name, config)| config.to_acp_mcp_server(name.clone()))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let cwd = task.working_dir.clone().unwrap_or(workspace.path);
+
+        log::info!(
+            "Starting queued task '{}' (attempt {}) with agent {} in {:?}",
+            task.name,
+            task.attempts,
+            task.agent_name,
+            cwd
+        );
+
+        let session_id = self
+            .agent_service
+            .create_session_with_mcp_and_cwd(&task.agent_name, mcp_servers, cwd)
+            .await?;
+        let result = self
+            .prompt_session(&task, &session_id, effective.as_ref(), prompt)
+            .await;
+        if result.is_err() {
+            // A retry creates a fresh session, and a failed task keeps its
+            // transcript; either way the agent no longer needs this one
+            self.end_session(&task.agent_name, &session_id).await;
+        }
+        result
+    }
+
+    /// Attach the session to the task and send it the prompt
+    async fn prompt_session(
+        &self,
+        task: &WorkspaceTask,
+        session_id: &str,
+        effective: Option<&EffectiveConfig>,
+        prompt: String,
+    ) -> Result<()> {
+        self.workspace_service
+            .set_task_session(&task.id, session_id.to_string())
+            .await?;
+        if let (Some(service), Some(effective)) = (&self.agent_config_service, effective) {
+            service
+                .apply_profile_defaults(effective, &task.agent_name, session_id)
+                .await;
+        }
+
+        if let Err(e) = self
+            .agent_service
+            .set_session_mode(&task.agent_name, session_id, &task.mode)
+            .await
+        {
+            log::warn!(
+                "Failed to set mode '{}' for task {}: {}",
+                task.mode,
+                task.id,
+                e
+            );
+        }
+
+        let mut prompt_blocks: Vec<acp::ContentBlock> = vec![prompt.into()];
+        prompt_blocks.extend(task.attachments.iter().cloned());
+
+        let response = self
+            .message_service
+            .send_message_to_session(&task.agent_name, session_id, prompt_blocks)
+            .await?;
+
+        match response.stop_reason {
+            acp::StopReason::Refusal => Err(anyhow!("Agent refused the prompt")),
+            acp::StopReason::Cancelled => Err(anyhow!("Prompt was cancelled")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Cancel any turn still running in a session of a failed run, and close it
+    async fn end_session(&self, agent_name: &str, session_id: &str) {
+        if let Err(e) = self
+            .agent_service
+            .cancel_session(agent_name, session_id)
+            .await
+        {
+            log::warn!("Failed to cancel session {}: {}", session_id, e);
+        }
+        if let Err(e) = self
+            .agent_service
+            .close_session(agent_name, session_id)
+            .await
+        {
+            log::warn!("Failed to close session {}: {}", session_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agentx_types::schemas::workspace::{TaskQueueSettings, Workspace, WorkspaceConfig};
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn create_test_config(settings: TaskQueueSettings) -> (WorkspaceConfig, String) {
+        let workspace = Workspace::new(PathBuf::from("/tmp/project"));
+        let workspace_id = workspace.id.clone();
+        let mut config = WorkspaceConfig {
+            queue: settings,
+            ..Default::default()
+        };
+        config.add_workspace(workspace);
+        (config, workspace_id)
+    }
+
+    fn queued_task(workspace_id: &str, agent: &str, name: &str) -> WorkspaceTask {
+        WorkspaceTask::new_queued(
+            workspace_id.to_string(),
+            name.to_string(),
+            agent.to_string(),
+            "Auto".to_string(),
+            format!("prompt for {}", name),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_runnable_tasks_respect_agent_limit() {
+        let (mut config, ws) = create_test_config(TaskQueueSettings::default());
+        config.add_task(queued_task(&ws, "claude", "a"));
+        config.add_task(queued_task(&ws, "claude", "b"));
+        config.add_task(queued_task(&ws, "codex", "c"));
+
+        let runnable = config.runnable_task_ids(chrono::Utc::now());
+        assert_eq!(runnable.len(), 2);
+
+        // A running task occupies the only slot for its agent
+        config.tasks[0].status = SessionStatus::InProgress;
+        let runnable = config.runnable_task_ids(chrono::Utc::now());
+        assert_eq!(runnable, vec![config.tasks[2].id.clone()]);
+    }
+
+    #[test]
+    fn test_manual_tasks_count_against_limit() {
+        let (mut config, ws) = create_test_config(TaskQueueSettings::default());
+        let mut manual = WorkspaceTask::new(
+            ws.clone(),
+            "manual".to_string(),
+            "claude".to_string(),
+            "Auto".to_string(),
+        );
+        manual.status = SessionStatus::InProgress;
+        config.add_task(manual);
+        config.add_task(queued_task(&ws, "claude", "a"));
+        config.add_task(queued_task(&ws, "codex", "b"));
+
+        let runnable = config.runnable_task_ids(chrono::Utc::now());
+        assert_eq!(runnable, vec![config.tasks[2].id.clone()]);
+    }
+
+    #[test]
+    fn test_timestamps_are_unix_seconds() {
+        let (_, ws) = create_test_config(TaskQueueSettings::default());
+        let mut task = queued_task(&ws, "claude", "a");
+        let json = serde_json::to_value(&task).unwrap();
+        assert!(json.get("scheduled_at").is_none());
+
+        let at = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        task.created_at = at;
+        task.scheduled_at = Some(at);
+        let json = serde_json::to_value(&task).unwrap();
+        assert_eq!(json["created_at"], 1_700_000_000);
+        assert_eq!(json["scheduled_at"], 1_700_000_000);
+
+        let task: WorkspaceTask = serde_json::from_value(json).unwrap();
+        assert_eq!(task.scheduled_at, Some(at));
+    }
+
+    #[test]
+    fn test_runnable_tasks_use_agent_override() {
+        let mut settings = TaskQueueSettings::default();
+        settings.agent_limits.insert("claude".to_string(), 3);
+        let (mut config, ws) = create_test_config(settings);
+        for name in ["a", "b", "c", "d"] {
+            config.add_task(queued_task(&ws, "claude", name));
+        }
+
+        assert_eq!(config.runnable_task_ids(chrono::Utc::now()).len(), 3);
+    }
+
+    #[test]
+    fn test_scheduled_and_manual_tasks_are_skipped() {
+        let (mut config, ws) = create_test_config(TaskQueueSettings::default());
+        let mut scheduled = queued_task(&ws, "claude", "later");
+        scheduled.scheduled_at = Some(chrono::Utc::now() + chrono::Duration::hours(1));
+        config.add_task(scheduled);
+        config.add_task(WorkspaceTask::new(
+            ws.clone(),
+            "manual".to_string(),
+            "claude".to_string(),
+            "Auto".to_string(),
+        ));
+
+        assert!(config.runnable_task_ids(chrono::Utc::now()).is_empty());
+        assert_eq!(
+            config
+                .runnable_task_ids(chrono::Utc::now() + chrono::Duration::hours(2))
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Task Template Service - Stores task templates and turns them into tasks
+//!
+//! Templates are stored either globally in the config file (through
+//! `AgentConfigService`) or with a workspace (through `WorkspaceService`).
+//! Using a template renders its prompt, optionally creates a git worktree and
+//! enqueues the resulting task on the task queue.
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{Result, anyhow};
+
+use agentx_types::schemas::task_template::{TaskTemplate, TemplateContext, TemplateScope};
+use agentx_types::schemas::workspace::WorkspaceTask;
+
+use super::agent_config_service::AgentConfigService;
+use super::git;
+use super::workspace_service::WorkspaceService;
+
+/// Default mode recorded on tasks whose template does not set one
+const DEFAULT_TASK_MODE: &str = "default";
+
+/// Values collected from the UI when a template is used
+#[derive(Clone, Debug, Default)]
+pub struct TemplateInput {
+    /// Files selected in the UI
+    pub selected_files: Vec<String>,
+    /// Text content of the clipboard
+    pub clipboard: Option<String>,
+    /// User-entered field values
+    pub values: HashMap<String, String>,
+}
+
+/// A template together with where it is stored
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScopedTemplate {
+    pub name: String,
+    pub template: TaskTemplate,
+    pub scope: TemplateScope,
+}
+
+/// Task template service - manages templates and instantiates them
+pub struct TaskTemplateService {
+    workspace_service: Arc<WorkspaceService>,
+    agent_config_service: Arc<AgentConfigService>,
+}
+
+impl TaskTemplateService {
+    pub fn new(
+        workspace_service: Arc<WorkspaceService>,
+        agent_config_service: Arc<AgentConfigService>,
+    ) -> Self {
+        Self {
+            workspace_service,
+            agent_config_service,
+        }
+    }
+
+    /// List the templates available in a workspace
+    ///
+    /// Workspace templates come first and hide global templates with the
+    /// same name. Without a workspace only global templates are listed.
+    pub async fn list_templates(&self, workspace_id: Option<&str>) -> Vec<ScopedTemplate> {
+        let mut templates: Vec<ScopedTemplate> = match workspace_id {
+            Some(workspace_id) => self
+                .workspace_service
+                .list_workspace_templates(workspace_id)
+                .await
+                .into_iter()
+                .map(|(name, template)| ScopedTemplate {
+                    name,
+                    template,
+                    scope: TemplateScope::Workspace(workspace_id.to_string()),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        for (name, template) in self.agent_config_service.list_task_templates().await {
+            if !templates.iter().any(|t| t.name == name) {
+                templates.push(ScopedTemplate {
+                    name,
+                    template,
+                    scope: TemplateScope::Global,
+                });
+            }
+        }
+
+        templates
+    }
+
+    /// Add or replace a template in the given scope
+    pub async fn save_template(
+        &self,
+        scope: &TemplateScope,
+        name: String,
+        template: TaskTemplate,
+    ) -> Result<()> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(anyhow!("Template name cannot be empty"));
+        }
+        if template.prompt.trim().is_empty() {
+            return Err(anyhow!("Template prompt cannot be empty"));
+        }
+
+        match scope {
+            TemplateScope::Global => {
+                let exists = self
+                    .agent_config_service
+                    .list_task_templates()
+                    .await
+                    .iter()
+                    .any(|(existing, _)| *existing == name);
+                if exists {
+                    self.agent_config_service
+                        .update_task_template(&name, template)
+                        .await
+                } else {
+                    self.agent_config_service
+                        .add_task_template(name, template)
+                        .await
+                }
+            }
+            TemplateScope::Workspace(workspace_id) => {
+                self.workspace_service
+                    .save_workspace_template(workspace_id, name, template)
+                    .await
+            }
+        }
+    }
+
+    /// Remove a template from the given scope
+    pub async fn remove_template(&self, scope: &TemplateScope, name: &str) -> Result<()> {
+        match scope {
+            TemplateScope::Global => self.agent_config_service.remove_task_template(name).await,
+            TemplateScope::Workspace(workspace_id) => {
+                self.workspace_service
+                    .remove_workspace_template(workspace_id, name)
+                    .await
+            }
+        }
+    }
+
+    /// Create a queued task from a template
+    ///
+    /// The prompt is rendered with the workspace's current branch and the
+    /// given input. If the template asks for a worktree, one is created on a
+    /// new branch and the task runs there.
+    pub async fn instantiate(
+        &self,
+        workspace_id: &str,
+        template_name: &str,
+        template: &TaskTemplate,
+        input: TemplateInput,
+    ) -> Result<WorkspaceTask> {
+        let workspace = self
+            .workspace_service
+            .get_workspace(workspace_id)
+            .await
+            .ok_or_else(|| anyhow!("Workspace not found: {}", workspace_id))?;
+
+        let branch = match git::current_branch(&workspace.path).await {
+            Ok(branch) => branch,
+            Err(e) => {
+                log::debug!("No git branch for workspace {:?}: {}", workspace.path, e);
+                None
+            }
+        };
+
+        let prompt = template.render(&TemplateContext {
+            selected_files: input.selected_files,
+            branch,
+            clipboard: input.clipboard,
+            values: input.values,
+        })?;
+
+        let agent_name = match &template.agent_name {
+            Some(agent_name) => agent_name.clone(),
+            None => self
+                .agent_config_service
+                .list_agents()
+                .await
+                .into_iter()
+                .next()
+                .map(|(name, _)| name)
+                .ok_or_else(|| anyhow!("No agent configured"))?,
+        };
+        let mode = template
+            .mode
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TASK_MODE.to_string());
+
+        let mut task = WorkspaceTask::new_queued(
+            workspace_id.to_string(),
+            template_name.to_string(),
+            agent_name,
+            mode,
+            prompt,
+            None,
+        );
+        task.mcp_servers = template.mcp_servers.clone();
+
+        let worktree = if template.use_worktree {
+            let worktree = git::create_worktree(&workspace.path, template_name).await?;
+            task.working_dir = Some(worktree.path.clone());
+            task.branch = Some(worktree.branch.clone());
+            Some(worktree)
+        } else {
+            None
+        };
+
+        let result = self
+            .workspace_service
+            .enqueue_tasks(workspace_id, vec![task])
+            .await
+            .and_then(|mut tasks| tasks.pop().ok_or_else(|| anyhow!("Task was not enqueued")));
+
+        // Don't leave the worktree behind if the task never made it to the queue
+        if result.is_err()
+            && let Some(worktree) = &worktree
+            && let Err(e) = git::remove_worktree(&workspace.path, worktree, true).await
+        {
+            log::warn!("Failed to clean up worktree {:?}: {}", worktree.path, e);
+        }
+
+        result
+    }
+}
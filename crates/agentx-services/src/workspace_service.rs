@@ -5,7 +5,11 @@ use tokio::sync::RwLock;
 
 use agentx_event_bus::{EventHub, WorkspaceUpdateEvent};
 use agentx_types::SessionStatus;
-use agentx_types::schemas::workspace::{Workspace, WorkspaceConfig, WorkspaceTask};
+use agentx_types::schemas::pipeline::{PipelineRun, PipelineStatus, StageTaskOutput};
+use agentx_types::schemas::task_template::TaskTemplate;
+use agentx_types::schemas::workspace::{
+    TaskChangeSummary, TaskComparison, TaskQueueSettings, Workspace, WorkspaceConfig, WorkspaceTask,
+};
 
 /// Service for managing workspaces and tasks
 ///
@@ -222,7 +226,637 @@ impl WorkspaceService {
         Ok(task_clone)
     }
 
+    /// Enqueue a task to be run by the task queue
+    ///
+    /// The task starts as `Pending` and is picked up once `scheduled_at` has
+    /// passed (immediately if `None`) and its agent has a free slot.
+    pub async fn enqueue_task(
+        &self,
+        workspace_id: &str,
+        name: String,
+        agent_name: String,
+        mode: String,
+        prompt: String,
+        scheduled_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<WorkspaceTask> {
+        let mut tasks = self
+            .enqueue_batch(
+                workspace_id,
+                agent_name,
+                mode,
+                vec![(name, prompt)],
+                scheduled_at,
+            )
+            .await?;
+        tasks.pop().context("Task was not enqueued")
+    }
+
+    /// Enqueue several tasks at once (e.g. one prompt per file or per issue)
+    ///
+    /// Each item is a `(name, prompt)` pair. All tasks share the agent, mode and
+    /// schedule, and are persisted with a single write.
+    pub async fn enqueue_batch(
+        &self,
+        workspace_id: &str,
+        agent_name: String,
+        mode: String,
+        items: Vec<(String, String)>,
+        scheduled_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<WorkspaceTask>> {
+        let tasks: Vec<WorkspaceTask> = items
+            .into_iter()
+            .map(|(name, prompt)| {
+                WorkspaceTask::new_queued(
+                    workspace_id.to_string(),
+                    name,
+                    agent_name.clone(),
+                    mode.clone(),
+                    prompt,
+                    scheduled_at,
+                )
+            })
+            .collect();
+
+        self.enqueue_tasks(workspace_id, tasks).await
+    }
+
+    /// Enqueue tasks that were already built with `WorkspaceTask::new_queued`
+    ///
+    /// Use this when tasks need extra settings such as a worktree directory or
+    /// a restricted set of MCP servers.
+    pub async fn enqueue_tasks(
+        &self,
+        workspace_id: &str,
+        tasks: Vec<WorkspaceTask>,
+    ) -> Result<Vec<WorkspaceTask>> {
+        {
+            let mut config = self.config.write().await;
+
+            // Verify workspace exists
+            if config.get_workspace(workspace_id).is_none() {
+                anyhow::bail!("Workspace not found: {}", workspace_id);
+            }
+
+            for task in &tasks {
+                config.add_task(task.clone());
+            }
+        }
+
+        self.save_config().await?;
+
+        for task in &tasks {
+            self.publish_event(WorkspaceUpdateEvent::TaskCreated {
+                workspace_id: workspace_id.to_string(),
+                task_id: task.id.clone(),
+            });
+        }
+
+        log::info!(
+            "Enqueued {} task(s) in workspace {}",
+            tasks.len(),
+            workspace_id
+        );
+        Ok(tasks)
+    }
+
+    /// Claim all queued tasks that may start now
+    ///
+    /// Claimed tasks are marked `InProgress` (so they count against the agent's
+    /// concurrency limit) and their attempt counter is incremented.
+    pub async fn claim_runnable_tasks(&self) -> Result<Vec<WorkspaceTask>> {
+        let claimed: Vec<WorkspaceTask> = {
+            let mut config = self.config.write().await;
+            let ids = config.runnable_task_ids(chrono::Utc::now());
+
+            config
+                .tasks
+                .iter_mut()
+                .filter(|t| ids.contains(&t.id))
+                .map(|task| {
+                    task.status = SessionStatus::InProgress;
+                    task.attempts += 1;
+                    task.last_error = None;
+                    task.clone()
+                })
+                .collect()
+        };
+
+        if claimed.is_empty() {
+            return Ok(claimed);
+        }
+
+        self.save_config().await?;
+
+        for task in &claimed {
+            self.publish_event(WorkspaceUpdateEvent::TaskUpdated {
+                task_id: task.id.clone(),
+            });
+        }
+
+        Ok(claimed)
+    }
+
+    /// Record the outcome of a queued task run
+    ///
+    /// Failed runs go back to `Pending` while retries remain, otherwise the
+    /// task is marked `Failed` with the error message.
+    pub async fn finish_queued_task(
+        &self,
+        task_id: &str,
+        result: std::result::Result<(), String>,
+    ) -> Result<SessionStatus> {
+        let status = {
+            let mut config = self.config.write().await;
+            let max_retries = config.queue.max_retries;
+
+            let task = config
+                .tasks
+                .iter_mut()
+                .find(|t| t.id == task_id)
+                .context("Task not found")?;
+
+            task.status = match result {
+                Ok(()) => {
+                    task.last_error = None;
+                    SessionStatus::Completed
+                }
+                Err(error) => {
+                    task.last_error = Some(error);
+                    if task.attempts <= max_retries {
+                        // A fresh session is created for the retry
+                        task.session_id = None;
+                        SessionStatus::Pending
+                    } else {
+                        SessionStatus::Failed
+                    }
+                }
+            };
+            task.status.clone()
+        };
+
+        self.save_config().await?;
+
+        self.publish_event(WorkspaceUpdateEvent::TaskUpdated {
+            task_id: task_id.to_string(),
+        });
+
+        Ok(status)
+    }
+
+    /// Put a failed or finished queued task back into the queue
+    pub async fn retry_task(&self, task_id: &str) -> Result<()> {
+        {
+            let mut config = self.config.write().await;
+
+            let task = config
+                .tasks
+                .iter_mut()
+                .find(|t| t.id == task_id)
+                .context("Task not found")?;
+
+            if !task.is_queued() {
+                anyhow::bail!("Task {} was not created by the task queue", task_id);
+            }
+            if task.status == SessionStatus::InProgress {
+                anyhow::bail!("Task {} is still running", task_id);
+            }
+
+            task.status = SessionStatus::Pending;
+            task.session_id = None;
+            task.attempts = 0;
+            task.last_error = None;
+            task.scheduled_at = None;
+        }
+
+        self.save_config().await?;
+
+        self.publish_event(WorkspaceUpdateEvent::TaskUpdated {
+            task_id: task_id.to_string(),
+        });
+
+        log::info!("Re-queued task: {}", task_id);
+        Ok(())
+    }
+
+    /// Re-queue queued tasks left `InProgress` by a previous run of the app
+    ///
+    /// Tasks started by hand that were left `InProgress` are marked `Idle`:
+    /// their prompt ended with the app, and they would otherwise hold a slot
+    /// of their agent's concurrency limit forever. Returns the number of
+    /// re-queued tasks.
+    pub async fn requeue_interrupted_tasks(&self) -> Result<usize> {
+        let (updated, requeued) = {
+            let mut config = self.config.write().await;
+            let mut updated = Vec::new();
+            let mut requeued = 0;
+            for task in config
+                .tasks
+                .iter_mut()
+                .filter(|t| t.status == SessionStatus::InProgress)
+            {
+                if task.is_queued() {
+                    task.status = SessionStatus::Pending;
+                    task.session_id = None;
+                    requeued += 1;
+                } else {
+                    task.status = SessionStatus::Idle;
+                }
+                updated.push(task.id.clone());
+            }
+            (updated, requeued)
+        };
+
+        if updated.is_empty() {
+            return Ok(0);
+        }
+
+        self.save_config().await?;
+
+        for task_id in updated {
+            self.publish_event(WorkspaceUpdateEvent::TaskUpdated { task_id });
+        }
+
+        Ok(requeued)
+    }
+
+    /// Get the task queue settings
+    pub async fn get_queue_settings(&self) -> TaskQueueSettings {
+        let config = self.config.read().await;
+        config.queue.clone()
+    }
+
+    /// Update the task queue settings
+    pub async fn set_queue_settings(&self, settings: TaskQueueSettings) -> Result<()> {
+        {
+            let mut config = self.config.write().await;
+            config.queue = settings;
+        }
+
+        self.save_config().await
+    }
+
+    // ========== Task Template Operations ==========
+
+    /// List the task templates stored with a workspace, sorted by name
+    pub async fn list_workspace_templates(
+        &self,
+        workspace_id: &str,
+    ) -> Vec<(String, TaskTemplate)> {
+        let config = self.config.read().await;
+        let mut templates: Vec<_> = config
+            .get_workspace(workspace_id)
+            .map(|ws| {
+                ws.templates
+                    .iter()
+                    .map(|(name, template)| (name.clone(), template.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        templates.sort_by(|a, b| a.0.cmp(&b.0));
+        templates
+    }
+
+    /// Add or replace a task template stored with a workspace
+    pub async fn save_workspace_template(
+        &self,
+        workspace_id: &str,
+        name: String,
+        template: TaskTemplate,
+    ) -> Result<()> {
+        {
+            let mut config = self.config.write().await;
+            let workspace = config
+                .workspaces
+                .iter_mut()
+                .find(|w| w.id == workspace_id)
+                .context("Workspace not found")?;
+            workspace.templates.insert(name.clone(), template);
+        }
+
+        self.save_config().await?;
+        self.publish_event(WorkspaceUpdateEvent::TemplatesUpdated {
+            workspace_id: workspace_id.to_string(),
+        });
+
+        log::info!(
+            "Saved task template '{}' in workspace {}",
+            name,
+            workspace_id
+        );
+        Ok(())
+    }
+
+    /// Remove a task template stored with a workspace
+    pub async fn remove_workspace_template(&self, workspace_id: &str, name: &str) -> Result<()> {
+        {
+            let mut config = self.config.write().await;
+            let workspace = config
+                .workspaces
+                .iter_mut()
+                .find(|w| w.id == workspace_id)
+                .context("Workspace not found")?;
+            if workspace.templates.remove(name).is_none() {
+                anyhow::bail!("Task template '{}' not found", name);
+            }
+        }
+
+        self.save_config().await?;
+        self.publish_event(WorkspaceUpdateEvent::TemplatesUpdated {
+            workspace_id: workspace_id.to_string(),
+        });
+
+        log::info!(
+            "Removed task template '{}' from workspace {}",
+            name,
+            workspace_id
+        );
+        Ok(())
+    }
+
+    // ========== Comparison Operations ==========
+
+    /// Store a comparison and enqueue its candidate tasks
+    ///
+    /// The tasks are linked to the comparison and its `task_ids` are set from
+    /// them, so both are persisted with a single write.
+    pub async fn add_comparison(
+        &self,
+        mut comparison: TaskComparison,
+        mut tasks: Vec<WorkspaceTask>,
+    ) -> Result<TaskComparison> {
+        for task in &mut tasks {
+            task.comparison_id = Some(comparison.id.clone());
+        }
+        comparison.task_ids = tasks.iter().map(|t| t.id.clone()).collect();
+
+        {
+            let mut config = self.config.write().await;
+            if config.get_workspace(&comparison.workspace_id).is_none() {
+                anyhow::bail!("Workspace not found: {}", comparison.workspace_id);
+            }
+
+            config.comparisons.push(comparison.clone());
+            for task in &tasks {
+                config.add_task(task.clone());
+            }
+        }
+
+        self.save_config().await?;
+
+        for task in &tasks {
+            self.publish_event(WorkspaceUpdateEvent::TaskCreated {
+                workspace_id: comparison.workspace_id.clone(),
+                task_id: task.id.clone(),
+            });
+        }
+        self.publish_event(WorkspaceUpdateEvent::ComparisonUpdated {
+            workspace_id: comparison.workspace_id.clone(),
+            comparison_id: comparison.id.clone(),
+        });
+
+        log::info!(
+            "Created comparison {} with {} candidate(s) in workspace {}",
+            comparison.id,
+            tasks.len(),
+            comparison.workspace_id
+        );
+        Ok(comparison)
+    }
+
+    /// Get a comparison by ID
+    pub async fn get_comparison(&self, comparison_id: &str) -> Option<TaskComparison> {
+        let config = self.config.read().await;
+        config.get_comparison(comparison_id).cloned()
+    }
+
+    /// Get the candidate tasks of a comparison, in comparison order
+    ///
+    /// Tasks that were removed since the comparison was created are skipped.
+    pub async fn get_comparison_tasks(&self, comparison_id: &str) -> Vec<WorkspaceTask> {
+        let config = self.config.read().await;
+        let Some(comparison) = config.get_comparison(comparison_id) else {
+            return Vec::new();
+        };
+
+        comparison
+            .task_ids
+            .iter()
+            .filter_map(|id| config.tasks.iter().find(|t| &t.id == id).cloned())
+            .collect()
+    }
+
+    /// Record the winning task of a comparison
+    ///
+    /// Fails if the comparison already has a winner, so only one of two
+    /// concurrent picks goes through.
+    pub async fn set_comparison_winner(&self, comparison_id: &str, task_id: &str) -> Result<()> {
+        let workspace_id = {
+            let mut config = self.config.write().await;
+            let comparison = config
+                .comparisons
+                .iter_mut()
+                .find(|c| c.id == comparison_id)
+                .context("Comparison not found")?;
+            if !comparison.task_ids.iter().any(|id| id == task_id) {
+                anyhow::bail!(
+                    "Task {} is not part of comparison {}",
+                    task_id,
+                    comparison_id
+                );
+            }
+            if comparison.winner_task_id.is_some() {
+                anyhow::bail!("A winner was already picked for this comparison");
+            }
+            comparison.winner_task_id = Some(task_id.to_string());
+            comparison.workspace_id.clone()
+        };
+
+        self.save_config().await?;
+        self.publish_event(WorkspaceUpdateEvent::ComparisonUpdated {
+            workspace_id,
+            comparison_id: comparison_id.to_string(),
+        });
+
+        log::info!("Task {} won comparison {}", task_id, comparison_id);
+        Ok(())
+    }
+
+    /// Clear the winner of a comparison whose merge failed
+    pub async fn clear_comparison_winner(&self, comparison_id: &str) -> Result<()> {
+        let workspace_id = {
+            let mut config = self.config.write().await;
+            let comparison = config
+                .comparisons
+                .iter_mut()
+                .find(|c| c.id == comparison_id)
+                .context("Comparison not found")?;
+            comparison.winner_task_id = None;
+            comparison.workspace_id.clone()
+        };
+
+        self.save_config().await?;
+        self.publish_event(WorkspaceUpdateEvent::ComparisonUpdated {
+            workspace_id,
+            comparison_id: comparison_id.to_string(),
+        });
+        Ok(())
+    }
+
+    // ========== Pipeline Operations ==========
+
+    /// Store a new pipeline run (its stages are started by `add_pipeline_stage`)
+    pub async fn add_pipeline_run(&self, run: PipelineRun) -> Result<PipelineRun> {
+        {
+            let mut config = self.config.write().await;
+            if config.get_workspace(&run.workspace_id).is_none() {
+                anyhow::bail!("Workspace not found: {}", run.workspace_id);
+            }
+            config.pipeline_runs.push(run.clone());
+        }
+
+        self.save_config().await?;
+        self.publish_event(WorkspaceUpdateEvent::PipelineUpdated {
+            workspace_id: run.workspace_id.clone(),
+            run_id: run.id.clone(),
+        });
+
+        log::info!(
+            "Created run {} of pipeline '{}' in workspace {}",
+            run.id,
+            run.pipeline_name,
+            run.workspace_id
+        );
+        Ok(run)
+    }
+
+    /// Get a pipeline run by ID
+    pub async fn get_pipeline_run(&self, run_id: &str) -> Option<PipelineRun> {
+        let config = self.config.read().await;
+        config.get_pipeline_run(run_id).cloned()
+    }
+
+    /// Get the pipeline runs that are still running
+    pub async fn get_running_pipeline_runs(&self) -> Vec<PipelineRun> {
+        let config = self.config.read().await;
+        config
+            .pipeline_runs
+            .iter()
+            .filter(|r| r.status == PipelineStatus::Running)
+            .cloned()
+            .collect()
+    }
+
+    /// Enqueue the tasks of the next stage of a pipeline run
+    ///
+    /// `base_commit` is the workspace snapshot the stage's changes are diffed
+    /// against. Fails if the run is no longer running or `stage` is not the
+    /// next stage, so a stage is never started twice.
+    pub async fn add_pipeline_stage(
+        &self,
+        run_id: &str,
+        stage: usize,
+        mut tasks: Vec<WorkspaceTask>,
+        base_commit: Option<String>,
+    ) -> Result<Vec<WorkspaceTask>> {
+        let workspace_id = {
+            let mut config = self.config.write().await;
+            let run = config
+                .get_pipeline_run_mut(run_id)
+                .context("Pipeline run not found")?;
+            if run.status != PipelineStatus::Running {
+                anyhow::bail!("Pipeline run {} is not running", run_id);
+            }
+            if run.stage_tasks.len() != stage {
+                anyhow::bail!("Stage {} of pipeline run {} already started", stage, run_id);
+            }
+
+            for task in &mut tasks {
+                task.pipeline_run_id = Some(run_id.to_string());
+                task.pipeline_stage = Some(stage);
+            }
+            run.stage_tasks
+                .push(tasks.iter().map(|t| t.id.clone()).collect());
+            run.stage_base_commits.resize(stage, None);
+            run.stage_base_commits.push(base_commit);
+            let workspace_id = run.workspace_id.clone();
+
+            for task in &tasks {
+                config.add_task(task.clone());
+            }
+            workspace_id
+        };
+
+        self.save_config().await?;
+
+        for task in &tasks {
+            self.publish_event(WorkspaceUpdateEvent::TaskCreated {
+                workspace_id: workspace_id.clone(),
+                task_id: task.id.clone(),
+            });
+        }
+        self.publish_event(WorkspaceUpdateEvent::PipelineUpdated {
+            workspace_id,
+            run_id: run_id.to_string(),
+        });
+
+        log::info!(
+            "Started stage {} of pipeline run {} with {} task(s)",
+            stage,
+            run_id,
+            tasks.len()
+        );
+        Ok(tasks)
+    }
+
+    /// Record the output of a finished stage task
+    pub async fn record_pipeline_output(
+        &self,
+        run_id: &str,
+        task_id: &str,
+        output: StageTaskOutput,
+    ) -> Result<()> {
+        {
+            let mut config = self.config.write().await;
+            let run = config
+                .get_pipeline_run_mut(run_id)
+                .context("Pipeline run not found")?;
+            run.outputs.insert(task_id.to_string(), output);
+        }
+
+        self.save_config().await
+    }
+
+    /// Mark a pipeline run as completed or failed
+    pub async fn finish_pipeline_run(
+        &self,
+        run_id: &str,
+        status: PipelineStatus,
+        error: Option<String>,
+    ) -> Result<()> {
+        let workspace_id = {
+            let mut config = self.config.write().await;
+            let run = config
+                .get_pipeline_run_mut(run_id)
+                .context("Pipeline run not found")?;
+            run.status = status;
+            run.error = error;
+            run.workspace_id.clone()
+        };
+
+        self.save_config().await?;
+        self.publish_event(WorkspaceUpdateEvent::PipelineUpdated {
+            workspace_id,
+            run_id: run_id.to_string(),
+        });
+
+        log::info!("Pipeline run {} finished: {:?}", run_id, status);
+        Ok(())
+    }
+
     /// Associate a session with a task
+    ///
+    /// Publishes `TaskUpdated` before the session is prompted, so listeners
+    /// such as pipelines see the session from its first update.
     pub async fn set_task_session(&self, task_id: &str, session_id: String) -> Result<()> {
         {
             let mut config = self.config.write().await;
@@ -238,6 +872,10 @@ impl WorkspaceService {
 
         self.save_config().await?;
 
+        self.publish_event(WorkspaceUpdateEvent::TaskUpdated {
+            task_id: task_id.to_string(),
+        });
+
         Ok(())
     }
 
@@ -286,6 +924,36 @@ impl WorkspaceService {
         Ok(())
     }
 
+    /// Update the accumulated file changes of the task bound to a session
+    ///
+    /// Returns `Ok(false)` if no task is associated with the session.
+    pub async fn update_task_changes(
+        &self,
+        session_id: &str,
+        changes: TaskChangeSummary,
+    ) -> Result<bool> {
+        let task_id = {
+            let mut config = self.config.write().await;
+
+            let Some(task) = config.find_task_by_session(session_id) else {
+                return Ok(false);
+            };
+
+            if task.changes == changes {
+                return Ok(true);
+            }
+
+            task.changes = changes;
+            task.id.clone()
+        };
+
+        self.save_config().await?;
+
+        self.publish_event(WorkspaceUpdateEvent::TaskUpdated { task_id });
+
+        Ok(true)
+    }
+
     /// Get a task by its session ID
     pub async fn get_task_by_session(&self, session_id: &str) -> Option<WorkspaceTask> {
         let config = self.config.read().await;
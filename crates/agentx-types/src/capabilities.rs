@@ -0,0 +1,201 @@
+//! What an agent supports, from its `initialize` response
+//!
+//! The UI uses [`AgentFeatures`] to disable actions an agent doesn't
+//! support, and prompts are adapted to it before they are sent: embedded
+//! resources are converted to text or links, while images and audio can't
+//! be converted and block the prompt.
+
+use agent_client_protocol as acp;
+
+/// An optional ACP feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    LoadSession,
+    ResumeSession,
+    ListSessions,
+    Images,
+    Audio,
+    EmbeddedContext,
+    McpHttp,
+    McpSse,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 8] = [
+        Feature::LoadSession,
+        Feature::ResumeSession,
+        Feature::ListSessions,
+        Feature::Images,
+        Feature::Audio,
+        Feature::EmbeddedContext,
+        Feature::McpHttp,
+        Feature::McpSse,
+    ];
+
+    /// Stable identifier, e.g. for locale keys
+    pub fn id(self) -> &'static str {
+        match self {
+            Feature::LoadSession => "load_session",
+            Feature::ResumeSession => "resume_session",
+            Feature::ListSessions => "list_sessions",
+            Feature::Images => "images",
+            Feature::Audio => "audio",
+            Feature::EmbeddedContext => "embedded_context",
+            Feature::McpHttp => "mcp_http",
+            Feature::McpSse => "mcp_sse",
+        }
+    }
+}
+
+/// Features an agent advertised in its `initialize` response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AgentFeatures {
+    pub load_session: bool,
+    pub resume_session: bool,
+    pub list_sessions: bool,
+    pub images: bool,
+    pub audio: bool,
+    pub embedded_context: bool,
+    pub mcp_http: bool,
+    pub mcp_sse: bool,
+}
+
+impl AgentFeatures {
+    pub fn from_init_response(response: &acp::InitializeResponse) -> Self {
+        let capabilities = &response.agent_capabilities;
+        Self {
+            load_session: capabilities.load_session,
+            resume_session: capabilities.session_capabilities.resume.is_some(),
+            list_sessions: capabilities.session_capabilities.list.is_some(),
+            images: capabilities.prompt_capabilities.image,
+            audio: capabilities.prompt_capabilities.audio,
+            embedded_context: capabilities.prompt_capabilities.embedded_context,
+            mcp_http: capabilities.mcp_capabilities.http,
+            mcp_sse: capabilities.mcp_capabilities.sse,
+        }
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::LoadSession => self.load_session,
+            Feature::ResumeSession => self.resume_session,
+            Feature::ListSessions => self.list_sessions,
+            Feature::Images => self.images,
+            Feature::Audio => self.audio,
+            Feature::EmbeddedContext => self.embedded_context,
+            Feature::McpHttp => self.mcp_http,
+            Feature::McpSse => self.mcp_sse,
+        }
+    }
+
+    /// Whether an existing session can be opened, by loading or resuming it
+    pub fn can_reopen_session(&self) -> bool {
+        self.load_session || self.resume_session
+    }
+
+    /// Whether the agent can connect to an MCP server (stdio is always supported)
+    pub fn supports_mcp_server(&self, server: &acp::McpServer) -> bool {
+        match server {
+            acp::McpServer::Http(_) => self.mcp_http,
+            acp::McpServer::Sse(_) => self.mcp_sse,
+            _ => true,
+        }
+    }
+
+    /// Adapt a prompt to the content the agent accepts
+    ///
+    /// Embedded resources become text (or a link, for binary contents) when
+    /// the agent doesn't support embedded context. Images and audio have no
+    /// fallback, so the feature they need is returned as the error.
+    pub fn adapt_prompt(
+        &self,
+        prompt: Vec<acp::ContentBlock>,
+    ) -> Result<Vec<acp::ContentBlock>, Feature> {
+        prompt
+            .into_iter()
+            .map(|block| match block {
+                acp::ContentBlock::Image(_) if !self.images => Err(Feature::Images),
+                acp::ContentBlock::Audio(_) if !self.audio => Err(Feature::Audio),
+                acp::ContentBlock::Resource(resource) if !self.embedded_context => {
+                    Ok(resource_fallback(resource))
+                }
+                block => Ok(block),
+            })
+            .collect()
+    }
+}
+
+/// Baseline content for an embedded resource
+fn resource_fallback(resource: acp::EmbeddedResource) -> acp::ContentBlock {
+    match resource.resource {
+        acp::EmbeddedResourceResource::TextResourceContents(contents) => {
+            acp::ContentBlock::from(format!("{}\n```\n{}\n```", contents.uri, contents.text))
+        }
+        acp::EmbeddedResourceResource::BlobResourceContents(contents) => {
+            resource_link(contents.uri)
+        }
+        _ => acp::ContentBlock::from("[Unsupported resource]".to_string()),
+    }
+}
+
+fn resource_link(uri: String) -> acp::ContentBlock {
+    let name = uri
+        .rsplit(['/', '\\'])
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&uri)
+        .to_string();
+    acp::ContentBlock::ResourceLink(acp::ResourceLink::new(name, uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adapt_prompt() {
+        let resource = acp::ContentBlock::Resource(acp::EmbeddedResource::new(
+            acp::EmbeddedResourceResource::TextResourceContents(acp::TextResourceContents::new(
+                "fn main() {}",
+                "file:///src/main.rs",
+            )),
+        ));
+        let image = acp::ContentBlock::Image(acp::ImageContent::new("", "image/png"));
+
+        let features = AgentFeatures::default();
+        let prompt = features
+            .adapt_prompt(vec![
+                acp::ContentBlock::from("hi".to_string()),
+                resource.clone(),
+            ])
+            .unwrap();
+        match &prompt[1] {
+            acp::ContentBlock::Text(text) => {
+                assert!(text.text.starts_with("file:///src/main.rs"));
+                assert!(text.text.contains("fn main() {}"));
+            }
+            _ => panic!("resource should be converted to text"),
+        }
+        assert_eq!(
+            features.adapt_prompt(vec![image.clone()]).unwrap_err(),
+            Feature::Images
+        );
+
+        let features = AgentFeatures {
+            images: true,
+            embedded_context: true,
+            ..Default::default()
+        };
+        let prompt = features.adapt_prompt(vec![resource, image]).unwrap();
+        assert!(matches!(prompt[0], acp::ContentBlock::Resource(_)));
+        assert!(matches!(prompt[1], acp::ContentBlock::Image(_)));
+    }
+
+    #[test]
+    fn test_resource_link_name() {
+        match resource_link("file:///data/report.pdf".to_string()) {
+            acp::ContentBlock::ResourceLink(link) => assert_eq!(link.name, "report.pdf"),
+            _ => panic!("expected a resource link"),
+        }
+    }
+}
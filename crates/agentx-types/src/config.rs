@@ -1,9 +1,20 @@
 use agent_client_protocol as acp;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+use crate::schemas::pipeline::PipelineDefinition;
+use crate::schemas::task_template::TaskTemplate;
+use crate::secrets::{resolve_env, resolve_secret};
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Config {
+    /// JSON Schema reference for editor completion (e.g. `./config.schema.json`)
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
     pub agent_servers: HashMap<String, AgentProcessConfig>,
     #[serde(default = "default_upload_dir")]
     pub upload_dir: PathBuf,
@@ -13,6 +24,15 @@ pub struct Config {
     pub mcp_servers: HashMap<String, McpServerConfig>,
     #[serde(default)]
     pub commands: HashMap<String, CommandConfig>,
+    /// Global task templates
+    #[serde(default)]
+    pub task_templates: HashMap<String, TaskTemplate>,
+    /// Agent pipelines (chained tasks)
+    #[serde(default)]
+    pub pipelines: HashMap<String, PipelineDefinition>,
+    /// Language servers used by the code editor
+    #[serde(default)]
+    pub language_servers: HashMap<String, LanguageServerConfig>,
     /// Global system prompts for AI features
     /// Keys: "doc_comment", "inline_comment", "explain", "improve"
     #[serde(default)]
@@ -23,6 +43,22 @@ pub struct Config {
     /// Network proxy configuration
     #[serde(default)]
     pub proxy: ProxyConfig,
+    /// Private Node.js runtime for agents that need Node.js
+    #[serde(default, skip_serializing_if = "NodeRuntimeConfig::is_default")]
+    pub node_runtime: NodeRuntimeConfig,
+    /// Folders whose `.agentx/config.json` may start the agents it defines
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_folders: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Whether the user trusted the agents defined in `dir`'s overlay
+    pub fn is_trusted_folder(&self, dir: &Path) -> bool {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        self.trusted_folders
+            .iter()
+            .any(|trusted| trusted.canonicalize().unwrap_or_else(|_| trusted.clone()) == dir)
+    }
 }
 
 fn default_upload_dir() -> PathBuf {
@@ -35,31 +71,186 @@ fn default_tool_call_preview_max_lines() -> usize {
     DEFAULT_TOOL_CALL_PREVIEW_MAX_LINES
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct AgentProcessConfig {
     pub command: String,
     #[serde(default)]
     pub args: Vec<String>,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Named variants of this agent, selectable when creating a task
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, AgentProfile>,
+    /// Where the agent may start sessions
+    #[serde(default, skip_serializing_if = "CwdPolicy::is_any")]
+    pub cwd_policy: CwdPolicy,
+
+    /// Minimum Node.js major version the agent runs on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_version: Option<u32>,
 
     /// Custom Node.js path (populated at runtime from AppSettings)
     #[serde(skip)]
     pub nodejs_path: Option<String>,
 }
 
+/// Separator between an agent and its profile in agent names, e.g. `codex@offline`
+pub const PROFILE_SEPARATOR: char = '@';
+
+impl AgentProcessConfig {
+    /// Whether the agent is started with Node.js (`npx`, `node`, or a script)
+    pub fn requires_nodejs(&self) -> bool {
+        self.command.ends_with(".js")
+            || self.command.ends_with(".ts")
+            || self.command.contains("node")
+            || self.command.contains("npx")
+    }
+
+    /// The process config of one of the agent's profiles
+    ///
+    /// The profile's env vars are merged over the agent's and its args are
+    /// appended.
+    pub fn with_profile(&self, profile: &str) -> Option<Self> {
+        let overrides = self.profiles.get(profile)?;
+        let mut config = self.clone();
+        config.args.extend(overrides.args.iter().cloned());
+        config.env.extend(
+            overrides
+                .env
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        config.profiles.clear();
+        Some(config)
+    }
+
+    /// Names of the agent and its profile variants, sorted by profile
+    pub fn variant_names(&self, agent: &str) -> Vec<String> {
+        let mut profiles: Vec<_> = self.profiles.keys().collect();
+        profiles.sort();
+        std::iter::once(agent.to_string())
+            .chain(profiles.into_iter().map(|p| profile_agent_name(agent, p)))
+            .collect()
+    }
+}
+
+/// Name of the agent process running a profile
+pub fn profile_agent_name(agent: &str, profile: &str) -> String {
+    format!("{}{}{}", agent, PROFILE_SEPARATOR, profile)
+}
+
+/// Split an agent name into the configured agent and its profile, if any
+pub fn split_profile_agent_name(name: &str) -> (&str, Option<&str>) {
+    match name.split_once(PROFILE_SEPARATOR) {
+        Some((agent, profile)) => (agent, Some(profile)),
+        None => (name, None),
+    }
+}
+
+/// A named variant of an agent (e.g. "staging creds", "offline")
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+pub struct AgentProfile {
+    /// Env vars set on top of the agent's
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Args appended to the agent's
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Session mode selected when a session starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Session model selected when a session starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// MCP servers the sessions may use, all enabled ones if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<Vec<String>>,
+}
+
+impl AgentProfile {
+    /// Whether the profile allows an MCP server
+    pub fn allows_mcp_server(&self, name: &str) -> bool {
+        self.mcp_servers
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|allowed| allowed == name))
+    }
+}
+
+/// Working directories an agent may start sessions in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CwdPolicy {
+    /// Any directory
+    #[default]
+    Any,
+    /// Only inside the folders of registered workspaces
+    Workspaces,
+}
+
+impl CwdPolicy {
+    fn is_any(&self) -> bool {
+        *self == Self::Any
+    }
+
+    /// Whether a session may start in `cwd`, given the allowed folders
+    ///
+    /// Paths are compared after resolving symlinks and `..`, so a cwd
+    /// cannot escape a folder through them.
+    pub fn allows(&self, cwd: &Path, allowed_dirs: &[PathBuf]) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Workspaces => {
+                let cwd = normalize_path(cwd);
+                allowed_dirs
+                    .iter()
+                    .any(|dir| cwd.starts_with(normalize_path(dir)))
+            }
+        }
+    }
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| {
+        // Not created yet: drop `.` and fold `..` lexically
+        let mut normalized = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    normalized.pop();
+                }
+                component => normalized.push(component),
+            }
+        }
+        normalized
+    })
+}
+
 /// Model configuration for LLM providers
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ModelConfig {
     pub enabled: bool,
     pub provider: String,
     pub base_url: String,
     pub api_key: String,
     pub model_name: String,
+    /// Cap on generated tokens, overriding the per-action defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Seconds to wait for the response to start and between streamed chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl ModelConfig {
+    /// The API key, with `secret:` and `${env:}` references resolved
+    pub fn resolved_api_key(&self) -> String {
+        resolve_secret(&self.api_key)
+    }
 }
 
 /// MCP (Model Context Protocol) server configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct McpServerConfig {
     #[serde(default = "default_true")]
     pub enabled: bool,
@@ -74,8 +265,7 @@ impl McpServerConfig {
     /// Convert to agent_client_protocol::McpServer
     pub fn to_acp_mcp_server(&self, name: String) -> acp::McpServer {
         // Try to deserialize into McpServerStdio via JSON
-        let env_vars: Vec<serde_json::Value> = self
-            .env
+        let env_vars: Vec<serde_json::Value> = resolve_env(&self.env)
             .iter()
             .map(|(k, v)| {
                 serde_json::json!({
@@ -111,19 +301,49 @@ impl McpServerConfig {
     }
 }
 
+/// Language server configuration
+///
+/// Example (in `config.json` under `language_servers`):
+///
+/// ```json
+/// {
+///   "rust-analyzer": { "command": "rust-analyzer", "languages": ["rust"] },
+///   "typescript": {
+///     "command": "typescript-language-server",
+///     "args": ["--stdio"],
+///     "languages": ["typescript", "tsx", "javascript"]
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct LanguageServerConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Editor languages handled by the server (e.g. "rust", "typescript")
+    pub languages: Vec<String>,
+    /// Sent as `initializationOptions` in the `initialize` request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialization_options: Option<serde_json::Value>,
+}
+
 fn default_true() -> bool {
     true
 }
 
 /// Custom command/shortcut configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct CommandConfig {
     pub description: String,
     pub template: String,
 }
 
 /// Network proxy configuration
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, Default)]
 pub struct ProxyConfig {
     /// Enable proxy
     #[serde(default)]
@@ -168,18 +388,23 @@ impl ProxyConfig {
             return Vec::new();
         }
 
+        // Proxy URLs may embed credentials, so they can be references too
+        let http_proxy_url = resolve_secret(&self.http_proxy_url);
+        let https_proxy_url = resolve_secret(&self.https_proxy_url);
+        let all_proxy_url = resolve_secret(&self.all_proxy_url);
+
         let mut vars = Vec::new();
-        if !self.http_proxy_url.is_empty() {
-            vars.push(("HTTP_PROXY".to_string(), self.http_proxy_url.clone()));
-            vars.push(("http_proxy".to_string(), self.http_proxy_url.clone()));
+        if !http_proxy_url.is_empty() {
+            vars.push(("HTTP_PROXY".to_string(), http_proxy_url.clone()));
+            vars.push(("http_proxy".to_string(), http_proxy_url));
         }
-        if !self.https_proxy_url.is_empty() {
-            vars.push(("HTTPS_PROXY".to_string(), self.https_proxy_url.clone()));
-            vars.push(("https_proxy".to_string(), self.https_proxy_url.clone()));
+        if !https_proxy_url.is_empty() {
+            vars.push(("HTTPS_PROXY".to_string(), https_proxy_url.clone()));
+            vars.push(("https_proxy".to_string(), https_proxy_url));
         }
-        if !self.all_proxy_url.is_empty() {
-            vars.push(("ALL_PROXY".to_string(), self.all_proxy_url.clone()));
-            vars.push(("all_proxy".to_string(), self.all_proxy_url.clone()));
+        if !all_proxy_url.is_empty() {
+            vars.push(("ALL_PROXY".to_string(), all_proxy_url.clone()));
+            vars.push(("all_proxy".to_string(), all_proxy_url));
         }
 
         if vars.is_empty() {
@@ -209,7 +434,7 @@ impl ProxyConfig {
         }
 
         let auth = if !self.username.is_empty() {
-            format!("{}:{}@", self.username, self.password)
+            format!("{}:{}@", self.username, resolve_secret(&self.password))
         } else {
             String::new()
         };
@@ -224,3 +449,134 @@ impl ProxyConfig {
 fn is_zero(value: &u16) -> bool {
     *value == 0
 }
+
+/// Node.js version installed by the managed runtime unless pinned otherwise
+pub const DEFAULT_NODE_RUNTIME_VERSION: &str = "24.11.0";
+
+/// Official Node.js distribution server
+pub const DEFAULT_NODE_RUNTIME_MIRROR: &str = "https://nodejs.org/dist";
+
+/// A private Node.js runtime, installed into the user data directory
+///
+/// When `managed` is set, agents that need Node.js are started with this
+/// runtime instead of the Node.js found on the system.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct NodeRuntimeConfig {
+    /// Download and use the private runtime
+    #[serde(default)]
+    pub managed: bool,
+    /// Exact Node.js version to install, e.g. `24.11.0`
+    #[serde(default = "default_node_runtime_version")]
+    pub version: String,
+    /// Distribution mirror laid out like `https://nodejs.org/dist`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<String>,
+    /// Local Node.js archive to install instead of downloading one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<PathBuf>,
+    /// Expected SHA-256 of the archive, instead of the `SHASUMS256.txt` of the release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+fn default_node_runtime_version() -> String {
+    DEFAULT_NODE_RUNTIME_VERSION.to_string()
+}
+
+impl Default for NodeRuntimeConfig {
+    fn default() -> Self {
+        Self {
+            managed: false,
+            version: default_node_runtime_version(),
+            mirror: None,
+            archive: None,
+            sha256: None,
+        }
+    }
+}
+
+impl NodeRuntimeConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Mirror to download from, without a trailing slash
+    pub fn mirror_url(&self) -> &str {
+        self.mirror
+            .as_deref()
+            .filter(|mirror| !mirror.trim().is_empty())
+            .unwrap_or(DEFAULT_NODE_RUNTIME_MIRROR)
+            .trim_end_matches('/')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_profiles() {
+        let config: AgentProcessConfig = serde_json::from_str(
+            r#"{
+                "command": "codex-acp",
+                "args": ["--verbose"],
+                "env": { "API_URL": "https://api.example.com" },
+                "profiles": {
+                    "staging": { "env": { "API_URL": "https://staging.example.com" } },
+                    "offline": { "args": ["--offline"], "mcp_servers": ["fs"], "mode": "plan" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let staging = config.with_profile("staging").unwrap();
+        assert_eq!(staging.env["API_URL"], "https://staging.example.com");
+        assert_eq!(staging.args, vec!["--verbose"]);
+        let offline = config.with_profile("offline").unwrap();
+        assert_eq!(offline.args, vec!["--verbose", "--offline"]);
+        assert!(config.with_profile("missing").is_none());
+
+        let profile = &config.profiles["offline"];
+        assert!(profile.allows_mcp_server("fs"));
+        assert!(!profile.allows_mcp_server("db"));
+        assert!(config.profiles["staging"].allows_mcp_server("db"));
+
+        assert_eq!(
+            config.variant_names("codex"),
+            vec!["codex", "codex@offline", "codex@staging"]
+        );
+        assert_eq!(
+            split_profile_agent_name("codex@offline"),
+            ("codex", Some("offline"))
+        );
+        assert_eq!(split_profile_agent_name("codex"), ("codex", None));
+    }
+
+    #[test]
+    fn test_cwd_policy() {
+        let dirs = vec![PathBuf::from("/work/app")];
+        let policy = CwdPolicy::Workspaces;
+        assert!(policy.allows(Path::new("/work/app"), &dirs));
+        assert!(policy.allows(Path::new("/work/app/crates/core"), &dirs));
+        assert!(!policy.allows(Path::new("/work/app-other"), &dirs));
+        assert!(!policy.allows(Path::new("/work/app/../secrets"), &dirs));
+        assert!(!policy.allows(Path::new("/work/app"), &[]));
+        assert!(CwdPolicy::Any.allows(Path::new("/tmp"), &[]));
+    }
+
+    #[test]
+    fn test_node_runtime() {
+        let runtime: NodeRuntimeConfig = serde_json::from_str(
+            r#"{ "managed": true, "mirror": "https://npmmirror.com/mirrors/node/" }"#,
+        )
+        .unwrap();
+        assert_eq!(runtime.version, DEFAULT_NODE_RUNTIME_VERSION);
+        assert_eq!(runtime.mirror_url(), "https://npmmirror.com/mirrors/node");
+        assert_eq!(
+            NodeRuntimeConfig::default().mirror_url(),
+            DEFAULT_NODE_RUNTIME_MIRROR
+        );
+        assert!(NodeRuntimeConfig::default().is_default());
+        assert!(!runtime.is_default());
+    }
+}
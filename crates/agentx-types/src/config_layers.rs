@@ -0,0 +1,416 @@
+//! Layered configuration
+//!
+//! The global `config.json` can be overridden per folder by an
+//! `.agentx/config.json` holding only the entries to change:
+//!
+//! ```json
+//! {
+//!   "mcpServers": { "db": { "command": "db-mcp", "args": ["--readonly"] } },
+//!   "agent_servers": { "codex": null },
+//!   "system_prompts": { "explain": "Answer in terms of this codebase." }
+//! }
+//! ```
+//!
+//! Layers are applied in order: global, then the workspace folder, then the
+//! project (the directory a session runs in, e.g. a package of a monorepo or
+//! a task worktree). Agents, MCP servers, commands and system prompts are
+//! merged by key; an entry replaces the inherited one as a whole, and `null`
+//! removes it.
+//!
+//! Overlays come with the folder, e.g. a cloned repository, so until the
+//! folder is listed in the global `trusted_folders` the agents they define
+//! are not started, their MCP servers are ignored, and `secret:` and
+//! `${env:}` references in them are dropped.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{
+    AgentProcessConfig, AgentProfile, CommandConfig, Config, McpServerConfig,
+    split_profile_agent_name,
+};
+use crate::config_schema::{ConfigError, parse_json};
+use crate::secrets::is_secret_ref;
+
+/// Directory holding the per-folder configuration
+pub const LOCAL_CONFIG_DIR: &str = ".agentx";
+/// File name of the per-folder configuration
+pub const LOCAL_CONFIG_FILE: &str = "config.json";
+
+/// Where a configuration value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ConfigLayer {
+    /// The global `config.json`
+    Global,
+    /// `.agentx/config.json` in the workspace folder
+    Workspace,
+    /// `.agentx/config.json` in the directory the session runs in
+    Project,
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::Workspace => write!(f, "workspace"),
+            Self::Project => write!(f, "project"),
+        }
+    }
+}
+
+/// Overrides read from an `.agentx/config.json`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ConfigOverlay {
+    #[serde(default)]
+    pub agent_servers: HashMap<String, Option<AgentProcessConfig>>,
+    #[serde(default, alias = "mcpServers")]
+    pub mcp_servers: HashMap<String, Option<McpServerConfig>>,
+    #[serde(default)]
+    pub commands: HashMap<String, Option<CommandConfig>>,
+    #[serde(default)]
+    pub system_prompts: HashMap<String, Option<String>>,
+}
+
+impl ConfigOverlay {
+    /// Path of the overlay file for a folder
+    pub fn path_in(dir: &Path) -> PathBuf {
+        dir.join(LOCAL_CONFIG_DIR).join(LOCAL_CONFIG_FILE)
+    }
+
+    /// Load the overlay of a folder, `Ok(None)` if it has none
+    pub fn load_from(dir: &Path) -> Result<Option<Self>, ConfigError> {
+        let path = Self::path_in(dir);
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(ConfigError {
+                    message: e.to_string(),
+                    line: 0,
+                    column: 0,
+                    path: None,
+                });
+            }
+        };
+        parse_json(&json).map(Some)
+    }
+
+    /// Drop what the overlay of an untrusted folder may not do: start MCP
+    /// servers, or read the user's vault and environment
+    ///
+    /// Its agents are kept so they can ask for the folder to be trusted;
+    /// they are not started until it is. Removing inherited MCP servers is
+    /// still allowed.
+    pub fn restrict_untrusted(&mut self, path: &Path) {
+        self.mcp_servers.retain(|name, config| {
+            if config.is_some() {
+                log::warn!(
+                    "Ignoring MCP server '{}' of untrusted {}",
+                    name,
+                    path.display()
+                );
+            }
+            config.is_none()
+        });
+        for (name, agent) in &mut self.agent_servers {
+            let Some(agent) = agent else {
+                continue;
+            };
+            let envs = std::iter::once(&mut agent.env)
+                .chain(agent.profiles.values_mut().map(|profile| &mut profile.env));
+            for env in envs {
+                env.retain(|key, value| {
+                    let is_ref = is_secret_ref(value);
+                    if is_ref {
+                        log::warn!(
+                            "Ignoring reference in env '{}' of agent '{}' in untrusted {}",
+                            key,
+                            name,
+                            path.display()
+                        );
+                    }
+                    !is_ref
+                });
+            }
+        }
+    }
+}
+
+/// An agent defined by the overlay of a folder the user has not trusted
+///
+/// Overlays come with the folder, e.g. a cloned repository, so the agents
+/// they define are only started once the folder is in `trusted_folders`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UntrustedFolder {
+    pub agent: String,
+    pub folder: PathBuf,
+}
+
+impl fmt::Display for UntrustedFolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Agent '{}' is defined by {}, which is not trusted",
+            self.agent,
+            ConfigOverlay::path_in(&self.folder).display()
+        )
+    }
+}
+
+impl std::error::Error for UntrustedFolder {}
+
+/// Layer of each merged entry, by name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayerSources {
+    pub agent_servers: HashMap<String, ConfigLayer>,
+    pub mcp_servers: HashMap<String, ConfigLayer>,
+    pub commands: HashMap<String, ConfigLayer>,
+    pub system_prompts: HashMap<String, ConfigLayer>,
+}
+
+/// A configuration with the overlays of a workspace applied
+#[derive(Debug, Clone)]
+pub struct EffectiveConfig {
+    pub config: Config,
+    pub sources: LayerSources,
+    /// Overlay files that were applied, by layer
+    pub overlays: Vec<(ConfigLayer, PathBuf)>,
+    /// Overlay files that failed to load and were skipped
+    pub errors: Vec<(PathBuf, ConfigError)>,
+}
+
+impl EffectiveConfig {
+    /// Start from the global configuration
+    pub fn new(global: Config) -> Self {
+        let sources = LayerSources {
+            agent_servers: global_sources(&global.agent_servers),
+            mcp_servers: global_sources(&global.mcp_servers),
+            commands: global_sources(&global.commands),
+            system_prompts: global_sources(&global.system_prompts),
+        };
+        Self {
+            config: global,
+            sources,
+            overlays: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Resolve the configuration for a workspace folder and, optionally, the
+    /// directory a session runs in
+    pub fn resolve(global: Config, workspace_dir: &Path, working_dir: Option<&Path>) -> Self {
+        let mut effective = Self::new(global);
+        effective.load_layer(ConfigLayer::Workspace, workspace_dir);
+        if let Some(working_dir) = working_dir.filter(|dir| *dir != workspace_dir) {
+            effective.load_layer(ConfigLayer::Project, working_dir);
+        }
+        effective
+    }
+
+    /// Apply the overlay of `dir`, if it has one
+    ///
+    /// An invalid overlay is skipped and recorded in `errors`. The overlay of
+    /// a folder that is not in `trusted_folders` is restricted first, see
+    /// [`ConfigOverlay::restrict_untrusted`].
+    pub fn load_layer(&mut self, layer: ConfigLayer, dir: &Path) {
+        let path = ConfigOverlay::path_in(dir);
+        match ConfigOverlay::load_from(dir) {
+            Ok(Some(mut overlay)) => {
+                if !self.config.is_trusted_folder(dir) {
+                    overlay.restrict_untrusted(&path);
+                }
+                self.apply(layer, &overlay);
+                self.overlays.push((layer, path));
+            }
+            Ok(None) => {}
+            Err(error) => {
+                log::warn!("Ignoring invalid config overlay {:?}: {}", path, error);
+                self.errors.push((path, error));
+            }
+        }
+    }
+
+    /// Folder whose overlay provided a layer, if one was applied
+    pub fn overlay_dir(&self, layer: ConfigLayer) -> Option<&Path> {
+        self.overlays
+            .iter()
+            .find(|(applied, _)| *applied == layer)
+            .and_then(|(_, path)| path.parent()?.parent())
+    }
+
+    /// Enabled MCP servers, sorted by name
+    pub fn enabled_mcp_servers(&self) -> Vec<(String, McpServerConfig)> {
+        let mut mcp_servers: Vec<_> = self
+            .config
+            .mcp_servers
+            .iter()
+            .filter(|(_, config)| config.enabled)
+            .map(|(name, config)| (name.clone(), config.clone()))
+            .collect();
+        mcp_servers.sort_by(|a, b| a.0.cmp(&b.0));
+        mcp_servers
+    }
+
+    /// Profile of a profile agent name such as `codex@offline`
+    pub fn agent_profile(&self, name: &str) -> Option<&AgentProfile> {
+        let (agent, profile) = split_profile_agent_name(name);
+        self.config.agent_servers.get(agent)?.profiles.get(profile?)
+    }
+
+    /// Enabled MCP servers an agent's sessions may use, sorted by name
+    ///
+    /// A profile agent only gets the servers its profile allows.
+    pub fn mcp_servers_for(&self, agent_name: &str) -> Vec<(String, McpServerConfig)> {
+        let mut mcp_servers = self.enabled_mcp_servers();
+        if let Some(profile) = self.agent_profile(agent_name) {
+            mcp_servers.retain(|(name, _)| profile.allows_mcp_server(name));
+        }
+        mcp_servers
+    }
+
+    /// Merge an overlay on top of the current values
+    pub fn apply(&mut self, layer: ConfigLayer, overlay: &ConfigOverlay) {
+        merge(
+            &mut self.config.agent_servers,
+            &mut self.sources.agent_servers,
+            &overlay.agent_servers,
+            layer,
+        );
+        merge(
+            &mut self.config.mcp_servers,
+            &mut self.sources.mcp_servers,
+            &overlay.mcp_servers,
+            layer,
+        );
+        merge(
+            &mut self.config.commands,
+            &mut self.sources.commands,
+            &overlay.commands,
+            layer,
+        );
+        merge(
+            &mut self.config.system_prompts,
+            &mut self.sources.system_prompts,
+            &overlay.system_prompts,
+            layer,
+        );
+    }
+}
+
+fn global_sources<T>(map: &HashMap<String, T>) -> HashMap<String, ConfigLayer> {
+    map.keys()
+        .map(|name| (name.clone(), ConfigLayer::Global))
+        .collect()
+}
+
+fn merge<T: Clone>(
+    values: &mut HashMap<String, T>,
+    sources: &mut HashMap<String, ConfigLayer>,
+    overlay: &HashMap<String, Option<T>>,
+    layer: ConfigLayer,
+) {
+    for (name, value) in overlay {
+        match value {
+            Some(value) => {
+                values.insert(name.clone(), value.clone());
+                sources.insert(name.clone(), layer);
+            }
+            None => {
+                values.remove(name);
+                sources.remove(name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_merge() {
+        let global: Config = serde_json::from_str(
+            r#"{
+                "agent_servers": {
+                    "claude": { "command": "claude-acp" },
+                    "codex": { "command": "codex-acp" }
+                },
+                "mcpServers": { "fs": { "command": "fs-mcp" } },
+                "system_prompts": { "explain": "Be brief" }
+            }"#,
+        )
+        .unwrap();
+        let workspace: ConfigOverlay = serde_json::from_str(
+            r#"{
+                "agent_servers": { "codex": null },
+                "mcpServers": { "db": { "command": "db-mcp" } },
+                "system_prompts": { "explain": "Use the project glossary" }
+            }"#,
+        )
+        .unwrap();
+        let project: ConfigOverlay = serde_json::from_str(
+            r#"{ "mcpServers": { "fs": { "command": "fs-mcp", "args": ["."] } } }"#,
+        )
+        .unwrap();
+
+        let mut effective = EffectiveConfig::new(global);
+        effective.apply(ConfigLayer::Workspace, &workspace);
+        effective.apply(ConfigLayer::Project, &project);
+
+        let config = &effective.config;
+        let sources = &effective.sources;
+        assert!(config.agent_servers.contains_key("claude"));
+        assert!(!config.agent_servers.contains_key("codex"));
+        assert!(!sources.agent_servers.contains_key("codex"));
+        assert_eq!(config.mcp_servers["fs"].args, vec!["."]);
+        assert_eq!(sources.mcp_servers["fs"], ConfigLayer::Project);
+        assert_eq!(sources.mcp_servers["db"], ConfigLayer::Workspace);
+        assert_eq!(config.system_prompts["explain"], "Use the project glossary");
+        assert_eq!(sources.system_prompts["explain"], ConfigLayer::Workspace);
+        assert_eq!(sources.agent_servers["claude"], ConfigLayer::Global);
+    }
+
+    #[test]
+    fn test_untrusted_overlay_adds_no_mcp_servers() {
+        let dir = std::env::temp_dir().join(format!("agentx-layers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join(LOCAL_CONFIG_DIR)).unwrap();
+        std::fs::write(
+            ConfigOverlay::path_in(&dir),
+            r#"{
+                "agent_servers": {
+                    "local": {
+                        "command": "local-acp",
+                        "env": { "TOKEN": "secret:github", "HOME_DIR": "${env:HOME}", "MODE": "fast" }
+                    }
+                },
+                "mcpServers": { "db": { "command": "db-mcp" }, "fs": null }
+            }"#,
+        )
+        .unwrap();
+        let global: Config = serde_json::from_str(
+            r#"{ "agent_servers": {}, "mcpServers": { "fs": { "command": "fs-mcp" } } }"#,
+        )
+        .unwrap();
+
+        let untrusted = EffectiveConfig::resolve(global.clone(), &dir, None);
+        assert!(untrusted.enabled_mcp_servers().is_empty());
+        assert!(!untrusted.sources.mcp_servers.contains_key("db"));
+        let env = &untrusted.config.agent_servers["local"].env;
+        assert_eq!(env.len(), 1);
+        assert_eq!(env["MODE"], "fast");
+
+        let mut global = global;
+        global.trusted_folders.push(dir.clone());
+        let trusted = EffectiveConfig::resolve(global, &dir, None);
+        assert_eq!(trusted.sources.mcp_servers["db"], ConfigLayer::Workspace);
+        assert_eq!(
+            trusted.config.agent_servers["local"].env["TOKEN"],
+            "secret:github"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+//! Configuration schema and validation
+//!
+//! `config.json` is parsed with error locations (line, column and the path
+//! of the offending key), and a JSON Schema generated from [`Config`] is
+//! written next to it so editors can validate and autocomplete the file.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// File name of the schema written next to `config.json`
+pub const CONFIG_SCHEMA_FILE: &str = "config.schema.json";
+
+/// Why a configuration file could not be loaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigError {
+    /// Error message without the location
+    pub message: String,
+    /// 1-based line of the error (0 if unknown)
+    pub line: usize,
+    /// 1-based column of the error (0 if unknown)
+    pub column: usize,
+    /// Path of the offending value, e.g. `models.gpt.max_tokens`
+    pub path: Option<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}: ", path)?;
+        }
+        write!(f, "{}", self.message)?;
+        if self.line > 0 {
+            write!(f, " (line {}, column {})", self.line, self.column)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigError {
+    fn new(error: serde_json::Error, path: Option<String>) -> Self {
+        // serde_json appends the location to the message, it's kept separately
+        let message = error.to_string();
+        let suffix = format!(" at line {} column {}", error.line(), error.column());
+        let message = message
+            .strip_suffix(&suffix)
+            .map(str::to_string)
+            .unwrap_or(message);
+
+        Self {
+            message,
+            line: error.line(),
+            column: error.column(),
+            path,
+        }
+    }
+}
+
+/// Parse and validate the contents of `config.json`
+pub fn parse_config(json: &str) -> Result<Config, ConfigError> {
+    parse_json(json)
+}
+
+/// Parse a JSON config file, reporting where it is invalid
+pub fn parse_json<T: DeserializeOwned>(json: &str) -> Result<T, ConfigError> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let value: T = serde_path_to_error::deserialize(&mut deserializer).map_err(|error| {
+        let path = error.path().to_string();
+        let path = (path != ".").then_some(path);
+        ConfigError::new(error.into_inner(), path)
+    })?;
+    deserializer
+        .end()
+        .map_err(|error| ConfigError::new(error, None))?;
+    Ok(value)
+}
+
+/// JSON Schema describing `config.json`
+pub fn config_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default()
+}
+
+/// Write the schema next to the config file, returning its path
+///
+/// Callers write it next to the app's own config only: a `--config` file may
+/// sit in a project, which must not gain files it did not ask for.
+pub fn write_config_schema(config_path: &Path) -> Result<PathBuf> {
+    let path = config_path.with_file_name(CONFIG_SCHEMA_FILE);
+    let content = serde_json::to_string_pretty(&config_schema())?;
+
+    // Skip the write when nothing changed, editors watch this file
+    if std::fs::read_to_string(&path).is_ok_and(|existing| existing == content) {
+        return Ok(path);
+    }
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write config schema: {:?}", path))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_errors() {
+        let config = parse_config(r#"{ "agent_servers": {} }"#).unwrap();
+        assert!(config.models.is_empty());
+
+        let error = parse_config(
+            r#"{
+  "agent_servers": {},
+  "models": {
+    "gpt": { "enabled": true, "provider": "openai", "base_url": "",
+             "api_key": "", "model_name": "gpt", "max_tokens": "many" }
+  }
+}"#,
+        )
+        .unwrap_err();
+        assert_eq!(error.path.as_deref(), Some("models.gpt.max_tokens"));
+        assert_eq!(error.line, 5);
+        assert!(error.message.starts_with("invalid type"));
+
+        let error = parse_config("{ \"agent_servers\": {}, }").unwrap_err();
+        assert_eq!(error.line, 1);
+        assert!(error.column > 0);
+        assert!(!error.to_string().contains(" at line "));
+
+        let error = parse_config(r#"{ "agent_servers": {} } trailing"#).unwrap_err();
+        assert_eq!(error.line, 1);
+    }
+
+    #[test]
+    fn test_config_schema() {
+        let schema = config_schema();
+        let properties = &schema["properties"];
+        assert!(properties["agent_servers"].is_object());
+        assert!(properties["models"].is_object());
+        assert_eq!(schema["required"], serde_json::json!(["agent_servers"]));
+    }
+}
@@ -3,12 +3,15 @@ use std::sync::Arc;
 use agent_client_protocol as acp;
 use agent_client_protocol::SessionUpdate;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 use crate::config::{AgentProcessConfig, CommandConfig, Config, McpServerConfig, ModelConfig};
+use crate::config_schema::ConfigError;
+use crate::schemas::task_template::TaskTemplate;
 use crate::session::SessionStatus;
 
 /// Events published when agent configuration changes
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AgentConfigEvent {
     // ========== Agent Events ==========
     /// A new agent was added
@@ -23,6 +26,12 @@ pub enum AgentConfigEvent {
     },
     /// An agent was removed
     AgentRemoved { name: String },
+    /// An agent's configuration changed on disk, but it was not restarted
+    /// because it has active sessions
+    AgentRestartDeferred {
+        name: String,
+        active_sessions: usize,
+    },
 
     // ========== Model Events ==========
     /// A new model was added
@@ -54,13 +63,32 @@ pub enum AgentConfigEvent {
     /// A command was removed
     CommandRemoved { name: String },
 
+    // ========== Task Template Events ==========
+    /// A new global task template was added
+    TaskTemplateAdded {
+        name: String,
+        template: TaskTemplate,
+    },
+    /// An existing global task template was updated
+    TaskTemplateUpdated {
+        name: String,
+        template: TaskTemplate,
+    },
+    /// A global task template was removed
+    TaskTemplateRemoved { name: String },
+
     // ========== Full Reload ==========
     /// The entire configuration was reloaded from file
     ConfigReloaded { config: Box<Config> },
+    /// The config file failed validation and the last good configuration is
+    /// still in use (`None` once the file is valid again)
+    ConfigValidated { error: Option<ConfigError> },
+    /// Plaintext secrets found in the config file were moved into the vault
+    SecretsExternalized { vault_path: String },
 }
 
 /// Session update event that can be broadcast to subscribers
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SessionUpdateEvent {
     pub session_id: String,
     pub agent_name: Option<String>,
@@ -68,7 +96,7 @@ pub struct SessionUpdateEvent {
 }
 
 /// Permission request event that can be broadcast to subscribers
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PermissionRequestEvent {
     /// Unique permission request ID from PermissionStore
     pub permission_id: String,
@@ -83,7 +111,7 @@ pub struct PermissionRequestEvent {
 }
 
 /// Workspace update events
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum WorkspaceUpdateEvent {
     /// A new task was created
     TaskCreated {
@@ -101,6 +129,18 @@ pub enum WorkspaceUpdateEvent {
     WorkspaceAdded { workspace_id: String },
     /// A workspace was removed
     WorkspaceRemoved { workspace_id: String },
+    /// A workspace's task templates were changed
+    TemplatesUpdated { workspace_id: String },
+    /// A multi-agent comparison was created or its winner was picked
+    ComparisonUpdated {
+        workspace_id: String,
+        comparison_id: String,
+    },
+    /// A pipeline run was started, advanced to a new stage or finished
+    PipelineUpdated {
+        workspace_id: String,
+        run_id: String,
+    },
     /// A session status was updated
     SessionStatusUpdated {
         session_id: String,
@@ -112,7 +152,7 @@ pub enum WorkspaceUpdateEvent {
 }
 
 /// Pure data struct for code selection (no GPUI dependency)
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CodeSelectionData {
     pub file_path: String,
     pub start_line: u32,
@@ -123,7 +163,7 @@ pub struct CodeSelectionData {
 }
 
 /// Event published when code is selected in the editor
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CodeSelectionEvent {
     pub selection: CodeSelectionData,
 }
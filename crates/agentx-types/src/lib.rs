@@ -1,13 +1,23 @@
+pub mod capabilities;
 pub mod config;
+pub mod config_layers;
 pub mod config_manager;
+pub mod config_schema;
 pub mod events;
 pub mod schemas;
+pub mod secrets;
 pub mod session;
 
+pub use capabilities::{AgentFeatures, Feature};
 pub use config::{
-    AgentProcessConfig, CommandConfig, Config, DEFAULT_TOOL_CALL_PREVIEW_MAX_LINES,
-    McpServerConfig, ModelConfig, ProxyConfig,
+    AgentProcessConfig, AgentProfile, CommandConfig, Config, CwdPolicy,
+    DEFAULT_TOOL_CALL_PREVIEW_MAX_LINES, LanguageServerConfig, McpServerConfig, ModelConfig,
+    NodeRuntimeConfig, ProxyConfig, profile_agent_name, split_profile_agent_name,
 };
+pub use config_layers::{
+    ConfigLayer, ConfigOverlay, EffectiveConfig, LayerSources, UntrustedFolder,
+};
+pub use config_schema::{ConfigError, parse_config};
 pub use events::{
     AgentConfigEvent, CodeSelectionEvent, PermissionRequestEvent, SessionUpdateEvent,
     WorkspaceUpdateEvent,
@@ -0,0 +1,259 @@
+//! Catalog of installable ACP agents
+//!
+//! The catalog is a local JSON file, so agents can be discovered and
+//! installed without a registry service:
+//!
+//! ```json
+//! {
+//!   "agents": [
+//!     {
+//!       "name": "Codex",
+//!       "description": "OpenAI Codex through the Zed ACP adapter",
+//!       "install": { "type": "npm", "package": "@zed-industries/codex-acp" },
+//!       "command": "codex-acp",
+//!       "node_version": 18
+//!     },
+//!     {
+//!       "name": "My Agent",
+//!       "install": {
+//!         "type": "binary",
+//!         "url": "https://example.com/my-agent-{os}-{arch}",
+//!         "sha256": { "linux-x86_64": "<sha256>", "macos-aarch64": "<sha256>" }
+//!       },
+//!       "command": "my-agent",
+//!       "args": ["--acp"]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::AgentProcessConfig;
+use crate::config_schema::{ConfigError, parse_json};
+
+/// File name of the catalog in the user data directory
+pub const AGENT_CATALOG_FILE: &str = "agent-catalog.json";
+
+/// Agents that can be installed from the settings
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+pub struct AgentCatalog {
+    #[serde(default)]
+    pub agents: Vec<CatalogAgent>,
+}
+
+/// An installable agent
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CatalogAgent {
+    /// Agent name, used as its key in `agent_servers`
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// How the agent is installed
+    pub install: InstallMethod,
+    /// Executable that speaks ACP, looked up in the install folder first
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Minimum Node.js major version, for agents running on Node.js
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_version: Option<u32>,
+}
+
+/// How a catalog agent is installed
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InstallMethod {
+    /// npm package installed into the agent's folder
+    Npm { package: String },
+    /// Single executable downloaded from `url`; `{os}` and `{arch}` are
+    /// replaced with the platform (e.g. `linux`, `x86_64`). The download is
+    /// only installed if it matches `sha256`.
+    Binary { url: String, sha256: BinaryChecksum },
+    /// Crate built with `cargo install`, from crates.io or a git repository
+    Cargo {
+        #[serde(rename = "crate")]
+        crate_name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        git: Option<String>,
+    },
+    /// Installed by the user, following `instructions`
+    Manual {
+        #[serde(default)]
+        instructions: String,
+    },
+}
+
+/// SHA-256 of a downloaded binary
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum BinaryChecksum {
+    /// The same file on every platform
+    Single(String),
+    /// One checksum per platform, keyed by `{os}-{arch}` (e.g. `linux-x86_64`)
+    PerPlatform(HashMap<String, String>),
+}
+
+impl BinaryChecksum {
+    /// Checksum of the download for a platform
+    pub fn for_platform(&self, os: &str, arch: &str) -> Option<&str> {
+        match self {
+            Self::Single(sha256) => Some(sha256),
+            Self::PerPlatform(checksums) => checksums
+                .get(&format!("{}-{}", os, arch))
+                .map(String::as_str),
+        }
+    }
+}
+
+impl AgentCatalog {
+    /// Parse a catalog, reporting where it is invalid
+    pub fn parse(json: &str) -> Result<Self, ConfigError> {
+        parse_json(json)
+    }
+
+    /// Load the catalog file at `path`
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let json = std::fs::read_to_string(path).map_err(|e| ConfigError {
+            message: e.to_string(),
+            line: 0,
+            column: 0,
+            path: None,
+        })?;
+        Self::parse(&json)
+    }
+
+    /// Find an agent by name
+    pub fn get(&self, name: &str) -> Option<&CatalogAgent> {
+        self.agents.iter().find(|agent| agent.name == name)
+    }
+}
+
+impl CatalogAgent {
+    /// Launch config for an agent that isn't installed locally
+    ///
+    /// npm packages run through `npx`, which fetches them on first use;
+    /// other agents are expected on `PATH`.
+    pub fn default_config(&self) -> AgentProcessConfig {
+        match &self.install {
+            InstallMethod::Npm { package } => {
+                let mut args = vec![package.clone()];
+                args.extend(self.args.iter().cloned());
+                self.config_with_command("npx".to_string(), args)
+            }
+            _ => self.config_with_command(self.command.clone(), self.args.clone()),
+        }
+    }
+
+    /// Launch config running `command` with the agent's args
+    pub fn installed_config(&self, command: String) -> AgentProcessConfig {
+        self.config_with_command(command, self.args.clone())
+    }
+
+    fn config_with_command(&self, command: String, args: Vec<String>) -> AgentProcessConfig {
+        AgentProcessConfig {
+            command,
+            args,
+            env: self.env.clone(),
+            profiles: HashMap::new(),
+            cwd_policy: Default::default(),
+            node_version: self.node_version,
+            nodejs_path: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_catalog() {
+        let catalog = AgentCatalog::parse(
+            r#"{
+                "agents": [
+                    {
+                        "name": "Qwen",
+                        "install": { "type": "npm", "package": "@qwen-code/qwen-code" },
+                        "command": "qwen",
+                        "args": ["--experimental-acp"],
+                        "node_version": 20
+                    },
+                    {
+                        "name": "Local",
+                        "install": { "type": "cargo", "crate": "local-acp" },
+                        "command": "local-acp"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let qwen = catalog.get("Qwen").unwrap();
+        assert_eq!(qwen.node_version, Some(20));
+        let config = qwen.default_config();
+        assert_eq!(config.command, "npx");
+        assert_eq!(
+            config.args,
+            vec!["@qwen-code/qwen-code", "--experimental-acp"]
+        );
+        let config = qwen.installed_config("/agents/qwen/node_modules/.bin/qwen".to_string());
+        assert_eq!(config.args, vec!["--experimental-acp"]);
+
+        let local = catalog.get("Local").unwrap();
+        assert_eq!(
+            local.install,
+            InstallMethod::Cargo {
+                crate_name: "local-acp".to_string(),
+                git: None
+            }
+        );
+        assert_eq!(local.default_config().command, "local-acp");
+
+        let error =
+            AgentCatalog::parse(r#"{ "agents": [{ "name": "x", "command": "x" }] }"#).unwrap_err();
+        assert!(error.message.contains("install"));
+    }
+
+    #[test]
+    fn test_binary_needs_checksum() {
+        let catalog = AgentCatalog::parse(
+            r#"{
+                "agents": [{
+                    "name": "Bin",
+                    "install": {
+                        "type": "binary",
+                        "url": "https://example.com/bin-{os}-{arch}",
+                        "sha256": { "linux-x86_64": "abc" }
+                    },
+                    "command": "bin"
+                }]
+            }"#,
+        )
+        .unwrap();
+        let InstallMethod::Binary { sha256, .. } = &catalog.get("Bin").unwrap().install else {
+            panic!("expected a binary install");
+        };
+        assert_eq!(sha256.for_platform("linux", "x86_64"), Some("abc"));
+        assert_eq!(sha256.for_platform("macos", "aarch64"), None);
+
+        let error = AgentCatalog::parse(
+            r#"{
+                "agents": [{
+                    "name": "Bin",
+                    "install": { "type": "binary", "url": "https://example.com/bin" },
+                    "command": "bin"
+                }]
+            }"#,
+        )
+        .unwrap_err();
+        assert!(error.message.contains("sha256"));
+    }
+}
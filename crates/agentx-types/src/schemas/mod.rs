@@ -1,5 +1,11 @@
+pub mod agent_catalog;
 pub mod conversation;
+pub mod pipeline;
+pub mod task_template;
 pub mod workspace;
 
+pub use agent_catalog::*;
 pub use conversation::*;
+pub use pipeline::*;
+pub use task_template::*;
 pub use workspace::*;
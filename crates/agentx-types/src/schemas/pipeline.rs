@@ -0,0 +1,248 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Placeholder replaced with the stage input
+pub const PIPELINE_INPUT_PLACEHOLDER: &str = "{{input}}";
+/// Placeholder replaced with the text the pipeline was started with
+pub const PIPELINE_PROMPT_PLACEHOLDER: &str = "{{pipeline_input}}";
+
+/// Chain of agent tasks where each stage consumes the output of the previous one
+///
+/// Example (in `config.json` under `pipelines`):
+///
+/// ```json
+/// {
+///   "description": "Plan, implement, review",
+///   "stages": [
+///     { "name": "plan", "agent": "claude", "mode": "plan",
+///       "prompt": "Make a step by step plan for: {{input}}" },
+///     { "name": "code", "agent": "codex", "input": "plan_entries",
+///       "prompt": "Implement this step of the plan: {{input}}" },
+///     { "name": "review", "agent": "claude", "input": "diff",
+///       "prompt": "Review this diff:\n{{input}}" }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize, JsonSchema)]
+pub struct PipelineDefinition {
+    #[serde(default)]
+    pub description: String,
+    pub stages: Vec<PipelineStage>,
+}
+
+/// A step of a pipeline, run as one or more queued tasks
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct PipelineStage {
+    /// Stage name (used in task names)
+    pub name: String,
+    /// Agent that runs the stage
+    #[serde(rename = "agent")]
+    pub agent_name: String,
+    /// Session mode to switch to before sending the prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Prompt text; `{{input}}` is replaced with the stage input
+    /// (appended to the prompt if the placeholder is missing)
+    pub prompt: String,
+    /// What the stage receives from the previous stage
+    #[serde(default)]
+    pub input: StageInput,
+}
+
+/// Output of the previous stage used as the input of a stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StageInput {
+    /// The text the pipeline was started with
+    #[default]
+    PipelineInput,
+    /// The last agent message of the previous stage's sessions
+    FinalMessage,
+    /// One task per plan entry reported by the previous stage's sessions
+    PlanEntries,
+    /// The changes made by the previous stage (`git diff`)
+    Diff,
+}
+
+/// Status of a pipeline run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum PipelineStatus {
+    #[default]
+    Running,
+    Completed,
+    Failed,
+}
+
+/// What a finished stage task produced
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct StageTaskOutput {
+    /// Last agent message of the session
+    #[serde(default)]
+    pub final_message: String,
+    /// Latest plan entries reported by the agent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plan_entries: Vec<String>,
+}
+
+/// A running (or finished) pipeline
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PipelineRun {
+    /// Unique identifier for the run
+    pub id: String,
+    /// Workspace the run belongs to
+    pub workspace_id: String,
+    /// Name of the pipeline definition
+    pub pipeline_name: String,
+    /// Definition at the time the run was started
+    pub definition: PipelineDefinition,
+    /// Text the pipeline was started with
+    pub input: String,
+    /// Task IDs of each started stage, in stage order
+    #[serde(default)]
+    pub stage_tasks: Vec<Vec<String>>,
+    /// Outputs of finished stage tasks (task ID -> output)
+    #[serde(default)]
+    pub outputs: HashMap<String, StageTaskOutput>,
+    /// Git snapshot of the workspace taken when each stage started, in stage order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stage_base_commits: Vec<Option<String>>,
+    #[serde(default)]
+    pub status: PipelineStatus,
+    /// Why the run failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl PipelineRun {
+    /// Create a new run of a pipeline definition
+    pub fn new(
+        workspace_id: String,
+        pipeline_name: String,
+        definition: PipelineDefinition,
+        input: String,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            workspace_id,
+            pipeline_name,
+            definition,
+            input,
+            stage_tasks: Vec::new(),
+            outputs: HashMap::new(),
+            stage_base_commits: Vec::new(),
+            status: PipelineStatus::Running,
+            error: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Index of the stage currently running (None before the first stage starts)
+    pub fn current_stage(&self) -> Option<usize> {
+        self.stage_tasks.len().checked_sub(1)
+    }
+
+    /// Git snapshot the changes of a stage are compared to, if one was taken
+    pub fn stage_base_commit(&self, stage: usize) -> Option<&str> {
+        self.stage_base_commits.get(stage)?.as_deref()
+    }
+
+    /// Outputs of the tasks of a stage, in task order
+    pub fn stage_outputs(&self, stage: usize) -> Vec<&StageTaskOutput> {
+        self.stage_tasks
+            .get(stage)
+            .map(|ids| ids.iter().filter_map(|id| self.outputs.get(id)).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl PipelineStage {
+    /// Build the prompt of a stage task from its input
+    ///
+    /// Placeholders are replaced in a single pass, so markers inside the
+    /// substituted text are kept as-is.
+    pub fn render_prompt(&self, input: &str, pipeline_input: &str) -> String {
+        let mut prompt = String::with_capacity(self.prompt.len() + input.len());
+        let mut has_input = false;
+        let mut rest = self.prompt.as_str();
+
+        while let Some(start) = rest.find("{{") {
+            prompt.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix(PIPELINE_INPUT_PLACEHOLDER) {
+                prompt.push_str(input);
+                has_input = true;
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(PIPELINE_PROMPT_PLACEHOLDER) {
+                prompt.push_str(pipeline_input);
+                rest = after;
+            } else {
+                prompt.push_str("{{");
+                rest = &rest[2..];
+            }
+        }
+        prompt.push_str(rest);
+
+        if has_input || input.is_empty() {
+            prompt
+        } else {
+            format!("{}\n\n{}", prompt.trim_end(), input)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_stage_prompt() {
+        let stage = PipelineStage {
+            name: "review".to_string(),
+            agent_name: "claude".to_string(),
+            mode: None,
+            prompt: "Review the change for {{pipeline_input}}:\n{{input}}".to_string(),
+            input: StageInput::Diff,
+        };
+        assert_eq!(
+            stage.render_prompt("+added", "issue #4"),
+            "Review the change for issue #4:\n+added"
+        );
+
+        let stage = PipelineStage {
+            prompt: "Implement this step.".to_string(),
+            ..stage
+        };
+        assert_eq!(
+            stage.render_prompt("Add tests", "issue #4"),
+            "Implement this step.\n\nAdd tests"
+        );
+    }
+
+    #[test]
+    fn test_render_stage_prompt_single_pass() {
+        let stage = PipelineStage {
+            name: "code".to_string(),
+            agent_name: "codex".to_string(),
+            mode: None,
+            prompt: "Goal: {{pipeline_input}}\nStep: {{input}} {{other}}".to_string(),
+            input: StageInput::PlanEntries,
+        };
+        assert_eq!(
+            stage.render_prompt("write {{pipeline_input}}", "explain {{input}}"),
+            "Goal: explain {{input}}\nStep: write {{pipeline_input}} {{other}}"
+        );
+
+        // A pipeline input holding `{{input}}` doesn't stop the input from being appended
+        let stage = PipelineStage {
+            prompt: "Do {{pipeline_input}}".to_string(),
+            ..stage
+        };
+        assert_eq!(
+            stage.render_prompt("step 1", "{{input}}"),
+            "Do {{input}}\n\nstep 1"
+        );
+    }
+}
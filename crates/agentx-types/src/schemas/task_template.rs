@@ -0,0 +1,249 @@
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Placeholder replaced with the selected files (one path per line)
+pub const PLACEHOLDER_FILES: &str = "files";
+/// Placeholder replaced with the current git branch of the workspace
+pub const PLACEHOLDER_BRANCH: &str = "branch";
+/// Placeholder replaced with the text content of the clipboard
+pub const PLACEHOLDER_CLIPBOARD: &str = "clipboard";
+
+/// Placeholders filled in automatically from the task context
+pub const BUILTIN_PLACEHOLDERS: &[&str] =
+    &[PLACEHOLDER_FILES, PLACEHOLDER_BRANCH, PLACEHOLDER_CLIPBOARD];
+
+/// Reusable task template
+///
+/// The prompt may contain `{{placeholder}}` markers. Built-in placeholders
+/// (`files`, `branch`, `clipboard`) are filled from the task context; any
+/// other placeholder is a field entered by the user when the template is used.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize, JsonSchema)]
+pub struct TaskTemplate {
+    #[serde(default)]
+    pub description: String,
+    /// Agent to run the task with (None = first available agent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+    /// Session mode to switch to before sending the prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// MCP servers attached to the session (None = all enabled servers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<Vec<String>>,
+    /// Prompt text with `{{placeholders}}`
+    pub prompt: String,
+    /// Run the task in a dedicated git worktree
+    #[serde(default)]
+    pub use_worktree: bool,
+    /// Labels and default values for user-entered fields
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<TemplateField>,
+}
+
+/// User-entered template field
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize, JsonSchema)]
+pub struct TemplateField {
+    /// Placeholder name used in the prompt
+    pub name: String,
+    /// Label shown in the input form (defaults to the name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Value used when the user leaves the field empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+impl TemplateField {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            label: None,
+            default: None,
+        }
+    }
+
+    /// Get the label to display for this field
+    pub fn display_label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Where a template is stored
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum TemplateScope {
+    /// Stored in the global config file
+    Global,
+    /// Stored with a workspace (workspace ID)
+    Workspace(String),
+}
+
+/// Values used to fill a template's placeholders
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    /// Selected files (absolute or workspace-relative paths)
+    pub selected_files: Vec<String>,
+    /// Current git branch of the workspace
+    pub branch: Option<String>,
+    /// Text content of the clipboard
+    pub clipboard: Option<String>,
+    /// User-entered field values
+    pub values: HashMap<String, String>,
+}
+
+impl TaskTemplate {
+    /// Get the placeholder names used in the prompt, in order of first appearance
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for (_, name) in scan_placeholders(&self.prompt) {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+        names
+    }
+
+    /// Get the fields the user must fill in
+    ///
+    /// Declared fields come first, followed by any other non built-in
+    /// placeholder used in the prompt.
+    pub fn input_fields(&self) -> Vec<TemplateField> {
+        let mut fields = self.fields.clone();
+        for name in self.placeholders() {
+            if !BUILTIN_PLACEHOLDERS.contains(&name.as_str())
+                && !fields.iter().any(|f| f.name == name)
+            {
+                fields.push(TemplateField::new(name));
+            }
+        }
+        fields
+    }
+
+    /// Render the prompt by replacing all placeholders
+    ///
+    /// Fails if a user field has neither a value nor a default.
+    pub fn render(&self, context: &TemplateContext) -> Result<String> {
+        let mut output = String::with_capacity(self.prompt.len());
+        let mut last = 0;
+
+        for (range, name) in scan_placeholders(&self.prompt) {
+            output.push_str(&self.prompt[last..range.start]);
+            output.push_str(&self.resolve(name, context)?);
+            last = range.end;
+        }
+        output.push_str(&self.prompt[last..]);
+
+        Ok(output)
+    }
+
+    fn resolve(&self, name: &str, context: &TemplateContext) -> Result<String> {
+        if let Some(value) = context.values.get(name).filter(|v| !v.is_empty()) {
+            return Ok(value.clone());
+        }
+
+        match name {
+            PLACEHOLDER_FILES => Ok(context.selected_files.join("\n")),
+            PLACEHOLDER_BRANCH => Ok(context.branch.clone().unwrap_or_default()),
+            PLACEHOLDER_CLIPBOARD => Ok(context.clipboard.clone().unwrap_or_default()),
+            _ => self
+                .fields
+                .iter()
+                .find(|f| f.name == name)
+                .and_then(|f| f.default.clone())
+                .ok_or_else(|| anyhow!("Missing value for template field '{}'", name)),
+        }
+    }
+}
+
+/// Find all `{{name}}` markers, returning their byte range and trimmed name
+///
+/// Names may contain letters, digits, `_` and `-`; anything else is left as-is.
+fn scan_placeholders(text: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut placeholders = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = text[offset..].find("{{").map(|i| offset + i) {
+        let Some(end) = text[start + 2..].find("}}").map(|i| start + 2 + i) else {
+            break;
+        };
+
+        let name = text[start + 2..end].trim();
+        let is_valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+        if is_valid {
+            placeholders.push((start..end + 2, name));
+            offset = end + 2;
+        } else {
+            offset = start + 2;
+        }
+    }
+
+    placeholders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(prompt: &str) -> TaskTemplate {
+        TaskTemplate {
+            prompt: prompt.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_builtin_placeholders() {
+        let template = template("Review {{ files }} on {{branch}}:\n{{clipboard}}");
+        let context = TemplateContext {
+            selected_files: vec!["src/a.rs".to_string(), "src/b.rs".to_string()],
+            branch: Some("main".to_string()),
+            clipboard: Some("panic at line 3".to_string()),
+            values: HashMap::new(),
+        };
+
+        assert_eq!(
+            template.render(&context).unwrap(),
+            "Review src/a.rs\nsrc/b.rs on main:\npanic at line 3"
+        );
+    }
+
+    #[test]
+    fn test_render_user_fields_and_defaults() {
+        let mut template = template("Fix issue #{{issue}} with {{style}} style");
+        template.fields.push(TemplateField {
+            name: "style".to_string(),
+            label: Some("Code style".to_string()),
+            default: Some("minimal".to_string()),
+        });
+
+        let fields = template.input_fields();
+        assert_eq!(
+            fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+            vec!["style", "issue"]
+        );
+
+        let mut context = TemplateContext::default();
+        assert!(template.render(&context).is_err());
+
+        context.values.insert("issue".to_string(), "42".to_string());
+        assert_eq!(
+            template.render(&context).unwrap(),
+            "Fix issue #42 with minimal style"
+        );
+    }
+
+    #[test]
+    fn test_render_keeps_invalid_markers() {
+        let template = template("Use {{ not a field }} and {{}} and {{open");
+        assert!(template.placeholders().is_empty());
+        assert_eq!(
+            template.render(&TemplateContext::default()).unwrap(),
+            "Use {{ not a field }} and {{}} and {{open"
+        );
+    }
+}
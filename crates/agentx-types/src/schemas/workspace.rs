@@ -1,6 +1,10 @@
+use agent_client_protocol as acp;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::schemas::pipeline::PipelineRun;
+use crate::schemas::task_template::TaskTemplate;
 use crate::session::SessionStatus;
 
 /// Workspace represents a local project folder
@@ -18,6 +22,9 @@ pub struct Workspace {
     /// Last accessed time
     #[serde(with = "chrono::serde::ts_seconds")]
     pub last_accessed: chrono::DateTime<chrono::Utc>,
+    /// Task templates stored with this workspace
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, TaskTemplate>,
 }
 
 impl Workspace {
@@ -37,6 +44,7 @@ impl Workspace {
             path,
             created_at: now,
             last_accessed: now,
+            templates: HashMap::new(),
         }
     }
 
@@ -69,6 +77,47 @@ pub struct WorkspaceTask {
     /// Last message preview (plain String, UI layer can convert to SharedString)
     #[serde(skip)]
     pub last_message: Option<String>,
+    /// Accumulated file changes made by the agent during this task
+    #[serde(default)]
+    pub changes: TaskChangeSummary,
+    /// Prompt sent when the task is run by the task queue (None for manual tasks)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Earliest time the queued task may start (None = as soon as possible),
+    /// in Unix seconds like `created_at`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "chrono::serde::ts_seconds_option"
+    )]
+    pub scheduled_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Number of times the task queue has started this task
+    #[serde(default)]
+    pub attempts: u32,
+    /// Error reported by the last failed run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Directory the session runs in, if not the workspace folder (e.g. a git worktree)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+    /// Git branch created for this task's worktree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// MCP servers attached to queued sessions (None = all enabled servers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<Vec<String>>,
+    /// Extra content sent after the queued prompt (images, code selections)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<acp::ContentBlock>,
+    /// Comparison this task is a candidate of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comparison_id: Option<String>,
+    /// Pipeline run this task is a stage of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_run_id: Option<String>,
+    /// Index of the pipeline stage this task runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_stage: Option<usize>,
 }
 
 impl WorkspaceTask {
@@ -84,9 +133,48 @@ impl WorkspaceTask {
             status: SessionStatus::Pending,
             created_at: chrono::Utc::now(),
             last_message: None,
+            changes: TaskChangeSummary::default(),
+            prompt: None,
+            scheduled_at: None,
+            attempts: 0,
+            last_error: None,
+            working_dir: None,
+            branch: None,
+            mcp_servers: None,
+            attachments: Vec::new(),
+            comparison_id: None,
+            pipeline_run_id: None,
+            pipeline_stage: None,
         }
     }
 
+    /// Create a task that is run by the task queue with the given prompt
+    pub fn new_queued(
+        workspace_id: String,
+        name: String,
+        agent_name: String,
+        mode: String,
+        prompt: String,
+        scheduled_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Self {
+        let mut task = Self::new(workspace_id, name, agent_name, mode);
+        task.prompt = Some(prompt);
+        task.scheduled_at = scheduled_at;
+        task
+    }
+
+    /// Whether this task is managed by the task queue
+    pub fn is_queued(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// Whether the queue may start this task at the given time
+    pub fn is_runnable_at(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.is_queued()
+            && self.status == SessionStatus::Pending
+            && self.scheduled_at.is_none_or(|at| at <= now)
+    }
+
     /// Associate a session with this task
     pub fn set_session(&mut self, session_id: String) {
         self.session_id = Some(session_id);
@@ -99,6 +187,131 @@ impl WorkspaceTask {
     }
 }
 
+/// Line statistics for a single file changed during a task
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskFileChange {
+    /// Path of the changed file as reported by the agent
+    pub path: PathBuf,
+    /// Number of added lines
+    pub additions: usize,
+    /// Number of deleted lines
+    pub deletions: usize,
+    /// Whether the file did not exist before the task
+    #[serde(default)]
+    pub is_new_file: bool,
+}
+
+impl TaskFileChange {
+    /// Get total number of changed lines
+    pub fn total_changes(&self) -> usize {
+        self.additions + self.deletions
+    }
+}
+
+/// Aggregated change statistics for a task
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskChangeSummary {
+    /// Per-file statistics, sorted by path
+    #[serde(default)]
+    pub files: Vec<TaskFileChange>,
+    /// Whether the statistics were confirmed against `git diff`
+    #[serde(default)]
+    pub confirmed_by_git: bool,
+}
+
+impl TaskChangeSummary {
+    /// Get total additions across all files
+    pub fn total_additions(&self) -> usize {
+        self.files.iter().map(|f| f.additions).sum()
+    }
+
+    /// Get total deletions across all files
+    pub fn total_deletions(&self) -> usize {
+        self.files.iter().map(|f| f.deletions).sum()
+    }
+
+    /// Get total number of files changed
+    pub fn total_files(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Check if there are any changes
+    pub fn has_changes(&self) -> bool {
+        !self.files.is_empty()
+    }
+}
+
+/// One prompt run against several agents, one candidate task per agent
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaskComparison {
+    /// Unique identifier for the comparison
+    pub id: String,
+    /// Workspace the comparison belongs to
+    pub workspace_id: String,
+    /// Comparison name (derived from the prompt)
+    pub name: String,
+    /// Candidate task IDs, in agent selection order
+    pub task_ids: Vec<String>,
+    /// Task picked as the winner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner_task_id: Option<String>,
+    /// When the comparison was created
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl TaskComparison {
+    /// Create a new comparison for a workspace
+    pub fn new(workspace_id: String, name: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            workspace_id,
+            name,
+            task_ids: Vec::new(),
+            winner_task_id: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// Task queue settings
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskQueueSettings {
+    /// Maximum number of queued tasks running concurrently per agent
+    #[serde(default = "default_max_concurrent_per_agent")]
+    pub max_concurrent_per_agent: usize,
+    /// Per-agent overrides of `max_concurrent_per_agent`
+    #[serde(default)]
+    pub agent_limits: HashMap<String, usize>,
+    /// How many times a failed queued task is retried automatically
+    #[serde(default)]
+    pub max_retries: u32,
+}
+
+fn default_max_concurrent_per_agent() -> usize {
+    1
+}
+
+impl Default for TaskQueueSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_per_agent: default_max_concurrent_per_agent(),
+            agent_limits: HashMap::new(),
+            max_retries: 0,
+        }
+    }
+}
+
+impl TaskQueueSettings {
+    /// Get the concurrency limit for an agent
+    pub fn limit_for(&self, agent_name: &str) -> usize {
+        self.agent_limits
+            .get(agent_name)
+            .copied()
+            .unwrap_or(self.max_concurrent_per_agent)
+    }
+}
+
 /// Persistent workspace configuration
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct WorkspaceConfig {
@@ -108,6 +321,15 @@ pub struct WorkspaceConfig {
     pub tasks: Vec<WorkspaceTask>,
     /// Currently active workspace ID
     pub active_workspace_id: Option<String>,
+    /// Task queue settings
+    #[serde(default)]
+    pub queue: TaskQueueSettings,
+    /// Multi-agent comparisons across workspaces
+    #[serde(default)]
+    pub comparisons: Vec<TaskComparison>,
+    /// Pipeline runs across workspaces
+    #[serde(default)]
+    pub pipeline_runs: Vec<PipelineRun>,
 }
 
 impl WorkspaceConfig {
@@ -119,8 +341,11 @@ impl WorkspaceConfig {
     /// Remove a workspace by ID
     pub fn remove_workspace(&mut self, workspace_id: &str) {
         self.workspaces.retain(|w| w.id != workspace_id);
-        // Also remove all tasks for this workspace
+        // Also remove all tasks, comparisons and pipeline runs for this workspace
         self.tasks.retain(|t| t.workspace_id != workspace_id);
+        self.comparisons.retain(|c| c.workspace_id != workspace_id);
+        self.pipeline_runs
+            .retain(|r| r.workspace_id != workspace_id);
     }
 
     /// Add a task to a workspace
@@ -160,6 +385,51 @@ impl WorkspaceConfig {
             .find(|t| t.session_id.as_ref() == Some(&session_id.to_string()))
     }
 
+    /// Get the IDs of queued tasks that may start now, honoring per-agent limits
+    ///
+    /// Tasks are picked in schedule/creation order. Tasks that are already in
+    /// progress, queued or started by hand, count against their agent's limit.
+    pub fn runnable_task_ids(&self, now: chrono::DateTime<chrono::Utc>) -> Vec<String> {
+        let mut running: HashMap<&str, usize> = HashMap::new();
+        for task in &self.tasks {
+            if task.status == SessionStatus::InProgress {
+                *running.entry(task.agent_name.as_str()).or_default() += 1;
+            }
+        }
+
+        let mut candidates: Vec<&WorkspaceTask> = self
+            .tasks
+            .iter()
+            .filter(|t| t.is_runnable_at(now))
+            .collect();
+        candidates.sort_by_key(|t| (t.scheduled_at.unwrap_or(t.created_at), t.created_at));
+
+        let mut runnable = Vec::new();
+        for task in candidates {
+            let count = running.entry(task.agent_name.as_str()).or_default();
+            if *count < self.queue.limit_for(&task.agent_name) {
+                *count += 1;
+                runnable.push(task.id.clone());
+            }
+        }
+        runnable
+    }
+
+    /// Get comparison by ID
+    pub fn get_comparison(&self, comparison_id: &str) -> Option<&TaskComparison> {
+        self.comparisons.iter().find(|c| c.id == comparison_id)
+    }
+
+    /// Get pipeline run by ID
+    pub fn get_pipeline_run(&self, run_id: &str) -> Option<&PipelineRun> {
+        self.pipeline_runs.iter().find(|r| r.id == run_id)
+    }
+
+    /// Get mutable pipeline run by ID
+    pub fn get_pipeline_run_mut(&mut self, run_id: &str) -> Option<&mut PipelineRun> {
+        self.pipeline_runs.iter_mut().find(|r| r.id == run_id)
+    }
+
     /// Get workspace by ID
     pub fn get_workspace(&self, workspace_id: &str) -> Option<&Workspace> {
         self.workspaces.iter().find(|w| w.id == workspace_id)
@@ -0,0 +1,124 @@
+//! Secret references in configuration values
+//!
+//! Sensitive config values can point somewhere else instead of holding the
+//! secret itself:
+//!
+//! - `secret:<name>` is looked up in the installed [`SecretSource`] (the
+//!   encrypted vault next to `config.json`)
+//! - `${env:<VAR>}` is read from the environment
+//!
+//! Any other value is used as-is, so plaintext configs keep working.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Prefix of values stored in the secret vault
+pub const SECRET_PREFIX: &str = "secret:";
+
+/// A parsed secret reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretRef<'a> {
+    /// `secret:<name>`
+    Vault(&'a str),
+    /// `${env:<VAR>}`
+    Env(&'a str),
+}
+
+impl<'a> SecretRef<'a> {
+    /// Parse `value`, returning `None` for plain values
+    pub fn parse(value: &'a str) -> Option<Self> {
+        let value = value.trim();
+        if let Some(name) = value.strip_prefix(SECRET_PREFIX) {
+            return (!name.is_empty()).then_some(Self::Vault(name));
+        }
+        value
+            .strip_prefix("${env:")
+            .and_then(|rest| rest.strip_suffix('}'))
+            .filter(|var| !var.is_empty())
+            .map(Self::Env)
+    }
+}
+
+/// Where `secret:` references are looked up
+pub trait SecretSource: Send + Sync {
+    fn get_secret(&self, name: &str) -> Option<String>;
+}
+
+static SECRET_SOURCE: RwLock<Option<Arc<dyn SecretSource>>> = RwLock::new(None);
+
+/// Install the source used to resolve `secret:` references
+pub fn set_secret_source(source: Arc<dyn SecretSource>) {
+    *SECRET_SOURCE.write().unwrap() = Some(source);
+}
+
+/// Whether `value` is a secret or environment reference
+pub fn is_secret_ref(value: &str) -> bool {
+    SecretRef::parse(value).is_some()
+}
+
+/// Resolve a config value that may be a secret reference
+///
+/// Missing secrets resolve to an empty string, so a reference never leaks
+/// into a request or a child process environment as if it were the value.
+pub fn resolve_secret(value: &str) -> String {
+    match SecretRef::parse(value) {
+        None => value.to_string(),
+        Some(SecretRef::Env(var)) => std::env::var(var).unwrap_or_else(|_| {
+            log::warn!("Environment variable '{}' is not set", var);
+            String::new()
+        }),
+        Some(SecretRef::Vault(name)) => {
+            let source = SECRET_SOURCE.read().unwrap().clone();
+            source
+                .and_then(|source| source.get_secret(name))
+                .unwrap_or_else(|| {
+                    log::warn!("Secret '{}' not found in the vault", name);
+                    String::new()
+                })
+        }
+    }
+}
+
+/// Resolve every value of an environment map
+pub fn resolve_env(env: &HashMap<String, String>) -> HashMap<String, String> {
+    env.iter()
+        .map(|(key, value)| (key.clone(), resolve_secret(value)))
+        .collect()
+}
+
+/// Whether an environment variable name looks like it holds a credential
+pub fn is_sensitive_env_key(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    ["TOKEN", "KEY", "SECRET", "PASSWORD", "PASSWD", "CREDENTIAL"]
+        .iter()
+        .any(|marker| key.contains(marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_refs() {
+        assert_eq!(
+            SecretRef::parse("secret:openai"),
+            Some(SecretRef::Vault("openai"))
+        );
+        assert_eq!(
+            SecretRef::parse("${env:OPENAI_API_KEY}"),
+            Some(SecretRef::Env("OPENAI_API_KEY"))
+        );
+        assert_eq!(SecretRef::parse("sk-plain"), None);
+        assert_eq!(SecretRef::parse("secret:"), None);
+        assert_eq!(SecretRef::parse("${env:PATH"), None);
+
+        assert_eq!(resolve_secret("sk-plain"), "sk-plain");
+        assert_eq!(
+            resolve_secret("${env:PATH}"),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        assert_eq!(resolve_secret("${env:AGENTX_SURELY_UNSET_VAR}"), "");
+        assert!(is_sensitive_env_key("GITHUB_TOKEN"));
+        assert!(!is_sensitive_env_key("NODE_ENV"));
+    }
+}
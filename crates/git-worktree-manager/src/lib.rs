@@ -3,8 +3,9 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
 use git2::{
-    BranchType, Index, MergeOptions as GitMergeOptions, Oid, Repository, StatusOptions,
-    WorktreeAddOptions, WorktreeLockStatus, WorktreePruneOptions, build::CheckoutBuilder,
+    BranchType, Index, IndexAddOption, MergeOptions as GitMergeOptions, Oid, Repository,
+    StatusOptions, WorktreeAddOptions, WorktreeLockStatus, WorktreePruneOptions,
+    build::CheckoutBuilder,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -310,11 +311,55 @@ impl WorktreeManager {
             &tree,
             &[&head_commit, &source_commit],
         )?;
+        // Clear the merge state (MERGE_HEAD, MERGE_MSG) so the repository
+        // is clean again
+        repo.cleanup_state()?;
         repo.checkout_head(Some(CheckoutBuilder::new().safe()))?;
 
         Ok(MergeOutcome::Merged)
     }
 
+    pub fn commit_all(&self, worktree_path: impl AsRef<Path>, message: &str) -> Result<bool> {
+        let worktree_path = worktree_path.as_ref();
+        let repo = self.open_repo(worktree_path)?;
+        let mut options = StatusOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(true);
+        if repo.statuses(Some(&mut options))?.is_empty() {
+            return Ok(false);
+        }
+
+        let mut index = repo.index()?;
+        index
+            .add_all(["*"], IndexAddOption::DEFAULT, None)
+            .context("failed to stage changes")?;
+        index
+            .update_all(["*"], None)
+            .context("failed to stage removed files")?;
+        index.write()?;
+
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let head_commit = repo.head()?.peel_to_commit()?;
+        let signature = repo.signature()?;
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &[&head_commit],
+        )?;
+        Ok(true)
+    }
+
+    pub fn delete_branch(&self, branch: &str) -> Result<()> {
+        let repo = self.open_repo(&self.repo_path)?;
+        repo.find_branch(branch, BranchType::Local)
+            .with_context(|| format!("branch not found: {}", branch))?
+            .delete()
+            .with_context(|| format!("failed to delete branch {}", branch))?;
+        Ok(())
+    }
+
     fn find_by_path(&self, path: &Path) -> Result<WorktreeInfo> {
         let path = path
             .canonicalize()
@@ -423,7 +468,7 @@ fn collect_conflicts_from_index(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use git2::{Repository, Signature};
+    use git2::{Repository, RepositoryState, Signature};
     use std::fs;
     use std::path::Path;
     use tempfile::TempDir;
@@ -491,6 +536,48 @@ mod tests {
         assert!(!list.iter().any(|entry| entry.path == expected));
     }
 
+    #[test]
+    fn commit_all_and_delete_branch() {
+        let (temp, repo) = init_repo();
+        repo.config()
+            .unwrap()
+            .set_str("user.name", "tests")
+            .unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("user.email", "tests@example.com")
+            .unwrap();
+        let manager = WorktreeManager::new(temp.path());
+        let worktree_path = temp.path().join("agent-b");
+
+        manager
+            .create(
+                &worktree_path,
+                WorktreeBranch::New {
+                    name: "agent-b".to_string(),
+                    start_point: None,
+                },
+                CreateOptions::default(),
+            )
+            .unwrap();
+
+        assert!(!manager.commit_all(&worktree_path, "nothing").unwrap());
+        fs::write(worktree_path.join("new.txt"), "agent change\n").unwrap();
+        fs::remove_file(worktree_path.join("README.md")).unwrap();
+        assert!(manager.commit_all(&worktree_path, "agent work").unwrap());
+
+        let worktree_repo = Repository::open(&worktree_path).unwrap();
+        let head = worktree_repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("agent work"));
+        let tree = head.tree().unwrap();
+        assert!(tree.get_name("new.txt").is_some());
+        assert!(tree.get_name("README.md").is_none());
+
+        manager.delete(&worktree_path, true).unwrap();
+        manager.delete_branch("agent-b").unwrap();
+        assert!(repo.find_branch("agent-b", BranchType::Local).is_err());
+    }
+
     #[test]
     fn merge_without_conflicts() {
         let (temp, repo) = init_repo();
@@ -517,6 +604,7 @@ mod tests {
             .unwrap();
 
         assert!(matches!(outcome, MergeOutcome::Merged));
+        assert_eq!(repo.state(), RepositoryState::Clean);
     }
 
     #[test]
@@ -4,6 +4,7 @@
 //! Actions 是 GPUI 中用于触发用户操作的类型安全机制。
 
 use agent_client_protocol::{ImageContent, ToolCall};
+use agentx_types::schemas::task_template::{TaskTemplate, TemplateScope};
 use gpui::{Action, SharedString, actions};
 use gpui_component::{ThemeMode, dock::DockPlacement, scroll::ScrollbarShow};
 use serde::Deserialize;
@@ -134,6 +135,12 @@ fn default_dock_placement() -> DockPlacement {
 // 切换 Dock 切换按钮的显示状态 / 打开会话管理面板
 actions!(agent_studio, [ToggleDockToggleButton, OpenSessionManager]);
 
+// 打开工作区搜索面板（全文搜索与替换）
+actions!(agent_studio, [SearchInWorkspace]);
+
+// 打开事件日志面板（浏览、过滤与回放记录的事件）
+actions!(agent_studio, [OpenEventJournal]);
+
 // ============================================================================
 // Task List Actions - 任务列表相关操作
 // ============================================================================
@@ -207,6 +214,8 @@ pub struct CreateTaskFromWelcome {
     pub code_selections: Vec<AddCodeSelection>,
     /// 目标工作区 ID（可选，如果未指定则使用 active workspace）
     pub workspace_id: Option<String>,
+    /// 会话工作目录（可选，须位于工作区内，否则使用工作区目录）
+    pub working_dir: Option<std::path::PathBuf>,
 }
 
 /// 发送消息到指定会话
@@ -236,6 +245,99 @@ pub struct CancelSession {
     /// 会话唯一标识符
     pub session_id: String,
 }
+
+/// 从任务模板创建任务
+///
+/// 打开模板选择对话框，填写模板字段后将任务加入任务队列
+/// 由欢迎面板和任务面板触发，实际逻辑在 workspace/actions/template_actions.rs 中实现
+#[derive(Action, Clone, Debug, PartialEq, Deserialize)]
+#[action(namespace = agentx, no_json)]
+pub struct NewTaskFromTemplate {
+    /// 目标工作区 ID（可选，如果未指定则使用 active workspace）
+    pub workspace_id: Option<String>,
+    /// 用于填充 {{files}} 占位符的已选文件
+    pub selected_files: Vec<String>,
+}
+
+/// 新建或编辑任务模板
+///
+/// 打开模板编辑对话框，`name` 为空时表示新建模板
+#[derive(Action, Clone, Debug, PartialEq, Deserialize)]
+#[action(namespace = agentx, no_json)]
+pub struct EditTaskTemplate {
+    /// 模板所在工作区 ID（为空时只能保存为全局模板）
+    pub workspace_id: Option<String>,
+    /// 已有模板的名称
+    pub name: Option<String>,
+    /// 模板的保存位置（编辑已有模板时使用）
+    pub scope: Option<TemplateScope>,
+    /// 预填充的模板内容
+    pub template: TaskTemplate,
+}
+/// 多 Agent 对比
+///
+/// 打开 Agent 选择对话框，将同一个提示词（含附件）发送给多个 Agent，
+/// 每个 Agent 使用独立的会话（可选独立的 git worktree）
+/// 实际逻辑在 workspace/actions/comparison_actions.rs 中实现
+#[derive(Action, Clone, Debug, PartialEq, Deserialize)]
+#[action(namespace = agentx, no_json)]
+pub struct CompareAgents {
+    /// 目标工作区 ID（可选，如果未指定则使用 active workspace）
+    pub workspace_id: Option<String>,
+    /// 发送给所有 Agent 的提示词
+    pub prompt: String,
+    /// 任务模式（可选）
+    pub mode: Option<String>,
+    /// 附加的图片列表 (ImageContent, filename)
+    pub images: Vec<(ImageContent, String)>,
+    /// 附带的代码选择列表
+    pub code_selections: Vec<AddCodeSelection>,
+}
+
+/// 打开多 Agent 对比面板
+#[derive(Action, Clone, Debug, PartialEq, Deserialize)]
+#[action(namespace = agentx, no_json)]
+pub struct OpenComparison {
+    /// 对比唯一标识符
+    pub comparison_id: String,
+}
+
+/// 选择对比的胜出任务
+///
+/// 提交胜出任务 worktree 中的修改并合并其分支，然后清理其他候选 worktree
+#[derive(Action, Clone, Debug, PartialEq, Deserialize)]
+#[action(namespace = agentx, no_json)]
+pub struct PickComparisonWinner {
+    /// 对比唯一标识符
+    pub comparison_id: String,
+    /// 胜出任务 ID
+    pub task_id: String,
+}
+
+/// 运行 Agent 流水线
+///
+/// 打开流水线选择对话框，填写输入后依次运行各阶段的任务，
+/// 上一阶段的输出（最终消息、计划条目或 diff）作为下一阶段的输入
+/// 实际逻辑在 workspace/actions/pipeline_actions.rs 中实现
+#[derive(Action, Clone, Debug, PartialEq, Deserialize)]
+#[action(namespace = agentx, no_json)]
+pub struct RunPipeline {
+    /// 目标工作区 ID（可选，如果未指定则使用 active workspace）
+    pub workspace_id: Option<String>,
+}
+
+/// 批量加入任务队列
+///
+/// 打开对话框，每行输入一个提示词，为每个提示词创建一个排队任务，
+/// 并可调整每个 Agent 的最大并发数和失败重试次数
+/// 实际逻辑在 workspace/actions/queue_actions.rs 中实现
+#[derive(Action, Clone, Debug, PartialEq, Deserialize)]
+#[action(namespace = agentx, no_json)]
+pub struct QueueTasks {
+    /// 目标工作区 ID（可选，如果未指定则使用 active workspace）
+    pub workspace_id: Option<String>,
+}
+
 /// 显示会话对话面板
 ///
 #[derive(Action, Clone, PartialEq, Deserialize)]
@@ -295,6 +397,30 @@ impl From<AddCodeSelection> for agentx_types::events::CodeSelectionData {
     }
 }
 
+// 保存代码编辑器中的当前文件 / 另存为（由 CodeEditorPanel 处理）
+actions!(code_editor, [SaveFile, SaveFileAs]);
+
+// 快速打开：在工作区文件索引中模糊搜索并打开文件
+actions!(code_editor, [QuickOpen]);
+
+// 跳转到工作区符号：基于 tree-sitter 提取的函数、类型等定义
+actions!(code_editor, [GoToSymbol]);
+
+/// 在代码编辑器中打开文件并跳转到指定位置
+///
+/// 优先使用已有的代码编辑器面板，没有时新建一个
+/// 实际逻辑在 workspace/actions/panel_actions.rs 中实现
+#[derive(Action, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[action(namespace = code_editor, no_json)]
+pub struct OpenFileInEditor {
+    /// 文件路径
+    pub path: PathBuf,
+    /// 行号（0-based）
+    pub line: u32,
+    /// 列号（0-based）
+    pub column: u32,
+}
+
 // 通用应用级操作 - 包含各种应用级别的命令和操作
 actions!(
     agent_studio,
@@ -3,7 +3,8 @@ use gpui_component::{ThemeMode, ThemeRegistry};
 use rust_i18n::t;
 
 use crate::{
-    About, CloseWindow, Open, Quit, SelectLocale, ToggleSearch,
+    About, CloseWindow, Open, OpenEventJournal, Quit, SearchInWorkspace, SelectLocale,
+    ToggleSearch,
     app::actions::{SwitchTheme, SwitchThemeMode},
 };
 
@@ -74,6 +75,10 @@ pub fn init(title: impl Into<SharedString>, cx: &mut App) {
                     t!("menu.edit.find").to_string(),
                     gpui_component::input::Search,
                 ),
+                MenuItem::action(
+                    t!("menu.edit.find_in_workspace").to_string(),
+                    SearchInWorkspace,
+                ),
                 MenuItem::separator(),
                 MenuItem::action(
                     t!("menu.edit.select_all").to_string(),
@@ -87,6 +92,10 @@ pub fn init(title: impl Into<SharedString>, cx: &mut App) {
                 MenuItem::action(t!("menu.window.close").to_string(), CloseWindow),
                 MenuItem::separator(),
                 MenuItem::action(t!("menu.window.toggle_search").to_string(), ToggleSearch),
+                MenuItem::action(
+                    t!("menu.window.event_journal").to_string(),
+                    OpenEventJournal,
+                ),
             ],
         },
         Menu {
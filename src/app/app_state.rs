@@ -5,10 +5,11 @@ use std::sync::Arc;
 use crate::{
     core::agent::{AgentManager, PermissionStore},
     core::config::DEFAULT_TOOL_CALL_PREVIEW_MAX_LINES,
-    core::event_bus::EventHub,
+    core::event_bus::{EventHub, EventJournal},
     core::services::{
-        AgentConfigService, AgentService, AiService, MessageService, PersistenceService,
-        WorkspaceService,
+        AgentConfigService, AgentService, AiService, ChangeTrackerService, ComparisonService,
+        FileIndexService, LspService, MessageService, PersistenceService, PipelineService,
+        SecretStore, TaskQueueService, TaskTemplateService, WorkspaceService,
     },
 };
 
@@ -29,6 +30,8 @@ pub struct AppState {
     // Infrastructure
     agent_manager: Option<Arc<AgentManager>>,
     permission_store: Option<Arc<PermissionStore>>,
+    /// Records the events of `services.event_hub` while recording is on
+    event_journal: Arc<EventJournal>,
 
     /// Service registry — Clone + Send, can be captured in async closures
     pub services: ServiceRegistry,
@@ -51,13 +54,23 @@ impl AppState {
         // Create shared event hub
         let event_hub = EventHub::new();
 
+        // Attach the event journal; it records once recording is turned on
+        let event_journal = Arc::new(EventJournal::default());
+        event_journal.attach(&event_hub);
+
         // Create service registry
         let mut services = ServiceRegistry::new(event_hub.clone());
 
         // Create workspace service and set its bus
         let mut workspace_service = WorkspaceService::new(config_path);
         workspace_service.set_event_hub(event_hub.clone());
-        services.set_workspace_service(Arc::new(workspace_service));
+        let workspace_service = Arc::new(workspace_service);
+        services.set_workspace_service(workspace_service.clone());
+
+        // Track per-task file changes from tool call diffs
+        let change_tracker_service = Arc::new(ChangeTrackerService::new(workspace_service));
+        change_tracker_service.init(&event_hub);
+        services.set_change_tracker_service(change_tracker_service);
 
         let sessions_dir = crate::core::config_manager::get_sessions_dir();
         services.set_persistence_service(Arc::new(PersistenceService::new(sessions_dir)));
@@ -66,6 +79,7 @@ impl AppState {
             invisible_panels: cx.new(|_| Vec::new()),
             agent_manager: None,
             permission_store: None,
+            event_journal,
             services,
             welcome_session: None,
             config_path: None,
@@ -150,6 +164,9 @@ impl AppState {
         // Initialize services when agent_manager is set
         let mut agent_service = AgentService::new(manager.clone());
         agent_service.set_event_hub(event_hub.clone());
+        if let Ok(workspace_service) = self.services.workspace_service() {
+            agent_service.set_workspace_service(workspace_service.clone());
+        }
         let agent_service = Arc::new(agent_service);
 
         let message_service = Arc::new(MessageService::new(
@@ -167,11 +184,75 @@ impl AppState {
                 event_hub.clone(),
             );
             service.set_agent_service(agent_service.clone());
-            self.services.set_agent_config_service(Arc::new(service));
+            if let Ok(secret_store) = self.services.secret_store() {
+                service.set_secret_store(secret_store.clone());
+            }
+            let service = Arc::new(service);
+            service.init();
+            self.services.set_agent_config_service(service);
         } else {
             log::warn!("Config path not set, AgentConfigService will not be initialized");
         }
 
+        // Initialize TaskQueueService (runs queued workspace tasks in the background)
+        if let Ok(workspace_service) = self.services.workspace_service() {
+            let mut task_queue_service = TaskQueueService::new(
+                workspace_service.clone(),
+                agent_service.clone(),
+                message_service.clone(),
+            );
+            if let Ok(agent_config_service) = self.services.agent_config_service() {
+                task_queue_service.set_agent_config_service(agent_config_service.clone());
+            }
+            let task_queue_service = Arc::new(task_queue_service);
+            task_queue_service.start();
+            self.services.set_task_queue_service(task_queue_service);
+        }
+
+        // Initialize TaskTemplateService (global and per-workspace task templates)
+        if let (Ok(workspace_service), Ok(agent_config_service)) = (
+            self.services.workspace_service(),
+            self.services.agent_config_service(),
+        ) {
+            self.services
+                .set_task_template_service(Arc::new(TaskTemplateService::new(
+                    workspace_service.clone(),
+                    agent_config_service.clone(),
+                )));
+        }
+
+        // Initialize ComparisonService (one prompt against several agents)
+        if let Ok(workspace_service) = self.services.workspace_service() {
+            self.services
+                .set_comparison_service(Arc::new(ComparisonService::new(
+                    workspace_service.clone(),
+                )));
+        }
+
+        // Initialize PipelineService (hands task output over to follow-up tasks)
+        if let (Ok(workspace_service), Ok(agent_config_service)) = (
+            self.services.workspace_service(),
+            self.services.agent_config_service(),
+        ) {
+            let pipeline_service = Arc::new(PipelineService::new(
+                workspace_service.clone(),
+                agent_config_service.clone(),
+                message_service.clone(),
+            ));
+            pipeline_service.init(&event_hub);
+            self.services.set_pipeline_service(pipeline_service);
+        }
+
+        // Initialize LspService (language servers for the code editor)
+        if let Ok(agent_config_service) = self.services.agent_config_service() {
+            self.services
+                .set_lsp_service(Arc::new(LspService::new(agent_config_service.clone())));
+        }
+
+        // Initialize FileIndexService (workspace file index for pickers and quick-open)
+        self.services
+            .set_file_index_service(Arc::new(FileIndexService::new()));
+
         // Initialize AI Service from config
         if !initial_config.models.is_empty() {
             log::info!(
@@ -192,12 +273,28 @@ impl AppState {
         self.tool_call_preview_max_lines = initial_config.tool_call_preview_max_lines;
 
         log::info!(
-            "Initialized service layer (AgentService, MessageService, PersistenceService, AgentConfigService, AiService)"
+            "Initialized service layer (AgentService, MessageService, PersistenceService, AgentConfigService, AiService, TaskQueueService, TaskTemplateService, ComparisonService, PipelineService, LspService, FileIndexService)"
         );
     }
 
     /// Set the config path for AgentConfigService
+    ///
+    /// Also opens the secret vault next to it, with its key from the per-user
+    /// key directory, so `secret:` references in the config resolve from here on.
     pub fn set_config_path(&mut self, path: PathBuf) {
+        if self.services.secret_store().is_err() {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let opened = crate::core::config_manager::get_vault_key_dir()
+                .and_then(|key_dir| SecretStore::open_in(dir, &key_dir));
+            match opened {
+                Ok(store) => {
+                    let store = Arc::new(store);
+                    agentx_types::secrets::set_secret_source(store.clone());
+                    self.services.set_secret_store(store);
+                }
+                Err(e) => log::error!("Failed to open secret vault: {}", e),
+            }
+        }
         self.config_path = Some(path);
     }
 
@@ -230,6 +327,10 @@ impl AppState {
         &self.services.event_hub
     }
 
+    pub fn event_journal(&self) -> &Arc<EventJournal> {
+        &self.event_journal
+    }
+
     /// Set the welcome session
     pub fn set_welcome_session(&mut self, session: WelcomeSession) {
         log::info!(
@@ -277,6 +378,38 @@ impl AppState {
         self.services.ai_service().ok()
     }
 
+    pub fn change_tracker_service(&self) -> Option<&Arc<ChangeTrackerService>> {
+        self.services.change_tracker_service().ok()
+    }
+
+    pub fn task_queue_service(&self) -> Option<&Arc<TaskQueueService>> {
+        self.services.task_queue_service().ok()
+    }
+
+    pub fn task_template_service(&self) -> Option<&Arc<TaskTemplateService>> {
+        self.services.task_template_service().ok()
+    }
+
+    pub fn comparison_service(&self) -> Option<&Arc<ComparisonService>> {
+        self.services.comparison_service().ok()
+    }
+
+    pub fn pipeline_service(&self) -> Option<&Arc<PipelineService>> {
+        self.services.pipeline_service().ok()
+    }
+
+    pub fn lsp_service(&self) -> Option<&Arc<LspService>> {
+        self.services.lsp_service().ok()
+    }
+
+    pub fn file_index_service(&self) -> Option<&Arc<FileIndexService>> {
+        self.services.file_index_service().ok()
+    }
+
+    pub fn secret_store(&self) -> Option<&Arc<SecretStore>> {
+        self.services.secret_store().ok()
+    }
+
     /// Get the current working directory
     pub fn current_working_dir(&self) -> &PathBuf {
         &self.current_working_dir
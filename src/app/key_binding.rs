@@ -1,6 +1,8 @@
 use gpui::{App, KeyBinding};
 
-use crate::app::actions::{Open, Paste, Quit, ToggleSearch};
+use crate::app::actions::{
+    GoToSymbol, Open, Paste, QuickOpen, Quit, SaveFile, SaveFileAs, SearchInWorkspace, ToggleSearch,
+};
 use gpui_term::{Clear, Copy, SelectAll};
 
 // 导出KeyBinding设置函数,供主应用使用
@@ -19,6 +21,27 @@ pub fn init(cx: &mut App) {
         KeyBinding::new("cmd-v", Paste, None),
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-v", Paste, None),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-f", SearchInWorkspace, None),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-f", SearchInWorkspace, None),
+        // Code editor keybindings
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-s", SaveFile, Some("CodeEditor")),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-s", SaveFile, Some("CodeEditor")),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-s", SaveFileAs, Some("CodeEditor")),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-s", SaveFileAs, Some("CodeEditor")),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-p", QuickOpen, Some("CodeEditor")),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-p", QuickOpen, Some("CodeEditor")),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-t", GoToSymbol, Some("CodeEditor")),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-t", GoToSymbol, Some("CodeEditor")),
         // Terminal keybindings
         #[cfg(target_os = "macos")]
         KeyBinding::new("cmd-c", Copy, Some("Terminal")),
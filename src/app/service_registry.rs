@@ -3,8 +3,9 @@ use std::sync::Arc;
 use crate::core::{
     event_bus::EventHub,
     services::{
-        AgentConfigService, AgentService, AiService, MessageService, PersistenceService,
-        WorkspaceService,
+        AgentConfigService, AgentService, AiService, ChangeTrackerService, ComparisonService,
+        FileIndexService, LspService, MessageService, PersistenceService, PipelineService,
+        SecretStore, TaskQueueService, TaskTemplateService, WorkspaceService,
     },
 };
 
@@ -21,6 +22,14 @@ pub struct ServiceRegistry {
     workspace_service: Option<Arc<WorkspaceService>>,
     agent_config_service: Option<Arc<AgentConfigService>>,
     ai_service: Option<Arc<AiService>>,
+    change_tracker_service: Option<Arc<ChangeTrackerService>>,
+    task_queue_service: Option<Arc<TaskQueueService>>,
+    task_template_service: Option<Arc<TaskTemplateService>>,
+    comparison_service: Option<Arc<ComparisonService>>,
+    pipeline_service: Option<Arc<PipelineService>>,
+    lsp_service: Option<Arc<LspService>>,
+    file_index_service: Option<Arc<FileIndexService>>,
+    secret_store: Option<Arc<SecretStore>>,
 }
 
 impl ServiceRegistry {
@@ -33,6 +42,14 @@ impl ServiceRegistry {
             workspace_service: None,
             agent_config_service: None,
             ai_service: None,
+            change_tracker_service: None,
+            task_queue_service: None,
+            task_template_service: None,
+            comparison_service: None,
+            pipeline_service: None,
+            lsp_service: None,
+            file_index_service: None,
+            secret_store: None,
         }
     }
 
@@ -72,6 +89,54 @@ impl ServiceRegistry {
             .ok_or_else(|| anyhow::anyhow!("AiService not initialized"))
     }
 
+    pub fn change_tracker_service(&self) -> anyhow::Result<&Arc<ChangeTrackerService>> {
+        self.change_tracker_service
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("ChangeTrackerService not initialized"))
+    }
+
+    pub fn task_queue_service(&self) -> anyhow::Result<&Arc<TaskQueueService>> {
+        self.task_queue_service
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("TaskQueueService not initialized"))
+    }
+
+    pub fn task_template_service(&self) -> anyhow::Result<&Arc<TaskTemplateService>> {
+        self.task_template_service
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("TaskTemplateService not initialized"))
+    }
+
+    pub fn comparison_service(&self) -> anyhow::Result<&Arc<ComparisonService>> {
+        self.comparison_service
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("ComparisonService not initialized"))
+    }
+
+    pub fn pipeline_service(&self) -> anyhow::Result<&Arc<PipelineService>> {
+        self.pipeline_service
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("PipelineService not initialized"))
+    }
+
+    pub fn lsp_service(&self) -> anyhow::Result<&Arc<LspService>> {
+        self.lsp_service
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("LspService not initialized"))
+    }
+
+    pub fn file_index_service(&self) -> anyhow::Result<&Arc<FileIndexService>> {
+        self.file_index_service
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("FileIndexService not initialized"))
+    }
+
+    pub fn secret_store(&self) -> anyhow::Result<&Arc<SecretStore>> {
+        self.secret_store
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("SecretStore not initialized"))
+    }
+
     // --- Setters (used by AppState during initialization) ---
 
     pub(crate) fn set_agent_service(&mut self, service: Arc<AgentService>) {
@@ -97,4 +162,36 @@ impl ServiceRegistry {
     pub(crate) fn set_ai_service(&mut self, service: Arc<AiService>) {
         self.ai_service = Some(service);
     }
+
+    pub(crate) fn set_change_tracker_service(&mut self, service: Arc<ChangeTrackerService>) {
+        self.change_tracker_service = Some(service);
+    }
+
+    pub(crate) fn set_task_queue_service(&mut self, service: Arc<TaskQueueService>) {
+        self.task_queue_service = Some(service);
+    }
+
+    pub(crate) fn set_task_template_service(&mut self, service: Arc<TaskTemplateService>) {
+        self.task_template_service = Some(service);
+    }
+
+    pub(crate) fn set_comparison_service(&mut self, service: Arc<ComparisonService>) {
+        self.comparison_service = Some(service);
+    }
+
+    pub(crate) fn set_pipeline_service(&mut self, service: Arc<PipelineService>) {
+        self.pipeline_service = Some(service);
+    }
+
+    pub(crate) fn set_lsp_service(&mut self, service: Arc<LspService>) {
+        self.lsp_service = Some(service);
+    }
+
+    pub(crate) fn set_file_index_service(&mut self, service: Arc<FileIndexService>) {
+        self.file_index_service = Some(service);
+    }
+
+    pub(crate) fn set_secret_store(&mut self, service: Arc<SecretStore>) {
+        self.secret_store = Some(service);
+    }
 }
@@ -14,6 +14,7 @@ pub struct Assets;
 #[derive(RustEmbed)]
 #[folder = "./"]
 #[include = "config.json"]
+#[include = "agent-catalog.json"]
 pub struct ConfigAssets;
 
 #[derive(RustEmbed)]
@@ -151,6 +152,12 @@ pub fn get_default_config() -> Option<String> {
     ConfigAssets::get("config.json").map(|file| String::from_utf8_lossy(&file.data).to_string())
 }
 
+/// Get default agent-catalog.json content embedded in the binary
+pub fn get_default_agent_catalog() -> Option<String> {
+    ConfigAssets::get(agentx_types::schemas::agent_catalog::AGENT_CATALOG_FILE)
+        .map(|file| String::from_utf8_lossy(&file.data).to_string())
+}
+
 /// Get all embedded theme files
 pub fn get_embedded_themes() -> Vec<(String, String)> {
     ThemeAssets::iter()
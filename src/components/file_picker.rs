@@ -3,9 +3,20 @@ use gpui_component::{
     ActiveTheme, Icon, IconName, IndexPath,
     list::{ListDelegate, ListItem, ListState},
 };
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::sync::mpsc;
 
+use crate::{
+    AppState,
+    core::services::{FileIndex, IndexEntry},
+};
+
+/// Maximum number of matches listed for a query
+const MAX_RESULTS: usize = 200;
+
 /// File item in the file picker
 #[derive(Clone, Debug)]
 pub struct FileItem {
@@ -15,57 +26,50 @@ pub struct FileItem {
     pub relative_path: String,
 }
 
-impl FileItem {
-    fn new(name: String, path: PathBuf, is_folder: bool, base_path: &Path) -> Self {
-        let relative_path = path
-            .strip_prefix(base_path)
-            .unwrap_or(&path)
-            .to_string_lossy()
-            .to_string()
-            .replace('\\', "/");
-
+impl From<&IndexEntry> for FileItem {
+    fn from(entry: &IndexEntry) -> Self {
         Self {
-            name,
-            path,
-            is_folder,
-            relative_path,
+            name: entry.name().to_string(),
+            path: entry.path.clone(),
+            is_folder: entry.is_dir,
+            relative_path: entry.relative_path.clone(),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum ScanState {
-    NotStarted,
-    Scanning,
-    Ready,
-}
-
 /// Delegate for the file picker list
+///
+/// Items come from the workspace file index of `root_path`, which is built in
+/// the background and kept current by a file watcher.
 pub struct FilePickerDelegate {
     root_path: PathBuf,
-    all_items: Vec<FileItem>,
+    index: Option<Arc<FileIndex>>,
     filtered_items: Vec<FileItem>,
     search_query: String,
     selected_index: Option<usize>,
     on_select: Option<Box<dyn Fn(FileItem) + 'static>>,
     selection_tx: Option<mpsc::UnboundedSender<FileItem>>,
-    scan_state: ScanState,
 }
 
 impl FilePickerDelegate {
-    pub fn new(root_path: &Path) -> Self {
+    pub fn new(root_path: &Path, cx: &App) -> Self {
         Self {
             root_path: root_path.to_path_buf(),
+            index: Self::index_for(root_path, cx),
             filtered_items: Vec::new(),
-            all_items: Vec::new(),
             search_query: String::new(),
             selected_index: None,
             on_select: None,
             selection_tx: None,
-            scan_state: ScanState::NotStarted,
         }
     }
 
+    fn index_for(root_path: &Path, cx: &App) -> Option<Arc<FileIndex>> {
+        AppState::global(cx)
+            .file_index_service()
+            .map(|service| service.index(root_path))
+    }
+
     pub fn with_selection_sender(mut self, tx: mpsc::UnboundedSender<FileItem>) -> Self {
         self.selection_tx = Some(tx);
         self
@@ -87,120 +91,37 @@ impl FilePickerDelegate {
         &self.root_path
     }
 
-    pub fn needs_scan(&self) -> bool {
-        self.scan_state == ScanState::NotStarted
-    }
-
-    pub fn is_scanning(&self) -> bool {
-        self.scan_state == ScanState::Scanning
-    }
-
-    pub fn mark_scanning(&mut self) {
-        self.scan_state = ScanState::Scanning;
+    pub fn index(&self) -> Option<&Arc<FileIndex>> {
+        self.index.as_ref()
     }
 
-    pub fn set_items(&mut self, items: Vec<FileItem>) {
-        self.all_items = items;
-        self.scan_state = ScanState::Ready;
-        let query = self.search_query.clone();
-        self.set_search_query(query);
+    /// Whether the index is still being built (results may be incomplete)
+    pub fn is_indexing(&self) -> bool {
+        self.index.as_ref().is_some_and(|index| !index.is_ready())
     }
 
-    pub fn reset_root(&mut self, root_path: PathBuf) {
+    pub fn reset_root(&mut self, root_path: PathBuf, cx: &App) {
+        self.index = Self::index_for(&root_path, cx);
         self.root_path = root_path;
-        self.all_items.clear();
         self.filtered_items.clear();
         self.search_query.clear();
         self.selected_index = None;
-        self.scan_state = ScanState::NotStarted;
-    }
-
-    /// Scan directory recursively and return all files and folders
-    pub(crate) fn scan_directory(path: &Path, base_path: &Path) -> Vec<FileItem> {
-        let mut items = Vec::new();
-
-        // Skip common ignore patterns
-        let ignore_patterns = [
-            "node_modules",
-            ".git",
-            "target",
-            ".next",
-            ".cache",
-            "dist",
-            "build",
-            ".vscode",
-            ".idea",
-        ];
-
-        if let Ok(entries) = std::fs::read_dir(path) {
-            for entry in entries.flatten() {
-                let entry_path = entry.path();
-                let file_name = entry_path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                // Skip ignored directories
-                if ignore_patterns.contains(&file_name.as_str()) {
-                    continue;
-                }
-
-                let is_folder = entry_path.is_dir();
-
-                // Add the current item (folder or file)
-                items.push(FileItem::new(
-                    file_name.clone(),
-                    entry_path.clone(),
-                    is_folder,
-                    base_path,
-                ));
-
-                // Recursively scan subdirectories (limit depth to avoid too many files)
-                if is_folder {
-                    // Only go 3 levels deep
-                    let depth = entry_path
-                        .strip_prefix(base_path)
-                        .map(|p| p.components().count())
-                        .unwrap_or(0);
-
-                    if depth < 3 {
-                        items.extend(Self::scan_directory(&entry_path, base_path));
-                    }
-                }
-            }
-        }
-
-        // Sort: folders first, then files, alphabetically within each group
-        items.sort_by(|a, b| match (a.is_folder, b.is_folder) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.relative_path.cmp(&b.relative_path),
-        });
-
-        items
     }
 
-    /// Update search query and filter items
+    /// Update search query and fuzzy-match the indexed files
     pub fn set_search_query(&mut self, query: String) {
-        self.search_query = query.to_lowercase();
-
-        if self.search_query.is_empty() {
-            self.filtered_items = self.all_items.clone();
-        } else {
-            self.filtered_items = self
-                .all_items
-                .iter()
-                .filter(|item| {
-                    item.name.to_lowercase().contains(&self.search_query)
-                        || item
-                            .relative_path
-                            .to_lowercase()
-                            .contains(&self.search_query)
-                })
-                .cloned()
-                .collect();
-        }
+        self.search_query = query;
+        self.filtered_items = self
+            .index
+            .as_ref()
+            .map(|index| {
+                index
+                    .search(&self.search_query, MAX_RESULTS)
+                    .iter()
+                    .map(|m| FileItem::from(&m.entry))
+                    .collect()
+            })
+            .unwrap_or_default();
     }
 }
 
@@ -1,2 +1,5 @@
 // Re-export from agentx-agent crate
-pub use agentx_agent::{AgentHandle, AgentManager, PermissionStore};
+pub use agentx_agent::{
+    AgentHandle, AgentInstaller, AgentManager, AgentNodejs, InstallProgress, PermissionStore,
+};
+pub use agentx_types::{AgentFeatures, Feature};
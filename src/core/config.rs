@@ -1,3 +1,6 @@
 // Re-export all types from agentx-types
 pub use agentx_types::DEFAULT_TOOL_CALL_PREVIEW_MAX_LINES;
 pub use agentx_types::config::*;
+pub use agentx_types::config_schema::{
+    CONFIG_SCHEMA_FILE, ConfigError, config_schema, parse_config, write_config_schema,
+};
@@ -1,3 +1,4 @@
+use agentx_types::schemas::agent_catalog::{AGENT_CATALOG_FILE, AgentCatalog};
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
@@ -33,6 +34,14 @@ pub fn get_user_data_dir() -> Result<PathBuf> {
     }
 }
 
+/// Directory of the secret vault key, kept apart from every config directory
+/// so a copied or committed config never carries its key
+pub fn get_vault_key_dir() -> Result<PathBuf> {
+    let dir = dirs::data_local_dir()
+        .ok_or_else(|| anyhow::anyhow!("Failed to get local data directory"))?;
+    Ok(dir.join("agentx").join("keys"))
+}
+
 /// Ensure the user data directory exists
 pub fn ensure_user_data_dir() -> Result<PathBuf> {
     let dir = get_user_data_dir()?;
@@ -178,8 +187,57 @@ pub fn get_docks_layout_path() -> PathBuf {
     user_data_dir_or_temp().join("docks-layout.json")
 }
 
+/// Get code editor sessions file path (open files per workspace)
+/// Always uses user data directory: <user_data_dir>/editor-sessions.json
+pub fn get_editor_sessions_path() -> PathBuf {
+    user_data_dir_or_temp().join("editor-sessions.json")
+}
+
 /// Get sessions directory path
 /// Always uses user data directory: <user_data_dir>/sessions
 pub fn get_sessions_dir() -> PathBuf {
     user_data_dir_or_temp().join("sessions")
 }
+
+/// Get installed agents directory path
+/// Always uses user data directory: <user_data_dir>/agents
+pub fn get_agents_dir() -> PathBuf {
+    user_data_dir_or_temp().join("agents")
+}
+
+/// Get managed Node.js runtimes directory path
+/// Always uses user data directory: <user_data_dir>/runtimes/node
+pub fn get_node_runtimes_dir() -> PathBuf {
+    user_data_dir_or_temp().join("runtimes").join("node")
+}
+
+/// Get event journals directory path
+/// Always uses user data directory: <user_data_dir>/journals
+pub fn get_journals_dir() -> PathBuf {
+    user_data_dir_or_temp().join("journals")
+}
+
+/// Get agent catalog file path
+/// Always uses user data directory: <user_data_dir>/agent-catalog.json
+pub fn get_agent_catalog_path() -> PathBuf {
+    user_data_dir_or_temp().join(AGENT_CATALOG_FILE)
+}
+
+/// Load the agent catalog from the user data directory
+/// The embedded catalog is written there first if the file doesn't exist,
+/// so it can be edited to add agents
+pub fn load_agent_catalog() -> Result<AgentCatalog> {
+    let path = get_agent_catalog_path();
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {:?}", parent))?;
+        }
+        let default_catalog = crate::assets::get_default_agent_catalog()
+            .ok_or_else(|| anyhow::anyhow!("Failed to get embedded agent catalog"))?;
+        std::fs::write(&path, default_catalog)
+            .with_context(|| format!("Failed to write agent catalog: {:?}", path))?;
+    }
+    AgentCatalog::load(&path)
+        .map_err(|e| anyhow::anyhow!("Invalid agent catalog {:?}: {}", path, e))
+}
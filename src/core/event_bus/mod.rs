@@ -6,8 +6,13 @@
 pub use agentx_event_bus::batching::{
     BatchedEventCollector, BatchedEvents, Debouncer, DebouncerContainer,
 };
+pub use agentx_event_bus::channel::{EventReceiver, OverflowPolicy, SubscriberStats};
 pub use agentx_event_bus::core::{EventBus, EventBusContainer, EventBusStats, SubscriptionId};
-pub use agentx_event_bus::hub::{AppEvent, EventHub};
+pub use agentx_event_bus::hub::{AppEvent, EventHub, EventKind};
+pub use agentx_event_bus::journal::{
+    DEFAULT_JOURNAL_CAPACITY, EventJournal, JournalEntry, JournalFilter, ReplayState,
+    SessionReplay, Speaker, load_journal, save_journal,
+};
 pub use agentx_event_bus::{
     AgentConfigEvent, CodeSelectionEvent, PermissionRequestEvent, SessionUpdateEvent,
     WorkspaceUpdateEvent,
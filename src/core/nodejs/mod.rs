@@ -1,2 +1,10 @@
 // Re-export from agentx-agent crate
 pub use agentx_agent::nodejs::*;
+
+use crate::core::config::NodeRuntimeConfig;
+use crate::core::config_manager;
+
+/// The managed Node.js runtime of a config, kept in the user data directory
+pub fn managed_runtime(config: &NodeRuntimeConfig) -> NodeRuntime {
+    NodeRuntime::new(config_manager::get_node_runtimes_dir(), config.clone())
+}
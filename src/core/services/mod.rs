@@ -5,10 +5,28 @@
 pub use agentx_services::AgentConfigService;
 pub use agentx_services::AgentService;
 pub use agentx_services::AgentSessionInfo;
+pub use agentx_services::AiCancelHandle;
 pub use agentx_services::AiService;
+pub use agentx_services::AiStream;
+pub use agentx_services::ChangeTrackerService;
 pub use agentx_services::CommentStyle;
+pub use agentx_services::ComparisonService;
+pub use agentx_services::ConfigDiff;
 pub use agentx_services::ConfigWatcher;
+pub use agentx_services::FileIndex;
+pub use agentx_services::FileIndexService;
+pub use agentx_services::FileMatch;
+pub use agentx_services::FileWatcher;
+pub use agentx_services::IndexEntry;
+pub use agentx_services::LspDocument;
+pub use agentx_services::LspService;
 pub use agentx_services::MessageService;
 pub use agentx_services::PersistenceService;
+pub use agentx_services::PipelineService;
+pub use agentx_services::SecretStore;
 pub use agentx_services::SessionStatus;
+pub use agentx_services::TaskQueueService;
+pub use agentx_services::TaskTemplateService;
 pub use agentx_services::WorkspaceService;
+pub use agentx_services::fuzzy_match;
+pub use agentx_services::{ComparisonRequest, ScopedTemplate, TemplateInput};
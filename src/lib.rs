@@ -18,13 +18,14 @@ pub use assets::get_default_config;
 // Re-export from panels module
 use crate::panels::{DockPanelContainer, DockPanelState};
 pub use panels::{
-    AppSettings, CodeEditorPanel, ConversationPanel, SessionManagerPanel, SettingsPanel, TaskPanel,
-    TerminalPanel, ToolCallDetailPanel, WelcomePanel,
+    AppSettings, CodeEditorPanel, ConversationPanel, EventJournalPanel, SearchPanel,
+    SessionManagerPanel, SettingsPanel, TaskPanel, TerminalPanel, ToolCallDetailPanel,
+    WelcomePanel,
 };
 
 // Re-export from core module
 pub use core::{
-    agent::{AgentHandle, AgentManager, PermissionStore},
+    agent::{AgentHandle, AgentManager, AgentNodejs, PermissionStore},
     config::{AgentProcessConfig, Config},
     event_bus::{
         AgentConfigEvent, AppEvent, CodeSelectionEvent, EventHub, PermissionRequestEvent,
@@ -36,12 +37,14 @@ pub use core::{
 pub use app::app_state::{AppState, WelcomeSession};
 pub use app::{
     actions::{
-        About, AddAgent, AddSessionToList, CancelSession, CloseWindow, CreateTaskFromWelcome, Info,
-        NewSessionConversationPanel, Open, OpenSessionManager, PanelAction, Quit,
-        ReloadAgentConfig, RemoveAgent, RestartAgent, SelectFont, SelectLocale, SelectRadius,
-        SelectScrollbarShow, SelectedAgentTask, SendMessageToSession, SetUploadDir, ShowPanelInfo,
-        Tab, TabPrev, TestAction, ToggleDockToggleButton, TogglePanelVisible, ToggleSearch,
-        UpdateAgent,
+        About, AddAgent, AddSessionToList, CancelSession, CloseWindow, CompareAgents,
+        CreateTaskFromWelcome, EditTaskTemplate, GoToSymbol, Info, NewSessionConversationPanel,
+        NewTaskFromTemplate, Open, OpenComparison, OpenEventJournal, OpenFileInEditor,
+        OpenSessionManager, PanelAction, PickComparisonWinner, QueueTasks, QuickOpen, Quit,
+        ReloadAgentConfig, RemoveAgent, RestartAgent, RunPipeline, SaveFile, SaveFileAs,
+        SearchInWorkspace, SelectFont, SelectLocale, SelectRadius, SelectScrollbarShow,
+        SelectedAgentTask, SendMessageToSession, SetUploadDir, ShowPanelInfo, Tab, TabPrev,
+        TestAction, ToggleDockToggleButton, TogglePanelVisible, ToggleSearch, UpdateAgent,
     },
     app_menus, menu, system_tray, themes, title_bar,
 };
@@ -1,8 +1,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use agentx::Assets;
+use agentx::core::config::{parse_config, write_config_schema};
 use agentx::core::config_manager;
-use agentx::{AgentManager, Config, PermissionStore, workspace::open_new};
+use agentx::{AgentManager, AgentNodejs, Config, PermissionStore, workspace::open_new};
 use anyhow::Context as _;
 use gpui::Application;
 use std::sync::Arc;
@@ -10,6 +11,10 @@ use std::sync::Arc;
 fn main() {
     // Parse config path from command line arguments
     let config_path = parse_config_path();
+    let journal_path = parse_journal_path();
+    // The schema is kept next to the user config; a `--config` file elsewhere,
+    // possibly inside a project, only gets one when asked for
+    let write_schema = !has_config_flag() || has_flag("--write-schema");
 
     let app = gpui_platform::application().with_assets(Assets);
     app.run(move |cx| {
@@ -31,6 +36,15 @@ fn main() {
             }
         }
 
+        // Record every event to a file when started with --journal
+        if let Some(path) = &journal_path {
+            let journal = agentx::AppState::global(cx).event_journal();
+            match journal.record_to_file(path) {
+                Ok(()) => journal.set_recording(true),
+                Err(e) => eprintln!("Failed to record events to {}: {}", path.display(), e),
+            }
+        }
+
         // Get EventHub from global AppState
         let event_hub = agentx::AppState::global(cx).event_hub().clone();
 
@@ -42,15 +56,17 @@ fn main() {
 
         // Initialize agents in the background (async, non-blocking)
         cx.spawn(async move |cx| {
+            // A config that fails validation is reported to the UI once the
+            // config service exists; the defaults are used until it's fixed
+            let mut config_error = None;
             let config: Config = match std::fs::read_to_string(&config_path)
                 .with_context(|| format!("failed to read {}", config_path.display()))
             {
-                Ok(raw) => match serde_json::from_str(&raw)
-                    .with_context(|| format!("invalid config at {}", config_path.display()))
-                {
+                Ok(raw) => match parse_config(&raw) {
                     Ok(config) => config,
                     Err(e) => {
-                        eprintln!("Failed to parse config: {}", e);
+                        eprintln!("Invalid config at {}: {}", config_path.display(), e);
+                        config_error = Some(e);
                         match load_default_config() {
                             Ok(config) => config,
                             Err(e) => {
@@ -72,21 +88,32 @@ fn main() {
                 }
             };
 
+            // Keep the schema next to the config for editor completion
+            if write_schema && let Err(e) = write_config_schema(&config_path) {
+                log::warn!("Failed to write config schema: {}", e);
+            }
+
             println!("Config loaded from {}", config_path.display());
 
-            // Inject nodejs_path from AppSettings into agent configs
-            let nodejs_path = cx.update(|cx| {
-                agentx::AppSettings::global(cx).nodejs_path.clone()
+            // Set config path before agents start: it opens the secret vault
+            // that `secret:` references in agent env resolve from
+            cx.update(|cx| {
+                agentx::AppState::global_mut(cx).set_config_path(config_path.clone());
             });
 
-            let mut agent_servers = config.agent_servers.clone();
-            if !nodejs_path.is_empty() {
-                log::info!("Using custom Node.js path from settings: {}", nodejs_path);
-                // Inject nodejs_path into all agent configs
-                for (_name, agent_config) in agent_servers.iter_mut() {
-                    agent_config.nodejs_path = Some(nodejs_path.to_string());
-                }
-            }
+            // Node.js for agents (managed runtime or AppSettings); the manager
+            // looks it up for every agent it starts, and installs the managed
+            // runtime in the background if needed
+            let settings_nodejs_path = cx.update(|cx| {
+                agentx::AppSettings::global(cx).nodejs_path.to_string()
+            });
+            let nodejs = AgentNodejs {
+                runtimes_dir: config_manager::get_node_runtimes_dir(),
+                runtime: config.node_runtime.clone(),
+                settings_path: Some(settings_nodejs_path).filter(|path| !path.is_empty()),
+            };
+
+            let agent_servers = config.agent_servers.clone();
             let agent_server_count = agent_servers.len();
 
             // Initialize agent manager (this happens in background after GUI is shown)
@@ -97,6 +124,7 @@ fn main() {
                 permission_store.clone(),
                 event_hub.clone(),
                 config.proxy.clone(),
+                nodejs,
             )
             .await
             {
@@ -107,17 +135,30 @@ fn main() {
                     );
 
                     // Store in global AppState
-                    let init_result = cx.update(|cx| {
-                        // Set config path first
-                        agentx::AppState::global_mut(cx).set_config_path(config_path.clone());
-                        // Then set agent manager with config
+                    let (init_result, agent_config_service) = cx.update(|cx| {
                         agentx::AppState::global_mut(cx).set_agent_manager(manager, config);
                         agentx::AppState::global_mut(cx).set_permission_store(permission_store);
 
                         // Get message service for persistence initialization
-                        agentx::AppState::global(cx).message_service().cloned()
+                        (
+                            agentx::AppState::global(cx).message_service().cloned(),
+                            agentx::AppState::global(cx).agent_config_service().cloned(),
+                        )
                     });
 
+                    if let Some(service) = agent_config_service {
+                        // Reported first: an invalid file must not be overwritten
+                        if let Some(error) = config_error {
+                            service.report_config_error(error).await;
+                        }
+
+                        // Move plaintext secrets left in config.json into the
+                        // vault; a no-op once they are, announced when it moves any
+                        if let Err(e) = service.externalize_secrets().await {
+                            log::error!("Failed to move secrets into the vault: {}", e);
+                        }
+                    }
+
                     // Initialize persistence subscription in async context
                     if let Some(message_service) = init_result {
                         message_service.init_persistence();
@@ -171,6 +212,33 @@ fn parse_config_path() -> std::path::PathBuf {
     }
 }
 
+/// Whether a config file was given with `--config <path>`
+fn has_config_flag() -> bool {
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "--config" && args.next().is_some() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Whether the bare `flag` was passed on the command line
+fn has_flag(flag: &str) -> bool {
+    std::env::args().skip(1).any(|arg| arg == flag)
+}
+
+/// Event journal file given with `--journal <path>`
+fn parse_journal_path() -> Option<std::path::PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "--journal" {
+            return args.next().map(std::path::PathBuf::from);
+        }
+    }
+    None
+}
+
 fn load_default_config() -> anyhow::Result<Config> {
     let raw = agentx::get_default_config()
         .ok_or_else(|| anyhow::anyhow!("embedded default config missing"))?;
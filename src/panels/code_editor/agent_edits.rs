@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+use similar::{DiffOp, TextDiff};
+
+/// A pending agent change: `base` lines `old` became `current` lines `new`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditHunk {
+    /// 0-based line range in the base text
+    pub old: Range<usize>,
+    /// 0-based line range in the current text
+    pub new: Range<usize>,
+    /// Removed lines, with their line endings
+    pub old_lines: Vec<String>,
+    /// Added lines, with their line endings
+    pub new_lines: Vec<String>,
+}
+
+/// Changes of `current` relative to `base`, adjacent changes joined
+pub fn edit_hunks(base: &str, current: &str) -> Vec<EditHunk> {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let current_lines: Vec<&str> = current.split_inclusive('\n').collect();
+    let diff = TextDiff::from_lines(base, current);
+
+    let mut hunks: Vec<EditHunk> = Vec::new();
+    for op in diff.ops() {
+        if let DiffOp::Equal { .. } = op {
+            continue;
+        }
+        let (old, new) = (op.old_range(), op.new_range());
+        let old_lines = base_lines[old.clone()].iter().map(|l| l.to_string());
+        let new_lines = current_lines[new.clone()].iter().map(|l| l.to_string());
+        match hunks.last_mut() {
+            Some(last) if last.old.end == old.start && last.new.end == new.start => {
+                last.old.end = old.end;
+                last.new.end = new.end;
+                last.old_lines.extend(old_lines);
+                last.new_lines.extend(new_lines);
+            }
+            _ => hunks.push(EditHunk {
+                old,
+                new,
+                old_lines: old_lines.collect(),
+                new_lines: new_lines.collect(),
+            }),
+        }
+    }
+    hunks
+}
+
+/// The base text with one hunk taken in, so the hunk is no longer pending
+pub fn accept_hunk(base: &str, hunk: &EditHunk) -> String {
+    splice_lines(base, hunk.old.clone(), &hunk.new_lines)
+}
+
+/// The current text with one hunk reverted to the base content
+pub fn reject_hunk(current: &str, hunk: &EditHunk) -> String {
+    splice_lines(current, hunk.new.clone(), &hunk.old_lines)
+}
+
+/// Replace lines `range` of `text`
+fn splice_lines(text: &str, range: Range<usize>, lines: &[String]) -> String {
+    let text_lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut output = String::with_capacity(text.len());
+    text_lines[..range.start]
+        .iter()
+        .for_each(|l| output.push_str(l));
+    lines.iter().for_each(|l| output.push_str(l));
+    text_lines[range.end..]
+        .iter()
+        .for_each(|l| output.push_str(l));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_hunks() {
+        let hunks = edit_hunks("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n");
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].old, 1..2);
+        assert_eq!(hunks[0].new, 1..2);
+        assert_eq!(hunks[0].old_lines, vec!["b\n"]);
+        assert_eq!(hunks[0].new_lines, vec!["B\n"]);
+        assert_eq!(hunks[1].old, 4..4);
+        assert_eq!(hunks[1].new_lines, vec!["e\n"]);
+    }
+
+    #[test]
+    fn test_accept_and_reject_hunks() {
+        let base = "a\nb\nc\nd\n";
+        let current = "a\nB\nc\nd\ne\n";
+        let hunks = edit_hunks(base, current);
+
+        // Accepting the first hunk leaves only the second pending
+        let base = accept_hunk(base, &hunks[0]);
+        assert_eq!(base, "a\nB\nc\nd\n");
+        let pending = edit_hunks(&base, current);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].new_lines, vec!["e\n"]);
+
+        // Rejecting it restores the base content
+        let current = reject_hunk(current, &pending[0]);
+        assert_eq!(current, base);
+        assert!(edit_hunks(&base, &current).is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Sizable, button::Button, h_flex, scroll::ScrollableElement as _, text::TextView,
+    v_flex,
+};
+use rust_i18n::t;
+
+use crate::core::services::AiCancelHandle;
+
+#[derive(Clone, PartialEq)]
+enum Status {
+    Streaming,
+    Done,
+    Cancelled,
+    Failed(String),
+}
+
+/// Text of an AI action shown while it is generated, with a cancel button
+pub(super) struct AiResponseView {
+    text: String,
+    status: Status,
+    cancel: AiCancelHandle,
+}
+
+impl AiResponseView {
+    pub fn new(cancel: AiCancelHandle) -> Self {
+        Self {
+            text: String::new(),
+            status: Status::Streaming,
+            cancel,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status == Status::Cancelled
+    }
+
+    pub fn push(&mut self, delta: &str, cx: &mut Context<Self>) {
+        self.text.push_str(delta);
+        cx.notify();
+    }
+
+    /// Mark the response complete, or failed with `error`
+    pub fn finish(&mut self, error: Option<String>, cx: &mut Context<Self>) {
+        if self.status == Status::Streaming {
+            self.status = error.map_or(Status::Done, Status::Failed);
+            cx.notify();
+        }
+    }
+
+    fn cancel(&mut self, cx: &mut Context<Self>) {
+        self.cancel.cancel();
+        self.status = Status::Cancelled;
+        cx.notify();
+    }
+}
+
+impl Render for AiResponseView {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let status = match &self.status {
+            Status::Streaming => Some(t!("code_editor.ai.generating").to_string()),
+            Status::Done => None,
+            Status::Cancelled => Some(t!("code_editor.ai.cancelled").to_string()),
+            Status::Failed(error) => Some(t!("code_editor.ai.failed", error = error).to_string()),
+        };
+        let failed = matches!(self.status, Status::Failed(_));
+
+        v_flex()
+            .w_full()
+            .gap_2()
+            .child(
+                div()
+                    .id("ai-response")
+                    .w_full()
+                    .max_h(px(420.))
+                    .overflow_y_scrollbar()
+                    .text_sm()
+                    .child(
+                        TextView::markdown("ai-response-text", self.text.clone()).selectable(true),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(if failed {
+                                cx.theme().danger
+                            } else {
+                                cx.theme().muted_foreground
+                            })
+                            .children(status),
+                    )
+                    .when(self.status == Status::Streaming, |this| {
+                        this.child(
+                            Button::new("ai-response-cancel")
+                                .label(t!("code_editor.ai.cancel").to_string())
+                                .small()
+                                .on_click(cx.listener(|this, _, _, cx| this.cancel(cx))),
+                        )
+                    }),
+            )
+    }
+}
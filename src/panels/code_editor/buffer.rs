@@ -0,0 +1,319 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use gpui::*;
+use gpui_component::{
+    highlighter::{Diagnostic, DiagnosticSeverity, Language},
+    input::{InputState, Position, RopeExt},
+};
+use lsp_types::{CodeActionKind, TextEdit, WorkspaceEdit};
+
+use super::agent_edits::{EditHunk, edit_hunks};
+use super::lsp_store::CodeEditorPanelLspStore;
+use super::merge::merge_texts;
+use super::panel::CodeEditorPanel;
+use super::symbols::{Symbol, SymbolLanguage, extract_symbols};
+use crate::AppState;
+
+/// A file open in a tab of the code editor
+///
+/// Every buffer has its own editor state, so cursor, scroll position and undo
+/// history are kept when switching tabs.
+pub(super) struct EditorBuffer {
+    pub editor: Entity<InputState>,
+    pub lsp_store: CodeEditorPanelLspStore,
+    pub path: PathBuf,
+    pub language: Language,
+    /// Content of the file as last loaded from or saved to disk
+    pub saved_text: String,
+    /// Whether the buffer differs from `saved_text`
+    pub dirty: bool,
+    /// Disk content the user was last asked about (avoids asking twice)
+    pub external_text: Option<String>,
+    /// Content of saves still being written (their change events are our own)
+    pub pending_writes: Vec<String>,
+    /// Outline of the file, refreshed shortly after each edit
+    pub symbols: Vec<Symbol>,
+    /// Content before the agent edits still waiting for review
+    pub agent_base: Option<String>,
+    /// Content the agent wrote, to tell its edits from the user's
+    pub agent_text: Option<String>,
+    /// Pending agent edits (`agent_base` against the buffer)
+    pub agent_hunks: Vec<EditHunk>,
+    pub _subscription: Subscription,
+    pub _lint_task: Task<()>,
+    pub _symbols_task: Task<()>,
+    /// Opens the document on its language server and applies its diagnostics
+    pub _lsp_task: Task<()>,
+}
+
+impl EditorBuffer {
+    pub fn language_for_path(path: &Path) -> Language {
+        let language = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        Language::from_str(&language)
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Tab title: the file name, prefixed with a dot when it has unsaved changes
+    pub fn title(&self) -> SharedString {
+        if self.dirty {
+            format!("● {}", self.file_name()).into()
+        } else {
+            self.file_name().into()
+        }
+    }
+
+    pub fn text(&self, cx: &App) -> String {
+        self.editor.read(cx).text().to_string()
+    }
+
+    /// Recompute the dirty flag, returning whether it changed
+    pub fn update_dirty(&mut self, cx: &App) -> bool {
+        let dirty = self.text(cx) != self.saved_text;
+        let changed = dirty != self.dirty;
+        self.dirty = dirty;
+        changed
+    }
+
+    /// Replace the content, keeping the cursor where it was
+    pub fn set_text(&self, text: String, window: &mut Window, cx: &mut App) {
+        self.editor.update(cx, |state, cx| {
+            let cursor = state.cursor_position();
+            state.set_value(text, window, cx);
+            state.set_cursor_position(cursor, window, cx);
+        });
+    }
+
+    /// Point the buffer at a (new) path, updating the highlighter
+    pub fn set_path(&mut self, path: PathBuf, cx: &mut App) {
+        let language = Self::language_for_path(&path);
+        self.editor.update(cx, |state, cx| {
+            state.set_highlighter(language.name(), cx);
+        });
+        self.language = language;
+        self.path = path;
+    }
+
+    pub fn lint(&mut self, cx: &mut Context<CodeEditorPanel>) {
+        let language = self.language.name().to_string();
+        let lsp_store = self.lsp_store.clone();
+        let text = self.editor.read(cx).text().clone();
+
+        self._lint_task = cx.background_spawn(async move {
+            let value = text.to_string();
+            let result = autocorrect::lint_for(value.as_str(), &language);
+
+            let mut code_actions = vec![];
+            let mut diagnostics = vec![];
+
+            for item in result.lines.iter() {
+                let severity = match item.severity {
+                    autocorrect::Severity::Error => DiagnosticSeverity::Warning,
+                    autocorrect::Severity::Warning => DiagnosticSeverity::Hint,
+                    autocorrect::Severity::Pass => DiagnosticSeverity::Info,
+                };
+
+                let line = item.line.saturating_sub(1); // Convert to 0-based index
+                let col = item.col.saturating_sub(1); // Convert to 0-based index
+
+                let start = Position::new(line as u32, col as u32);
+                let end = Position::new(line as u32, (col + item.old.chars().count()) as u32);
+                let message = format!("AutoCorrect: {}", item.new);
+                diagnostics.push(Diagnostic::new(start..end, message).with_severity(severity));
+
+                let range = text.position_to_offset(&start)..text.position_to_offset(&end);
+
+                let text_edit = TextEdit {
+                    range: lsp_types::Range { start, end },
+                    new_text: item.new.clone(),
+                    ..Default::default()
+                };
+
+                let edit = WorkspaceEdit {
+                    changes: Some(
+                        std::iter::once((
+                            lsp_types::Uri::from_str("file://CodeEditorPanel").unwrap(),
+                            vec![text_edit],
+                        ))
+                        .collect(),
+                    ),
+                    ..Default::default()
+                };
+
+                code_actions.push((
+                    range,
+                    lsp_types::CodeAction {
+                        title: format!("Change to '{}'", item.new),
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        edit: Some(edit),
+                        ..Default::default()
+                    },
+                ));
+            }
+
+            lsp_store.update_code_actions(code_actions.clone());
+            lsp_store.update_diagnostics(diagnostics.clone());
+        });
+    }
+
+    /// Content to write while agent edits are under review
+    ///
+    /// Holds the accepted agent edits and the user's own changes, but not the
+    /// pending agent edits. User changes that overlap a pending edit stay in
+    /// the buffer only.
+    pub fn reviewed_text(&self, cx: &App) -> String {
+        let text = self.text(cx);
+        let (Some(base), Some(agent_text)) = (&self.agent_base, &self.agent_text) else {
+            return text;
+        };
+        let result = merge_texts(agent_text, &text, base);
+        if result.conflicts > 0 {
+            base.clone()
+        } else {
+            result.text
+        }
+    }
+
+    /// Recompute the pending agent edits and their line markers
+    ///
+    /// Any change to the buffer (typing, reload, merge) is reconciled here: the
+    /// hunks are always `agent_base` against the current content, and the
+    /// review ends once nothing differs.
+    pub fn update_agent_hunks(&mut self, cx: &App) {
+        let hunks = match &self.agent_base {
+            Some(base) => edit_hunks(base, &self.text(cx)),
+            None => Vec::new(),
+        };
+        if hunks.is_empty() {
+            self.agent_base = None;
+            self.agent_text = None;
+        }
+
+        let diagnostics = hunks
+            .iter()
+            .filter(|hunk| !hunk.new.is_empty())
+            .map(|hunk| {
+                let last_line = hunk
+                    .new_lines
+                    .last()
+                    .map_or("", |l| l.trim_end_matches(['\n', '\r']));
+                let start = Position::new(hunk.new.start as u32, 0);
+                let end = Position::new(hunk.new.end as u32 - 1, last_line.chars().count() as u32);
+                let message = format!(
+                    "Agent edit: +{} -{}",
+                    hunk.new_lines.len(),
+                    hunk.old_lines.len()
+                );
+                Diagnostic::new(start..end, message).with_severity(DiagnosticSeverity::Info)
+            })
+            .collect();
+        self.lsp_store.update_agent_diagnostics(diagnostics);
+        self.agent_hunks = hunks;
+    }
+
+    /// Re-extract the outline symbols in the background
+    pub fn update_symbols(&mut self, debounce: bool, cx: &mut Context<CodeEditorPanel>) {
+        let Some(language) = SymbolLanguage::from_path(&self.path) else {
+            self.symbols.clear();
+            self._symbols_task = Task::ready(());
+            return;
+        };
+
+        let text = self.text(cx);
+        let editor = self.editor.clone();
+        self._symbols_task = cx.spawn(async move |this, cx| {
+            if debounce {
+                smol::Timer::after(Duration::from_millis(200)).await;
+            }
+            let symbols = cx
+                .background_spawn(async move { extract_symbols(language, &text) })
+                .await;
+
+            _ = this.update(cx, |this, cx| {
+                if let Some(buffer) = this.buffer_mut(&editor) {
+                    buffer.symbols = symbols;
+                    cx.notify();
+                }
+            });
+        });
+    }
+
+    /// Open the file on the language server configured for its language
+    pub fn open_lsp_document(&mut self, root: PathBuf, cx: &mut Context<CodeEditorPanel>) {
+        // Close the previous document before the new one is opened
+        self.lsp_store.set_document(None);
+
+        let Some(lsp_service) = AppState::global(cx).lsp_service().cloned() else {
+            self._lsp_task = Task::ready(());
+            return;
+        };
+
+        let path = self.path.clone();
+        let text = self.text(cx);
+        let language = self.language.name().to_string();
+        let lsp_store = self.lsp_store.clone();
+        let editor = self.editor.clone();
+
+        self._lsp_task = cx.spawn(async move |this, cx| {
+            let document = match lsp_service
+                .open_document(&root, &language, &path, text)
+                .await
+            {
+                Ok(Some(document)) => Arc::new(document),
+                Ok(None) => return,
+                Err(e) => {
+                    log::warn!(
+                        "[CodeEditorPanel] Failed to open {} on a language server: {}",
+                        path.display(),
+                        e
+                    );
+                    return;
+                }
+            };
+
+            log::info!(
+                "[CodeEditorPanel] Opened {} on language server '{}'",
+                path.display(),
+                document.server_name()
+            );
+            lsp_store.set_document(Some(document.clone()));
+            // The file may have been edited while the server was starting
+            if let Ok(text) = this.update(cx, |_, cx| editor.read(cx).text().to_string())
+                && let Err(e) = document.change(text)
+            {
+                log::warn!("[CodeEditorPanel] Failed to sync document: {}", e);
+            }
+
+            while let Some(diagnostics) = document.next_diagnostics().await {
+                lsp_store.update_server_diagnostics(diagnostics);
+                if this.update(cx, |_, cx| cx.notify()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Send the editor content to the language server
+    pub fn sync_lsp_document(&self, cx: &App) {
+        let Some(document) = self.lsp_store.document() else {
+            return;
+        };
+
+        if let Err(e) = document.change(self.text(cx)) {
+            log::warn!("[CodeEditorPanel] Failed to sync document: {}", e);
+        }
+    }
+}
@@ -13,11 +13,14 @@ use gpui_component::{
 use lsp_types::{
     CodeAction, CodeActionKind, CompletionContext, CompletionResponse, TextEdit, WorkspaceEdit,
 };
+use rust_i18n::t;
 
-use crate::AppState;
+use crate::{AppState, core::services::AiStream};
 
+use super::ai_response::AiResponseView;
 use super::lsp_store::CodeEditorPanelLspStore;
-use super::types::{RUST_DOC_URLS, completion_item};
+use super::symbols::{SymbolLanguage, starts_with_definition};
+use super::types::completion_item;
 
 // ============================================================================
 // CompletionProvider Implementation
@@ -33,6 +36,18 @@ impl CompletionProvider for CodeEditorPanelLspStore {
         cx: &mut Context<InputState>,
     ) -> Task<Result<CompletionResponse>> {
         let trigger_character = trigger.trigger_character.unwrap_or_default();
+
+        // Slash commands stay local, everything else goes to the language server
+        if let Some(document) = self.document()
+            && !trigger_character.starts_with("/")
+        {
+            let position = rope.offset_to_position(offset);
+            let trigger_character = (!trigger_character.is_empty()).then_some(trigger_character);
+            return AppContext::background_spawn(cx, async move {
+                document.completion(position, trigger_character).await
+            });
+        }
+
         if trigger_character.is_empty() {
             return Task::ready(Ok(CompletionResponse::Array(vec![])));
         }
@@ -85,10 +100,16 @@ impl CompletionProvider for CodeEditorPanelLspStore {
     fn is_completion_trigger(
         &self,
         _offset: usize,
-        _new_text: &str,
+        new_text: &str,
         _cx: &mut Context<InputState>,
     ) -> bool {
-        true
+        let Some(document) = self.document() else {
+            return true;
+        };
+
+        new_text.starts_with("/")
+            || document.is_trigger_character(new_text)
+            || (!new_text.is_empty() && new_text.chars().all(|c| c.is_alphanumeric() || c == '_'))
     }
 }
 
@@ -103,10 +124,10 @@ impl CodeActionProvider for CodeEditorPanelLspStore {
 
     fn code_actions(
         &self,
-        _state: Entity<InputState>,
+        state: Entity<InputState>,
         range: Range<usize>,
         _window: &mut Window,
-        _cx: &mut App,
+        cx: &mut App,
     ) -> Task<Result<Vec<CodeAction>>> {
         let mut actions = vec![];
         for (node_range, code_action) in self.code_actions().iter() {
@@ -117,7 +138,29 @@ impl CodeActionProvider for CodeEditorPanelLspStore {
             actions.push(code_action.clone());
         }
 
-        Task::ready(Ok(actions))
+        let Some(document) = self.document() else {
+            return Task::ready(Ok(actions));
+        };
+
+        let text = state.read(cx).text();
+        let range = lsp_types::Range {
+            start: text.offset_to_position(range.start),
+            end: text.offset_to_position(range.end),
+        };
+        // Send the server diagnostics touching the range as context
+        let diagnostics = self
+            .server_diagnostics()
+            .into_iter()
+            .filter(|d| d.range.start <= range.end && range.start <= d.range.end)
+            .collect();
+
+        cx.background_spawn(async move {
+            match document.code_actions(range, diagnostics).await {
+                Ok(server_actions) => actions.extend(server_actions),
+                Err(e) => log::warn!("Failed to get code actions: {}", e),
+            }
+            Ok(actions)
+        })
     }
 
     fn perform_code_action(
@@ -128,22 +171,24 @@ impl CodeActionProvider for CodeEditorPanelLspStore {
         window: &mut Window,
         cx: &mut App,
     ) -> Task<Result<()>> {
-        let Some(edit) = action.edit else {
-            return Task::ready(Ok(()));
-        };
+        let document = self.document();
+        let state = state.downgrade();
 
-        let changes = if let Some(changes) = edit.changes {
-            changes
-        } else {
-            return Task::ready(Ok(()));
-        };
+        window.spawn(cx, async move |cx| {
+            // Servers may leave the edit to be resolved when the action is picked
+            let action = match &document {
+                Some(document) => document.resolve_code_action(action).await?,
+                None => action,
+            };
+            let Some(edit) = action.edit else {
+                return Ok(());
+            };
 
-        let Some((_, text_edits)) = changes.into_iter().next() else {
-            return Task::ready(Ok(()));
-        };
+            let text_edits = document_text_edits(edit, document.as_ref().map(|d| d.uri()));
+            if text_edits.is_empty() {
+                return Ok(());
+            }
 
-        let state = state.downgrade();
-        window.spawn(cx, async move |cx| {
             state.update_in(cx, |state, window, cx| {
                 state.apply_lsp_edits(&text_edits, window, cx);
             })
@@ -151,6 +196,36 @@ impl CodeActionProvider for CodeEditorPanelLspStore {
     }
 }
 
+/// Pick the edits of a workspace edit that apply to the open document
+///
+/// Local fixes (e.g. lint) address the document as `file://CodeEditorPanel`.
+fn document_text_edits(edit: WorkspaceEdit, uri: Option<&lsp_types::Uri>) -> Vec<TextEdit> {
+    let mut documents: Vec<(lsp_types::Uri, Vec<TextEdit>)> = Vec::new();
+
+    if let Some(changes) = edit.changes {
+        documents.extend(changes);
+    }
+    if let Some(lsp_types::DocumentChanges::Edits(edits)) = edit.document_changes {
+        documents.extend(edits.into_iter().map(|edit| {
+            let edits = edit
+                .edits
+                .into_iter()
+                .map(|edit| match edit {
+                    lsp_types::OneOf::Left(edit) => edit,
+                    lsp_types::OneOf::Right(annotated) => annotated.text_edit,
+                })
+                .collect();
+            (edit.text_document.uri, edits)
+        }));
+    }
+
+    documents
+        .into_iter()
+        .find(|(u, _)| Some(u) == uri || u.as_str() == "file://CodeEditorPanel")
+        .map(|(_, edits)| edits)
+        .unwrap_or_default()
+}
+
 // ============================================================================
 // HoverProvider Implementation
 // ============================================================================
@@ -161,32 +236,14 @@ impl HoverProvider for CodeEditorPanelLspStore {
         text: &Rope,
         offset: usize,
         _window: &mut Window,
-        _cx: &mut App,
+        cx: &mut App,
     ) -> Task<Result<Option<lsp_types::Hover>>> {
-        let word = text.word_at(offset);
-        if word.is_empty() {
-            return Task::ready(Ok(None));
+        if let Some(document) = self.document() {
+            let position = text.offset_to_position(offset);
+            return cx.background_spawn(async move { document.hover(position).await });
         }
 
-        let Some(item) = self.completions.iter().find(|item| item.label == word) else {
-            return Task::ready(Ok(None));
-        };
-
-        let contents = if let Some(doc) = &item.documentation {
-            match doc {
-                lsp_types::Documentation::String(s) => s.clone(),
-                lsp_types::Documentation::MarkupContent(mc) => mc.value.clone(),
-            }
-        } else {
-            "No documentation available.".to_string()
-        };
-
-        let hover = lsp_types::Hover {
-            contents: lsp_types::HoverContents::Scalar(lsp_types::MarkedString::String(contents)),
-            range: None,
-        };
-
-        Task::ready(Ok(Some(hover)))
+        Task::ready(Ok(None))
     }
 }
 
@@ -200,60 +257,30 @@ impl DefinitionProvider for CodeEditorPanelLspStore {
         text: &Rope,
         offset: usize,
         _window: &mut Window,
-        _cx: &mut App,
+        cx: &mut App,
     ) -> Task<Result<Vec<lsp_types::LocationLink>>> {
+        let Some(document) = self.document() else {
+            return Task::ready(Ok(vec![]));
+        };
         let Some(word_range) = text.word_range(offset) else {
             return Task::ready(Ok(vec![]));
         };
-        let word = text.slice(word_range.clone()).to_string();
 
-        let document_uri = lsp_types::Uri::from_str("file://CodeEditorPanel").unwrap();
         let start = text.offset_to_position(word_range.start);
         let end = text.offset_to_position(word_range.end);
         let symbol_range = lsp_types::Range { start, end };
-
-        if word == "Duration" {
-            let target_range = lsp_types::Range {
-                start: lsp_types::Position {
-                    line: 2,
-                    character: 4,
-                },
-                end: lsp_types::Position {
-                    line: 2,
-                    character: 23,
-                },
-            };
-            return Task::ready(Ok(vec![lsp_types::LocationLink {
-                target_uri: document_uri,
-                target_range: target_range,
-                target_selection_range: target_range,
-                origin_selection_range: Some(symbol_range),
-            }]));
-        }
-
-        let names = RUST_DOC_URLS
-            .iter()
-            .map(|(name, _)| *name)
-            .collect::<Vec<_>>();
-        for (ix, t) in names.iter().enumerate() {
-            if *t == word {
-                let url = RUST_DOC_URLS[ix].1;
-                let location = lsp_types::LocationLink {
-                    target_uri: lsp_types::Uri::from_str(&format!(
-                        "https://doc.rust-lang.org/std/{}.html",
-                        url
-                    ))
-                    .unwrap(),
-                    target_selection_range: lsp_types::Range::default(),
-                    target_range: lsp_types::Range::default(),
-                    origin_selection_range: Some(symbol_range),
-                };
-
-                return Task::ready(Ok(vec![location]));
-            }
-        }
-
-        Task::ready(Ok(vec![]))
+        let position = text.offset_to_position(offset);
+
+        cx.background_spawn(async move {
+            let links = document.definition(position).await?;
+            Ok(links
+                .into_iter()
+                .map(|link| lsp_types::LocationLink {
+                    origin_selection_range: link.origin_selection_range.or(Some(symbol_range)),
+                    ..link
+                })
+                .collect())
+        })
     }
 }
 
@@ -298,7 +325,16 @@ impl DocumentColorProvider for CodeEditorPanelLspStore {
 // TextConvertor - Additional CodeActionProvider
 // ============================================================================
 
-pub struct TextConvertor;
+pub struct TextConvertor {
+    /// Language of the edited file, used to pick the comment syntax
+    language: Option<SymbolLanguage>,
+}
+
+impl TextConvertor {
+    pub fn new(language: Option<SymbolLanguage>) -> Self {
+        Self { language }
+    }
+}
 
 impl CodeActionProvider for TextConvertor {
     fn id(&self) -> SharedString {
@@ -584,6 +620,7 @@ impl TextConvertor {
 
         let ai_service = ai_service.clone();
         let state_weak = state.downgrade();
+        let language = self.language;
 
         match ai_action.as_str() {
             "doc_comment" | "inline_comment" => {
@@ -602,259 +639,143 @@ impl TextConvertor {
                     CommentStyle::Inline
                 };
 
-                // Show loading notification
-                struct AiCommentLoading;
-                let loading_note =
-                    Notification::info("Generating comment with AI...").id::<AiCommentLoading>();
-                window.push_notification(loading_note, cx);
-
-                window.spawn(cx, async move |cx| {
-                    // Call AI service
-                    let comment_result = ai_service.generate_comment(&code, style).await;
-
-                    match comment_result {
-                        Ok(comment) => {
-                            let formatted = format_comment_for_code(&code, &comment, style);
-
-                            state_weak.update_in(cx, |state, window, cx| {
-                                state.apply_lsp_edits(
-                                    &vec![TextEdit {
-                                        range,
-                                        new_text: formatted,
-                                        ..Default::default()
-                                    }],
-                                    window,
-                                    cx,
-                                );
-
-                                // Show success notification
-                                struct AiCommentSuccess;
-                                let success_note =
-                                    Notification::success("Comment generated successfully!")
-                                        .id::<AiCommentSuccess>();
-                                window.push_notification(success_note, cx);
-                            })?;
-
-                            Ok(())
-                        }
-                        Err(e) => {
-                            log::error!("Failed to generate comment: {}", e);
-
-                            // Show error notification
-                            struct AiCommentError;
-                            log::debug!("Attempting to show error notification...");
-
-                            let result = cx.update(|_, cx| {
-                                log::debug!("Inside cx.update");
-                                if let Some(window) = cx.active_window() {
-                                    log::debug!("Found active window");
-                                    window.update(cx, |_, window, cx| {
-                                        log::debug!("Inside window.update, pushing notification");
-                                        let error_note = Notification::error(format!(
-                                            "Failed to generate comment: {}",
-                                            e
-                                        ))
-                                        .id::<AiCommentError>();
-                                        window.push_notification(error_note, cx);
-                                        log::debug!("Notification pushed successfully");
-                                    })
-                                } else {
-                                    log::warn!("No active window found!");
-                                    Err(anyhow!("No active window"))
-                                }
-                            });
-
-                            if let Err(e) = result {
-                                log::error!("Failed to update context: {:?}", e);
-                            }
-
-                            Err(anyhow!("Failed to generate comment: {}", e))
-                        }
-                    }
-                })
-            }
-
-            "explain" => {
-                // Show loading notification
-                struct AiExplainLoading;
-                let loading_note =
-                    Notification::info("Analyzing code with AI...").id::<AiExplainLoading>();
-                window.push_notification(loading_note, cx);
-
-                window.spawn(cx, async move |cx| {
-                    // Call AI service
-                    let explanation_result = ai_service.explain_code(&code).await;
-
-                    match explanation_result {
-                        Ok(explanation) => {
-                            log::info!(
-                                "=== Code Explanation ===\n{}\n========================",
-                                explanation
+                let stream = ai_service.stream_comment(&code, style);
+                stream_ai_response(
+                    t!("code_editor.ai.comment_title").to_string(),
+                    stream,
+                    window,
+                    cx,
+                    move |comment, window, cx| {
+                        let formatted = format_comment_for_code(&code, &comment, style, language);
+                        _ = state_weak.update(cx, |state, cx| {
+                            state.apply_lsp_edits(
+                                &vec![TextEdit {
+                                    range,
+                                    new_text: formatted,
+                                    ..Default::default()
+                                }],
+                                window,
+                                cx,
                             );
-
-                            // Show explanation in notification
-                            cx.update(|_, cx| {
-                                if let Some(window) = cx.active_window() {
-                                    window.update(cx, |_, window, cx| {
-                                        struct AiExplainResult;
-                                        let success_note = Notification::success("Code explanation generated! Check logs for details.")
-                                            .id::<AiExplainResult>();
-                                        window.push_notification(success_note, cx);
-                                    }).ok();
-                                }
-                            }).ok();
-
-                            Ok(())
-                        }
-                        Err(e) => {
-                            log::error!("Failed to explain code: {}", e);
-
-                            // Show error notification
-                            struct AiExplainError;
-                            log::debug!("Attempting to show explain error notification...");
-
-                            let result = cx.update(|_, cx| {
-                                log::debug!("Inside cx.update for explain error");
-                                if let Some(window) = cx.active_window() {
-                                    log::debug!("Found active window for explain error");
-                                    window.update(cx, |_, window, cx| {
-                                        log::debug!("Inside window.update, pushing explain error notification");
-                                        let error_note = Notification::error(format!("Failed to explain code: {}", e))
-                                            .id::<AiExplainError>();
-                                        window.push_notification(error_note, cx);
-                                        log::debug!("Explain error notification pushed successfully");
-                                    })
-                                } else {
-                                    log::warn!("No active window found for explain error!");
-                                    Err(anyhow!("No active window"))
-                                }
-                            });
-
-                            if let Err(e) = result {
-                                log::error!("Failed to update context for explain error: {:?}", e);
-                            }
-
-                            Err(anyhow!("Failed to explain code: {}", e))
-                        }
-                    }
-                })
+                        });
+                        // The comment is in the editor, no need to keep it open
+                        window.close_dialog(cx);
+                    },
+                )
             }
 
-            "improve" => {
-                // Show loading notification
-                struct AiImproveLoading;
-                let loading_note = Notification::info("Analyzing code for improvements with AI...")
-                    .id::<AiImproveLoading>();
-                window.push_notification(loading_note, cx);
-
-                window.spawn(cx, async move |cx| {
-                    // Call AI service
-                    let suggestions_result = ai_service.suggest_improvements(&code).await;
-
-                    match suggestions_result {
-                        Ok(suggestions) => {
-                            log::info!(
-                                "=== Code Improvement Suggestions ===\n{}\n====================================",
-                                suggestions
-                            );
-
-                            // Show success in notification
-                            cx.update(|_, cx| {
-                                if let Some(window) = cx.active_window() {
-                                    window.update(cx, |_, window, cx| {
-                                        struct AiImproveResult;
-                                        let success_note = Notification::success("Code improvement suggestions generated! Check logs for details.")
-                                            .id::<AiImproveResult>();
-                                        window.push_notification(success_note, cx);
-                                    }).ok();
-                                }
-                            }).ok();
-
-                            Ok(())
-                        }
-                        Err(e) => {
-                            log::error!("Failed to generate suggestions: {}", e);
-
-                            // Show error notification
-                            struct AiImproveError;
-                            log::debug!("Attempting to show improve error notification...");
-
-                            let result = cx.update(|_, cx| {
-                                log::debug!("Inside cx.update for improve error");
-                                if let Some(window) = cx.active_window() {
-                                    log::debug!("Found active window for improve error");
-                                    window.update(cx, |_, window, cx| {
-                                        log::debug!("Inside window.update, pushing improve error notification");
-                                        let error_note = Notification::error(format!("Failed to generate suggestions: {}", e))
-                                            .id::<AiImproveError>();
-                                        window.push_notification(error_note, cx);
-                                        log::debug!("Improve error notification pushed successfully");
-                                    })
-                                } else {
-                                    log::warn!("No active window found for improve error!");
-                                    Err(anyhow!("No active window"))
-                                }
-                            });
-
-                            if let Err(e) = result {
-                                log::error!("Failed to update context for improve error: {:?}", e);
-                            }
-
-                            Err(anyhow!("Failed to generate suggestions: {}", e))
-                        }
-                    }
-                })
-            }
+            "explain" => stream_ai_response(
+                t!("code_editor.ai.explain_title").to_string(),
+                ai_service.stream_explanation(&code),
+                window,
+                cx,
+                |_, _, _| {},
+            ),
+
+            "improve" => stream_ai_response(
+                t!("code_editor.ai.improve_title").to_string(),
+                ai_service.stream_improvements(&code),
+                window,
+                cx,
+                |_, _, _| {},
+            ),
 
             _ => Task::ready(Err(anyhow!("Unknown AI action: {}", ai_action))),
         }
     }
 }
 
+/// Show a streaming AI response in a dialog, then hand the full text to `on_done`
+///
+/// Closing the dialog or pressing cancel stops the request.
+fn stream_ai_response(
+    title: String,
+    stream: anyhow::Result<AiStream>,
+    window: &mut Window,
+    cx: &mut App,
+    on_done: impl FnOnce(String, &mut Window, &mut App) + 'static,
+) -> Task<Result<()>> {
+    let mut stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => {
+            log::error!("Failed to start AI request: {}", e);
+            struct AiRequestError;
+            let note = Notification::error(e.to_string()).id::<AiRequestError>();
+            window.push_notification(note, cx);
+            return Task::ready(Err(e));
+        }
+    };
+
+    let view = cx.new(|_| AiResponseView::new(stream.cancel_handle()));
+    let response = view.downgrade();
+    window.open_dialog(cx, move |dialog, _, _| {
+        dialog.title(title.clone()).child(view.clone())
+    });
+
+    window.spawn(cx, async move |cx| {
+        while let Some(delta) = stream.next_delta().await {
+            let result = match delta {
+                Ok(delta) => response.update(cx, |view, cx| view.push(&delta, cx)),
+                Err(e) => {
+                    log::error!("AI request failed: {}", e);
+                    _ = response.update(cx, |view, cx| view.finish(Some(e.to_string()), cx));
+                    return Err(e);
+                }
+            };
+            // The dialog was closed: dropping the stream cancels the request
+            if result.is_err() {
+                return Ok(());
+            }
+        }
+
+        let text = response.update(cx, |view, cx| {
+            view.finish(None, cx);
+            (!view.is_cancelled()).then(|| view.text().trim().to_string())
+        })?;
+        if let Some(text) = text {
+            cx.update(|window, cx| on_done(text, window, cx))?;
+        }
+        Ok(())
+    })
+}
+
 /// Smart comment formatting based on code type and language
 fn format_comment_for_code(
     code: &str,
     comment: &str,
     style: crate::core::services::CommentStyle,
+    language: Option<SymbolLanguage>,
 ) -> String {
     use crate::core::services::CommentStyle;
 
-    let trimmed = code.trim();
-
-    // Detect if this is a function or class definition
-    let is_function = trimmed.starts_with("fn ")
-        || trimmed.starts_with("pub fn ")
-        || trimmed.starts_with("async fn ")
-        || trimmed.contains("class ")
-        || trimmed.contains("def ")
-        || (trimmed.contains('(') && trimmed.contains('{'));
-
-    match (is_function, style) {
-        (true, CommentStyle::FunctionDoc) => {
-            // Choose documentation comment format based on language
-            if code.contains("fn ") || code.contains("impl ") {
-                // Rust: /// style
-                format!("/// {}\n{}", comment.replace('\n', "\n/// "), code)
-            } else if code.starts_with("def ") {
-                // Python: """docstring"""
-                format!("\"\"\"\n{}\n\"\"\"\n{}", comment, code)
-            } else if code.contains("function ") || code.contains("=>") {
-                // JavaScript/TypeScript: /** JSDoc */
-                format!("/**\n * {}\n */\n{}", comment.replace('\n', "\n * "), code)
-            } else {
-                // Default C-style
-                format!("/*\n * {}\n */\n{}", comment.replace('\n', "\n * "), code)
-            }
+    // Detect if this is a function or type definition
+    let is_definition = language.is_some_and(|language| starts_with_definition(language, code));
+
+    match (is_definition, style, language) {
+        (true, CommentStyle::FunctionDoc, Some(SymbolLanguage::Rust)) => {
+            format!("/// {}\n{}", comment.replace('\n', "\n/// "), code)
         }
-        _ => {
-            // Inline comment
-            if code.contains("fn ") || code.contains("function ") {
-                format!("// {}\n{}", comment, code)
-            } else if code.starts_with("def ") || code.contains("import ") {
-                format!("# {}\n{}", comment, code)
-            } else {
-                format!("// {}\n{}", comment, code)
-            }
+        (true, CommentStyle::FunctionDoc, Some(SymbolLanguage::Python)) => {
+            format!("\"\"\"\n{}\n\"\"\"\n{}", comment, code)
+        }
+        (
+            true,
+            CommentStyle::FunctionDoc,
+            Some(SymbolLanguage::JavaScript | SymbolLanguage::TypeScript | SymbolLanguage::Tsx),
+        ) => {
+            // JSDoc
+            format!("/**\n * {}\n */\n{}", comment.replace('\n', "\n * "), code)
+        }
+        (true, CommentStyle::FunctionDoc, Some(SymbolLanguage::Go)) => {
+            format!("// {}\n{}", comment.replace('\n', "\n// "), code)
+        }
+        (_, CommentStyle::FunctionDoc, None) => {
+            // Unknown language: default C-style
+            format!("/*\n * {}\n */\n{}", comment.replace('\n', "\n * "), code)
+        }
+        (_, _, Some(SymbolLanguage::Python)) => {
+            format!("# {}\n{}", comment.replace('\n', "\n# "), code)
         }
+        _ => format!("// {}\n{}", comment.replace('\n', "\n// "), code),
     }
 }
@@ -1,14 +1,22 @@
 use std::sync::{Arc, RwLock};
 
-use gpui_component::highlighter::Diagnostic;
+use gpui_component::highlighter::{Diagnostic, DiagnosticSeverity};
 use lsp_types::{CodeAction, CompletionItem};
 use std::ops::Range;
 
+use crate::core::services::LspDocument;
+
 #[derive(Clone)]
 pub struct CodeEditorPanelLspStore {
     pub(super) completions: Arc<Vec<CompletionItem>>,
     pub(super) code_actions: Arc<RwLock<Vec<(Range<usize>, CodeAction)>>>,
     pub(super) diagnostics: Arc<RwLock<Vec<Diagnostic>>>,
+    /// Diagnostics published by the language server of the open document
+    pub(super) server_diagnostics: Arc<RwLock<Vec<lsp_types::Diagnostic>>>,
+    /// Markers on the lines of pending agent edits
+    pub(super) agent_diagnostics: Arc<RwLock<Vec<Diagnostic>>>,
+    /// Open document on a language server (None without a configured server)
+    pub(super) document: Arc<RwLock<Option<Arc<LspDocument>>>>,
     pub(super) dirty: Arc<RwLock<bool>>,
 }
 
@@ -23,13 +31,26 @@ impl CodeEditorPanelLspStore {
             completions: Arc::new(vec![]),
             code_actions: Arc::new(RwLock::new(vec![])),
             diagnostics: Arc::new(RwLock::new(vec![])),
+            server_diagnostics: Arc::new(RwLock::new(vec![])),
+            agent_diagnostics: Arc::new(RwLock::new(vec![])),
+            document: Arc::new(RwLock::new(None)),
             dirty: Arc::new(RwLock::new(false)),
         }
     }
 
+    /// Lint diagnostics, the language server diagnostics and the agent edit markers
     pub fn diagnostics(&self) -> Vec<Diagnostic> {
         let guard = self.diagnostics.read().unwrap();
-        guard.clone()
+        let mut diagnostics = guard.clone();
+        diagnostics.extend(
+            self.server_diagnostics
+                .read()
+                .unwrap()
+                .iter()
+                .map(editor_diagnostic),
+        );
+        diagnostics.extend(self.agent_diagnostics.read().unwrap().iter().cloned());
+        diagnostics
     }
 
     pub fn update_diagnostics(&self, diagnostics: Vec<Diagnostic>) {
@@ -38,6 +59,23 @@ impl CodeEditorPanelLspStore {
         *self.dirty.write().unwrap() = true;
     }
 
+    pub fn server_diagnostics(&self) -> Vec<lsp_types::Diagnostic> {
+        let guard = self.server_diagnostics.read().unwrap();
+        guard.clone()
+    }
+
+    pub fn update_server_diagnostics(&self, diagnostics: Vec<lsp_types::Diagnostic>) {
+        let mut guard = self.server_diagnostics.write().unwrap();
+        *guard = diagnostics;
+        *self.dirty.write().unwrap() = true;
+    }
+
+    pub fn update_agent_diagnostics(&self, diagnostics: Vec<Diagnostic>) {
+        let mut guard = self.agent_diagnostics.write().unwrap();
+        *guard = diagnostics;
+        *self.dirty.write().unwrap() = true;
+    }
+
     pub fn code_actions(&self) -> Vec<(Range<usize>, CodeAction)> {
         let guard = self.code_actions.read().unwrap();
         guard.clone()
@@ -49,8 +87,41 @@ impl CodeEditorPanelLspStore {
         *self.dirty.write().unwrap() = true;
     }
 
+    pub fn document(&self) -> Option<Arc<LspDocument>> {
+        let guard = self.document.read().unwrap();
+        guard.clone()
+    }
+
+    /// Replace the open document, dropping the diagnostics of the previous one
+    pub fn set_document(&self, document: Option<Arc<LspDocument>>) {
+        *self.document.write().unwrap() = document;
+        self.update_server_diagnostics(vec![]);
+    }
+
     pub fn is_dirty(&self) -> bool {
         let guard = self.dirty.read().unwrap();
         *guard
     }
+
+    /// Return whether the diagnostics changed since the last call
+    pub fn take_dirty(&self) -> bool {
+        let mut guard = self.dirty.write().unwrap();
+        std::mem::take(&mut *guard)
+    }
+}
+
+/// Convert a language server diagnostic for the editor
+fn editor_diagnostic(diagnostic: &lsp_types::Diagnostic) -> Diagnostic {
+    let severity = match diagnostic.severity {
+        Some(lsp_types::DiagnosticSeverity::ERROR) => DiagnosticSeverity::Error,
+        Some(lsp_types::DiagnosticSeverity::WARNING) => DiagnosticSeverity::Warning,
+        Some(lsp_types::DiagnosticSeverity::HINT) => DiagnosticSeverity::Hint,
+        _ => DiagnosticSeverity::Info,
+    };
+    let message = match &diagnostic.source {
+        Some(source) => format!("{}: {}", source, diagnostic.message),
+        None => diagnostic.message.clone(),
+    };
+
+    Diagnostic::new(diagnostic.range.start..diagnostic.range.end, message).with_severity(severity)
 }
@@ -0,0 +1,178 @@
+use similar::{DiffOp, TextDiff};
+
+/// A change of one side relative to the base: base lines `start..end` become `lines`
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Result of a three-way merge
+pub struct MergeResult {
+    pub text: String,
+    /// Number of conflicting regions (marked with `<<<<<<<` / `>>>>>>>`)
+    pub conflicts: usize,
+}
+
+/// Merge the editor buffer (`ours`) with the file on disk (`theirs`)
+///
+/// `base` is the content both sides started from (the last loaded or saved
+/// content). Regions changed differently on both sides are kept with conflict
+/// markers.
+pub fn merge_texts(base: &str, ours: &str, theirs: &str) -> MergeResult {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours_hunks = hunks(base, ours);
+    let theirs_hunks = hunks(base, theirs);
+
+    let mut text = String::new();
+    let mut conflicts = 0;
+    let mut pos = 0;
+    let (mut a, mut b) = (0, 0);
+
+    loop {
+        let start = match (ours_hunks.get(a), theirs_hunks.get(b)) {
+            (None, None) => break,
+            (Some(h), None) | (None, Some(h)) => h.start,
+            (Some(x), Some(y)) => x.start.min(y.start),
+        };
+
+        // Collect the hunks of both sides that overlap the region
+        let mut end = start;
+        let (a_start, b_start) = (a, b);
+        loop {
+            let mut grew = false;
+            if let Some(h) = ours_hunks.get(a)
+                && (h.start < end || h.start == start)
+            {
+                end = end.max(h.end);
+                a += 1;
+                grew = true;
+            }
+            if let Some(h) = theirs_hunks.get(b)
+                && (h.start < end || h.start == start)
+            {
+                end = end.max(h.end);
+                b += 1;
+                grew = true;
+            }
+            if !grew {
+                break;
+            }
+        }
+
+        base_lines[pos..start].iter().for_each(|l| text.push_str(l));
+
+        let ours_region = apply(&base_lines, &ours_hunks[a_start..a], start, end);
+        let theirs_region = apply(&base_lines, &theirs_hunks[b_start..b], start, end);
+        if a == a_start {
+            text.push_str(&theirs_region);
+        } else if b == b_start || ours_region == theirs_region {
+            text.push_str(&ours_region);
+        } else {
+            conflicts += 1;
+            text.push_str("<<<<<<< editor\n");
+            push_line_block(&mut text, &ours_region);
+            text.push_str("=======\n");
+            push_line_block(&mut text, &theirs_region);
+            text.push_str(">>>>>>> disk\n");
+        }
+
+        pos = end;
+    }
+
+    base_lines[pos..].iter().for_each(|l| text.push_str(l));
+
+    MergeResult { text, conflicts }
+}
+
+/// Changes of `new` relative to `base`, adjacent changes joined
+fn hunks<'a>(base: &str, new: &'a str) -> Vec<Hunk<'a>> {
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let diff = TextDiff::from_lines(base, new);
+
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for op in diff.ops() {
+        if let DiffOp::Equal { .. } = op {
+            continue;
+        }
+        let old = op.old_range();
+        let lines = new_lines[op.new_range()].to_vec();
+        match hunks.last_mut() {
+            Some(last) if last.end == old.start => {
+                last.end = old.end;
+                last.lines.extend(lines);
+            }
+            _ => hunks.push(Hunk {
+                start: old.start,
+                end: old.end,
+                lines,
+            }),
+        }
+    }
+    hunks
+}
+
+/// Content of base lines `start..end` after applying one side's hunks
+fn apply(base_lines: &[&str], hunks: &[Hunk], start: usize, end: usize) -> String {
+    let mut text = String::new();
+    let mut pos = start;
+    for hunk in hunks {
+        base_lines[pos..hunk.start]
+            .iter()
+            .for_each(|l| text.push_str(l));
+        hunk.lines.iter().for_each(|l| text.push_str(l));
+        pos = hunk.end;
+    }
+    base_lines[pos..end].iter().for_each(|l| text.push_str(l));
+    text
+}
+
+/// Append a region inside conflict markers, making sure it ends with a newline
+fn push_line_block(text: &mut String, region: &str) {
+    text.push_str(region);
+    if !region.is_empty() && !region.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_non_overlapping_changes() {
+        let base = "a\nb\nc\nd\n";
+        let ours = "a\nB\nc\nd\n";
+        let theirs = "a\nb\nc\nD\ne\n";
+
+        let result = merge_texts(base, ours, theirs);
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.text, "a\nB\nc\nD\ne\n");
+    }
+
+    #[test]
+    fn test_merge_same_change_on_both_sides() {
+        let result = merge_texts("a\nb\n", "a\nx\n", "a\nx\n");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.text, "a\nx\n");
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let result = merge_texts("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.text,
+            "a\n<<<<<<< editor\nours\n=======\ntheirs\n>>>>>>> disk\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_one_side_unchanged() {
+        let base = "one\ntwo\n";
+        let theirs = "zero\none\ntwo\nthree\n";
+        let result = merge_texts(base, base, theirs);
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.text, theirs);
+    }
+}
@@ -1,6 +1,13 @@
+mod agent_edits;
+mod ai_response;
+mod buffer;
 mod lsp_providers;
 mod lsp_store;
+mod merge;
 mod panel;
+mod session;
+mod symbol_picker;
+mod symbols;
 mod types;
 
 pub use panel::CodeEditorPanel;
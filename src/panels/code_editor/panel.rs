@@ -1,44 +1,85 @@
-use std::{path::PathBuf, rc::Rc, str::FromStr};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
+use agent_client_protocol as acp;
 use autocorrect::ignorer::Ignorer;
 use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
-    ActiveTheme, Icon, IconName, Sizable, StyledExt, WindowExt,
+    ActiveTheme, Icon, IconName, IndexPath, Sizable, StyledExt, WindowExt,
     button::{Button, ButtonVariants as _},
+    dialog::DialogButtonProps,
     h_flex,
-    highlighter::{Diagnostic, DiagnosticSeverity, Language},
-    input::{Input, InputEvent, InputState, Position, RopeExt, TabSize},
-    list::ListItem,
+    input::{Input, InputEvent, InputState, Position, TabSize},
+    list::{List, ListItem, ListState},
+    notification::Notification,
     resizable::{h_resizable, resizable_panel},
+    scroll::ScrollableElement as _,
+    tab::{Tab, TabBar},
     tree::{TreeState, tree},
     v_flex,
 };
-use lsp_types::{CodeActionKind, TextEdit, WorkspaceEdit};
 use rust_i18n::t;
 
+use super::agent_edits::{accept_hunk, reject_hunk};
+use super::buffer::EditorBuffer;
 use super::lsp_providers::TextConvertor;
 use super::lsp_store::CodeEditorPanelLspStore;
+use super::merge::merge_texts;
+use super::session::{self, EditorSession, EditorSessionFile};
+use super::symbol_picker::{
+    SymbolCache, SymbolPickerDelegate, WorkspaceSymbol, scan_workspace_symbols,
+};
+use super::symbols::{SymbolLanguage, symbol_at};
 use super::types::build_file_items;
-use crate::AppState;
+use crate::{
+    AppState, GoToSymbol, QuickOpen, SaveFile, SaveFileAs,
+    components::{DiffView, FilePickerDelegate},
+    core::services::{FileWatcher, SessionStatus},
+};
+
+/// Content of a file that is not open, before the edits of an agent session
+struct AgentBase {
+    session_id: String,
+    text: String,
+}
 
 pub struct CodeEditorPanel {
-    editor: Entity<InputState>,
     tree_state: Entity<TreeState>,
     go_to_line_state: Entity<InputState>,
-    language: Language,
+    /// Fuzzy file search over the workspace index
+    quick_open: Entity<ListState<FilePickerDelegate>>,
+    /// Fuzzy symbol search over the workspace files
+    symbol_picker: Entity<ListState<SymbolPickerDelegate>>,
+    symbol_cache: SymbolCache,
+    /// Content before the agent edits of files that are not open
+    agent_bases: HashMap<PathBuf, AgentBase>,
+    /// Open files, in tab order
+    buffers: Vec<EditorBuffer>,
+    /// Index of the active tab in `buffers`
+    active_buffer: usize,
     line_number: bool,
     indent_guides: bool,
     soft_wrap: bool,
     show_file_tree: bool,
+    show_outline: bool,
     files_loaded: bool,
-    lsp_store: CodeEditorPanelLspStore,
-    current_file_path: Option<PathBuf>,
-    has_opened_file: bool,
+    /// Whether the saved session was restored (not saving over it before)
+    session_restored: bool,
+    file_watcher: Option<FileWatcher>,
     workspace_id: Option<String>,
     workspace_name: Option<String>,
     working_directory: PathBuf,
     _subscriptions: Vec<Subscription>,
-    _lint_task: Task<()>,
+    _watch_task: Task<()>,
+    _quick_open_task: Task<()>,
+    _symbol_picker_task: Task<()>,
+    _workspace_symbols_task: Task<()>,
+    _agent_edits_task: Task<()>,
+    /// Drops `agent_bases` entries when their session ends
+    _agent_sessions_task: Task<()>,
 }
 
 impl crate::panels::dock_panel::DockPanel for CodeEditorPanel {
@@ -85,58 +126,160 @@ impl CodeEditorPanel {
     }
 
     pub fn new(window: &mut Window, working_dir: Option<PathBuf>, cx: &mut Context<Self>) -> Self {
-        let default_language = Language::from_str("rust");
-        let lsp_store = CodeEditorPanelLspStore::new();
-
-        let editor = cx.new(|cx| {
-            let mut editor = InputState::new(window, cx)
-                .code_editor(default_language.name())
-                .line_number(true)
-                .indent_guides(true)
-                .tab_size(TabSize {
-                    tab_size: 4,
-                    hard_tabs: false,
-                })
-                .soft_wrap(false)
-                .placeholder("Enter your code here...");
-
-            let lsp_store = Rc::new(lsp_store.clone());
-            // editor.lsp.completion_provider = Some(lsp_store.clone());
-            editor.lsp.code_action_providers = vec![lsp_store.clone(), Rc::new(TextConvertor)];
-            // editor.lsp.hover_provider = Some(lsp_store.clone());
-            // editor.lsp.definition_provider = Some(lsp_store.clone());
-            // editor.lsp.document_color_provider = Some(lsp_store.clone());
-
-            editor
-        });
         let go_to_line_state = cx.new(|cx| InputState::new(window, cx));
 
         let tree_state = cx.new(|cx| TreeState::new(cx));
         let working_dir =
             working_dir.unwrap_or_else(|| AppState::global(cx).current_working_dir().clone());
 
-        let _subscriptions = vec![cx.subscribe(&editor, |this, _, _: &InputEvent, cx| {
-            this.lint_document(cx);
-        })];
+        // Keep the open files (and cursors) for the next start
+        let _subscriptions = vec![
+            cx.on_app_quit(|this, cx| this.save_session_task(cx)),
+            cx.on_release(|this, cx| {
+                this.save_session_task(cx).detach();
+            }),
+        ];
+
+        // Reload open files when they are rewritten on disk (e.g. by an agent)
+        let (file_watcher, _watch_task) = match FileWatcher::new() {
+            Ok((watcher, changes)) => {
+                let task = cx.spawn_in(window, async move |this, cx| {
+                    while let Ok(path) = changes.recv().await {
+                        let result = this.update_in(cx, |this, window, cx| {
+                            this.on_file_changed_on_disk(path, window, cx);
+                        });
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                });
+                (Some(watcher), task)
+            }
+            Err(e) => {
+                log::warn!("[CodeEditorPanel] File watcher unavailable: {}", e);
+                (None, Task::ready(()))
+            }
+        };
+
+        // Open the files picked in quick-open
+        let (quick_open_tx, mut quick_open_rx) = tokio::sync::mpsc::unbounded_channel();
+        let quick_open = cx.new(|cx| {
+            let delegate =
+                FilePickerDelegate::new(&working_dir, cx).with_selection_sender(quick_open_tx);
+            ListState::new(delegate, window, cx).searchable(true)
+        });
+        let _quick_open_task = cx.spawn_in(window, async move |this, cx| {
+            while let Some(item) = quick_open_rx.recv().await {
+                let Some(view) = this.upgrade() else {
+                    break;
+                };
+                _ = cx.update(|window, cx| {
+                    window.close_dialog(cx);
+                    if let Err(e) = Self::open_file(view, item.path.clone(), window, cx) {
+                        log::warn!(
+                            "[CodeEditorPanel] Failed to open {}: {}",
+                            item.path.display(),
+                            e
+                        );
+                    }
+                });
+            }
+        });
+
+        // Jump to the symbols picked in "go to symbol"
+        let (symbol_tx, mut symbol_rx) = tokio::sync::mpsc::unbounded_channel();
+        let symbol_picker = cx.new(|cx| {
+            ListState::new(SymbolPickerDelegate::new(symbol_tx), window, cx).searchable(true)
+        });
+        let _symbol_picker_task = cx.spawn_in(window, async move |this, cx| {
+            while let Some(item) = symbol_rx.recv().await {
+                let Some(view) = this.upgrade() else {
+                    break;
+                };
+                _ = cx.update(|window, cx| {
+                    window.close_dialog(cx);
+                    Self::open_workspace_symbol(view, item, window, cx);
+                });
+            }
+        });
+
+        // Show the file edits reported by agent tool calls for review
+        let _agent_edits_task = match AppState::global(cx).message_service() {
+            Some(message_service) => {
+                let mut updates = message_service.subscribe_session_updates(None);
+                cx.spawn_in(window, async move |this, cx| {
+                    while let Some(event) = updates.recv().await {
+                        let diffs = agent_diffs(&event.update);
+                        if diffs.is_empty() {
+                            continue;
+                        }
+                        let result = this.update_in(cx, |this, window, cx| {
+                            for diff in diffs {
+                                this.on_agent_diff(&event.session_id, diff, window, cx);
+                            }
+                        });
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                })
+            }
+            None => Task::ready(()),
+        };
+
+        // Edits of files that were never opened aren't reviewed after their
+        // session is over
+        let _agent_sessions_task = {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            AppState::global(cx)
+                .event_hub()
+                .subscribe_workspace_session_status(move |session_id, status| {
+                    if matches!(status, SessionStatus::Closed | SessionStatus::Failed) {
+                        let _ = tx.send(session_id.clone());
+                    }
+                });
+            cx.spawn(async move |this, cx| {
+                while let Some(session_id) = rx.recv().await {
+                    let result = this.update(cx, |this, _| {
+                        this.agent_bases
+                            .retain(|_, base| base.session_id != session_id);
+                    });
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+
+        Self::restore_session(working_dir.clone(), window, cx);
 
         Self {
-            editor,
             tree_state,
             go_to_line_state,
-            language: default_language,
+            quick_open,
+            symbol_picker,
+            symbol_cache: SymbolCache::default(),
+            agent_bases: HashMap::new(),
+            buffers: Vec::new(),
+            active_buffer: 0,
             line_number: true,
             indent_guides: true,
             soft_wrap: false,
             show_file_tree: true,
+            show_outline: false,
             files_loaded: false,
-            lsp_store,
-            current_file_path: None,
-            has_opened_file: false,
+            session_restored: false,
+            file_watcher,
             workspace_id: None,
             workspace_name: None,
             working_directory: working_dir,
             _subscriptions,
-            _lint_task: Task::ready(()),
+            _watch_task,
+            _quick_open_task,
+            _symbol_picker_task,
+            _workspace_symbols_task: Task::ready(()),
+            _agent_edits_task,
+            _agent_sessions_task,
         }
     }
 
@@ -184,8 +327,29 @@ impl CodeEditorPanel {
         self.working_directory.clone()
     }
 
+    /// Dock tab title: the active file, prefixed with a dot when it has unsaved changes
+    pub fn tab_title(&self) -> Option<SharedString> {
+        self.active().map(EditorBuffer::title)
+    }
+
+    fn active(&self) -> Option<&EditorBuffer> {
+        self.buffers.get(self.active_buffer)
+    }
+
+    fn active_editor(&self) -> Option<Entity<InputState>> {
+        self.active().map(|buffer| buffer.editor.clone())
+    }
+
+    pub(super) fn buffer_mut(&mut self, editor: &Entity<InputState>) -> Option<&mut EditorBuffer> {
+        self.buffers
+            .iter_mut()
+            .find(|buffer| &buffer.editor == editor)
+    }
+
     fn go_to_line(&mut self, _: &ClickEvent, window: &mut Window, cx: &mut Context<Self>) {
-        let editor = self.editor.clone();
+        let Some(editor) = self.active_editor() else {
+            return;
+        };
         let input_state = self.go_to_line_state.clone();
 
         window.open_dialog(cx, move |dialog, window, cx| {
@@ -222,109 +386,994 @@ impl CodeEditorPanel {
                             column.saturating_sub(1) as u32,
                         );
 
-                        editor.update(cx, |state, cx| {
-                            state.set_cursor_position(position, window, cx);
-                        });
+                        editor.update(cx, |state, cx| {
+                            state.set_cursor_position(position, window, cx);
+                        });
+
+                        true
+                    }
+                })
+        });
+    }
+
+    fn quick_open(&mut self, _: &QuickOpen, window: &mut Window, cx: &mut Context<Self>) {
+        let list = self.quick_open.clone();
+        list.update(cx, |state, cx| {
+            state.delegate_mut().set_search_query(String::new());
+            state.set_selected_index(Some(IndexPath::default()), window, cx);
+            cx.notify();
+        });
+
+        window.open_dialog(cx, move |dialog, window, cx| {
+            list.focus_handle(cx).focus(window, cx);
+
+            dialog
+                .title(t!("code_editor.quick_open.title").to_string())
+                .child(
+                    div()
+                        .w_full()
+                        .h(px(360.))
+                        .child(List::new(&list).size_full()),
+                )
+        });
+    }
+
+    fn go_to_symbol(&mut self, _: &GoToSymbol, window: &mut Window, cx: &mut Context<Self>) {
+        let list = self.symbol_picker.clone();
+        list.update(cx, |state, cx| {
+            state.delegate_mut().set_loading();
+            cx.notify();
+        });
+
+        // Parse the workspace files (unchanged files come from the cache)
+        if let Some(service) = AppState::global(cx).file_index_service() {
+            let index = service.index(&self.working_directory);
+            let cache = self.symbol_cache.clone();
+            let list = list.clone();
+            self._workspace_symbols_task = cx.spawn_in(window, async move |_, cx| {
+                index.wait_until_ready().await;
+                let files = index.files();
+                let symbols = cx
+                    .background_spawn(async move { scan_workspace_symbols(files, &cache) })
+                    .await;
+
+                _ = list.update_in(cx, |state, window, cx| {
+                    state.delegate_mut().set_symbols(symbols);
+                    state.set_selected_index(Some(IndexPath::default()), window, cx);
+                    cx.notify();
+                });
+            });
+        }
+
+        window.open_dialog(cx, move |dialog, window, cx| {
+            list.focus_handle(cx).focus(window, cx);
+            let loading = list.read(cx).delegate().is_loading();
+
+            dialog
+                .title(t!("code_editor.go_to_symbol.title").to_string())
+                .when(loading, |this| {
+                    this.child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(t!("code_editor.go_to_symbol.loading").to_string()),
+                    )
+                })
+                .child(
+                    div()
+                        .w_full()
+                        .h(px(360.))
+                        .child(List::new(&list).size_full()),
+                )
+        });
+    }
+
+    fn open_workspace_symbol(
+        view: Entity<Self>,
+        item: WorkspaceSymbol,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let position = Position::new(item.symbol.line, item.symbol.column);
+        if let Err(e) = Self::open_file_at(view, item.path.clone(), Some(position), window, cx) {
+            log::warn!(
+                "[CodeEditorPanel] Failed to open {}: {}",
+                item.path.display(),
+                e
+            );
+        }
+    }
+
+    fn on_editor_event(
+        &mut self,
+        editor: Entity<InputState>,
+        event: &InputEvent,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(buffer) = self.buffer_mut(&editor) else {
+            return;
+        };
+
+        if matches!(event, InputEvent::Change) {
+            if buffer.update_dirty(cx) {
+                cx.notify();
+            }
+            buffer.sync_lsp_document(cx);
+            buffer.update_symbols(true, cx);
+            if buffer.agent_base.is_some() {
+                buffer.update_agent_hunks(cx);
+                cx.notify();
+            }
+        }
+        buffer.lint(cx);
+    }
+
+    fn open_file(
+        view: Entity<Self>,
+        path: PathBuf,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Result<()> {
+        Self::open_file_at(view, path, None, window, cx)
+    }
+
+    /// Open a file in a tab, optionally moving the cursor to a position
+    pub fn open_file_at(
+        view: Entity<Self>,
+        path: PathBuf,
+        cursor: Option<Position>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Result<()> {
+        // The buffer is opened on the next frame, as this may be called while
+        // the panel is being updated (e.g. from a click on the file tree)
+        let content = std::fs::read_to_string(&path)?;
+
+        window
+            .spawn(cx, async move |window| {
+                _ = view.update_in(window, |this, window, cx| {
+                    this.open_buffer(path, content, cursor, window, cx);
+                });
+            })
+            .detach();
+
+        Ok(())
+    }
+
+    /// Open a file in a new tab (or switch to its tab) and make it active
+    ///
+    /// `content` is ignored when the file is already open.
+    fn open_buffer(
+        &mut self,
+        path: PathBuf,
+        content: String,
+        cursor: Option<Position>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(ix) = self.buffers.iter().position(|b| b.path == path) {
+            if let Some(cursor) = cursor {
+                self.buffers[ix].editor.update(cx, |state, cx| {
+                    state.set_cursor_position(cursor, window, cx);
+                });
+            }
+            self.activate_buffer(ix, window, cx);
+            return;
+        }
+
+        // The file on disk holds the edits an agent made before it was opened
+        let agent_base = self.agent_bases.remove(&path);
+        let agent_text = agent_base.as_ref().map(|_| content.clone());
+        let language = EditorBuffer::language_for_path(&path);
+        let symbol_language = SymbolLanguage::from_path(&path);
+        let lsp_store = CodeEditorPanelLspStore::new();
+        let editor = cx.new(|cx| {
+            let mut editor = InputState::new(window, cx)
+                .code_editor(language.name())
+                .line_number(self.line_number)
+                .indent_guides(self.indent_guides)
+                .tab_size(TabSize {
+                    tab_size: 4,
+                    hard_tabs: false,
+                })
+                .soft_wrap(self.soft_wrap)
+                .placeholder("Enter your code here...");
+
+            let lsp_store = Rc::new(lsp_store.clone());
+            editor.lsp.completion_provider = Some(lsp_store.clone());
+            editor.lsp.code_action_providers = vec![
+                lsp_store.clone(),
+                Rc::new(TextConvertor::new(symbol_language)),
+            ];
+            editor.lsp.hover_provider = Some(lsp_store.clone());
+            editor.lsp.definition_provider = Some(lsp_store.clone());
+            // editor.lsp.document_color_provider = Some(lsp_store.clone());
+
+            editor.set_value(content.clone(), window, cx);
+            if let Some(cursor) = cursor {
+                editor.set_cursor_position(cursor, window, cx);
+            }
+            editor
+        });
+
+        let _subscription = cx.subscribe(&editor, |this, editor, event: &InputEvent, cx| {
+            this.on_editor_event(editor, event, cx);
+        });
+
+        if let Some(watcher) = self.file_watcher.as_mut()
+            && let Err(e) = watcher.watch(&path)
+        {
+            log::warn!("[CodeEditorPanel] {}", e);
+        }
+
+        let mut buffer = EditorBuffer {
+            editor,
+            lsp_store,
+            path,
+            language,
+            saved_text: content,
+            dirty: false,
+            external_text: None,
+            pending_writes: Vec::new(),
+            symbols: Vec::new(),
+            // Agent edits made to the file before it was opened
+            agent_base: agent_base.map(|base| base.text),
+            agent_text,
+            agent_hunks: Vec::new(),
+            _subscription,
+            _lint_task: Task::ready(()),
+            _symbols_task: Task::ready(()),
+            _lsp_task: Task::ready(()),
+        };
+        buffer.lint(cx);
+        buffer.update_symbols(false, cx);
+        buffer.update_agent_hunks(cx);
+        buffer.open_lsp_document(self.working_directory.clone(), cx);
+
+        self.buffers.push(buffer);
+        self.activate_buffer(self.buffers.len() - 1, window, cx);
+    }
+
+    fn activate_buffer(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(buffer) = self.buffers.get(ix) else {
+            return;
+        };
+
+        self.active_buffer = ix;
+        buffer
+            .editor
+            .update(cx, |state, cx| state.focus(window, cx));
+        self.save_session(cx);
+        cx.notify();
+    }
+
+    /// Close a tab, asking before discarding unsaved changes
+    fn close_buffer(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(buffer) = self.buffers.get(ix) else {
+            return;
+        };
+        if !buffer.dirty {
+            self.remove_buffer(ix, cx);
+            return;
+        }
+
+        let editor = buffer.editor.clone();
+        let name = buffer.file_name();
+        let view = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            dialog
+                .title(t!("code_editor.close_dirty.title", name = name).to_string())
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(t!("code_editor.close_dirty.message").to_string()),
+                )
+                .confirm()
+                .button_props(
+                    DialogButtonProps::default()
+                        .ok_text(t!("code_editor.close_dirty.discard").to_string())
+                        .cancel_text(t!("code_editor.close_dirty.cancel").to_string()),
+                )
+                .on_ok({
+                    let view = view.clone();
+                    let editor = editor.clone();
+                    move |_, _window, cx| {
+                        _ = view.update(cx, |this, cx| {
+                            // The tab may have moved while the dialog was open
+                            if let Some(ix) = this.buffers.iter().position(|b| b.editor == editor) {
+                                this.remove_buffer(ix, cx);
+                            }
+                        });
+                        true
+                    }
+                })
+        });
+    }
+
+    fn remove_buffer(&mut self, ix: usize, cx: &mut Context<Self>) {
+        if ix >= self.buffers.len() {
+            return;
+        }
+
+        let buffer = self.buffers.remove(ix);
+        buffer.lsp_store.set_document(None);
+        if let Some(watcher) = self.file_watcher.as_mut() {
+            watcher.unwatch(&buffer.path);
+        }
+
+        if self.active_buffer > ix || self.active_buffer >= self.buffers.len() {
+            self.active_buffer = self.active_buffer.saturating_sub(1);
+        }
+        self.save_session(cx);
+        cx.notify();
+    }
+
+    /// The open files and their cursors
+    fn session(&self, cx: &App) -> EditorSession {
+        EditorSession {
+            files: self
+                .buffers
+                .iter()
+                .map(|buffer| {
+                    let cursor = buffer.editor.read(cx).cursor_position();
+                    EditorSessionFile {
+                        path: buffer.path.clone(),
+                        line: cursor.line,
+                        character: cursor.character,
+                    }
+                })
+                .collect(),
+            active: self.active_buffer,
+        }
+    }
+
+    fn save_session_task(&self, cx: &App) -> Task<()> {
+        if !self.session_restored {
+            return Task::ready(());
+        }
+
+        let working_dir = self.working_directory.clone();
+        let session = self.session(cx);
+        cx.background_spawn(async move {
+            if let Err(e) = session::save_session(&working_dir, session) {
+                log::warn!("[CodeEditorPanel] Failed to save editor session: {}", e);
+            }
+        })
+    }
+
+    fn save_session(&self, cx: &App) {
+        self.save_session_task(cx).detach();
+    }
+
+    /// Reopen the files that were open in this working directory
+    fn restore_session(working_dir: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        cx.spawn_in(window, async move |this, cx| {
+            let files = cx
+                .background_spawn(async move {
+                    let session = session::load_session(&working_dir).unwrap_or_default();
+                    let files: Vec<_> = session
+                        .files
+                        .into_iter()
+                        .filter_map(|file| {
+                            // Files deleted since the last run are dropped
+                            let content = std::fs::read_to_string(&file.path).ok()?;
+                            Some((file, content))
+                        })
+                        .collect();
+                    (files, session.active)
+                })
+                .await;
+
+            _ = this.update_in(cx, |this, window, cx| {
+                let (files, active) = files;
+                // A file opened before the session was loaded stays active
+                let opened = this.active_editor();
+                for (file, content) in files {
+                    if this.buffers.iter().any(|b| b.path == file.path) {
+                        continue;
+                    }
+                    let cursor = Position::new(file.line, file.character);
+                    this.open_buffer(file.path, content, Some(cursor), window, cx);
+                }
+                this.session_restored = true;
+
+                let active = match opened {
+                    Some(editor) => this.buffers.iter().position(|b| b.editor == editor),
+                    None => Some(active.min(this.buffers.len().saturating_sub(1))),
+                };
+                if let Some(ix) = active {
+                    this.activate_buffer(ix, window, cx);
+                }
+            });
+        })
+        .detach();
+    }
+
+    fn save(&mut self, _: &SaveFile, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(buffer) = self.active() else {
+            return;
+        };
+
+        let (editor, path) = (buffer.editor.clone(), buffer.path.clone());
+        self.save_to(editor, path, window, cx);
+    }
+
+    fn save_as(&mut self, _: &SaveFileAs, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(buffer) = self.active() else {
+            return;
+        };
+
+        let editor = buffer.editor.clone();
+        let directory = buffer
+            .path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.working_directory.clone());
+        let file_name = buffer.file_name();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let mut dialog = rfd::AsyncFileDialog::new()
+                .set_title(t!("code_editor.dialog.save_as.title").to_string())
+                .set_directory(&directory);
+            if !file_name.is_empty() {
+                dialog = dialog.set_file_name(file_name);
+            }
+
+            let Some(file) = dialog.save_file().await else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+            _ = this.update_in(cx, |this, window, cx| {
+                this.save_to(editor, path, window, cx)
+            });
+        })
+        .detach();
+    }
+
+    /// Write a buffer to `path`, switching the buffer to it for "save as"
+    fn save_to(
+        &mut self,
+        editor: Entity<InputState>,
+        path: PathBuf,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let text = editor.read(cx).text().to_string();
+        self.write_buffer(editor, path, text, window, cx);
+    }
+
+    /// Write `text` for a buffer to `path`
+    fn write_buffer(
+        &mut self,
+        editor: Entity<InputState>,
+        path: PathBuf,
+        text: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // The watcher may report the write before it completes
+        if let Some(buffer) = self.buffer_mut(&editor) {
+            buffer.pending_writes.push(text.clone());
+        }
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = smol::fs::write(&path, &text).await;
+
+            _ = this.update_in(cx, |this, window, cx| {
+                if let Some(buffer) = this.buffer_mut(&editor)
+                    && let Some(ix) = buffer.pending_writes.iter().position(|t| *t == text)
+                {
+                    buffer.pending_writes.remove(ix);
+                }
+                this.on_saved(editor, path, text, result, window, cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Update a buffer once its save finished
+    fn on_saved(
+        &mut self,
+        editor: Entity<InputState>,
+        path: PathBuf,
+        text: String,
+        result: std::io::Result<()>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match result {
+            Ok(()) => {
+                log::info!("[CodeEditorPanel] Saved {}", path.display());
+                let root = self.working_directory.clone();
+                let mut renamed = None;
+                let Some(buffer) = self.buffer_mut(&editor) else {
+                    return;
+                };
+
+                buffer.saved_text = text;
+                buffer.external_text = None;
+                if buffer.path != path {
+                    renamed = Some(buffer.path.clone());
+                    buffer.set_path(path.clone(), cx);
+                    buffer.update_symbols(false, cx);
+                    buffer.open_lsp_document(root, cx);
+                } else if let Some(document) = buffer.lsp_store.document()
+                    && let Err(e) = document.save()
+                {
+                    log::warn!("[CodeEditorPanel] Failed to notify save: {}", e);
+                }
+                buffer.update_dirty(cx);
+
+                // "Save as" moved the buffer to another file
+                if let Some(previous) = renamed {
+                    if let Some(watcher) = self.file_watcher.as_mut() {
+                        watcher.unwatch(&previous);
+                        if let Err(e) = watcher.watch(&path) {
+                            log::warn!("[CodeEditorPanel] {}", e);
+                        }
+                    }
+                    self.save_session(cx);
+                }
+                cx.notify();
+            }
+            Err(e) => {
+                log::error!("[CodeEditorPanel] Failed to save {}: {}", path.display(), e);
+                struct SaveFailed;
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let note = Notification::error(
+                    t!("code_editor.error.save", name = name, error = e.to_string()).to_string(),
+                )
+                .id::<SaveFailed>();
+                window.push_notification(note, cx);
+            }
+        }
+    }
+
+    /// Handle a change of a watched file made outside the editor
+    fn on_file_changed_on_disk(
+        &mut self,
+        path: PathBuf,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(buffer) = self.buffers.iter_mut().find(|b| b.path == path) else {
+            return;
+        };
+        // Deleted or being replaced: wait for the next event
+        let Ok(disk_text) = std::fs::read_to_string(&path) else {
+            return;
+        };
+        // Our own save (finished or not), or a write that did not change anything
+        if disk_text == buffer.saved_text
+            || buffer.pending_writes.contains(&disk_text)
+            || buffer.external_text.as_ref() == Some(&disk_text)
+        {
+            return;
+        }
+        // The agent wrote the edit already shown in the buffer
+        if disk_text == buffer.text(cx) {
+            buffer.saved_text = disk_text;
+            buffer.update_dirty(cx);
+            cx.notify();
+            return;
+        }
+
+        if !buffer.dirty {
+            log::info!(
+                "[CodeEditorPanel] Reloading {} (changed on disk)",
+                path.display()
+            );
+            buffer.saved_text = disk_text.clone();
+            buffer.set_text(disk_text, window, cx);
+
+            struct FileReloaded;
+            let note = Notification::info(
+                t!("code_editor.reloaded", name = buffer.file_name()).to_string(),
+            )
+            .id::<FileReloaded>();
+            window.push_notification(note, cx);
+            return;
+        }
 
-                        true
-                    }
-                })
-        });
+        buffer.external_text = Some(disk_text.clone());
+        let (editor, name) = (buffer.editor.clone(), buffer.file_name());
+        self.open_external_change_dialog(editor, name, path, disk_text, window, cx);
     }
 
-    fn lint_document(&mut self, cx: &mut Context<Self>) {
-        let language = self.language.name().to_string();
-        let lsp_store = self.lsp_store.clone();
-        let text = self.editor.read(cx).text().clone();
+    /// Ask how to combine unsaved changes with the version written on disk
+    fn open_external_change_dialog(
+        &mut self,
+        editor: Entity<InputState>,
+        name: String,
+        path: PathBuf,
+        disk_text: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let buffer = editor.read(cx).text().to_string();
+        let diff = acp::Diff::new(path, disk_text.clone()).old_text(buffer);
+        let view = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            let choice_button = |id: &'static str, label: String, choice: ExternalChange| {
+                let view = view.clone();
+                let editor = editor.clone();
+                let disk_text = disk_text.clone();
+                Button::new(id)
+                    .label(label)
+                    .small()
+                    .when(choice == ExternalChange::Merge, |this| this.primary())
+                    .on_click(move |_, window, cx| {
+                        window.close_dialog(cx);
+                        _ = view.update(cx, |this, cx| {
+                            this.resolve_external_change(
+                                &editor,
+                                choice,
+                                disk_text.clone(),
+                                window,
+                                cx,
+                            );
+                        });
+                    })
+            };
 
-        self._lint_task = cx.background_spawn(async move {
-            let value = text.to_string();
-            let result = autocorrect::lint_for(value.as_str(), &language);
+            dialog
+                .title(t!("code_editor.external_change.title", name = name).to_string())
+                .child(
+                    v_flex()
+                        .w_full()
+                        .gap_3()
+                        .p_4()
+                        .child(
+                            div()
+                                .text_sm()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(t!("code_editor.external_change.message").to_string()),
+                        )
+                        .child(
+                            div()
+                                .id("external-change-diff")
+                                .max_h(px(360.))
+                                .overflow_y_scroll()
+                                .child(
+                                    DiffView::new(diff.clone()).context_lines(3).max_lines(2000),
+                                ),
+                        )
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .justify_end()
+                                .child(choice_button(
+                                    "external-change-keep",
+                                    t!("code_editor.external_change.keep").to_string(),
+                                    ExternalChange::KeepBuffer,
+                                ))
+                                .child(choice_button(
+                                    "external-change-reload",
+                                    t!("code_editor.external_change.reload").to_string(),
+                                    ExternalChange::Reload,
+                                ))
+                                .child(choice_button(
+                                    "external-change-merge",
+                                    t!("code_editor.external_change.merge").to_string(),
+                                    ExternalChange::Merge,
+                                )),
+                        ),
+                )
+        });
+    }
 
-            let mut code_actions = vec![];
-            let mut diagnostics = vec![];
+    fn resolve_external_change(
+        &mut self,
+        editor: &Entity<InputState>,
+        choice: ExternalChange,
+        disk_text: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // The tab was closed while the dialog was open
+        let Some(buffer) = self.buffer_mut(editor) else {
+            return;
+        };
 
-            for item in result.lines.iter() {
-                let severity = match item.severity {
-                    autocorrect::Severity::Error => DiagnosticSeverity::Warning,
-                    autocorrect::Severity::Warning => DiagnosticSeverity::Hint,
-                    autocorrect::Severity::Pass => DiagnosticSeverity::Info,
+        match choice {
+            ExternalChange::Reload => {
+                buffer.saved_text = disk_text.clone();
+                buffer.set_text(disk_text, window, cx);
+            }
+            ExternalChange::Merge => {
+                let result = merge_texts(&buffer.saved_text, &buffer.text(cx), &disk_text);
+                buffer.saved_text = disk_text;
+                buffer.set_text(result.text, window, cx);
+
+                struct FileMerged;
+                let message = if result.conflicts > 0 {
+                    t!(
+                        "code_editor.merged_with_conflicts",
+                        name = buffer.file_name(),
+                        count = result.conflicts
+                    )
+                } else {
+                    t!("code_editor.merged", name = buffer.file_name())
+                };
+                let note = if result.conflicts > 0 {
+                    Notification::warning(message.to_string())
+                } else {
+                    Notification::success(message.to_string())
                 };
+                window.push_notification(note.id::<FileMerged>(), cx);
+            }
+            // The buffer stays dirty against the new disk content
+            ExternalChange::KeepBuffer => {
+                buffer.saved_text = disk_text;
+            }
+        }
 
-                let line = item.line.saturating_sub(1); // Convert to 0-based index
-                let col = item.col.saturating_sub(1); // Convert to 0-based index
+        buffer.external_text = None;
+        buffer.update_dirty(cx);
+        cx.notify();
+    }
 
-                let start = Position::new(line as u32, col as u32);
-                let end = Position::new(line as u32, (col + item.old.chars().count()) as u32);
-                let message = format!("AutoCorrect: {}", item.new);
-                diagnostics.push(Diagnostic::new(start..end, message).with_severity(severity));
+    /// Show an edit reported by an agent as pending changes of the file
+    ///
+    /// The agent already wrote the file, so its new content is what's on disk.
+    fn on_agent_diff(
+        &mut self,
+        session_id: &str,
+        diff: acp::Diff,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let base = diff.old_text.unwrap_or_default();
+        let Some(buffer) = self.buffers.iter_mut().find(|b| b.path == diff.path) else {
+            // Reviewed when the file is opened; later edits keep the first base
+            self.agent_bases.entry(diff.path).or_insert(AgentBase {
+                session_id: session_id.to_string(),
+                text: base,
+            });
+            return;
+        };
 
-                let range = text.position_to_offset(&start)..text.position_to_offset(&end);
+        if buffer.agent_base.is_none() {
+            buffer.agent_base = Some(base.clone());
+        }
+        let text = buffer.text(cx);
+        if text != diff.new_text {
+            if buffer.dirty {
+                // Keep the unsaved changes made next to the agent's edit
+                let result = merge_texts(&base, &text, &diff.new_text);
+                buffer.set_text(result.text, window, cx);
+            } else {
+                buffer.set_text(diff.new_text.clone(), window, cx);
+            }
+        }
+        buffer.saved_text = diff.new_text.clone();
+        buffer.agent_text = Some(diff.new_text);
+        buffer.update_dirty(cx);
+        buffer.update_agent_hunks(cx);
+        cx.notify();
+    }
 
-                let text_edit = TextEdit {
-                    range: lsp_types::Range { start, end },
-                    new_text: item.new.clone(),
-                    ..Default::default()
-                };
+    /// Take in one pending agent edit (all of them when `hunk` is None) and save
+    ///
+    /// While other edits are pending, only the reviewed content is written.
+    fn accept_agent_edit(
+        &mut self,
+        editor: &Entity<InputState>,
+        hunk: Option<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(buffer) = self.buffer_mut(editor) else {
+            return;
+        };
+        let Some(base) = buffer.agent_base.as_deref() else {
+            return;
+        };
 
-                let edit = WorkspaceEdit {
-                    changes: Some(
-                        std::iter::once((
-                            lsp_types::Uri::from_str("file://CodeEditorPanel").unwrap(),
-                            vec![text_edit],
-                        ))
-                        .collect(),
-                    ),
-                    ..Default::default()
-                };
+        let base = match hunk {
+            Some(ix) => match buffer.agent_hunks.get(ix) {
+                Some(hunk) => accept_hunk(base, hunk),
+                None => return,
+            },
+            None => buffer.text(cx),
+        };
+        buffer.agent_base = Some(base);
+        buffer.update_agent_hunks(cx);
+        let (path, text) = (buffer.path.clone(), buffer.reviewed_text(cx));
+        self.write_buffer(editor.clone(), path, text, window, cx);
+        cx.notify();
+    }
 
-                code_actions.push((
-                    range,
-                    lsp_types::CodeAction {
-                        title: format!("Change to '{}'", item.new),
-                        kind: Some(CodeActionKind::QUICKFIX),
-                        edit: Some(edit),
-                        ..Default::default()
-                    },
-                ));
-            }
+    /// Restore the original content of one pending agent edit (all of them
+    /// when `hunk` is None) and save
+    ///
+    /// While other edits are pending, only the reviewed content is written.
+    fn reject_agent_edit(
+        &mut self,
+        editor: &Entity<InputState>,
+        hunk: Option<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(buffer) = self.buffer_mut(editor) else {
+            return;
+        };
+        let Some(base) = buffer.agent_base.as_deref() else {
+            return;
+        };
 
-            lsp_store.update_code_actions(code_actions.clone());
-            lsp_store.update_diagnostics(diagnostics.clone());
-        });
+        let text = match hunk {
+            Some(ix) => match buffer.agent_hunks.get(ix) {
+                Some(hunk) => reject_hunk(&buffer.text(cx), hunk),
+                None => return,
+            },
+            None => base.to_string(),
+        };
+        buffer.set_text(text, window, cx);
+        buffer.update_agent_hunks(cx);
+        let (path, text) = (buffer.path.clone(), buffer.reviewed_text(cx));
+        self.write_buffer(editor.clone(), path, text, window, cx);
+        cx.notify();
     }
 
-    fn open_file(
-        view: Entity<Self>,
-        path: PathBuf,
-        window: &mut Window,
-        cx: &mut App,
-    ) -> Result<()> {
-        let language = path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or_default();
-        let language = Language::from_str(&language);
-        let content = std::fs::read_to_string(&path)?;
-        let path_clone = path.clone();
+    /// Review bar listing the pending agent edits of the active file
+    fn render_agent_edits(
+        &self,
+        editor: &Entity<InputState>,
+        cx: &mut Context<Self>,
+    ) -> Option<impl IntoElement> {
+        let hunks = &self.active()?.agent_hunks;
+        if hunks.is_empty() {
+            return None;
+        }
 
-        window
-            .spawn(cx, async move |window| {
-                _ = view.update_in(window, |this, window, cx| {
-                    _ = this.editor.update(cx, |this, cx| {
-                        this.set_highlighter(language.name(), cx);
-                        this.set_value(content, window, cx);
-                    });
+        let line = |text: &str, color: Hsla| {
+            div()
+                .px_2()
+                .bg(color.opacity(0.15))
+                .text_color(color)
+                .child(text.trim_end_matches(['\n', '\r']).to_string())
+        };
 
-                    this.language = language;
-                    this.current_file_path = Some(path_clone);
-                    this.has_opened_file = true;
-                    cx.notify();
-                });
+        let rows: Vec<_> = hunks
+            .iter()
+            .enumerate()
+            .map(|(ix, hunk)| {
+                let start = Position::new(hunk.new.start as u32, 0);
+                v_flex()
+                    .w_full()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        h_flex()
+                            .id(("agent-edit", ix))
+                            .w_full()
+                            .gap_2()
+                            .px_2()
+                            .py_0p5()
+                            .cursor_pointer()
+                            .hover(|this| this.bg(cx.theme().accent.opacity(0.3)))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(
+                                        t!(
+                                            "code_editor.agent_edits.hunk",
+                                            line = hunk.new.start + 1,
+                                            added = hunk.new_lines.len(),
+                                            removed = hunk.old_lines.len()
+                                        )
+                                        .to_string(),
+                                    ),
+                            )
+                            .child(
+                                Button::new(("agent-edit-accept", ix))
+                                    .icon(IconName::Check)
+                                    .ghost()
+                                    .xsmall()
+                                    .tooltip(t!("code_editor.agent_edits.accept").to_string())
+                                    .on_click(cx.listener({
+                                        let editor = editor.clone();
+                                        move |this, _, window, cx| {
+                                            cx.stop_propagation();
+                                            this.accept_agent_edit(&editor, Some(ix), window, cx);
+                                        }
+                                    })),
+                            )
+                            .child(
+                                Button::new(("agent-edit-reject", ix))
+                                    .icon(IconName::Close)
+                                    .ghost()
+                                    .xsmall()
+                                    .tooltip(t!("code_editor.agent_edits.reject").to_string())
+                                    .on_click(cx.listener({
+                                        let editor = editor.clone();
+                                        move |this, _, window, cx| {
+                                            cx.stop_propagation();
+                                            this.reject_agent_edit(&editor, Some(ix), window, cx);
+                                        }
+                                    })),
+                            )
+                            .on_click({
+                                let editor = editor.clone();
+                                move |_, window, cx| {
+                                    editor.update(cx, |state, cx| {
+                                        state.set_cursor_position(start, window, cx);
+                                        state.focus(window, cx);
+                                    });
+                                }
+                            }),
+                    )
+                    .child(
+                        v_flex()
+                            .w_full()
+                            .font_family(cx.theme().mono_font_family.clone())
+                            .text_xs()
+                            .children(hunk.old_lines.iter().map(|text| line(text, cx.theme().red)))
+                            .children(
+                                hunk.new_lines
+                                    .iter()
+                                    .map(|text| line(text, cx.theme().green)),
+                            ),
+                    )
             })
-            .detach();
+            .collect();
 
-        Ok(())
+        Some(
+            v_flex()
+                .w_full()
+                .max_h(px(240.))
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_2()
+                        .py_1()
+                        .bg(cx.theme().secondary)
+                        .child(div().flex_1().text_sm().child(
+                            t!("code_editor.agent_edits.title", count = hunks.len()).to_string(),
+                        ))
+                        .child(
+                            Button::new("agent-edits-accept-all")
+                                .label(t!("code_editor.agent_edits.accept_all").to_string())
+                                .primary()
+                                .xsmall()
+                                .on_click(cx.listener({
+                                    let editor = editor.clone();
+                                    move |this, _, window, cx| {
+                                        this.accept_agent_edit(&editor, None, window, cx);
+                                    }
+                                })),
+                        )
+                        .child(
+                            Button::new("agent-edits-reject-all")
+                                .label(t!("code_editor.agent_edits.reject_all").to_string())
+                                .xsmall()
+                                .on_click(cx.listener({
+                                    let editor = editor.clone();
+                                    move |this, _, window, cx| {
+                                        this.reject_agent_edit(&editor, None, window, cx);
+                                    }
+                                })),
+                        ),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_h_0()
+                        .child(div().size_full().overflow_y_scrollbar().children(rows)),
+                ),
+        )
     }
 
     fn render_file_tree(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
@@ -396,6 +1445,170 @@ impl CodeEditorPanel {
             }))
     }
 
+    fn render_outline_button(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        Button::new("toggle-outline")
+            .ghost()
+            .xsmall()
+            .tooltip(t!("code_editor.tooltip.outline").to_string())
+            .child(
+                Icon::new(crate::assets::Icon::ListTree)
+                    .size(px(16.))
+                    .text_color(if self.show_outline {
+                        cx.theme().accent_foreground
+                    } else {
+                        cx.theme().muted_foreground
+                    }),
+            )
+            .on_click(cx.listener(|this, _, _, cx| {
+                this.show_outline = !this.show_outline;
+                cx.notify();
+            }))
+    }
+
+    /// Symbols of the active file; the one under the cursor is highlighted
+    fn render_outline(
+        &self,
+        editor: &Entity<InputState>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let symbols = self
+            .active()
+            .map(|buffer| buffer.symbols.as_slice())
+            .unwrap_or_default();
+        let current = symbol_at(symbols, editor.read(cx).cursor()).map(|s| s.range.clone());
+
+        let rows: Vec<_> = symbols
+            .iter()
+            .enumerate()
+            .map(|(ix, symbol)| {
+                let selected = current.as_ref() == Some(&symbol.range);
+                let range = symbol.range.clone();
+
+                h_flex()
+                    .id(("outline-symbol", ix))
+                    .w_full()
+                    .gap_2()
+                    .py_0p5()
+                    .pr_1()
+                    .pl(px(8. + 12. * symbol.depth as f32))
+                    .rounded(cx.theme().radius)
+                    .cursor_pointer()
+                    .when(selected, |this| this.bg(cx.theme().accent.opacity(0.5)))
+                    .hover(|this| this.bg(cx.theme().accent.opacity(0.3)))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(symbol.kind.label()),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .text_ellipsis()
+                            .child(symbol.name.clone()),
+                    )
+                    .child(
+                        Button::new(("outline-attach", ix))
+                            .icon(IconName::Plus)
+                            .ghost()
+                            .xsmall()
+                            .tooltip(t!("code_editor.tooltip.attach_symbol").to_string())
+                            .on_click(cx.listener({
+                                let range = range.clone();
+                                move |this, _, window, cx| {
+                                    cx.stop_propagation();
+                                    this.attach_symbol_to_chat(range.clone(), window, cx);
+                                }
+                            })),
+                    )
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.go_to_offset(range.start, window, cx);
+                    }))
+            })
+            .collect();
+
+        v_flex()
+            .w(px(220.))
+            .h_full()
+            .border_l_1()
+            .border_color(cx.theme().border)
+            .bg(cx.theme().sidebar)
+            .text_color(cx.theme().sidebar_foreground)
+            .child(
+                div()
+                    .px_2()
+                    .py_1()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(t!("code_editor.outline.title").to_string()),
+            )
+            .child(
+                div().flex_1().min_h_0().child(
+                    div()
+                        .size_full()
+                        .overflow_y_scrollbar()
+                        .p_1()
+                        .when(symbols.is_empty(), |this| {
+                            this.child(
+                                div()
+                                    .px_2()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(t!("code_editor.outline.empty").to_string()),
+                            )
+                        })
+                        .child(v_flex().w_full().children(rows)),
+                ),
+            )
+    }
+
+    /// Move the cursor of the active editor to a byte offset
+    fn go_to_offset(&mut self, offset: usize, window: &mut Window, cx: &mut Context<Self>) {
+        use gpui_component::input::RopeExt;
+
+        let Some(editor) = self.active_editor() else {
+            return;
+        };
+        editor.update(cx, |state, cx| {
+            let position = state.text().offset_to_position(offset);
+            state.set_cursor_position(position, window, cx);
+            state.focus(window, cx);
+        });
+    }
+
+    /// Send the definition of a symbol to the chat input as a code selection
+    fn attach_symbol_to_chat(
+        &mut self,
+        range: std::ops::Range<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        use gpui_component::input::RopeExt;
+
+        let Some(editor) = self.active_editor() else {
+            return;
+        };
+        let selection = {
+            let text = editor.read(cx).text();
+            let end = range.end.min(text.len());
+            (
+                text.offset_to_position(range.start.min(end)),
+                text.offset_to_position(end),
+            )
+        };
+        self.add_selection_to_chat(window, cx, selection);
+    }
+
+    fn render_quick_open_button(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        Button::new("quick-open")
+            .icon(IconName::Search)
+            .ghost()
+            .xsmall()
+            .tooltip(t!("code_editor.tooltip.quick_open").to_string())
+            .on_click(cx.listener(|this, _, window, cx| this.quick_open(&QuickOpen, window, cx)))
+    }
+
     fn render_line_number_button(
         &self,
         _: &mut Window,
@@ -416,9 +1629,11 @@ impl CodeEditorPanel {
             )
             .on_click(cx.listener(|this, _, window, cx| {
                 this.line_number = !this.line_number;
-                this.editor.update(cx, |state, cx| {
-                    state.set_line_number(this.line_number, window, cx);
-                });
+                for buffer in &this.buffers {
+                    buffer.editor.update(cx, |state, cx| {
+                        state.set_line_number(this.line_number, window, cx);
+                    });
+                }
                 cx.notify();
             }))
     }
@@ -439,9 +1654,11 @@ impl CodeEditorPanel {
             )
             .on_click(cx.listener(|this, _, window, cx| {
                 this.soft_wrap = !this.soft_wrap;
-                this.editor.update(cx, |state, cx| {
-                    state.set_soft_wrap(this.soft_wrap, window, cx);
-                });
+                for buffer in &this.buffers {
+                    buffer.editor.update(cx, |state, cx| {
+                        state.set_soft_wrap(this.soft_wrap, window, cx);
+                    });
+                }
                 cx.notify();
             }))
     }
@@ -466,20 +1683,23 @@ impl CodeEditorPanel {
             )
             .on_click(cx.listener(|this, _, window, cx| {
                 this.indent_guides = !this.indent_guides;
-                this.editor.update(cx, |state, cx| {
-                    state.set_indent_guides(this.indent_guides, window, cx);
-                });
+                for buffer in &this.buffers {
+                    buffer.editor.update(cx, |state, cx| {
+                        state.set_indent_guides(this.indent_guides, window, cx);
+                    });
+                }
                 cx.notify();
             }))
     }
 
     fn render_go_to_line_button(
         &self,
+        editor: &Entity<InputState>,
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        let position = self.editor.read(cx).cursor_position();
-        let cursor = self.editor.read(cx).cursor();
+        let position = editor.read(cx).cursor_position();
+        let cursor = editor.read(cx).cursor();
 
         Button::new("line-column")
             .ghost()
@@ -552,9 +1772,12 @@ impl CodeEditorPanel {
         use gpui_component::input::RopeExt;
 
         let (start_pos, end_pos) = selection;
+        let Some(buffer) = self.active() else {
+            return;
+        };
 
         // 获取选中的文本内容
-        let content = self.editor.update(cx, |state, _cx| {
+        let content = buffer.editor.update(cx, |state, _cx| {
             let text = state.text();
             let start_offset = text.position_to_offset(&start_pos);
             let end_offset = text.position_to_offset(&end_pos);
@@ -562,12 +1785,7 @@ impl CodeEditorPanel {
         });
 
         // 获取当前文件路径
-        let file_path = self
-            .current_file_path
-            .as_ref()
-            .and_then(|p| p.to_str())
-            .unwrap_or("untitled")
-            .to_string();
+        let file_path = buffer.path.to_str().unwrap_or("untitled").to_string();
 
         log::info!(
             "[CodeEditorPanel] Creating AddCodeSelection action - file: {}, start: {}:{}, end: {}:{}, content length: {}",
@@ -599,6 +1817,28 @@ impl CodeEditorPanel {
         log::info!("[CodeEditorPanel] Code selection event published");
     }
 
+    fn render_tabs(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        TabBar::new("code-editor-tabs")
+            .w_full()
+            .selected_index(self.active_buffer)
+            .on_click(cx.listener(|this, ix: &usize, window, cx| {
+                this.activate_buffer(*ix, window, cx);
+            }))
+            .children(self.buffers.iter().enumerate().map(|(ix, buffer)| {
+                Tab::new().label(buffer.title()).suffix(
+                    Button::new(("close-tab", ix))
+                        .icon(IconName::Close)
+                        .ghost()
+                        .xsmall()
+                        .tooltip(t!("code_editor.tooltip.close_tab").to_string())
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            cx.stop_propagation();
+                            this.close_buffer(ix, window, cx);
+                        })),
+                )
+            }))
+    }
+
     fn render_empty_state(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         v_flex()
             .size_full()
@@ -637,56 +1877,77 @@ impl Render for CodeEditorPanel {
         use gpui_component::input::RopeExt;
 
         // Update diagnostics
-        // if self.lsp_store.is_dirty() {
-        //     let diagnostics = self.lsp_store.diagnostics();
-        //     self.editor.update(cx, |state, cx| {
-        //         state.diagnostics_mut().map(|set| {
-        //             set.clear();
-        //             set.extend(diagnostics);
-        //         });
-        //         cx.notify();
-        //     });
-        // }
+        for buffer in &self.buffers {
+            if !buffer.lsp_store.take_dirty() {
+                continue;
+            }
+            let diagnostics = buffer.lsp_store.diagnostics();
+            buffer.editor.update(cx, |state, cx| {
+                if let Some(set) = state.diagnostics_mut() {
+                    set.clear();
+                    set.extend(diagnostics);
+                }
+                cx.notify();
+            });
+        }
+
+        let active_editor = self.active_editor();
 
         // 提取选择范围信息
-        let selection_info = self.editor.update(cx, |state, cx| {
-            let selection_utf16 = state.selected_text_range(false, window, cx);
+        let selection_info = active_editor.as_ref().and_then(|editor| {
+            editor.update(cx, |state, cx| {
+                let selection_utf16 = state.selected_text_range(false, window, cx);
 
-            if let Some(utf16_sel) = selection_utf16 {
-                let range = utf16_sel.range;
+                if let Some(utf16_sel) = selection_utf16 {
+                    let range = utf16_sel.range;
 
-                // 如果选择范围为空（只有光标）
-                if range.start == range.end {
-                    return None;
-                }
+                    // 如果选择范围为空（只有光标）
+                    if range.start == range.end {
+                        return None;
+                    }
 
-                // 将 UTF-16 偏移量转换回字节偏移量
-                let text = state.text();
-                let start_offset = text.offset_utf16_to_offset(range.start);
-                let end_offset = text.offset_utf16_to_offset(range.end);
+                    // 将 UTF-16 偏移量转换回字节偏移量
+                    let text = state.text();
+                    let start_offset = text.offset_utf16_to_offset(range.start);
+                    let end_offset = text.offset_utf16_to_offset(range.end);
 
-                // 转换为行列位置
-                let start_pos = text.offset_to_position(start_offset);
-                let end_pos = text.offset_to_position(end_offset);
-                let length = end_offset - start_offset;
+                    // 转换为行列位置
+                    let start_pos = text.offset_to_position(start_offset);
+                    let end_pos = text.offset_to_position(end_offset);
+                    let length = end_offset - start_offset;
 
-                Some((start_pos, end_pos, length))
-            } else {
-                None
-            }
+                    Some((start_pos, end_pos, length))
+                } else {
+                    None
+                }
+            })
         });
 
-        let editor_input = Input::new(&self.editor)
-            .bordered(false)
-            .p_0()
-            .h_full()
-            .font_family(cx.theme().mono_font_family.clone())
-            .text_size(cx.theme().mono_font_size)
-            .focus_bordered(false)
-            .into_any_element();
-
         // 根据是否打开文件决定显示内容
-        let main_content = if self.has_opened_file {
+        let main_content = if let Some(editor) = &active_editor {
+            let editor_input = h_flex()
+                .size_full()
+                .child(
+                    v_flex()
+                        .flex_1()
+                        .h_full()
+                        .child(self.render_tabs(cx))
+                        .children(self.render_agent_edits(editor, cx))
+                        .child(
+                            Input::new(editor)
+                                .bordered(false)
+                                .p_0()
+                                .flex_1()
+                                .font_family(cx.theme().mono_font_family.clone())
+                                .text_size(cx.theme().mono_font_size)
+                                .focus_bordered(false),
+                        ),
+                )
+                .when(self.show_outline, |this| {
+                    this.child(self.render_outline(editor, cx))
+                })
+                .into_any_element();
+
             // 已打开文件，显示编辑器
             if self.show_file_tree {
                 h_resizable("editor-container")
@@ -719,38 +1980,86 @@ impl Render for CodeEditorPanel {
             }
         };
 
-        v_flex().id("app").size_full().child(
-            v_flex()
-                .id("source")
-                .w_full()
-                .flex_1()
-                .child(main_content)
-                .child(
-                    h_flex()
-                        .justify_between()
-                        .text_sm()
-                        .bg(cx.theme().background)
-                        // .py_1p5()
-                        .h(px(30.))
-                        .px_4()
-                        .border_t_1()
-                        .border_color(cx.theme().border)
-                        .text_color(cx.theme().muted_foreground)
-                        .child(
-                            h_flex()
-                                .gap_3()
-                                .child(self.render_toggle_file_tree_button(window, cx))
-                                .child(self.render_line_number_button(window, cx))
-                                .child(self.render_soft_wrap_button(window, cx))
-                                .child(self.render_indent_guides_button(window, cx)),
-                        )
-                        .child(
-                            h_flex()
-                                .gap_3()
-                                .child(self.render_selection_range_info(window, cx, selection_info))
-                                .child(self.render_go_to_line_button(window, cx)),
-                        ),
-                ),
-        )
+        v_flex()
+            .id("app")
+            .key_context("CodeEditor")
+            .on_action(cx.listener(Self::save))
+            .on_action(cx.listener(Self::save_as))
+            .on_action(cx.listener(Self::quick_open))
+            .on_action(cx.listener(Self::go_to_symbol))
+            .size_full()
+            .child(
+                v_flex()
+                    .id("source")
+                    .w_full()
+                    .flex_1()
+                    .child(main_content)
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .text_sm()
+                            .bg(cx.theme().background)
+                            // .py_1p5()
+                            .h(px(30.))
+                            .px_4()
+                            .border_t_1()
+                            .border_color(cx.theme().border)
+                            .text_color(cx.theme().muted_foreground)
+                            .child(
+                                h_flex()
+                                    .gap_3()
+                                    .child(self.render_toggle_file_tree_button(window, cx))
+                                    .child(self.render_quick_open_button(window, cx))
+                                    .child(self.render_outline_button(window, cx))
+                                    .child(self.render_line_number_button(window, cx))
+                                    .child(self.render_soft_wrap_button(window, cx))
+                                    .child(self.render_indent_guides_button(window, cx)),
+                            )
+                            .child(
+                                h_flex()
+                                    .gap_3()
+                                    .child(self.render_selection_range_info(
+                                        window,
+                                        cx,
+                                        selection_info,
+                                    ))
+                                    .when_some(active_editor.as_ref(), |this, editor| {
+                                        this.child(
+                                            self.render_go_to_line_button(editor, window, cx),
+                                        )
+                                    }),
+                            ),
+                    ),
+            )
     }
 }
+
+/// How to resolve a file rewritten on disk while the buffer has unsaved changes
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExternalChange {
+    /// Discard the buffer and load the disk version
+    Reload,
+    /// Three-way merge of the buffer and the disk version
+    Merge,
+    /// Keep the buffer; saving overwrites the disk version
+    KeepBuffer,
+}
+
+/// Diffs carried by a tool call or tool call update
+fn agent_diffs(update: &acp::SessionUpdate) -> Vec<acp::Diff> {
+    let content: &[acp::ToolCallContent] = match update {
+        acp::SessionUpdate::ToolCall(tool_call) => &tool_call.content,
+        acp::SessionUpdate::ToolCallUpdate(update) => {
+            update.fields.content.as_deref().unwrap_or(&[])
+        }
+        _ => return Vec::new(),
+    };
+
+    content
+        .iter()
+        .filter_map(|content| match content {
+            acp::ToolCallContent::Diff(diff) => Some(diff.clone()),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Files open in a code editor panel, restored when the panel is recreated
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EditorSession {
+    pub files: Vec<EditorSessionFile>,
+    /// Index of the active tab in `files`
+    #[serde(default)]
+    pub active: usize,
+}
+
+/// An open file with the cursor position to restore (0-based)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorSessionFile {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub line: u32,
+    #[serde(default)]
+    pub character: u32,
+}
+
+/// All editor sessions, keyed by working directory
+type EditorSessions = HashMap<String, EditorSession>;
+
+fn read_sessions() -> EditorSessions {
+    let path = crate::core::config_manager::get_editor_sessions_path();
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Load the session saved for a working directory
+pub fn load_session(working_dir: &Path) -> Option<EditorSession> {
+    read_sessions()
+        .remove(working_dir.to_string_lossy().as_ref())
+        .filter(|session| !session.files.is_empty())
+}
+
+/// Save the session of a working directory, removing it when no file is open
+pub fn save_session(working_dir: &Path, session: EditorSession) -> Result<()> {
+    let mut sessions = read_sessions();
+    let key = working_dir.to_string_lossy().to_string();
+    if session.files.is_empty() {
+        if sessions.remove(&key).is_none() {
+            return Ok(());
+        }
+    } else {
+        sessions.insert(key, session);
+    }
+
+    let path = crate::core::config_manager::get_editor_sessions_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&sessions)?)?;
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use gpui::{App, Context, ParentElement, Styled, Task, Window, div};
+use gpui_component::{
+    ActiveTheme, IndexPath, h_flex,
+    list::{ListDelegate, ListItem, ListState},
+};
+use tokio::sync::mpsc;
+
+use super::symbols::{Symbol, SymbolLanguage, extract_symbols};
+use crate::core::services::{IndexEntry, fuzzy_match};
+
+/// Maximum number of symbols listed for a query
+const MAX_RESULTS: usize = 200;
+/// Files larger than this are not parsed
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// A symbol of a workspace file
+#[derive(Debug, Clone)]
+pub(super) struct WorkspaceSymbol {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub symbol: Symbol,
+}
+
+/// Parsed symbols by file, reused while the file is unchanged
+pub(super) type SymbolCache = Arc<Mutex<HashMap<PathBuf, (SystemTime, Vec<Symbol>)>>>;
+
+/// Extract the symbols of the indexed files, skipping unchanged files
+pub(super) fn scan_workspace_symbols(
+    files: Vec<IndexEntry>,
+    cache: &SymbolCache,
+) -> Vec<WorkspaceSymbol> {
+    let mut symbols = Vec::new();
+    let mut cache = cache.lock().unwrap();
+
+    for file in files {
+        let Some(language) = SymbolLanguage::from_path(&file.path) else {
+            continue;
+        };
+        let Ok(metadata) = std::fs::metadata(&file.path) else {
+            continue;
+        };
+        if metadata.len() > MAX_FILE_SIZE {
+            continue;
+        }
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+
+        let file_symbols = match cache.get(&file.path) {
+            Some((cached_at, symbols)) if *cached_at == modified => symbols.clone(),
+            _ => {
+                let Ok(text) = std::fs::read_to_string(&file.path) else {
+                    continue;
+                };
+                let symbols = extract_symbols(language, &text);
+                cache.insert(file.path.clone(), (modified, symbols.clone()));
+                symbols
+            }
+        };
+
+        symbols.extend(file_symbols.into_iter().map(|symbol| WorkspaceSymbol {
+            path: file.path.clone(),
+            relative_path: file.relative_path.clone(),
+            symbol,
+        }));
+    }
+
+    symbols
+}
+
+/// Delegate for the "go to symbol in workspace" picker
+pub(super) struct SymbolPickerDelegate {
+    symbols: Vec<WorkspaceSymbol>,
+    /// Indices into `symbols` matching the query, best first
+    filtered: Vec<usize>,
+    query: String,
+    loading: bool,
+    selected_index: Option<usize>,
+    selection_tx: mpsc::UnboundedSender<WorkspaceSymbol>,
+}
+
+impl SymbolPickerDelegate {
+    pub fn new(selection_tx: mpsc::UnboundedSender<WorkspaceSymbol>) -> Self {
+        Self {
+            symbols: Vec::new(),
+            filtered: Vec::new(),
+            query: String::new(),
+            loading: false,
+            selected_index: None,
+            selection_tx,
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading
+    }
+
+    pub fn set_loading(&mut self) {
+        self.loading = true;
+    }
+
+    pub fn set_symbols(&mut self, symbols: Vec<WorkspaceSymbol>) {
+        self.symbols = symbols;
+        self.loading = false;
+        self.filter();
+    }
+
+    fn filter(&mut self) {
+        let query = self.query.trim();
+        if query.is_empty() {
+            self.filtered = (0..self.symbols.len().min(MAX_RESULTS)).collect();
+            return;
+        }
+
+        let mut scored: Vec<(i64, usize)> = self
+            .symbols
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, item)| {
+                fuzzy_match(query, &item.symbol.name).map(|(score, _)| (score, ix))
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            b.0.cmp(&a.0).then_with(|| {
+                self.symbols[a.1]
+                    .symbol
+                    .name
+                    .len()
+                    .cmp(&self.symbols[b.1].symbol.name.len())
+            })
+        });
+        self.filtered = scored
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, ix)| ix)
+            .collect();
+    }
+}
+
+impl ListDelegate for SymbolPickerDelegate {
+    type Item = ListItem;
+
+    fn perform_search(
+        &mut self,
+        query: &str,
+        _: &mut Window,
+        _: &mut Context<ListState<Self>>,
+    ) -> Task<()> {
+        self.query = query.to_string();
+        self.filter();
+        Task::ready(())
+    }
+
+    fn items_count(&self, _: usize, _: &App) -> usize {
+        self.filtered.len()
+    }
+
+    fn render_item(
+        &mut self,
+        ix: IndexPath,
+        _window: &mut Window,
+        cx: &mut Context<'_, ListState<Self>>,
+    ) -> Option<Self::Item> {
+        let item = self.symbols.get(*self.filtered.get(ix.row)?)?;
+        let theme = cx.theme();
+
+        Some(
+            ListItem::new(ix).w_full().py_1().px_2().child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(theme.muted_foreground)
+                                    .child(item.symbol.kind.label()),
+                            )
+                            .child(div().text_sm().child(item.symbol.name.clone())),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(format!("{}:{}", item.relative_path, item.symbol.line + 1)),
+                    ),
+            ),
+        )
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: Option<IndexPath>,
+        _: &mut Window,
+        _: &mut Context<ListState<Self>>,
+    ) {
+        self.selected_index = ix.map(|i| i.row);
+    }
+
+    fn confirm(&mut self, _: bool, _window: &mut Window, _cx: &mut Context<ListState<Self>>) {
+        let item = self
+            .selected_index
+            .and_then(|row| self.filtered.get(row))
+            .and_then(|ix| self.symbols.get(*ix));
+        if let Some(item) = item {
+            let _ = self.selection_tx.send(item.clone());
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::{ops::Range, path::Path};
+
+use tree_sitter::{Node, Parser};
+
+/// Languages with a symbol extractor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl SymbolLanguage {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        Some(match ext {
+            "rs" => Self::Rust,
+            "py" | "pyi" => Self::Python,
+            "js" | "jsx" | "mjs" | "cjs" => Self::JavaScript,
+            "ts" | "mts" | "cts" => Self::TypeScript,
+            "tsx" => Self::Tsx,
+            "go" => Self::Go,
+            _ => return None,
+        })
+    }
+
+    fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Interface,
+    Class,
+    Impl,
+    Module,
+    Type,
+    Constant,
+}
+
+impl SymbolKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Function => "fn",
+            Self::Method => "method",
+            Self::Struct => "struct",
+            Self::Enum => "enum",
+            Self::Trait => "trait",
+            Self::Interface => "interface",
+            Self::Class => "class",
+            Self::Impl => "impl",
+            Self::Module => "mod",
+            Self::Type => "type",
+            Self::Constant => "const",
+        }
+    }
+
+    /// Whether the symbol's body may contain other symbols
+    fn is_container(self) -> bool {
+        matches!(
+            self,
+            Self::Impl | Self::Trait | Self::Interface | Self::Class | Self::Module
+        )
+    }
+}
+
+/// A definition found in a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Nesting level, 0 for top-level symbols
+    pub depth: usize,
+    /// Byte range of the whole definition
+    pub range: Range<usize>,
+    /// 0-based line and character of the start of the definition
+    pub line: u32,
+    pub column: u32,
+    /// 0-based line of the end of the definition
+    pub end_line: u32,
+}
+
+/// Extract the definitions of a source file, in document order
+pub fn extract_symbols(language: SymbolLanguage, text: &str) -> Vec<Symbol> {
+    let mut parser = Parser::new();
+    if let Err(e) = parser.set_language(&language.grammar()) {
+        log::warn!("Failed to load the {:?} grammar: {}", language, e);
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(text, None) else {
+        return Vec::new();
+    };
+
+    let mut symbols = Vec::new();
+    collect(language, tree.root_node(), text, 0, None, &mut symbols);
+    symbols
+}
+
+/// The innermost symbol containing a byte offset
+pub fn symbol_at(symbols: &[Symbol], offset: usize) -> Option<&Symbol> {
+    symbols
+        .iter()
+        .filter(|symbol| symbol.range.contains(&offset))
+        .max_by_key(|symbol| symbol.depth)
+}
+
+/// Whether a piece of code starts with a definition (function, type, ...)
+pub fn starts_with_definition(language: SymbolLanguage, code: &str) -> bool {
+    let start = code.len() - code.trim_start().len();
+    extract_symbols(language, code)
+        .first()
+        .is_some_and(|symbol| symbol.range.start <= start)
+}
+
+fn collect(
+    language: SymbolLanguage,
+    node: Node,
+    text: &str,
+    depth: usize,
+    container: Option<SymbolKind>,
+    symbols: &mut Vec<Symbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match definition(language, child, text, container) {
+            Some((kind, name)) => {
+                let start = child.start_position();
+                let line_start = child.start_byte() - start.column;
+                symbols.push(Symbol {
+                    name,
+                    kind,
+                    depth,
+                    range: child.byte_range(),
+                    line: start.row as u32,
+                    column: text
+                        .get(line_start..child.start_byte())
+                        .map_or(0, |prefix| prefix.chars().count() as u32),
+                    end_line: child.end_position().row as u32,
+                });
+                if kind.is_container() {
+                    collect(language, child, text, depth + 1, Some(kind), symbols);
+                }
+            }
+            // Look through wrappers such as `export`, decorators and bodies
+            None => collect(language, child, text, depth, container, symbols),
+        }
+    }
+}
+
+/// The kind and name of a definition node
+fn definition(
+    language: SymbolLanguage,
+    node: Node,
+    text: &str,
+    container: Option<SymbolKind>,
+) -> Option<(SymbolKind, String)> {
+    let name = |field: &str| {
+        node.child_by_field_name(field)
+            .and_then(|n| n.utf8_text(text.as_bytes()).ok())
+            .map(|s| s.to_string())
+    };
+    let function = if container.is_some_and(SymbolKind::is_container) {
+        SymbolKind::Method
+    } else {
+        SymbolKind::Function
+    };
+
+    let kind = match (language, node.kind()) {
+        (SymbolLanguage::Rust, "function_item" | "function_signature_item") => function,
+        (SymbolLanguage::Rust, "struct_item" | "union_item") => SymbolKind::Struct,
+        (SymbolLanguage::Rust, "enum_item") => SymbolKind::Enum,
+        (SymbolLanguage::Rust, "trait_item") => SymbolKind::Trait,
+        (SymbolLanguage::Rust, "mod_item") => SymbolKind::Module,
+        (SymbolLanguage::Rust, "type_item") => SymbolKind::Type,
+        (SymbolLanguage::Rust, "const_item" | "static_item") => SymbolKind::Constant,
+        (SymbolLanguage::Rust, "impl_item") => {
+            let ty = name("type")?;
+            let name = match name("trait") {
+                Some(trait_name) => format!("{} for {}", trait_name, ty),
+                None => ty,
+            };
+            return Some((SymbolKind::Impl, name));
+        }
+
+        (SymbolLanguage::Python, "function_definition") => function,
+        (SymbolLanguage::Python, "class_definition") => SymbolKind::Class,
+
+        (SymbolLanguage::JavaScript | SymbolLanguage::TypeScript | SymbolLanguage::Tsx, kind) => {
+            match kind {
+                "function_declaration" | "generator_function_declaration" => SymbolKind::Function,
+                "method_definition" | "method_signature" | "abstract_method_signature" => {
+                    SymbolKind::Method
+                }
+                "class_declaration" | "abstract_class_declaration" => SymbolKind::Class,
+                "interface_declaration" => SymbolKind::Interface,
+                "type_alias_declaration" => SymbolKind::Type,
+                "enum_declaration" => SymbolKind::Enum,
+                "internal_module" | "module" => SymbolKind::Module,
+                // `const handler = () => {}`
+                "variable_declarator" => {
+                    let value = node.child_by_field_name("value")?;
+                    if !matches!(
+                        value.kind(),
+                        "arrow_function" | "function_expression" | "function"
+                    ) {
+                        return None;
+                    }
+                    SymbolKind::Function
+                }
+                _ => return None,
+            }
+        }
+
+        (SymbolLanguage::Go, "function_declaration") => SymbolKind::Function,
+        (SymbolLanguage::Go, "method_declaration") => SymbolKind::Method,
+        (SymbolLanguage::Go, "type_spec") => match node.child_by_field_name("type")?.kind() {
+            "struct_type" => SymbolKind::Struct,
+            "interface_type" => SymbolKind::Interface,
+            _ => SymbolKind::Type,
+        },
+
+        _ => return None,
+    };
+
+    Some((kind, name("name")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(language: SymbolLanguage, text: &str) -> Vec<(String, SymbolKind, usize)> {
+        extract_symbols(language, text)
+            .into_iter()
+            .map(|s| (s.name, s.kind, s.depth))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_symbols() {
+        let text = "struct Foo;\nimpl Display for Foo {\n    fn fmt(&self) {}\n}\nfn main() {\n    fn nested() {}\n}\n";
+        assert_eq!(
+            names(SymbolLanguage::Rust, text),
+            vec![
+                ("Foo".to_string(), SymbolKind::Struct, 0),
+                ("Display for Foo".to_string(), SymbolKind::Impl, 0),
+                ("fmt".to_string(), SymbolKind::Method, 1),
+                ("main".to_string(), SymbolKind::Function, 0),
+            ]
+        );
+
+        let symbols = extract_symbols(SymbolLanguage::Rust, text);
+        assert_eq!(symbols[2].line, 2);
+        assert_eq!(symbols[2].column, 4);
+        let offset = text.find("&self").unwrap();
+        assert_eq!(symbol_at(&symbols, offset).unwrap().name, "fmt");
+    }
+
+    #[test]
+    fn test_python_and_typescript_symbols() {
+        let text = "@dataclass\nclass Point:\n    def norm(self):\n        pass\n";
+        assert_eq!(
+            names(SymbolLanguage::Python, text),
+            vec![
+                ("Point".to_string(), SymbolKind::Class, 0),
+                ("norm".to_string(), SymbolKind::Method, 1),
+            ]
+        );
+
+        let text = "export interface Props {}\nexport const render = () => null;\n";
+        assert_eq!(
+            names(SymbolLanguage::TypeScript, text),
+            vec![
+                ("Props".to_string(), SymbolKind::Interface, 0),
+                ("render".to_string(), SymbolKind::Function, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_starts_with_definition() {
+        assert!(starts_with_definition(
+            SymbolLanguage::Rust,
+            "  pub async fn run() {}"
+        ));
+        assert!(!starts_with_definition(
+            SymbolLanguage::Rust,
+            "let x = run();"
+        ));
+        assert!(starts_with_definition(
+            SymbolLanguage::Python,
+            "def f():\n    return 1\n"
+        ));
+    }
+}
@@ -4,24 +4,6 @@ use autocorrect::ignorer::Ignorer;
 use gpui_component::tree::TreeItem;
 use lsp_types::{CompletionItem, CompletionTextEdit, InsertReplaceEdit};
 
-// ============================================================================
-// Constants
-// ============================================================================
-
-pub const RUST_DOC_URLS: &[(&str, &str)] = &[
-    ("String", "string/struct.String"),
-    ("Debug", "fmt/trait.Debug"),
-    ("Clone", "clone/trait.Clone"),
-    ("Option", "option/enum.Option"),
-    ("Result", "result/enum.Result"),
-    ("Vec", "vec/struct.Vec"),
-    ("HashMap", "collections/hash_map/struct.HashMap"),
-    ("HashSet", "collections/hash_set/struct.HashSet"),
-    ("Arc", "sync/struct.Arc"),
-    ("RwLock", "sync/struct.RwLock"),
-    ("Duration", "time/struct.Duration"),
-];
-
 // ============================================================================
 // Helper Functions
 // ============================================================================
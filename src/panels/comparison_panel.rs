@@ -0,0 +1,393 @@
+use gpui::{
+    App, AppContext, Context, Entity, FocusHandle, Focusable, InteractiveElement, IntoElement,
+    ParentElement, Render, Styled, Window, div, prelude::FluentBuilder, px,
+};
+use gpui_component::{
+    ActiveTheme, Icon, IconName, Sizable,
+    button::{Button, ButtonVariants},
+    h_flex, v_flex,
+};
+use rust_i18n::t;
+
+use crate::{
+    AppState, ConversationPanel, DiffSummary, PickComparisonWinner,
+    components::{DiffSummaryData, FileChangeStats},
+    core::event_bus::WorkspaceUpdateEvent,
+    core::services::SessionStatus,
+    panels::dock_panel::DockPanel,
+    schemas::workspace_schema::{TaskComparison, WorkspaceTask},
+};
+
+/// A candidate of the comparison and the views showing its progress
+struct Candidate {
+    task: WorkspaceTask,
+    diff_summary: Entity<DiffSummary>,
+    /// Created once the task queue has started a session for the task
+    conversation: Option<Entity<ConversationPanel>>,
+}
+
+/// Panel showing the candidates of a multi-agent comparison side by side
+///
+/// Each column shows one agent's conversation with the combined diff summary of
+/// its task, and lets the user pick the winner to merge.
+pub struct ComparisonPanel {
+    focus_handle: FocusHandle,
+    comparison_id: Option<String>,
+    comparison: Option<TaskComparison>,
+    candidates: Vec<Candidate>,
+}
+
+impl DockPanel for ComparisonPanel {
+    fn title() -> &'static str {
+        "Comparison"
+    }
+
+    fn title_key() -> Option<&'static str> {
+        Some("comparison.title")
+    }
+
+    fn description() -> &'static str {
+        "Compare how several agents handle the same prompt"
+    }
+
+    fn new_view(window: &mut Window, cx: &mut App) -> Entity<impl Render> {
+        Self::view(window, cx)
+    }
+}
+
+impl ComparisonPanel {
+    pub fn view(_window: &mut Window, cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| Self::new(None, cx))
+    }
+
+    pub fn view_for_comparison(
+        comparison_id: String,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        let entity = cx.new(|cx| Self::new(Some(comparison_id), cx));
+        Self::load(&entity, cx);
+        Self::subscribe_to_workspace_updates(&entity, cx);
+        entity
+    }
+
+    pub fn comparison_id(&self) -> Option<String> {
+        self.comparison_id.clone()
+    }
+
+    fn new(comparison_id: Option<String>, cx: &mut App) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            comparison_id,
+            comparison: None,
+            candidates: Vec::new(),
+        }
+    }
+
+    /// Reload the comparison and its candidate tasks
+    fn load(entity: &Entity<Self>, cx: &mut App) {
+        let Some(comparison_id) = entity.read(cx).comparison_id.clone() else {
+            return;
+        };
+        let Some(workspace_service) = AppState::global(cx).workspace_service().cloned() else {
+            log::warn!("WorkspaceService not available for comparison panel");
+            return;
+        };
+
+        let weak_entity = entity.downgrade();
+        cx.spawn(async move |cx| {
+            let comparison = workspace_service.get_comparison(&comparison_id).await;
+            let tasks = workspace_service.get_comparison_tasks(&comparison_id).await;
+
+            if let Some(entity) = weak_entity.upgrade() {
+                cx.update(|cx| {
+                    entity.update(cx, |this, cx| {
+                        this.comparison = comparison;
+                        this.apply_tasks(tasks, cx);
+                        cx.notify();
+                    });
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Update the candidates from freshly loaded tasks, keeping existing views
+    fn apply_tasks(&mut self, tasks: Vec<WorkspaceTask>, cx: &mut Context<Self>) {
+        let mut previous = std::mem::take(&mut self.candidates);
+
+        self.candidates = tasks
+            .into_iter()
+            .map(|task| {
+                let data = diff_summary_data(&task);
+                match previous.iter().position(|c| c.task.id == task.id) {
+                    Some(pos) => {
+                        let mut candidate = previous.remove(pos);
+                        candidate
+                            .diff_summary
+                            .update(cx, |summary, cx| summary.update_data(data, cx));
+                        // The queue may have retried the task in a new session
+                        if candidate.task.session_id != task.session_id {
+                            candidate.conversation = None;
+                        }
+                        candidate.task = task;
+                        candidate
+                    }
+                    None => Candidate {
+                        task,
+                        diff_summary: cx.new(|_| DiffSummary::new(data)),
+                        conversation: None,
+                    },
+                }
+            })
+            .collect();
+    }
+
+    fn subscribe_to_workspace_updates(entity: &Entity<Self>, cx: &mut App) {
+        let event_hub = AppState::global(cx).event_hub().clone();
+        let weak_entity = entity.downgrade();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        event_hub.subscribe_workspace_updates(move |event| {
+            let _ = tx.send(event.clone());
+        });
+
+        cx.spawn(async move |cx| {
+            while let Some(event) = rx.recv().await {
+                let Some(entity) = weak_entity.upgrade() else {
+                    break;
+                };
+
+                cx.update(|cx| {
+                    let this = entity.read(cx);
+                    let is_relevant = match &event {
+                        WorkspaceUpdateEvent::TaskUpdated { task_id } => {
+                            this.candidates.iter().any(|c| &c.task.id == task_id)
+                        }
+                        WorkspaceUpdateEvent::SessionStatusUpdated { session_id, .. } => this
+                            .candidates
+                            .iter()
+                            .any(|c| c.task.session_id.as_ref() == Some(session_id)),
+                        WorkspaceUpdateEvent::ComparisonUpdated { comparison_id, .. } => {
+                            this.comparison_id.as_ref() == Some(comparison_id)
+                        }
+                        _ => false,
+                    };
+
+                    if is_relevant {
+                        Self::load(&entity, cx);
+                    }
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Create conversation views for candidates whose session has started
+    fn ensure_conversations(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        for candidate in &mut self.candidates {
+            if candidate.conversation.is_some() {
+                continue;
+            }
+            if let Some(session_id) = candidate.task.session_id.clone() {
+                candidate.conversation =
+                    Some(ConversationPanel::view_for_session(session_id, window, cx));
+            }
+        }
+    }
+
+    fn render_candidate(
+        &self,
+        idx: usize,
+        candidate: &Candidate,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let task = &candidate.task;
+        let winner_id = self
+            .comparison
+            .as_ref()
+            .and_then(|c| c.winner_task_id.as_ref());
+        let is_winner = winner_id == Some(&task.id);
+        let can_pick = winner_id.is_none()
+            && matches!(
+                task.status,
+                SessionStatus::Completed | SessionStatus::Idle | SessionStatus::Failed
+            );
+
+        let header = h_flex()
+            .w_full()
+            .gap_2()
+            .px_3()
+            .py_2()
+            .items_center()
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                v_flex()
+                    .flex_1()
+                    .min_w_0()
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(gpui::FontWeight::SEMIBOLD)
+                            .child(task.agent_name.clone()),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(status_label(&task.status)),
+                    )
+                    .when_some(task.branch.clone(), |this, branch| {
+                        this.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(branch),
+                        )
+                    }),
+            )
+            .when(is_winner, |this| {
+                this.child(
+                    h_flex()
+                        .gap_1()
+                        .items_center()
+                        .text_xs()
+                        .text_color(cx.theme().green)
+                        .child(Icon::new(IconName::CircleCheck).size(px(14.)))
+                        .child(t!("comparison.winner").to_string()),
+                )
+            })
+            .when(winner_id.is_none(), |this| {
+                let comparison_id = self.comparison_id.clone().unwrap_or_default();
+                let task_id = task.id.clone();
+                this.child(
+                    Button::new(("comparison-pick-winner", idx))
+                        .label(t!("comparison.button.pick_winner").to_string())
+                        .primary()
+                        .small()
+                        .disabled(!can_pick)
+                        .on_click(move |_, window, cx| {
+                            window.dispatch_action(
+                                Box::new(PickComparisonWinner {
+                                    comparison_id: comparison_id.clone(),
+                                    task_id: task_id.clone(),
+                                }),
+                                cx,
+                            );
+                        }),
+                )
+            });
+
+        let body = match &candidate.conversation {
+            Some(conversation) => div().flex_1().min_h_0().child(conversation.clone()),
+            None => div()
+                .flex_1()
+                .flex()
+                .items_center()
+                .justify_center()
+                .text_sm()
+                .text_color(cx.theme().muted_foreground)
+                .child(match &task.last_error {
+                    Some(error) => error.clone(),
+                    None => t!("comparison.waiting").to_string(),
+                }),
+        };
+
+        v_flex()
+            .id(("comparison-candidate", idx))
+            .flex_1()
+            .min_w(px(280.))
+            .h_full()
+            .border_1()
+            .rounded(cx.theme().radius)
+            .border_color(if is_winner {
+                cx.theme().green
+            } else {
+                cx.theme().border
+            })
+            .overflow_hidden()
+            .child(header)
+            .when(task.changes.has_changes(), |this| {
+                this.child(div().px_2().py_1().child(candidate.diff_summary.clone()))
+            })
+            .child(body)
+    }
+}
+
+impl Focusable for ComparisonPanel {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for ComparisonPanel {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        self.ensure_conversations(window, cx);
+
+        let title = self
+            .comparison
+            .as_ref()
+            .map(|c| c.name.clone())
+            .unwrap_or_default();
+
+        v_flex()
+            .size_full()
+            .track_focus(&self.focus_handle)
+            .bg(cx.theme().background)
+            .child(
+                h_flex()
+                    .w_full()
+                    .px_3()
+                    .py_2()
+                    .gap_2()
+                    .items_center()
+                    .child(Icon::new(IconName::LayoutDashboard).size(px(16.)))
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(gpui::FontWeight::SEMIBOLD)
+                            .child(title),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .id("comparison-candidates")
+                    .flex_1()
+                    .min_h_0()
+                    .w_full()
+                    .gap_2()
+                    .p_2()
+                    .overflow_x_scroll()
+                    .children(
+                        self.candidates
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, candidate)| self.render_candidate(idx, candidate, cx)),
+                    ),
+            )
+    }
+}
+
+/// Convert a task's tracked changes into diff summary data
+fn diff_summary_data(task: &WorkspaceTask) -> DiffSummaryData {
+    DiffSummaryData::from_file_stats(task.changes.files.iter().map(|file| FileChangeStats {
+        path: file.path.clone(),
+        additions: file.additions,
+        deletions: file.deletions,
+        is_new_file: file.is_new_file,
+    }))
+}
+
+fn status_label(status: &SessionStatus) -> String {
+    match status {
+        SessionStatus::Active => t!("task_panel.status.active"),
+        SessionStatus::Idle => t!("task_panel.status.idle"),
+        SessionStatus::Pending => t!("task_panel.status.pending"),
+        SessionStatus::InProgress => t!("task_panel.status.in_progress"),
+        SessionStatus::Completed => t!("task_panel.status.completed"),
+        SessionStatus::Failed => t!("task_panel.status.failed"),
+        SessionStatus::Closed => t!("task_panel.status.closed"),
+    }
+    .to_string()
+}
@@ -4,8 +4,8 @@ use gpui::{
 };
 
 use gpui_component::{
-    ActiveTheme, Icon, IconName, Sizable, StyledExt, h_flex, input::InputState, skeleton::Skeleton,
-    spinner::Spinner, v_flex,
+    ActiveTheme, Icon, IconName, Sizable, StyledExt, WindowExt as _, h_flex, input::InputState,
+    notification::Notification, skeleton::Skeleton, spinner::Spinner, v_flex,
 };
 
 // Use the published ACP schema crate
@@ -659,34 +659,59 @@ impl ConversationPanel {
     fn handle_paste(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
         log::info!("Handling paste in ConversationPanel");
 
-        let mut handled = false;
-        if let Some(clipboard_item) = cx.read_from_clipboard() {
-            for entry in clipboard_item.entries().iter() {
-                if let ClipboardEntry::Image(image) = entry {
-                    log::info!("Processing pasted image: {:?}", image.format);
-                    let image = image.clone();
-                    handled = true;
-
-                    cx.spawn_in(window, async move |this, cx| {
-                        match crate::utils::clipboard::image_to_content(image).await {
-                            Ok((image_content, filename)) => {
-                                _ = cx.update(move |_window, cx| {
-                                    let _ = this.update(cx, |this, cx| {
-                                        this.pasted_images.push((image_content, filename));
-                                        cx.notify();
-                                    });
-                                });
-                            }
-                            Err(e) => {
-                                log::error!("Failed to process pasted image: {}", e);
-                            }
-                        }
+        let images: Vec<_> = cx
+            .read_from_clipboard()
+            .map(|clipboard_item| {
+                clipboard_item
+                    .entries()
+                    .iter()
+                    .filter_map(|entry| match entry {
+                        ClipboardEntry::Image(image) => Some(image.clone()),
+                        _ => None,
                     })
-                    .detach();
+                    .collect()
+            })
+            .unwrap_or_default();
+        if images.is_empty() {
+            return false;
+        }
+
+        let agent_service = AppState::global(cx).agent_service().cloned();
+        let agent_name = self
+            .session_id
+            .as_ref()
+            .and_then(|session_id| agent_service.as_ref()?.get_agent_for_session(session_id));
+        cx.spawn_in(window, async move |this, cx| {
+            // Don't attach images the agent would refuse
+            if let Some(agent_name) = agent_name
+                && !crate::utils::clipboard::agent_accepts_images(agent_service, &agent_name).await
+            {
+                _ = cx.update(|window, cx| {
+                    let note = Notification::warning(
+                        t!("conversation.image_unsupported", agent = agent_name).to_string(),
+                    );
+                    window.push_notification(note, cx);
+                });
+                return;
+            }
+
+            for image in images {
+                log::info!("Processing pasted image: {:?}", image.format);
+                match crate::utils::clipboard::image_to_content(image).await {
+                    Ok((image_content, filename)) => {
+                        _ = this.update(cx, |this, cx| {
+                            this.pasted_images.push((image_content, filename));
+                            cx.notify();
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Failed to process pasted image: {}", e);
+                    }
                 }
             }
-        }
-        handled
+        })
+        .detach();
+        true
     }
 
     /// Send a message to the current session
@@ -15,8 +15,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::AppState;
 use crate::panels::{
-    CodeEditorPanel, ConversationPanel, SessionManagerPanel, SettingsPanel, TaskPanel,
-    TerminalPanel, ToolCallDetailPanel, WelcomePanel,
+    CodeEditorPanel, ComparisonPanel, ConversationPanel, EventJournalPanel, SearchPanel,
+    SessionManagerPanel, SettingsPanel, TaskPanel, TerminalPanel, ToolCallDetailPanel,
+    WelcomePanel,
 };
 use crate::{ShowPanelInfo, ToggleSearch};
 
@@ -319,6 +320,36 @@ impl DockPanelContainer {
         cx.notify();
     }
 
+    /// Create a ComparisonPanel showing the candidates of a comparison side by side
+    pub fn panel_for_comparison(
+        comparison_id: String,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        let name = ComparisonPanel::title();
+        let title_key = ComparisonPanel::title_key();
+        let description = ComparisonPanel::description();
+        let agent_studio = ComparisonPanel::view_for_comparison(comparison_id, window, cx);
+        let agent_studio_klass = ComparisonPanel::klass();
+
+        let view = cx.new(|cx| {
+            let mut container = Self::new(cx)
+                .agent_studio(agent_studio.into(), agent_studio_klass)
+                .on_active(ComparisonPanel::on_active_any);
+            container.focus_handle = cx.focus_handle();
+            container.closable = ComparisonPanel::closable();
+            container.zoomable = ComparisonPanel::zoomable();
+            container.name = name.into();
+            container.title_key = title_key.map(SharedString::from);
+            container.description = description.into();
+            container.title_bg = ComparisonPanel::title_bg();
+            container.paddings = ComparisonPanel::paddings();
+            container
+        });
+
+        view
+    }
+
     /// Create a WelcomePanel for a specific workspace
     /// This will display the workspace name when creating a new task
     pub fn panel_for_workspace(
@@ -425,8 +456,21 @@ impl DockPanelContainer {
         match agent_state.agent_studio_klass.as_ref() {
             "TaskPanel" => Self::panel::<TaskPanel>(window, cx),
             "SessionManagerPanel" => Self::panel::<SessionManagerPanel>(window, cx),
+            "SearchPanel" => Self::panel::<SearchPanel>(window, cx),
+            "EventJournalPanel" => Self::panel::<EventJournalPanel>(window, cx),
             "SettingsPanel" => Self::panel::<SettingsPanel>(window, cx),
             "ToolCallDetailPanel" => Self::panel::<ToolCallDetailPanel>(window, cx),
+            "ComparisonPanel" => {
+                if let Some(comparison_id) = agent_state
+                    .comparison_id
+                    .as_deref()
+                    .filter(|id| !id.is_empty())
+                {
+                    Self::panel_for_comparison(comparison_id.to_string(), window, cx)
+                } else {
+                    Self::panel::<ComparisonPanel>(window, cx)
+                }
+            }
             "ConversationPanel" => {
                 if let Some(session_id) = agent_state
                     .session_id
@@ -596,6 +640,8 @@ pub struct DockPanelState {
     pub workspace_name: Option<String>,
     #[serde(default)]
     pub working_directory: Option<String>,
+    #[serde(default)]
+    pub comparison_id: Option<String>,
 }
 
 impl DockPanelState {
@@ -606,6 +652,7 @@ impl DockPanelState {
             "workspace_id": self.workspace_id,
             "workspace_name": self.workspace_name,
             "working_directory": self.working_directory,
+            "comparison_id": self.comparison_id,
         })
     }
 
@@ -622,9 +669,18 @@ impl Panel for DockPanelContainer {
     fn title(
         &mut self,
         _window: &mut gpui::Window,
-        _cx: &mut gpui::Context<'_, DockPanelContainer>,
+        cx: &mut gpui::Context<'_, DockPanelContainer>,
     ) -> impl gpui::IntoElement {
-        let title = if let Some(key) = &self.title_key {
+        // The code editor shows the open file and its unsaved state
+        let editor_title = self
+            .agent_studio
+            .clone()
+            .and_then(|view| view.downcast::<CodeEditorPanel>().ok())
+            .and_then(|editor| editor.read(cx).tab_title());
+
+        let title = if let Some(title) = editor_title {
+            title
+        } else if let Some(key) = &self.title_key {
             SharedString::from(t!(key.as_ref()).to_string())
         } else {
             self.name.clone()
@@ -717,6 +773,7 @@ impl Panel for DockPanelContainer {
         let mut workspace_id = None;
         let mut workspace_name = None;
         let mut working_directory = None;
+        let mut comparison_id = None;
 
         // Helper function to normalize Windows paths (remove \\?\ prefix)
         fn normalize_path(path: std::path::PathBuf) -> String {
@@ -764,6 +821,11 @@ impl Panel for DockPanelContainer {
                         working_directory = panel.working_directory().map(normalize_path);
                     }
                 }
+                "ComparisonPanel" => {
+                    if let Ok(entity) = agent_studio.clone().downcast::<ComparisonPanel>() {
+                        comparison_id = entity.read(cx).comparison_id();
+                    }
+                }
                 _ => {}
             }
         }
@@ -774,6 +836,7 @@ impl Panel for DockPanelContainer {
             workspace_id,
             workspace_name,
             working_directory,
+            comparison_id,
         };
         state.info = PanelInfo::panel(agent_state.to_value());
         state
@@ -0,0 +1,795 @@
+use std::{path::PathBuf, time::Duration};
+
+use agent_client_protocol::{ContentBlock, SessionUpdate};
+use chrono::Local;
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Icon, IconName, Sizable, WindowExt,
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    input::{Input, InputEvent, InputState},
+    notification::Notification,
+    scroll::ScrollableElement as _,
+    v_flex,
+};
+use rust_i18n::t;
+use smol::Timer;
+
+use crate::{
+    AppState,
+    core::config_manager,
+    core::event_bus::{
+        AppEvent, EventKind, JournalEntry, JournalFilter, OverflowPolicy, ReplayState,
+        SessionReplay, Speaker, load_journal,
+    },
+    panels::dock_panel::DockPanel,
+};
+
+/// Entries rendered at once, newest first
+const MAX_VISIBLE_ENTRIES: usize = 500;
+
+/// Delay between a recorded event and the refresh of the list
+const REFRESH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Transcript messages shown for the replayed session
+const MAX_TRANSCRIPT_MESSAGES: usize = 6;
+
+/// Where the shown entries come from
+enum Source {
+    /// The journal of this run
+    Live,
+    /// A journal file loaded from disk
+    File(PathBuf),
+}
+
+/// A visible entry, with its summary computed once
+struct Row {
+    index: usize,
+    summary: String,
+}
+
+/// Event Journal Panel - browse, filter and replay recorded events
+///
+/// Selecting an entry replays the journal up to it into a fresh event hub
+/// and shows the conversation and task state the subscribers rebuilt.
+pub struct EventJournalPanel {
+    focus_handle: FocusHandle,
+    filter_input: Entity<InputState>,
+    /// Kinds to show, all of them when empty
+    kinds: Vec<EventKind>,
+    source: Source,
+    entries: Vec<JournalEntry>,
+    rows: Vec<Row>,
+    /// Entries matching the filter, including the ones not rendered
+    matched: usize,
+    /// Sequence number of the selected entry
+    selected: Option<u64>,
+    replay: Option<ReplayState>,
+    _subscriptions: Vec<Subscription>,
+    _watch_task: Task<()>,
+    _replay_task: Task<()>,
+}
+
+impl DockPanel for EventJournalPanel {
+    fn title() -> &'static str {
+        "Event Journal"
+    }
+
+    fn title_key() -> Option<&'static str> {
+        Some("event_journal.title")
+    }
+
+    fn description() -> &'static str {
+        "Browse, filter and replay the recorded application events"
+    }
+
+    fn new_view(window: &mut Window, cx: &mut App) -> Entity<impl Render> {
+        Self::view(window, cx)
+    }
+
+    fn paddings() -> Pixels {
+        px(0.)
+    }
+}
+
+impl EventJournalPanel {
+    pub fn view(window: &mut Window, cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| Self::new(window, cx))
+    }
+
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let filter_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder(t!("event_journal.filter").to_string())
+        });
+
+        let _subscriptions =
+            vec![
+                cx.subscribe(&filter_input, |this, _, event: &InputEvent, cx| {
+                    if let InputEvent::Change = event {
+                        this.apply_filter(cx);
+                    }
+                }),
+            ];
+
+        let mut this = Self {
+            focus_handle: cx.focus_handle(),
+            filter_input,
+            kinds: Vec::new(),
+            source: Source::Live,
+            entries: Vec::new(),
+            rows: Vec::new(),
+            matched: 0,
+            selected: None,
+            replay: None,
+            _subscriptions,
+            _watch_task: Self::watch_journal(cx),
+            _replay_task: Task::ready(()),
+        };
+        this.refresh(cx);
+        this
+    }
+
+    /// Refresh the live entries shortly after events are recorded
+    fn watch_journal(cx: &mut Context<Self>) -> Task<()> {
+        let state = AppState::global(cx);
+        let journal = state.event_journal().clone();
+        // Only a signal that events arrived, so a single slot is enough
+        let mut receiver = state.event_hub().subscribe_channel_with_filter(
+            1,
+            OverflowPolicy::DropOldest,
+            move |_| journal.is_recording(),
+        );
+
+        cx.spawn(async move |this, cx| {
+            while receiver.recv().await.is_some() {
+                Timer::after(REFRESH_DEBOUNCE).await;
+                while receiver.try_recv().is_some() {}
+                if this.update(cx, |this, cx| this.refresh(cx)).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    fn refresh(&mut self, cx: &mut Context<Self>) {
+        if let Source::Live = self.source {
+            let entries = AppState::global(cx).event_journal().entries();
+            self.set_entries(entries, cx);
+        }
+    }
+
+    fn set_entries(&mut self, entries: Vec<JournalEntry>, cx: &mut Context<Self>) {
+        self.entries = entries;
+        // Keep the selection while it's still in the journal
+        if self.selected_entry().is_none() {
+            self.selected = None;
+            self.replay = None;
+        }
+        self.apply_filter(cx);
+    }
+
+    fn apply_filter(&mut self, cx: &mut Context<Self>) {
+        let filter = JournalFilter {
+            kinds: self.kinds.clone(),
+            session_id: None,
+            text: self.filter_input.read(cx).value().to_string(),
+        };
+        let matches: Vec<usize> = (0..self.entries.len())
+            .rev()
+            .filter(|&index| filter.matches(&self.entries[index]))
+            .collect();
+
+        self.matched = matches.len();
+        self.rows = matches
+            .into_iter()
+            .take(MAX_VISIBLE_ENTRIES)
+            .map(|index| Row {
+                index,
+                summary: summary(&self.entries[index].event),
+            })
+            .collect();
+        cx.notify();
+    }
+
+    fn toggle_kind(&mut self, kind: EventKind, cx: &mut Context<Self>) {
+        match self.kinds.iter().position(|k| *k == kind) {
+            Some(pos) => {
+                self.kinds.remove(pos);
+            }
+            None => self.kinds.push(kind),
+        }
+        self.apply_filter(cx);
+    }
+
+    fn selected_entry(&self) -> Option<&JournalEntry> {
+        let seq = self.selected?;
+        self.entries.iter().find(|entry| entry.seq == seq)
+    }
+
+    /// Select an entry and replay the journal up to it
+    fn select(&mut self, index: usize, cx: &mut Context<Self>) {
+        let seq = self.entries[index].seq;
+        self.selected = Some(seq);
+        self.replay = None;
+        cx.notify();
+
+        let entries = self.entries[..=index].to_vec();
+        self._replay_task = cx.spawn(async move |this, cx| {
+            let replay = cx
+                .background_spawn(async move { ReplayState::replay(&entries) })
+                .await;
+            _ = this.update(cx, |this, cx| {
+                if this.selected == Some(seq) {
+                    this.replay = Some(replay);
+                    cx.notify();
+                }
+            });
+        });
+    }
+
+    fn toggle_recording(&mut self, cx: &mut Context<Self>) {
+        let journal = AppState::global(cx).event_journal();
+        journal.set_recording(!journal.is_recording());
+        cx.notify();
+    }
+
+    fn clear(&mut self, cx: &mut Context<Self>) {
+        AppState::global(cx).event_journal().clear();
+        self.selected = None;
+        self.replay = None;
+        self.refresh(cx);
+    }
+
+    fn show_live(&mut self, cx: &mut Context<Self>) {
+        self.source = Source::Live;
+        self.selected = None;
+        self.replay = None;
+        self.refresh(cx);
+    }
+
+    /// Save the journal of this run to the journals folder
+    fn save(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let journal = AppState::global(cx).event_journal().clone();
+        let path = config_manager::get_journals_dir().join(format!(
+            "journal-{}.jsonl",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = smol::unblock({
+                let path = path.clone();
+                move || journal.save(&path)
+            })
+            .await;
+
+            _ = this.update_in(cx, |_, window, cx| {
+                struct JournalSaved;
+                let note = match result {
+                    Ok(count) => Notification::success(
+                        t!(
+                            "event_journal.saved",
+                            count = count,
+                            path = path.display().to_string()
+                        )
+                        .to_string(),
+                    ),
+                    Err(e) => {
+                        log::warn!("Failed to save event journal: {:#}", e);
+                        Notification::error(
+                            t!("event_journal.error.save", error = format!("{:#}", e)).to_string(),
+                        )
+                    }
+                };
+                window.push_notification(note.id::<JournalSaved>(), cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Load a journal file to browse and replay it
+    fn open_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: None,
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            let result = smol::unblock({
+                let path = path.clone();
+                move || load_journal(&path)
+            })
+            .await;
+
+            _ = this.update_in(cx, |this, window, cx| match result {
+                Ok(entries) => {
+                    this.source = Source::File(path);
+                    this.selected = None;
+                    this.replay = None;
+                    this.set_entries(entries, cx);
+                }
+                Err(e) => {
+                    log::warn!("Failed to load event journal: {:#}", e);
+                    struct JournalLoadFailed;
+                    let note = Notification::error(
+                        t!("event_journal.error.load", error = format!("{:#}", e)).to_string(),
+                    )
+                    .id::<JournalLoadFailed>();
+                    window.push_notification(note, cx);
+                }
+            });
+        })
+        .detach();
+    }
+
+    fn render_toolbar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let journal = AppState::global(cx).event_journal();
+        let recording = journal.is_recording();
+        let live = matches!(self.source, Source::Live);
+
+        v_flex()
+            .gap_2()
+            .p_2()
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Input::new(&self.filter_input)
+                            .small()
+                            .flex_1()
+                            .prefix(Icon::new(IconName::Search).xsmall()),
+                    )
+                    .when(live, |this| {
+                        this.child(
+                            Button::new("event-journal-record")
+                                .label(if recording {
+                                    t!("event_journal.button.stop").to_string()
+                                } else {
+                                    t!("event_journal.button.record").to_string()
+                                })
+                                .xsmall()
+                                .map(|this| {
+                                    if recording {
+                                        this.danger()
+                                    } else {
+                                        this.primary()
+                                    }
+                                })
+                                .on_click(cx.listener(|this, _, _, cx| this.toggle_recording(cx))),
+                        )
+                        .child(
+                            Button::new("event-journal-clear")
+                                .label(t!("event_journal.button.clear").to_string())
+                                .xsmall()
+                                .ghost()
+                                .disabled(self.entries.is_empty())
+                                .on_click(cx.listener(|this, _, _, cx| this.clear(cx))),
+                        )
+                        .child(
+                            Button::new("event-journal-save")
+                                .label(t!("event_journal.button.save").to_string())
+                                .xsmall()
+                                .ghost()
+                                .disabled(self.entries.is_empty())
+                                .on_click(cx.listener(|this, _, window, cx| this.save(window, cx))),
+                        )
+                    })
+                    .when(!live, |this| {
+                        this.child(
+                            Button::new("event-journal-live")
+                                .label(t!("event_journal.button.live").to_string())
+                                .xsmall()
+                                .ghost()
+                                .on_click(cx.listener(|this, _, _, cx| this.show_live(cx))),
+                        )
+                    })
+                    .child(
+                        Button::new("event-journal-open")
+                            .label(t!("event_journal.button.open").to_string())
+                            .xsmall()
+                            .ghost()
+                            .on_click(
+                                cx.listener(|this, _, window, cx| this.open_file(window, cx)),
+                            ),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .children(EventKind::ALL.iter().map(|&kind| {
+                        let enabled = self.kinds.contains(&kind);
+                        Button::new(SharedString::from(format!(
+                            "event-journal-kind-{}",
+                            kind.id()
+                        )))
+                        .label(kind_label(kind))
+                        .xsmall()
+                        .map(|this| {
+                            if enabled {
+                                this.primary()
+                            } else {
+                                this.ghost()
+                            }
+                        })
+                        .on_click(cx.listener(move |this, _, _, cx| this.toggle_kind(kind, cx)))
+                    })),
+            )
+    }
+
+    fn render_status(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let journal = AppState::global(cx).event_journal();
+        let mut parts = Vec::new();
+        match &self.source {
+            Source::Live => {
+                parts.push(if journal.is_recording() {
+                    t!("event_journal.status.recording").to_string()
+                } else {
+                    t!("event_journal.status.paused").to_string()
+                });
+                if let Some(file) = journal.file() {
+                    parts.push(
+                        t!(
+                            "event_journal.status.file",
+                            path = file.display().to_string()
+                        )
+                        .to_string(),
+                    );
+                }
+            }
+            Source::File(path) => parts.push(
+                t!(
+                    "event_journal.status.loaded",
+                    path = path.display().to_string()
+                )
+                .to_string(),
+            ),
+        }
+        parts.push(
+            t!(
+                "event_journal.status.count",
+                matched = self.matched,
+                count = self.entries.len()
+            )
+            .to_string(),
+        );
+        if self.matched > self.rows.len() {
+            parts.push(t!("event_journal.status.truncated", count = self.rows.len()).to_string());
+        }
+
+        div()
+            .px_3()
+            .py_1()
+            .text_xs()
+            .text_color(cx.theme().muted_foreground)
+            .child(parts.join(" · "))
+    }
+
+    fn render_row(&self, row: &Row, cx: &mut Context<Self>) -> impl IntoElement {
+        let entry = &self.entries[row.index];
+        let selected = self.selected == Some(entry.seq);
+        let index = row.index;
+
+        h_flex()
+            .id(("event-journal-entry", index))
+            .w_full()
+            .gap_2()
+            .px_2()
+            .py_0p5()
+            .text_xs()
+            .cursor_pointer()
+            .when(selected, |this| this.bg(cx.theme().accent))
+            .hover(|this| this.bg(cx.theme().accent.opacity(0.3)))
+            .child(
+                div()
+                    .w(px(48.))
+                    .text_right()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(entry.seq.to_string()),
+            )
+            .child(
+                div()
+                    .w(px(84.))
+                    .font_family(cx.theme().mono_font_family.clone())
+                    .text_color(cx.theme().muted_foreground)
+                    .child(
+                        entry
+                            .timestamp
+                            .with_timezone(&Local)
+                            .format("%H:%M:%S%.3f")
+                            .to_string(),
+                    ),
+            )
+            .child(div().w(px(120.)).child(kind_label(entry.event.kind())))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_ellipsis()
+                    .child(row.summary.clone()),
+            )
+            .on_click(cx.listener(move |this, _, _, cx| this.select(index, cx)))
+    }
+
+    fn render_detail(&self, entry: &JournalEntry, cx: &mut Context<Self>) -> impl IntoElement {
+        let json = serde_json::to_string_pretty(&entry.event).unwrap_or_default();
+        let muted = cx.theme().muted_foreground;
+
+        let replay = match &self.replay {
+            None => div()
+                .text_xs()
+                .text_color(muted)
+                .child(t!("event_journal.replay.running").to_string())
+                .into_any_element(),
+            Some(replay) => {
+                let session = entry
+                    .event
+                    .session_id()
+                    .and_then(|id| replay.sessions.get(id).map(|session| (id, session)));
+                let tasks: usize = replay.tasks.values().map(Vec::len).sum();
+
+                v_flex()
+                    .gap_1()
+                    .text_xs()
+                    .child(
+                        div().text_color(muted).child(
+                            t!(
+                                "event_journal.replay.summary",
+                                events = replay.events,
+                                sessions = replay.sessions.len(),
+                                tasks = tasks
+                            )
+                            .to_string(),
+                        ),
+                    )
+                    .when_some(session, |this, (id, session)| {
+                        this.child(render_session(id, session, cx))
+                    })
+                    .into_any_element()
+            }
+        };
+
+        v_flex()
+            .size_full()
+            .gap_2()
+            .p_2()
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .child(t!("event_journal.replay.title", seq = entry.seq).to_string()),
+            )
+            .child(replay)
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .child(t!("event_journal.event").to_string()),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .p_2()
+                    .rounded(cx.theme().radius)
+                    .bg(cx.theme().muted)
+                    .text_xs()
+                    .font_family(cx.theme().mono_font_family.clone())
+                    .whitespace_normal()
+                    .children(json.lines().map(|line| div().child(line.to_string()))),
+            )
+    }
+}
+
+fn render_session(id: &str, session: &SessionReplay, cx: &App) -> impl IntoElement {
+    let muted = cx.theme().muted_foreground;
+    let transcript = session.transcript();
+    let skipped = transcript.len().saturating_sub(MAX_TRANSCRIPT_MESSAGES);
+
+    v_flex()
+        .gap_1()
+        .p_2()
+        .rounded(cx.theme().radius)
+        .border_1()
+        .border_color(cx.theme().border)
+        .child(
+            div().font_weight(FontWeight::SEMIBOLD).child(
+                t!(
+                    "event_journal.replay.session",
+                    id = id,
+                    agent = session.agent_name.clone().unwrap_or_default()
+                )
+                .to_string(),
+            ),
+        )
+        .child(
+            div().text_color(muted).child(
+                t!(
+                    "event_journal.replay.session_state",
+                    status = session
+                        .status
+                        .as_ref()
+                        .map(|status| format!("{:?}", status))
+                        .unwrap_or_else(|| "-".to_string()),
+                    messages = transcript.len(),
+                    tool_calls = session.tool_calls.len(),
+                    permissions = session.permission_requests.len()
+                )
+                .to_string(),
+            ),
+        )
+        .children(transcript.into_iter().skip(skipped).map(|(speaker, text)| {
+            let speaker = match speaker {
+                Speaker::User => t!("event_journal.speaker.user"),
+                Speaker::Agent => t!("event_journal.speaker.agent"),
+                Speaker::Thought => t!("event_journal.speaker.thought"),
+            };
+            h_flex()
+                .gap_2()
+                .items_start()
+                .child(
+                    div()
+                        .w(px(56.))
+                        .text_color(muted)
+                        .child(speaker.to_string()),
+                )
+                .child(div().flex_1().min_w_0().child(preview(&text)))
+        }))
+        .children(session.tool_calls.iter().map(|tool_call| {
+            h_flex()
+                .gap_2()
+                .child(
+                    div()
+                        .w(px(56.))
+                        .text_color(muted)
+                        .child(format!("{:?}", tool_call.status)),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w_0()
+                        .text_ellipsis()
+                        .child(tool_call.title.clone()),
+                )
+        }))
+}
+
+impl Focusable for EventJournalPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for EventJournalPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let rows: Vec<AnyElement> = self
+            .rows
+            .iter()
+            .map(|row| self.render_row(row, cx).into_any_element())
+            .collect();
+        let detail = self
+            .selected_entry()
+            .map(|entry| self.render_detail(entry, cx).into_any_element());
+
+        v_flex()
+            .size_full()
+            .track_focus(&self.focus_handle)
+            .bg(cx.theme().background)
+            .child(self.render_toolbar(cx))
+            .child(self.render_status(cx))
+            .child(
+                h_flex()
+                    .flex_1()
+                    .min_h_0()
+                    .w_full()
+                    .child(
+                        div().flex_1().h_full().min_w_0().child(
+                            div()
+                                .size_full()
+                                .overflow_y_scrollbar()
+                                .pb_4()
+                                .child(v_flex().w_full().children(rows)),
+                        ),
+                    )
+                    .when_some(detail, |this, detail| {
+                        this.child(
+                            div()
+                                .w(relative(0.45))
+                                .h_full()
+                                .border_l_1()
+                                .border_color(cx.theme().border)
+                                .child(div().size_full().overflow_y_scrollbar().child(detail)),
+                        )
+                    }),
+            )
+    }
+}
+
+fn kind_label(kind: EventKind) -> String {
+    let key = format!("event_journal.kind.{}", kind.id());
+    t!(key.as_str()).to_string()
+}
+
+/// One-line description of an event for the list
+fn summary(event: &AppEvent) -> String {
+    let description = match event {
+        AppEvent::SessionUpdate(event) => session_update_summary(&event.update),
+        AppEvent::PermissionRequest(event) => {
+            event.tool_call.fields.title.clone().unwrap_or_default()
+        }
+        AppEvent::WorkspaceUpdate(event) => variant_name(event),
+        AppEvent::AgentConfig(event) => variant_name(event),
+        AppEvent::CodeSelection(event) => format!(
+            "{}:{}-{}",
+            event.selection.file_path, event.selection.start_line, event.selection.end_line
+        ),
+    };
+    match event.session_id() {
+        Some(session_id) => format!("[{}] {}", short_id(session_id), description),
+        None => description,
+    }
+}
+
+fn session_update_summary(update: &SessionUpdate) -> String {
+    match update {
+        SessionUpdate::UserMessageChunk(chunk)
+        | SessionUpdate::AgentMessageChunk(chunk)
+        | SessionUpdate::AgentThoughtChunk(chunk) => {
+            let text = match &chunk.content {
+                ContentBlock::Text(text) => preview(&text.text),
+                _ => String::new(),
+            };
+            format!("{}: {}", variant_name(update), text)
+        }
+        SessionUpdate::ToolCall(tool_call) => {
+            format!("{}: {}", variant_name(update), tool_call.title)
+        }
+        SessionUpdate::ToolCallUpdate(tool_call) => format!(
+            "{}: {} {}",
+            variant_name(update),
+            tool_call.tool_call_id,
+            tool_call
+                .fields
+                .status
+                .as_ref()
+                .map(|status| format!("{:?}", status))
+                .unwrap_or_default()
+        ),
+        _ => variant_name(update),
+    }
+}
+
+/// Name of an enum variant, from its `Debug` output
+fn variant_name(value: &impl std::fmt::Debug) -> String {
+    let debug = format!("{:?}", value);
+    debug
+        .split(['(', ' ', '{'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// First line of a text, shortened for a single row
+fn preview(text: &str) -> String {
+    const MAX_CHARS: usize = 120;
+    let line = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    if line.chars().count() > MAX_CHARS {
+        format!("{}…", line.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
@@ -1,8 +1,11 @@
 // Panel-related modules
 
 pub mod code_editor;
+mod comparison_panel;
 pub mod conversation;
 pub mod dock_panel;
+mod event_journal_panel;
+mod search_panel;
 mod session_manager;
 mod settings_panel;
 mod task_panel;
@@ -12,8 +15,11 @@ mod welcome_panel;
 
 // Re-export panel types
 pub use code_editor::CodeEditorPanel;
+pub use comparison_panel::ComparisonPanel;
 pub use conversation::ConversationPanel;
 pub use dock_panel::{DockPanel, DockPanelContainer, DockPanelState};
+pub use event_journal_panel::EventJournalPanel;
+pub use search_panel::SearchPanel;
 pub use session_manager::SessionManagerPanel;
 pub use settings_panel::{AppSettings, SettingsPanel};
 pub use task_panel::TaskPanel;
@@ -0,0 +1,4 @@
+mod panel;
+mod search;
+
+pub use panel::SearchPanel;
@@ -0,0 +1,709 @@
+use std::{collections::HashSet, path::PathBuf, time::Duration};
+
+use agent_client_protocol as acp;
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Icon, IconName, Sizable, WindowExt,
+    button::{Button, ButtonVariants as _},
+    dialog::DialogButtonProps,
+    h_flex,
+    input::{Input, InputEvent, InputState},
+    notification::Notification,
+    scroll::ScrollableElement as _,
+    v_flex,
+};
+use regex::Regex;
+use rust_i18n::t;
+use smol::Timer;
+
+use super::search::{
+    FileMatches, LineMatch, MAX_MATCHES, SearchOptions, SearchResults, build_regex, replace_text,
+    search_workspace,
+};
+use crate::{
+    AppState, OpenFileInEditor, app::actions::AddCodeSelection, components::DiffView,
+    core::event_bus::CodeSelectionEvent, panels::dock_panel::DockPanel,
+};
+
+/// Delay between the last keystroke and the search
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// A pending replacement of one file, shown in the preview
+struct FileReplacement {
+    path: PathBuf,
+    old_text: String,
+    new_text: String,
+    count: usize,
+}
+
+/// Search Panel - project-wide text search and replace
+pub struct SearchPanel {
+    focus_handle: FocusHandle,
+    query_input: Entity<InputState>,
+    replace_input: Entity<InputState>,
+    options: SearchOptions,
+    show_replace: bool,
+    results: SearchResults,
+    /// Files whose matches are hidden
+    collapsed: HashSet<PathBuf>,
+    searching: bool,
+    error: Option<String>,
+    _subscriptions: Vec<Subscription>,
+    _search_task: Task<()>,
+}
+
+impl DockPanel for SearchPanel {
+    fn title() -> &'static str {
+        "Search"
+    }
+
+    fn title_key() -> Option<&'static str> {
+        Some("search.title")
+    }
+
+    fn description() -> &'static str {
+        "Search and replace text across the workspace"
+    }
+
+    fn on_active(&mut self, active: bool, window: &mut Window, cx: &mut App) {
+        if active {
+            self.query_input
+                .update(cx, |state, cx| state.focus(window, cx));
+        }
+    }
+
+    fn new_view(window: &mut Window, cx: &mut App) -> Entity<impl Render> {
+        Self::view(window, cx)
+    }
+
+    fn paddings() -> Pixels {
+        px(0.)
+    }
+}
+
+impl SearchPanel {
+    pub fn view(window: &mut Window, cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| Self::new(window, cx))
+    }
+
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let query_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder(t!("search.placeholder").to_string())
+        });
+        let replace_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder(t!("search.replace_placeholder").to_string())
+        });
+
+        let _subscriptions =
+            vec![cx.subscribe(
+                &query_input,
+                |this, _, event: &InputEvent, cx| match event {
+                    InputEvent::Change => this.schedule_search(SEARCH_DEBOUNCE, cx),
+                    InputEvent::PressEnter { .. } => this.schedule_search(Duration::ZERO, cx),
+                    _ => {}
+                },
+            )];
+
+        Self {
+            focus_handle: cx.focus_handle(),
+            query_input,
+            replace_input,
+            options: SearchOptions::default(),
+            show_replace: false,
+            results: SearchResults::default(),
+            collapsed: HashSet::new(),
+            searching: false,
+            error: None,
+            _subscriptions,
+            _search_task: Task::ready(()),
+        }
+    }
+
+    fn root(cx: &App) -> PathBuf {
+        AppState::global(cx).current_working_dir().clone()
+    }
+
+    fn regex(&self, cx: &App) -> Option<Result<Regex, String>> {
+        let query = self.query_input.read(cx).value();
+        if query.is_empty() {
+            return None;
+        }
+        Some(build_regex(&query, self.options).map_err(|e| format!("{:#}", e)))
+    }
+
+    /// Run the search after `delay`, replacing a pending one
+    fn schedule_search(&mut self, delay: Duration, cx: &mut Context<Self>) {
+        let regex = match self.regex(cx) {
+            None => {
+                self.results = SearchResults::default();
+                self.error = None;
+                self.searching = false;
+                self._search_task = Task::ready(());
+                cx.notify();
+                return;
+            }
+            Some(Err(e)) => {
+                self.error = Some(e);
+                self.searching = false;
+                self._search_task = Task::ready(());
+                cx.notify();
+                return;
+            }
+            Some(Ok(regex)) => regex,
+        };
+
+        let root = Self::root(cx);
+        self.error = None;
+        self.searching = true;
+        cx.notify();
+
+        self._search_task = cx.spawn(async move |this, cx| {
+            if !delay.is_zero() {
+                Timer::after(delay).await;
+            }
+
+            let results = cx
+                .background_spawn(async move { search_workspace(&root, &regex) })
+                .await;
+
+            _ = this.update(cx, |this, cx| {
+                this.results = results;
+                this.searching = false;
+                cx.notify();
+            });
+        });
+    }
+
+    fn toggle_option(&mut self, toggle: fn(&mut SearchOptions), cx: &mut Context<Self>) {
+        toggle(&mut self.options);
+        self.schedule_search(Duration::ZERO, cx);
+    }
+
+    fn open_match(path: PathBuf, line: &LineMatch, window: &mut Window, cx: &mut App) {
+        let column = line
+            .ranges
+            .first()
+            .map(|range| line.text[..range.start].chars().count())
+            .unwrap_or_default();
+
+        window.dispatch_action(
+            Box::new(OpenFileInEditor {
+                path,
+                line: line.line as u32,
+                column: column as u32,
+            }),
+            cx,
+        );
+    }
+
+    /// Send a match with its context lines to the chat input
+    fn send_to_chat(path: &PathBuf, line: &LineMatch, cx: &mut App) {
+        let start_line = line.line - line.before.len();
+        let content = line
+            .before
+            .iter()
+            .chain(std::iter::once(&line.text))
+            .chain(&line.after)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+        let last_line = line.after.last().unwrap_or(&line.text);
+
+        let selection = AddCodeSelection {
+            file_path: path.to_string_lossy().to_string(),
+            start_line: start_line as u32 + 1,
+            start_column: 1,
+            end_line: (line.line + line.after.len()) as u32 + 1,
+            end_column: last_line.chars().count() as u32 + 1,
+            content,
+        };
+
+        let event_hub = AppState::global(cx).event_hub().clone();
+        event_hub.publish_code_selection(CodeSelectionEvent {
+            selection: selection.into(),
+        });
+    }
+
+    /// Compute the replacements and show them before writing anything
+    fn preview_replace_all(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(Ok(regex)) = self.regex(cx) else {
+            return;
+        };
+        let replacement = self.replace_input.read(cx).value().to_string();
+        let expand = self.options.regex;
+        let paths: Vec<PathBuf> = self.results.files.iter().map(|f| f.path.clone()).collect();
+        if paths.is_empty() {
+            return;
+        }
+
+        cx.spawn_in(window, async move |this, cx| {
+            let replacements = cx
+                .background_spawn(async move {
+                    paths
+                        .into_iter()
+                        .filter_map(|path| {
+                            let old_text = std::fs::read_to_string(&path).ok()?;
+                            let (new_text, count) =
+                                replace_text(&old_text, &regex, &replacement, expand);
+                            (count > 0).then_some(FileReplacement {
+                                path,
+                                old_text,
+                                new_text,
+                                count,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await;
+
+            _ = this.update_in(cx, |this, window, cx| {
+                if !replacements.is_empty() {
+                    this.open_replace_preview(replacements, window, cx);
+                }
+            });
+        })
+        .detach();
+    }
+
+    fn open_replace_preview(
+        &mut self,
+        replacements: Vec<FileReplacement>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let count: usize = replacements.iter().map(|r| r.count).sum();
+        let files = replacements.len();
+        let diffs: Vec<acp::Diff> = replacements
+            .iter()
+            .map(|r| {
+                acp::Diff::new(r.path.clone(), r.new_text.clone()).old_text(r.old_text.clone())
+            })
+            .collect();
+        let replacements = std::rc::Rc::new(replacements);
+        let view = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .title(t!("search.preview.title", count = count, files = files).to_string())
+                .child(
+                    div()
+                        .id("search-replace-preview")
+                        .max_h(px(480.))
+                        .overflow_y_scroll()
+                        .child(
+                            v_flex().gap_3().children(
+                                diffs
+                                    .iter()
+                                    .map(|diff| DiffView::new(diff.clone()).context_lines(2)),
+                            ),
+                        ),
+                )
+                .confirm()
+                .button_props(
+                    DialogButtonProps::default()
+                        .ok_text(t!("search.preview.ok").to_string())
+                        .cancel_text(t!("search.preview.cancel").to_string()),
+                )
+                .on_ok({
+                    let view = view.clone();
+                    let replacements = replacements.clone();
+                    move |_, window, cx| {
+                        _ = view.update(cx, |this, cx| {
+                            this.apply_replacements(&replacements, window, cx);
+                        });
+                        true
+                    }
+                })
+        });
+    }
+
+    /// Write the previewed replacements, skipping files changed since the preview
+    fn apply_replacements(
+        &mut self,
+        replacements: &[FileReplacement],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let writes: Vec<(PathBuf, String, String, usize)> = replacements
+            .iter()
+            .map(|r| {
+                (
+                    r.path.clone(),
+                    r.old_text.clone(),
+                    r.new_text.clone(),
+                    r.count,
+                )
+            })
+            .collect();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let (replaced, files, failures) = cx
+                .background_spawn(async move {
+                    let (mut replaced, mut files, mut failures) = (0, 0, Vec::new());
+                    for (path, old_text, new_text, count) in writes {
+                        let current = std::fs::read_to_string(&path).unwrap_or_default();
+                        let result = if current != old_text {
+                            Err(t!("search.error.changed").to_string())
+                        } else {
+                            std::fs::write(&path, new_text).map_err(|e| e.to_string())
+                        };
+                        match result {
+                            Ok(()) => {
+                                replaced += count;
+                                files += 1;
+                            }
+                            Err(e) => failures.push((path, e)),
+                        }
+                    }
+                    (replaced, files, failures)
+                })
+                .await;
+
+            _ = this.update_in(cx, |this, window, cx| {
+                for (path, error) in failures {
+                    log::warn!(
+                        "[SearchPanel] Failed to replace in {}: {}",
+                        path.display(),
+                        error
+                    );
+                    struct ReplaceFailed;
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let note = Notification::error(
+                        t!("search.error.replace", name = name, error = error).to_string(),
+                    )
+                    .id::<ReplaceFailed>();
+                    window.push_notification(note, cx);
+                }
+
+                struct ReplacedAll;
+                let note = Notification::success(
+                    t!("search.replaced", count = replaced, files = files).to_string(),
+                )
+                .id::<ReplacedAll>();
+                window.push_notification(note, cx);
+
+                this.schedule_search(Duration::ZERO, cx);
+            });
+        })
+        .detach();
+    }
+
+    fn render_option_button(
+        &self,
+        id: &'static str,
+        label: &'static str,
+        tooltip: String,
+        enabled: bool,
+        toggle: fn(&mut SearchOptions),
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        Button::new(id)
+            .label(label)
+            .xsmall()
+            .tooltip(tooltip)
+            .map(|this| {
+                if enabled {
+                    this.primary()
+                } else {
+                    this.ghost()
+                }
+            })
+            .on_click(cx.listener(move |this, _, _, cx| this.toggle_option(toggle, cx)))
+    }
+
+    fn render_search_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_results = !self.results.files.is_empty();
+
+        v_flex()
+            .gap_2()
+            .p_2()
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Button::new("toggle-replace")
+                            .icon(if self.show_replace {
+                                IconName::ChevronDown
+                            } else {
+                                IconName::ChevronRight
+                            })
+                            .ghost()
+                            .xsmall()
+                            .tooltip(t!("search.toggle_replace").to_string())
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.show_replace = !this.show_replace;
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        Input::new(&self.query_input)
+                            .small()
+                            .flex_1()
+                            .prefix(Icon::new(IconName::Search).xsmall()),
+                    )
+                    .child(self.render_option_button(
+                        "search-case-sensitive",
+                        "Aa",
+                        t!("search.option.case_sensitive").to_string(),
+                        self.options.case_sensitive,
+                        |options| options.case_sensitive = !options.case_sensitive,
+                        cx,
+                    ))
+                    .child(self.render_option_button(
+                        "search-whole-word",
+                        "ab",
+                        t!("search.option.whole_word").to_string(),
+                        self.options.whole_word,
+                        |options| options.whole_word = !options.whole_word,
+                        cx,
+                    ))
+                    .child(self.render_option_button(
+                        "search-regex",
+                        ".*",
+                        t!("search.option.regex").to_string(),
+                        self.options.regex,
+                        |options| options.regex = !options.regex,
+                        cx,
+                    )),
+            )
+            .when(self.show_replace, |this| {
+                this.child(
+                    h_flex()
+                        .gap_1()
+                        .pl_6()
+                        .child(Input::new(&self.replace_input).small().flex_1())
+                        .child(
+                            Button::new("replace-all")
+                                .label(t!("search.replace_all").to_string())
+                                .xsmall()
+                                .disabled(!has_results || self.searching)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.preview_replace_all(window, cx);
+                                })),
+                        ),
+                )
+            })
+    }
+
+    fn render_summary(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let text = if let Some(error) = &self.error {
+            error.clone()
+        } else if self.searching {
+            t!("search.searching").to_string()
+        } else if self.query_input.read(cx).value().is_empty() {
+            String::new()
+        } else if self.results.files.is_empty() {
+            t!("search.no_results").to_string()
+        } else {
+            let summary = t!(
+                "search.summary",
+                matches = self.results.match_count(),
+                files = self.results.files.len()
+            )
+            .to_string();
+            if self.results.truncated {
+                format!(
+                    "{} · {}",
+                    summary,
+                    t!("search.truncated", count = MAX_MATCHES)
+                )
+            } else {
+                summary
+            }
+        };
+
+        div()
+            .px_3()
+            .py_1()
+            .text_xs()
+            .text_color(if self.error.is_some() {
+                cx.theme().danger
+            } else {
+                cx.theme().muted_foreground
+            })
+            .child(text)
+    }
+
+    fn render_file(&self, ix: usize, file: &FileMatches, cx: &mut Context<Self>) -> AnyElement {
+        let collapsed = self.collapsed.contains(&file.path);
+        let count: usize = file.lines.iter().map(|line| line.ranges.len()).sum();
+        let path = file.path.clone();
+
+        v_flex()
+            .w_full()
+            .child(
+                h_flex()
+                    .id(("search-file", ix))
+                    .w_full()
+                    .gap_1()
+                    .px_2()
+                    .py_0p5()
+                    .cursor_pointer()
+                    .hover(|this| this.bg(cx.theme().accent.opacity(0.3)))
+                    .child(
+                        Icon::new(if collapsed {
+                            IconName::ChevronRight
+                        } else {
+                            IconName::ChevronDown
+                        })
+                        .xsmall(),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .text_ellipsis()
+                            .child(file.relative_path.clone()),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(count.to_string()),
+                    )
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        if !this.collapsed.remove(&path) {
+                            this.collapsed.insert(path.clone());
+                        }
+                        cx.notify();
+                    })),
+            )
+            .when(!collapsed, |this| {
+                this.children(
+                    file.lines
+                        .iter()
+                        .enumerate()
+                        .map(|(line_ix, line)| self.render_line(ix, line_ix, file, line, cx)),
+                )
+            })
+            .into_any_element()
+    }
+
+    fn render_line(
+        &self,
+        file_ix: usize,
+        line_ix: usize,
+        file: &FileMatches,
+        line: &LineMatch,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let highlight = HighlightStyle {
+            background_color: Some(cx.theme().warning.opacity(0.35)),
+            font_weight: Some(FontWeight::SEMIBOLD),
+            ..Default::default()
+        };
+        let muted = cx.theme().muted_foreground;
+        let context_line = |number: usize, text: &String| {
+            h_flex()
+                .gap_2()
+                .text_color(muted)
+                .child(
+                    div()
+                        .w(px(36.))
+                        .text_right()
+                        .child((number + 1).to_string()),
+                )
+                .child(div().flex_1().overflow_hidden().child(text.clone()))
+        };
+
+        let id = file_ix * MAX_MATCHES + line_ix;
+        let first_before = line.line - line.before.len();
+
+        v_flex()
+            .id(("search-line", id))
+            .w_full()
+            .pl_4()
+            .pr_2()
+            .py_0p5()
+            .text_xs()
+            .font_family(cx.theme().mono_font_family.clone())
+            .cursor_pointer()
+            .hover(|this| this.bg(cx.theme().accent.opacity(0.3)))
+            .children(
+                line.before
+                    .iter()
+                    .enumerate()
+                    .map(|(i, text)| context_line(first_before + i, text)),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        div()
+                            .w(px(36.))
+                            .text_right()
+                            .text_color(muted)
+                            .child((line.line + 1).to_string()),
+                    )
+                    .child(div().flex_1().overflow_hidden().child(
+                        StyledText::new(line.text.clone()).with_highlights(
+                            line.ranges.iter().map(|range| (range.clone(), highlight)),
+                        ),
+                    ))
+                    .child(
+                        Button::new(("search-send-to-chat", id))
+                            .icon(IconName::SquareTerminal)
+                            .ghost()
+                            .xsmall()
+                            .tooltip(t!("search.send_to_chat").to_string())
+                            .on_click({
+                                let path = file.path.clone();
+                                let line = line.clone();
+                                move |_, _, cx| {
+                                    cx.stop_propagation();
+                                    Self::send_to_chat(&path, &line, cx);
+                                }
+                            }),
+                    ),
+            )
+            .children(
+                line.after
+                    .iter()
+                    .enumerate()
+                    .map(|(i, text)| context_line(line.line + 1 + i, text)),
+            )
+            .on_click({
+                let path = file.path.clone();
+                let line = line.clone();
+                move |_, window, cx| Self::open_match(path.clone(), &line, window, cx)
+            })
+    }
+}
+
+impl Focusable for SearchPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for SearchPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let files: Vec<AnyElement> = self
+            .results
+            .files
+            .iter()
+            .enumerate()
+            .map(|(ix, file)| self.render_file(ix, file, cx))
+            .collect();
+
+        v_flex()
+            .size_full()
+            .bg(cx.theme().background)
+            .child(self.render_search_bar(cx))
+            .child(self.render_summary(cx))
+            .child(
+                div().flex_1().min_h_0().w_full().child(
+                    div()
+                        .size_full()
+                        .overflow_y_scrollbar()
+                        .pb_4()
+                        .child(v_flex().w_full().children(files)),
+                ),
+            )
+    }
+}
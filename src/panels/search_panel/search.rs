@@ -0,0 +1,280 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use autocorrect::ignorer::Ignorer;
+use regex::{NoExpand, Regex, RegexBuilder};
+
+/// Lines of context shown around each match
+pub const CONTEXT_LINES: usize = 1;
+/// Stop searching after this many matching lines
+pub const MAX_MATCHES: usize = 2000;
+/// Files larger than this are skipped
+const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+}
+
+/// A matching line
+#[derive(Debug, Clone)]
+pub struct LineMatch {
+    /// 0-based line number
+    pub line: usize,
+    pub text: String,
+    /// Byte ranges of the matches in `text`
+    pub ranges: Vec<Range<usize>>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// The matches in one file
+#[derive(Debug, Clone)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    /// Path relative to the search root, for display
+    pub relative_path: String,
+    pub lines: Vec<LineMatch>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    pub files: Vec<FileMatches>,
+    /// Whether the search stopped at `MAX_MATCHES`
+    pub truncated: bool,
+}
+
+impl SearchResults {
+    pub fn match_count(&self) -> usize {
+        self.files
+            .iter()
+            .flat_map(|file| &file.lines)
+            .map(|line| line.ranges.len())
+            .sum()
+    }
+}
+
+/// Build the regex for a query
+pub fn build_regex(query: &str, options: SearchOptions) -> Result<Regex> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .context("Invalid search pattern")
+}
+
+/// Search the files under `root`, honoring `.gitignore`
+pub fn search_workspace(root: &Path, regex: &Regex) -> SearchResults {
+    let ignorer = Ignorer::new(&root.to_string_lossy());
+    let mut results = SearchResults::default();
+    let mut remaining = MAX_MATCHES;
+    search_dir(&ignorer, root, root, regex, &mut results, &mut remaining);
+    results
+}
+
+fn search_dir(
+    ignorer: &Ignorer,
+    root: &Path,
+    dir: &Path,
+    regex: &Regex,
+    results: &mut SearchResults,
+    remaining: &mut usize,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        if *remaining == 0 {
+            results.truncated = true;
+            return;
+        }
+
+        let relative_path = path.strip_prefix(root).unwrap_or(&path);
+        if ignorer.is_ignored(&relative_path.to_string_lossy()) || relative_path.ends_with(".git") {
+            continue;
+        }
+
+        if path.is_dir() {
+            search_dir(ignorer, root, &path, regex, results, remaining);
+        } else if let Some(text) = read_text_file(&path) {
+            let lines = search_text(&text, regex, remaining);
+            if !lines.is_empty() {
+                results.files.push(FileMatches {
+                    relative_path: relative_path.to_string_lossy().to_string(),
+                    path,
+                    lines,
+                });
+            }
+        }
+    }
+}
+
+/// Read a file, skipping large and binary files
+fn read_text_file(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    if bytes[..bytes.len().min(8192)].contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Find the matching lines of a text
+pub fn search_text(text: &str, regex: &Regex, remaining: &mut usize) -> Vec<LineMatch> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut matches = Vec::new();
+
+    for (ix, line) in lines.iter().enumerate() {
+        if *remaining == 0 {
+            break;
+        }
+        let ranges: Vec<Range<usize>> = regex
+            .find_iter(line)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+            .collect();
+        if ranges.is_empty() {
+            continue;
+        }
+
+        *remaining -= 1;
+        matches.push(LineMatch {
+            line: ix,
+            text: line.to_string(),
+            ranges,
+            before: lines[ix.saturating_sub(CONTEXT_LINES)..ix]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            after: lines[(ix + 1).min(lines.len())..(ix + 1 + CONTEXT_LINES).min(lines.len())]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+        });
+    }
+
+    matches
+}
+
+/// Replace the matches in a text line by line, as they were found
+///
+/// `$1`-style group references are expanded only in regex mode.
+pub fn replace_text(text: &str, regex: &Regex, replacement: &str, expand: bool) -> (String, usize) {
+    let mut output = String::with_capacity(text.len());
+    let mut count = 0;
+
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[content.len()..];
+
+        let found = regex.find_iter(content).filter(|m| !m.is_empty()).count();
+        if found == 0 {
+            output.push_str(line);
+            continue;
+        }
+
+        count += found;
+        let replaced = if expand {
+            regex.replace_all(content, replacement)
+        } else {
+            regex.replace_all(content, NoExpand(replacement))
+        };
+        output.push_str(&replaced);
+        output.push_str(ending);
+    }
+
+    (output, count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_regex_options() {
+        let literal = build_regex("a.b", SearchOptions::default()).unwrap();
+        assert!(literal.is_match("A.B"));
+        assert!(!literal.is_match("axb"));
+
+        let case_sensitive = build_regex(
+            "foo",
+            SearchOptions {
+                case_sensitive: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(!case_sensitive.is_match("Foo"));
+
+        let whole_word = build_regex(
+            "foo",
+            SearchOptions {
+                whole_word: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(whole_word.is_match("a foo b"));
+        assert!(!whole_word.is_match("food"));
+
+        assert!(
+            build_regex(
+                "(",
+                SearchOptions {
+                    regex: true,
+                    ..Default::default()
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_search_text_with_context() {
+        let regex = build_regex("needle", SearchOptions::default()).unwrap();
+        let mut remaining = MAX_MATCHES;
+        let matches = search_text("one\nneedle needle\nthree\n", &regex, &mut remaining);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, 1);
+        assert_eq!(matches[0].ranges, vec![0..6, 7..13]);
+        assert_eq!(matches[0].before, vec!["one"]);
+        assert_eq!(matches[0].after, vec!["three"]);
+    }
+
+    #[test]
+    fn test_replace_text() {
+        let options = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+        let regex = build_regex(r"(\w+)@", options).unwrap();
+        let (text, count) = replace_text("a@ b@\r\nc\n", &regex, "$1#", true);
+        assert_eq!(text, "a# b#\r\nc\n");
+        assert_eq!(count, 2);
+
+        let (text, _) = replace_text("a@\n", &regex, "$1", false);
+        assert_eq!(text, "$1\n");
+    }
+}
@@ -6,7 +6,7 @@ use gpui::{
     Render, Styled, Window, prelude::FluentBuilder, px,
 };
 use gpui_component::{
-    ActiveTheme, Icon, IconName, Sizable,
+    ActiveTheme, Disableable as _, Icon, IconName, Sizable,
     button::{Button, ButtonVariants},
     h_flex,
     scroll::ScrollableElement as _,
@@ -15,6 +15,7 @@ use gpui_component::{
 
 use crate::{
     AppState,
+    core::agent::AgentFeatures,
     core::services::{AgentSessionInfo, SessionStatus},
     panels::dock_panel::DockPanel,
 };
@@ -33,6 +34,8 @@ pub struct SessionManagerPanel {
     focus_handle: FocusHandle,
     sessions_by_agent: Vec<(String, Vec<AgentSessionInfo>)>,
     agent_sessions_by_agent: HashMap<String, AgentSessionListState>,
+    /// Features of each agent, to disable the actions it doesn't support
+    features_by_agent: HashMap<String, AgentFeatures>,
 }
 
 impl DockPanel for SessionManagerPanel {
@@ -67,6 +70,7 @@ impl SessionManagerPanel {
             focus_handle: cx.focus_handle(),
             sessions_by_agent: Vec::new(),
             agent_sessions_by_agent: HashMap::new(),
+            features_by_agent: HashMap::new(),
         };
 
         // Load initial session data
@@ -92,7 +96,11 @@ impl SessionManagerPanel {
 
             // Group sessions by agent
             let mut sessions_by_agent = Vec::new();
+            let mut features_by_agent = HashMap::new();
             for agent_name in agents {
+                if let Some(features) = agent_service.get_agent_features(&agent_name).await {
+                    features_by_agent.insert(agent_name.clone(), features);
+                }
                 let sessions = agent_service.list_workspace_sessions_for_agent(&agent_name);
                 sessions_by_agent.push((agent_name, sessions));
             }
@@ -101,6 +109,7 @@ impl SessionManagerPanel {
                 if let Some(this) = weak_self.upgrade() {
                     this.update(cx, |this, cx| {
                         this.sessions_by_agent = sessions_by_agent;
+                        this.features_by_agent = features_by_agent;
                         cx.notify();
                     });
                 }
@@ -543,6 +552,11 @@ impl Render for SessionManagerPanel {
                                             .get(agent_name)
                                             .map(|state| state.is_importing)
                                             .unwrap_or(false);
+                                        // Agents that haven't reported their features yet keep all actions
+                                        let features = self.features_by_agent.get(agent_name);
+                                        let can_list = features.is_none_or(|features| features.list_sessions);
+                                        let can_import = features.is_none_or(|features| features.list_sessions && features.resume_session);
+                                        let can_reopen = features.is_none_or(|features| features.can_reopen_session());
                                         let workspace_session_ids: HashSet<String> = sessions
                                             .iter()
                                             .map(|session| session.session_id.clone())
@@ -591,6 +605,7 @@ impl Render for SessionManagerPanel {
                                                                     .icon(Icon::new(IconName::Search))
                                                                     .ghost()
                                                                     .small()
+                                                                    .disabled(!can_list)
                                                                     .on_click({
                                                                         let agent_name = agent_name_clone.clone();
                                                                         cx.listener(move |this, _, _window, cx| {
@@ -604,6 +619,7 @@ impl Render for SessionManagerPanel {
                                                                     .icon(Icon::new(IconName::ArrowDown))
                                                                     .ghost()
                                                                     .small()
+                                                                    .disabled(!can_import)
                                                                     .on_click({
                                                                         let agent_name = agent_name_clone.clone();
                                                                         cx.listener(move |this, _, _window, cx| {
@@ -798,6 +814,7 @@ impl Render for SessionManagerPanel {
                                                                         .label("Open")
                                                                         .ghost()
                                                                         .small()
+                                                                        .disabled(!can_reopen)
                                                                         .on_click(cx.listener(move |this, _, window, cx| {
                                                                             this.open_or_resume_agent_session(
                                                                                 agent_name_for_open.clone(),
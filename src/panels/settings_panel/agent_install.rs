@@ -0,0 +1,313 @@
+use std::path::PathBuf;
+
+use agentx_types::schemas::agent_catalog::{AgentCatalog, InstallMethod};
+use gpui::{
+    AppContext as _, Context, Entity, IntoElement, ParentElement as _, Render, Styled, Task,
+    Window, prelude::FluentBuilder,
+};
+use gpui_component::{
+    ActiveTheme, Disableable as _, IconName, Sizable,
+    button::{Button, ButtonVariants as _},
+    label::Label,
+    select::{Select, SelectState},
+    v_flex,
+};
+use rust_i18n::t;
+
+use super::types::AppSettings;
+use crate::AppState;
+use crate::core::{
+    agent::{AgentInstaller, AgentManager, InstallProgress},
+    config::AgentProcessConfig,
+    config_manager, nodejs,
+    services::AgentConfigService,
+};
+
+/// Output lines kept in the install log
+const MAX_LOG_LINES: usize = 8;
+
+#[derive(Clone, PartialEq)]
+enum InstallStatus {
+    Idle,
+    Installing(String),
+    Probing,
+    Installed { agent: String, version: String },
+    Failed(String),
+}
+
+/// Installs an agent of the agent catalog
+///
+/// After the install, the agent is started once to check that it completes
+/// the ACP `initialize` handshake, and only then added to the config.
+pub(super) struct AgentInstall {
+    catalog: AgentCatalog,
+    agent_select: Entity<SelectState<Vec<String>>>,
+    status: InstallStatus,
+    log: Vec<String>,
+    _task: Option<Task<()>>,
+}
+
+impl AgentInstall {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let (catalog, status) = match config_manager::load_agent_catalog() {
+            Ok(catalog) => (catalog, InstallStatus::Idle),
+            Err(e) => {
+                log::error!("Failed to load agent catalog: {}", e);
+                (
+                    AgentCatalog::default(),
+                    InstallStatus::Failed(e.to_string()),
+                )
+            }
+        };
+        let names: Vec<String> = catalog
+            .agents
+            .iter()
+            .map(|agent| agent.name.clone())
+            .collect();
+        let agent_select = cx.new(|cx| SelectState::new(names, None, window, cx));
+
+        Self {
+            catalog,
+            agent_select,
+            status,
+            log: Vec::new(),
+            _task: None,
+        }
+    }
+
+    fn busy(&self) -> bool {
+        matches!(
+            self.status,
+            InstallStatus::Installing(_) | InstallStatus::Probing
+        )
+    }
+
+    fn push_progress(&mut self, progress: InstallProgress, cx: &mut Context<Self>) {
+        match progress {
+            InstallProgress::Step(step) => self.status = InstallStatus::Installing(step),
+            InstallProgress::Output(line) => {
+                self.log.push(line);
+                if self.log.len() > MAX_LOG_LINES {
+                    self.log.remove(0);
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    fn install(&mut self, cx: &mut Context<Self>) {
+        let Some(name) = self.agent_select.read(cx).selected_value().cloned() else {
+            return;
+        };
+        let Some(agent) = self.catalog.get(&name).cloned() else {
+            return;
+        };
+        let state = AppState::global(cx);
+        let (Some(agent_manager), Some(agent_config_service)) = (
+            state.agent_manager().cloned(),
+            state.agent_config_service().cloned(),
+        ) else {
+            log::warn!("Agent services not available");
+            return;
+        };
+        let settings_nodejs_path = AppSettings::global(cx).nodejs_path.to_string();
+
+        self.status = InstallStatus::Installing(String::new());
+        self.log.clear();
+        cx.notify();
+
+        self._task = Some(cx.spawn(async move |this, cx| {
+            let node_runtime = agent_config_service.node_runtime_config().await;
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let install = smol::unblock({
+                let agent = agent.clone();
+                move || {
+                    let report = |progress: InstallProgress| {
+                        let _ = tx.send(progress);
+                    };
+                    // The managed runtime replaces the Node.js from the
+                    // settings, and is installed first if needed; a pinned
+                    // version too old for the agent fails before that
+                    let nodejs_path = if node_runtime.managed {
+                        let runtime = nodejs::managed_runtime(&node_runtime);
+                        runtime.check_required_version(&agent.name, agent.node_version)?;
+                        let node = runtime.install_blocking(report)?;
+                        Some(node.to_string_lossy().to_string())
+                    } else {
+                        Some(settings_nodejs_path).filter(|path| !path.is_empty())
+                    };
+                    let config = AgentInstaller::new(config_manager::get_agents_dir())
+                        .with_nodejs_path(nodejs_path.clone().map(PathBuf::from))
+                        .install_blocking(&agent, report)?;
+                    anyhow::Ok((config, nodejs_path))
+                }
+            });
+            let report = async {
+                while let Some(progress) = rx.recv().await {
+                    _ = this.update(cx, |this, cx| this.push_progress(progress, cx));
+                }
+            };
+            let (result, _) = smol::future::zip(install, report).await;
+
+            let result = match result {
+                Ok((config, nodejs_path)) => {
+                    _ = this.update(cx, |this, cx| {
+                        this.status = InstallStatus::Probing;
+                        cx.notify();
+                    });
+                    Self::probe_and_save(
+                        &agent_manager,
+                        &agent_config_service,
+                        &agent.name,
+                        config,
+                        nodejs_path,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+
+            _ = this.update(cx, |this, cx| {
+                this.status = match result {
+                    Ok(status) => status,
+                    Err(e) => {
+                        log::warn!("Failed to install agent '{}': {}", agent.name, e);
+                        InstallStatus::Failed(e.to_string())
+                    }
+                };
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Check the installed agent answers `initialize`, then save its config
+    async fn probe_and_save(
+        agent_manager: &AgentManager,
+        agent_config_service: &AgentConfigService,
+        name: &str,
+        mut config: AgentProcessConfig,
+        nodejs_path: Option<String>,
+    ) -> anyhow::Result<InstallStatus> {
+        // Probe with the Node.js agents are started with; the saved config
+        // leaves it unset
+        let mut probe_config = config.clone();
+        probe_config.nodejs_path = nodejs_path;
+        let response = agent_manager.probe_agent(name, probe_config).await?;
+
+        match agent_config_service.get_agent(name).await {
+            Some(existing) => {
+                config.profiles = existing.profiles;
+                config.cwd_policy = existing.cwd_policy;
+                agent_config_service.update_agent(name, config).await?;
+            }
+            None => {
+                agent_config_service
+                    .add_agent(name.to_string(), config)
+                    .await?
+            }
+        }
+
+        let (agent, version) = match response.agent_info {
+            Some(info) => (info.name, info.version),
+            None => (name.to_string(), String::new()),
+        };
+        Ok(InstallStatus::Installed { agent, version })
+    }
+}
+
+impl Render for AgentInstall {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let busy = self.busy();
+        let selected = self
+            .agent_select
+            .read(cx)
+            .selected_value()
+            .and_then(|name| self.catalog.get(name));
+        let details = selected.map(|agent| {
+            let source = match &agent.install {
+                InstallMethod::Npm { package } => {
+                    t!("settings.agents.install.source.npm", package = package).to_string()
+                }
+                InstallMethod::Binary { url, .. } => {
+                    t!("settings.agents.install.source.binary", url = url).to_string()
+                }
+                InstallMethod::Cargo { crate_name, .. } => {
+                    t!("settings.agents.install.source.cargo", name = crate_name).to_string()
+                }
+                InstallMethod::Manual { instructions } => instructions.clone(),
+            };
+            (agent.description.clone(), source)
+        });
+
+        let status = match &self.status {
+            InstallStatus::Idle => None,
+            InstallStatus::Installing(step) if step.is_empty() => {
+                Some(t!("settings.agents.install.installing").to_string())
+            }
+            InstallStatus::Installing(step) => Some(step.clone()),
+            InstallStatus::Probing => Some(t!("settings.agents.install.probing").to_string()),
+            InstallStatus::Installed { agent, version } => Some(
+                t!(
+                    "settings.agents.install.installed",
+                    agent = agent,
+                    version = version
+                )
+                .to_string(),
+            ),
+            InstallStatus::Failed(error) => Some(error.clone()),
+        };
+        let status_color = match self.status {
+            InstallStatus::Installed { .. } => cx.theme().green,
+            InstallStatus::Failed(_) => cx.theme().danger,
+            _ => cx.theme().muted_foreground,
+        };
+
+        v_flex()
+            .w_full()
+            .gap_3()
+            .p_4()
+            .child(
+                Select::new(&self.agent_select)
+                    .placeholder(t!("settings.agents.install.select").to_string())
+                    .disabled(busy),
+            )
+            .when_some(details, |this, (description, source)| {
+                this.when(!description.is_empty(), |this| {
+                    this.child(Label::new(description).text_sm())
+                })
+                .child(
+                    Label::new(source)
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground),
+                )
+            })
+            .child(
+                Button::new("agent-install-btn")
+                    .label(t!("settings.agents.install.button").to_string())
+                    .icon(IconName::ArrowDown)
+                    .primary()
+                    .small()
+                    .loading(busy)
+                    .disabled(busy || selected.is_none())
+                    .on_click(cx.listener(|this, _, _, cx| this.install(cx))),
+            )
+            .when_some(status, |this, status| {
+                this.child(Label::new(status).text_xs().text_color(status_color))
+            })
+            .when(!self.log.is_empty(), |this| {
+                this.child(
+                    v_flex()
+                        .w_full()
+                        .p_2()
+                        .rounded_md()
+                        .bg(cx.theme().muted)
+                        .children(self.log.iter().map(|line| {
+                            Label::new(line.clone())
+                                .text_xs()
+                                .font_family(cx.theme().mono_font_family.clone())
+                                .text_color(cx.theme().muted_foreground)
+                        })),
+                )
+            })
+    }
+}
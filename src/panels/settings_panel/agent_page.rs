@@ -1,6 +1,9 @@
-use gpui::{AppContext as _, Context, Entity, ParentElement as _, Styled, Window, px};
+use gpui::{
+    App, AppContext as _, Context, Div, Entity, ParentElement as _, Styled, Window, div,
+    prelude::FluentBuilder, px,
+};
 use gpui_component::{
-    ActiveTheme, IconName, Sizable, WindowExt as _,
+    ActiveTheme, Icon, IconName, Sizable, WindowExt as _,
     button::Button,
     dialog::DialogButtonProps,
     h_flex,
@@ -12,12 +15,17 @@ use gpui_component::{
 use rust_i18n::t;
 use std::collections::HashMap;
 
+use super::agent_install::AgentInstall;
 use super::panel::SettingsPanel;
 use crate::{
     AppState,
     app::actions::{
         AddAgent, ChangeConfigPath, ReloadAgentConfig, RemoveAgent, RestartAgent, UpdateAgent,
     },
+    core::{
+        agent::{AgentFeatures, Feature},
+        config::CwdPolicy,
+    },
 };
 
 impl SettingsPanel {
@@ -40,6 +48,7 @@ impl SettingsPanel {
                                             t!("settings.agents.config.path.not_configured")
                                                 .to_string()
                                         });
+                                    let config_error = view.read(cx).config_error.clone();
 
                                     v_flex()
                                         .w_full()
@@ -55,6 +64,19 @@ impl SettingsPanel {
                                                         .whitespace_nowrap()
                                                 )
                                         )
+                                        .when_some(config_error, |this, error| {
+                                            this.child(
+                                                Label::new(
+                                                    t!(
+                                                        "settings.agents.config.invalid",
+                                                        error = error.to_string()
+                                                    )
+                                                    .to_string(),
+                                                )
+                                                .text_sm()
+                                                .text_color(cx.theme().danger),
+                                            )
+                                        })
                                         .child(
                                             h_flex()
                                                 .gap_2()
@@ -134,6 +156,7 @@ impl SettingsPanel {
                         let view = view.clone();
                         move |_options, _window, cx| {
                             let agent_configs = view.read(cx).cached_agents.clone();
+                            let pending_restarts = view.read(cx).pending_restarts.clone();
 
                             let mut content = v_flex()
                                 .w_full()
@@ -143,6 +166,23 @@ impl SettingsPanel {
                                     h_flex()
                                         .w_full()
                                         .justify_end()
+                                        .gap_2()
+                                        .child(
+                                            Button::new("install-agent-btn")
+                                                .label(
+                                                    t!("settings.agents.button.install").to_string(),
+                                                )
+                                                .icon(IconName::ArrowDown)
+                                                .small()
+                                                .on_click({
+                                                    let view = view.clone();
+                                                    move |_, window, cx| {
+                                                        view.update(cx, |this, cx| {
+                                                            this.show_install_agent_dialog(window, cx);
+                                                        });
+                                                    }
+                                                })
+                                        )
                                         .child(
                                             Button::new("add-agent-btn")
                                                 .label(
@@ -227,6 +267,44 @@ impl SettingsPanel {
                                         );
                                     }
 
+                                    if !config.profiles.is_empty() {
+                                        let mut profiles: Vec<_> =
+                                            config.profiles.keys().cloned().collect();
+                                        profiles.sort();
+                                        agent_info = agent_info.child(
+                                            Label::new(
+                                                t!(
+                                                    "settings.agents.field.profiles",
+                                                    profiles = profiles.join(", ")
+                                                )
+                                                .to_string(),
+                                            )
+                                                .text_xs()
+                                                .text_color(cx.theme().muted_foreground)
+                                        );
+                                    }
+
+                                    if config.cwd_policy == CwdPolicy::Workspaces {
+                                        agent_info = agent_info.child(
+                                            Label::new(
+                                                t!("settings.agents.field.cwd_workspaces")
+                                                    .to_string(),
+                                            )
+                                                .text_xs()
+                                                .text_color(cx.theme().muted_foreground)
+                                        );
+                                    }
+
+                                    if pending_restarts.contains(name) {
+                                        agent_info = agent_info.child(
+                                            Label::new(
+                                                t!("settings.agents.restart_pending").to_string(),
+                                            )
+                                                .text_xs()
+                                                .text_color(cx.theme().warning)
+                                        );
+                                    }
+
                                     content = content.child(
                                         h_flex()
                                             .w_full()
@@ -275,14 +353,21 @@ impl SettingsPanel {
                                                             .icon(IconName::LoaderCircle)
                                                             .outline()
                                                             .small()
-                                                            .on_click(move |_, window, cx| {
-                                                                log::info!("Restart agent: {}", name_for_restart);
-                                                                window.dispatch_action(
-                                                                    Box::new(RestartAgent {
-                                                                        name: name_for_restart.clone(),
-                                                                    }),
-                                                                    cx
-                                                                );
+                                                            .on_click({
+                                                                let view = view.clone();
+                                                                move |_, window, cx| {
+                                                                    log::info!("Restart agent: {}", name_for_restart);
+                                                                    view.update(cx, |this, cx| {
+                                                                        this.pending_restarts.remove(&name_for_restart);
+                                                                        cx.notify();
+                                                                    });
+                                                                    window.dispatch_action(
+                                                                        Box::new(RestartAgent {
+                                                                            name: name_for_restart.clone(),
+                                                                        }),
+                                                                        cx
+                                                                    );
+                                                                }
                                                             })
                                                     )
                                                     .child(
@@ -315,9 +400,78 @@ impl SettingsPanel {
                             content
                         }
                     })),
+                SettingGroup::new()
+                    .title(t!("settings.agents.group.capabilities").to_string())
+                    .description(t!("settings.agents.capabilities.description").to_string())
+                    .item(SettingItem::render({
+                        let view = view.clone();
+                        move |_options, _window, cx| {
+                            let agents = view.read(cx).cached_agent_features.clone();
+                            Self::capability_table(agents, cx)
+                        }
+                    })),
             ])
     }
 
+    /// Table of the features each running agent supports
+    fn capability_table(agents: Vec<(String, Option<AgentFeatures>)>, cx: &App) -> Div {
+        if agents.is_empty() {
+            return v_flex().w_full().p_4().items_center().child(
+                Label::new(t!("settings.agents.capabilities.empty").to_string())
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground),
+            );
+        }
+
+        let name_cell = |label: String| div().w(px(140.)).flex_none().child(label);
+        let feature_cell = || div().w(px(96.)).flex_none().flex().justify_center();
+
+        let header = h_flex()
+            .w_full()
+            .py_1()
+            .text_xs()
+            .font_weight(gpui::FontWeight::SEMIBOLD)
+            .text_color(cx.theme().muted_foreground)
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(name_cell(
+                t!("settings.agents.capabilities.agent").to_string(),
+            ))
+            .children(Feature::ALL.iter().map(|feature| {
+                let key = format!("settings.agents.capability.{}", feature.id());
+                feature_cell().child(t!(key.as_str()).to_string())
+            }));
+
+        let rows = agents.into_iter().map(|(name, features)| {
+            h_flex()
+                .w_full()
+                .py_1()
+                .text_sm()
+                .child(name_cell(name))
+                .map(|row| match features {
+                    Some(features) => row.children(Feature::ALL.iter().map(|feature| {
+                        let (icon, color) = if features.supports(*feature) {
+                            (IconName::Check, cx.theme().green)
+                        } else {
+                            (IconName::Close, cx.theme().muted_foreground)
+                        };
+                        feature_cell().child(Icon::new(icon).small().text_color(color))
+                    })),
+                    None => row.child(
+                        Label::new(t!("settings.agents.capabilities.not_started").to_string())
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground),
+                    ),
+                })
+        });
+
+        v_flex()
+            .w_full()
+            .overflow_x_hidden()
+            .child(header)
+            .children(rows)
+    }
+
     /// Show dialog to add or edit an agent
     pub fn show_add_edit_agent_dialog(
         &mut self,
@@ -519,6 +673,17 @@ impl SettingsPanel {
         });
     }
 
+    /// Show the dialog installing agents from the agent catalog
+    pub fn show_install_agent_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let install = cx.new(|cx| AgentInstall::new(window, cx));
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .title(t!("settings.agents.dialog.install.title").to_string())
+                .child(install.clone())
+        });
+    }
+
     /// Show confirmation dialog before deleting an agent
     pub fn show_delete_confirm_dialog(
         &mut self,
@@ -1,4 +1,4 @@
-use gpui::{AppContext as _, Context, Entity, ParentElement as _, Styled, Window, px};
+use gpui::{App, AppContext as _, Context, Div, Entity, ParentElement as _, Styled, Window, px};
 use gpui_component::{
     ActiveTheme, IconName, Sizable, WindowExt as _,
     button::Button,
@@ -12,7 +12,8 @@ use gpui_component::{
 use rust_i18n::t;
 
 use super::panel::SettingsPanel;
-use crate::AppState;
+use crate::{AppState, EditTaskTemplate, core::services::ScopedTemplate};
+use agentx_types::schemas::task_template::TemplateScope;
 
 impl SettingsPanel {
     pub fn command_page(&self, view: &Entity<Self>) -> SettingPage {
@@ -134,9 +135,209 @@ impl SettingsPanel {
                         content
                     }
                 })),
+            SettingGroup::new()
+                .title(t!("settings.templates.group").to_string())
+                .item(SettingItem::render({
+                    let view = view.clone();
+                    move |_options, _window, cx| view.read(cx).render_task_templates(cx)
+                })),
         ])
     }
 
+    fn render_task_templates(&self, cx: &App) -> Div {
+        let workspace_id = self
+            .cached_template_workspace
+            .as_ref()
+            .map(|(id, _)| id.clone());
+
+        let mut content = v_flex().w_full().gap_3().child(
+            h_flex()
+                .w_full()
+                .justify_between()
+                .items_center()
+                .child(
+                    Label::new(match &self.cached_template_workspace {
+                        Some((_, name)) => {
+                            t!("settings.templates.workspace_hint", workspace = name).to_string()
+                        }
+                        None => t!("settings.templates.global_hint").to_string(),
+                    })
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground),
+                )
+                .child(
+                    Button::new("add-template-btn")
+                        .label(t!("settings.templates.button.add").to_string())
+                        .icon(IconName::Plus)
+                        .small()
+                        .on_click({
+                            let workspace_id = workspace_id.clone();
+                            move |_, window, cx| {
+                                window.dispatch_action(
+                                    Box::new(EditTaskTemplate {
+                                        workspace_id: workspace_id.clone(),
+                                        name: None,
+                                        scope: None,
+                                        template: Default::default(),
+                                    }),
+                                    cx,
+                                );
+                            }
+                        }),
+                ),
+        );
+
+        if self.cached_task_templates.is_empty() {
+            return content.child(
+                h_flex().w_full().p_4().justify_center().child(
+                    Label::new(t!("settings.templates.empty").to_string())
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground),
+                ),
+            );
+        }
+
+        for (idx, scoped) in self.cached_task_templates.iter().enumerate() {
+            let scope_label = match scoped.scope {
+                TemplateScope::Global => t!("task_template.scope.global"),
+                TemplateScope::Workspace(_) => t!("task_template.scope.workspace"),
+            };
+
+            let template_info = v_flex()
+                .flex_1()
+                .gap_1()
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(
+                            Label::new(scoped.name.clone())
+                                .text_sm()
+                                .font_weight(gpui::FontWeight::SEMIBOLD),
+                        )
+                        .child(
+                            Label::new(scope_label.to_string())
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground),
+                        ),
+                )
+                .child(
+                    Label::new(scoped.template.description.clone())
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground),
+                );
+
+            content = content.child(
+                h_flex()
+                    .w_full()
+                    .items_start()
+                    .justify_between()
+                    .p_3()
+                    .gap_3()
+                    .rounded(px(6.))
+                    .bg(cx.theme().secondary)
+                    .border_1()
+                    .border_color(cx.theme().border)
+                    .child(template_info)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(
+                                Button::new(("edit-template-btn", idx))
+                                    .label(t!("settings.commands.button.edit").to_string())
+                                    .icon(IconName::Settings)
+                                    .outline()
+                                    .small()
+                                    .on_click({
+                                        let workspace_id = workspace_id.clone();
+                                        let scoped = scoped.clone();
+                                        move |_, window, cx| {
+                                            window.dispatch_action(
+                                                Box::new(EditTaskTemplate {
+                                                    workspace_id: workspace_id.clone(),
+                                                    name: Some(scoped.name.clone()),
+                                                    scope: Some(scoped.scope.clone()),
+                                                    template: scoped.template.clone(),
+                                                }),
+                                                cx,
+                                            );
+                                        }
+                                    }),
+                            )
+                            .child(
+                                Button::new(("delete-template-btn", idx))
+                                    .label(t!("settings.commands.button.delete").to_string())
+                                    .icon(IconName::Delete)
+                                    .outline()
+                                    .small()
+                                    .on_click({
+                                        let scoped = scoped.clone();
+                                        move |_, window, cx| {
+                                            Self::show_delete_template_dialog(
+                                                scoped.clone(),
+                                                window,
+                                                cx,
+                                            );
+                                        }
+                                    }),
+                            ),
+                    ),
+            );
+        }
+
+        content
+    }
+
+    fn show_delete_template_dialog(scoped: ScopedTemplate, window: &mut Window, cx: &mut App) {
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let scoped = scoped.clone();
+            dialog
+                .title(t!("settings.commands.dialog.delete.title").to_string())
+                .confirm()
+                .button_props(
+                    DialogButtonProps::default()
+                        .ok_text(t!("settings.commands.dialog.delete.ok").to_string())
+                        .ok_variant(gpui_component::button::ButtonVariant::Danger)
+                        .cancel_text(t!("settings.commands.dialog.cancel").to_string()),
+                )
+                .on_ok({
+                    let scoped = scoped.clone();
+                    move |_, _window, cx| {
+                        if let Some(service) = AppState::global(cx).task_template_service() {
+                            let service = service.clone();
+                            let scoped = scoped.clone();
+                            cx.spawn(async move |_cx| {
+                                if let Err(e) =
+                                    service.remove_template(&scoped.scope, &scoped.name).await
+                                {
+                                    log::error!("Failed to delete task template: {}", e);
+                                } else {
+                                    log::info!(
+                                        "Successfully deleted task template: {}",
+                                        scoped.name
+                                    );
+                                }
+                            })
+                            .detach();
+                        }
+                        true
+                    }
+                })
+                .child(
+                    v_flex().w_full().gap_2().p_4().child(
+                        Label::new(
+                            t!(
+                                "settings.templates.dialog.delete.message",
+                                name = scoped.name
+                            )
+                            .to_string(),
+                        )
+                        .text_sm(),
+                    ),
+                )
+        });
+    }
+
     pub fn show_add_command_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let name_input = cx.new(|cx| {
             InputState::new(window, cx)
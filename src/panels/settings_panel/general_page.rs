@@ -10,6 +10,7 @@ use rust_i18n::t;
 
 use super::panel::SettingsPanel;
 use super::types::AppSettings;
+use crate::AppState;
 
 impl SettingsPanel {
     pub fn general_page(&self, _view: &Entity<Self>, resettable: bool) -> SettingPage {
@@ -238,7 +239,17 @@ impl SettingsPanel {
                                 |cx: &App| AppSettings::global(cx).nodejs_path.clone(),
                                 |val: SharedString, cx: &mut App| {
                                     log::info!("nodejs_path set to: {}", val);
-                                    AppSettings::global_mut(cx).nodejs_path = val;
+                                    AppSettings::global_mut(cx).nodejs_path = val.clone();
+                                    // Agents started from now on use it
+                                    if let Some(manager) = AppState::global(cx).agent_manager() {
+                                        let manager = manager.clone();
+                                        let path =
+                                            Some(val.to_string()).filter(|path| !path.is_empty());
+                                        cx.spawn(async move |_cx| {
+                                            manager.set_nodejs_path(path).await;
+                                        })
+                                        .detach();
+                                    }
                                 },
                             )
                             .default_value(default_settings.nodejs_path),
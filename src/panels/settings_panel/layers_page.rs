@@ -0,0 +1,221 @@
+use gpui::{AppContext as _, Entity, Hsla, IntoElement, ParentElement as _, Styled, px};
+use gpui_component::{
+    ActiveTheme, IconName, Sizable,
+    button::Button,
+    h_flex,
+    label::Label,
+    setting::{SettingGroup, SettingItem, SettingPage},
+    v_flex,
+};
+use rust_i18n::t;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::panel::SettingsPanel;
+use crate::AppState;
+use agentx_types::ConfigLayer;
+
+/// Sections of the config merged by the workspace layers
+#[derive(Clone, Copy)]
+enum Section {
+    Agents,
+    McpServers,
+    Commands,
+    SystemPrompts,
+}
+
+impl Section {
+    fn title(self) -> String {
+        match self {
+            Self::Agents => t!("settings.layers.section.agents"),
+            Self::McpServers => t!("settings.layers.section.mcp_servers"),
+            Self::Commands => t!("settings.layers.section.commands"),
+            Self::SystemPrompts => t!("settings.layers.section.system_prompts"),
+        }
+        .to_string()
+    }
+}
+
+fn layer_label(layer: ConfigLayer) -> String {
+    match layer {
+        ConfigLayer::Global => t!("settings.layers.layer.global"),
+        ConfigLayer::Workspace => t!("settings.layers.layer.workspace"),
+        ConfigLayer::Project => t!("settings.layers.layer.project"),
+    }
+    .to_string()
+}
+
+impl SettingsPanel {
+    /// Effective configuration of the active workspace, and where each
+    /// value comes from
+    pub fn layers_page(&self, view: &Entity<Self>) -> SettingPage {
+        let mut groups = vec![
+            SettingGroup::new()
+                .title(t!("settings.layers.group.files").to_string())
+                .item(SettingItem::render({
+                    let view = view.clone();
+                    move |_options, _window, cx| {
+                        let workspace_config = view.read(cx).workspace_config.clone();
+                        let mut content = v_flex().w_full().gap_2();
+
+                        let Some((workspace_name, effective)) = workspace_config else {
+                            return content.child(
+                                Label::new(t!("settings.layers.no_workspace").to_string())
+                                    .text_sm()
+                                    .text_color(cx.theme().muted_foreground),
+                            );
+                        };
+
+                        content = content.child(
+                            Label::new(
+                                t!("settings.layers.workspace", workspace = workspace_name)
+                                    .to_string(),
+                            )
+                            .text_sm(),
+                        );
+                        if effective.overlays.is_empty() && effective.errors.is_empty() {
+                            content = content.child(
+                                Label::new(t!("settings.layers.no_overlays").to_string())
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground),
+                            );
+                        }
+                        for (ix, (layer, path)) in effective.overlays.iter().enumerate() {
+                            let mut row = h_flex().w_full().gap_2().child(
+                                Label::new(format!("{}: {}", layer_label(*layer), path.display()))
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground),
+                            );
+                            // Agents of an untrusted overlay are not started
+                            if let Some(folder) = effective.overlay_dir(*layer)
+                                && !effective.config.is_trusted_folder(folder)
+                            {
+                                row = row
+                                    .child(
+                                        Label::new(t!("settings.layers.untrusted").to_string())
+                                            .text_xs()
+                                            .text_color(cx.theme().warning),
+                                    )
+                                    .child(trust_button(ix, folder.to_path_buf(), view.clone()));
+                            }
+                            content = content.child(row);
+                        }
+                        for (path, error) in &effective.errors {
+                            content = content.child(
+                                Label::new(
+                                    t!(
+                                        "settings.layers.invalid",
+                                        path = path.display().to_string(),
+                                        error = error.to_string()
+                                    )
+                                    .to_string(),
+                                )
+                                .text_xs()
+                                .text_color(cx.theme().danger),
+                            );
+                        }
+                        content
+                    }
+                }))
+                .description(t!("settings.layers.description").to_string()),
+        ];
+
+        for section in [
+            Section::Agents,
+            Section::McpServers,
+            Section::Commands,
+            Section::SystemPrompts,
+        ] {
+            groups.push(
+                SettingGroup::new()
+                    .title(section.title())
+                    .item(SettingItem::render({
+                        let view = view.clone();
+                        move |_options, _window, cx| {
+                            let sources = view
+                                .read(cx)
+                                .workspace_config
+                                .as_ref()
+                                .map(|(_, effective)| {
+                                    let sources = &effective.sources;
+                                    match section {
+                                        Section::Agents => sources.agent_servers.clone(),
+                                        Section::McpServers => sources.mcp_servers.clone(),
+                                        Section::Commands => sources.commands.clone(),
+                                        Section::SystemPrompts => sources.system_prompts.clone(),
+                                    }
+                                })
+                                .unwrap_or_default();
+                            source_list(sources, cx.theme().muted_foreground, cx.theme().primary)
+                        }
+                    })),
+            );
+        }
+
+        SettingPage::new(t!("settings.layers.title").to_string())
+            .resettable(false)
+            .groups(groups)
+    }
+}
+
+/// Names of a section with the layer they come from, overrides highlighted
+fn source_list(
+    sources: HashMap<String, ConfigLayer>,
+    muted: Hsla,
+    highlight: Hsla,
+) -> impl IntoElement {
+    let mut entries: Vec<_> = sources.into_iter().collect();
+    entries.sort();
+
+    let mut content = v_flex().w_full().gap_1();
+    if entries.is_empty() {
+        return content.child(
+            Label::new(t!("settings.layers.empty").to_string())
+                .text_sm()
+                .text_color(muted),
+        );
+    }
+
+    for (name, layer) in entries {
+        let color = if layer == ConfigLayer::Global {
+            muted
+        } else {
+            highlight
+        };
+        content = content.child(
+            h_flex()
+                .w_full()
+                .gap_3()
+                .justify_between()
+                .px_2()
+                .py_1()
+                .rounded(px(4.))
+                .child(Label::new(name).text_sm())
+                .child(Label::new(layer_label(layer)).text_xs().text_color(color)),
+        );
+    }
+    content
+}
+
+/// Button trusting the folder of an overlay, refreshing the page once done
+fn trust_button(ix: usize, folder: PathBuf, view: Entity<SettingsPanel>) -> Button {
+    Button::new(("trust-folder", ix))
+        .label(t!("settings.layers.trust.ok").to_string())
+        .icon(IconName::Check)
+        .outline()
+        .xsmall()
+        .on_click(move |_, _window, cx| {
+            let Some(service) = AppState::global(cx).agent_config_service().cloned() else {
+                return;
+            };
+            let folder = folder.clone();
+            let view = view.clone();
+            cx.spawn(async move |cx| {
+                if let Err(e) = service.trust_folder(folder).await {
+                    log::error!("Failed to trust folder: {}", e);
+                }
+                _ = view.update(cx, |_, cx| SettingsPanel::load_workspace_config(cx));
+            })
+            .detach();
+        })
+}
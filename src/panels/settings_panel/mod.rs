@@ -1,8 +1,11 @@
 mod about_page;
+mod agent_install;
 mod agent_page;
 mod command_page;
 mod general_page;
+mod layers_page;
 mod mcp_page;
+mod model_check;
 mod model_page;
 mod network_page;
 mod panel;
@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use gpui::{
+    AppContext as _, Context, Entity, IntoElement, ParentElement as _, Render, Styled,
+    Subscription, Task, Window, prelude::FluentBuilder,
+};
+use gpui_component::{
+    ActiveTheme, Disableable as _, IconName, Sizable,
+    button::Button,
+    h_flex,
+    input::InputState,
+    label::Label,
+    select::{Select, SelectEvent, SelectState},
+    v_flex,
+};
+use rust_i18n::t;
+
+use crate::AppState;
+use crate::core::config::ModelConfig;
+
+#[derive(Clone, PartialEq)]
+enum CheckStatus {
+    Idle,
+    Testing,
+    Connected(Duration),
+    Fetching,
+    Fetched(usize),
+    Failed(String),
+}
+
+/// Connection test and model discovery for the model being edited
+///
+/// Reads the dialog's inputs as they are, so settings can be checked before
+/// they are saved. Picking a discovered model fills in the model name input.
+pub(super) struct ModelCheck {
+    provider_input: Entity<InputState>,
+    url_input: Entity<InputState>,
+    key_input: Entity<InputState>,
+    model_input: Entity<InputState>,
+    model_select: Entity<SelectState<Vec<String>>>,
+    has_models: bool,
+    status: CheckStatus,
+    _subscription: Subscription,
+    _task: Option<Task<()>>,
+}
+
+impl ModelCheck {
+    pub fn new(
+        provider_input: Entity<InputState>,
+        url_input: Entity<InputState>,
+        key_input: Entity<InputState>,
+        model_input: Entity<InputState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let model_select = cx.new(|cx| SelectState::new(Vec::<String>::new(), None, window, cx));
+        let _subscription = cx.subscribe_in(
+            &model_select,
+            window,
+            |this, _, _: &SelectEvent<Vec<String>>, window, cx| {
+                this.on_model_selected(window, cx);
+            },
+        );
+
+        Self {
+            provider_input,
+            url_input,
+            key_input,
+            model_input,
+            model_select,
+            has_models: false,
+            status: CheckStatus::Idle,
+            _subscription,
+            _task: None,
+        }
+    }
+
+    /// The model as currently entered in the dialog
+    fn model_config(&self, cx: &Context<Self>) -> Result<ModelConfig, String> {
+        let value =
+            |input: &Entity<InputState>| input.read(cx).text().to_string().trim().to_string();
+
+        let base_url = value(&self.url_input);
+        if base_url.is_empty() {
+            return Err(t!("settings.models.check.url_required").to_string());
+        }
+
+        Ok(ModelConfig {
+            enabled: true,
+            provider: value(&self.provider_input),
+            base_url,
+            api_key: value(&self.key_input),
+            model_name: value(&self.model_input),
+            max_tokens: None,
+            timeout_secs: None,
+        })
+    }
+
+    fn test_connection(&mut self, cx: &mut Context<Self>) {
+        let Some(ai_service) = AppState::global(cx).ai_service().cloned() else {
+            log::warn!("AiService not available");
+            return;
+        };
+        let config = match self.model_config(cx) {
+            Ok(config) => config,
+            Err(error) => {
+                self.status = CheckStatus::Failed(error);
+                cx.notify();
+                return;
+            }
+        };
+
+        self.status = CheckStatus::Testing;
+        cx.notify();
+
+        self._task = Some(cx.spawn(async move |this, cx| {
+            let result = ai_service.test_connection(&config).await;
+            _ = this.update(cx, |this, cx| {
+                this.status = match result {
+                    Ok(latency) => CheckStatus::Connected(latency),
+                    Err(e) => {
+                        log::warn!("Model connection test failed: {}", e);
+                        CheckStatus::Failed(e.to_string())
+                    }
+                };
+                cx.notify();
+            });
+        }));
+    }
+
+    fn fetch_models(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(ai_service) = AppState::global(cx).ai_service().cloned() else {
+            log::warn!("AiService not available");
+            return;
+        };
+        let config = match self.model_config(cx) {
+            Ok(config) => config,
+            Err(error) => {
+                self.status = CheckStatus::Failed(error);
+                cx.notify();
+                return;
+            }
+        };
+
+        self.status = CheckStatus::Fetching;
+        cx.notify();
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let result = ai_service.list_models(&config).await;
+            _ = this.update_in(cx, |this, window, cx| {
+                match result {
+                    Ok(models) => {
+                        this.status = CheckStatus::Fetched(models.len());
+                        this.has_models = !models.is_empty();
+                        this.model_select.update(cx, |state, cx| {
+                            state.set_items(models, window, cx);
+                        });
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to list models: {}", e);
+                        this.status = CheckStatus::Failed(e.to_string());
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn on_model_selected(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(model) = self.model_select.read(cx).selected_value().cloned() else {
+            return;
+        };
+        self.model_input.update(cx, |state, cx| {
+            state.set_value(model, window, cx);
+        });
+    }
+}
+
+impl Render for ModelCheck {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let busy = matches!(self.status, CheckStatus::Testing | CheckStatus::Fetching);
+        let status = match &self.status {
+            CheckStatus::Idle => None,
+            CheckStatus::Testing => Some(t!("settings.models.check.testing").to_string()),
+            CheckStatus::Connected(latency) => {
+                Some(t!("settings.models.check.connected", ms = latency.as_millis()).to_string())
+            }
+            CheckStatus::Fetching => Some(t!("settings.models.check.fetching").to_string()),
+            CheckStatus::Fetched(0) => Some(t!("settings.models.check.no_models").to_string()),
+            CheckStatus::Fetched(count) => {
+                Some(t!("settings.models.check.fetched", count = count).to_string())
+            }
+            CheckStatus::Failed(error) => Some(error.clone()),
+        };
+        let status_color = match self.status {
+            CheckStatus::Connected(_) => cx.theme().green,
+            CheckStatus::Failed(_) => cx.theme().danger,
+            _ => cx.theme().muted_foreground,
+        };
+
+        v_flex()
+            .w_full()
+            .gap_2()
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .child(
+                        Button::new("model-test-connection")
+                            .label(t!("settings.models.check.test").to_string())
+                            .icon(IconName::Globe)
+                            .outline()
+                            .small()
+                            .disabled(busy)
+                            .on_click(cx.listener(|this, _, _, cx| this.test_connection(cx))),
+                    )
+                    .child(
+                        Button::new("model-fetch-models")
+                            .label(t!("settings.models.check.fetch").to_string())
+                            .icon(IconName::Search)
+                            .outline()
+                            .small()
+                            .disabled(busy)
+                            .on_click(
+                                cx.listener(|this, _, window, cx| this.fetch_models(window, cx)),
+                            ),
+                    ),
+            )
+            .when_some(status, |this, status| {
+                this.child(Label::new(status).text_xs().text_color(status_color))
+            })
+            .when(self.has_models, |this| {
+                this.child(Select::new(&self.model_select).small())
+            })
+    }
+}
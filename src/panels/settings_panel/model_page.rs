@@ -14,6 +14,7 @@ use gpui_component::{
 };
 use rust_i18n::t;
 
+use super::model_check::ModelCheck;
 use super::panel::SettingsPanel;
 use crate::AppState;
 
@@ -265,6 +266,16 @@ impl SettingsPanel {
             InputState::new(window, cx)
                 .placeholder(t!("settings.models.input.model_name.placeholder").to_string())
         });
+        let model_check = cx.new(|cx| {
+            ModelCheck::new(
+                provider_input.clone(),
+                url_input.clone(),
+                key_input.clone(),
+                model_input.clone(),
+                window,
+                cx,
+            )
+        });
         let entity = cx.entity().downgrade();
 
         window.open_dialog(cx, move |dialog, _window, _cx| {
@@ -310,6 +321,8 @@ impl SettingsPanel {
                                 base_url: url,
                                 api_key: key,
                                 model_name: model,
+                                max_tokens: None,
+                                timeout_secs: None,
                             };
                             let name_clone = name.clone();
                             let entity = entity.clone();
@@ -379,7 +392,8 @@ impl SettingsPanel {
                                     t!("settings.models.field.model_name_label").to_string(),
                                 ))
                                 .child(Input::new(&model_input)),
-                        ),
+                        )
+                        .child(model_check.clone()),
                 )
         });
     }
@@ -415,8 +429,19 @@ impl SettingsPanel {
             state.set_value(config.model_name.clone(), window, cx);
             state
         });
+        let model_check = cx.new(|cx| {
+            ModelCheck::new(
+                provider_input.clone(),
+                url_input.clone(),
+                key_input.clone(),
+                model_input.clone(),
+                window,
+                cx,
+            )
+        });
 
         let enabled = config.enabled;
+        let (max_tokens, timeout_secs) = (config.max_tokens, config.timeout_secs);
 
         window.open_dialog(cx, move |dialog, _window, _cx| {
             dialog
@@ -458,6 +483,8 @@ impl SettingsPanel {
                                 base_url: url.to_string(),
                                 api_key: key.to_string(),
                                 model_name: model.to_string(),
+                                max_tokens,
+                                timeout_secs,
                             };
 
                             cx.spawn(async move |cx| {
@@ -508,7 +535,8 @@ impl SettingsPanel {
                                     t!("settings.models.field.model_name_label").to_string(),
                                 ))
                                 .child(Input::new(&model_input)),
-                        ),
+                        )
+                        .child(model_check.clone()),
                 )
         });
     }
@@ -2,20 +2,30 @@ use gpui::{
     App, AppContext as _, Context, Entity, FocusHandle, Focusable, IntoElement, Render, Window, px,
 };
 use gpui_component::{
+    WindowExt as _,
     input::InputState,
+    notification::Notification,
     setting::{SettingPage, Settings},
 };
 use rust_i18n::t;
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 use crate::{
     AppState,
     core::{
-        config::{AgentProcessConfig, CommandConfig, McpServerConfig, ModelConfig},
+        agent::AgentFeatures,
+        config::{AgentProcessConfig, CommandConfig, ConfigError, McpServerConfig, ModelConfig},
+        event_bus::{AgentConfigEvent, WorkspaceUpdateEvent},
+        services::ScopedTemplate,
         updater::UpdateManager,
     },
 };
 
+use agentx_types::EffectiveConfig;
+
 use super::types::{AppSettings, UpdateStatus};
 
 pub struct SettingsPanel {
@@ -24,9 +34,21 @@ pub struct SettingsPanel {
     pub(super) update_manager: UpdateManager,
     // Cached configuration state (synchronized by events)
     pub(super) cached_agents: HashMap<String, AgentProcessConfig>,
+    /// Agents whose reloaded config waits for their sessions to end
+    pub(super) pending_restarts: HashSet<String>,
+    /// Features of the running agents, `None` until an agent is initialized
+    pub(super) cached_agent_features: Vec<(String, Option<AgentFeatures>)>,
+    /// Why config.json was rejected (the last valid config is shown meanwhile)
+    pub(super) config_error: Option<ConfigError>,
     pub(super) cached_models: HashMap<String, ModelConfig>,
     pub(super) cached_mcp_servers: HashMap<String, McpServerConfig>,
     pub(super) cached_commands: HashMap<String, CommandConfig>,
+    /// Global templates plus those of the active workspace
+    pub(super) cached_task_templates: Vec<ScopedTemplate>,
+    /// Active workspace (id, name) whose templates are listed
+    pub(super) cached_template_workspace: Option<(String, String)>,
+    /// Effective config of the active workspace (workspace name, config)
+    pub(super) workspace_config: Option<(String, EffectiveConfig)>,
     pub(super) cached_upload_dir: PathBuf,
     pub(super) cached_proxy: crate::core::config::ProxyConfig,
     // JSON editor state for MCP servers
@@ -94,9 +116,15 @@ impl SettingsPanel {
             update_status: UpdateStatus::Idle,
             update_manager: UpdateManager::default(),
             cached_agents: HashMap::new(),
+            pending_restarts: HashSet::new(),
+            cached_agent_features: Vec::new(),
+            config_error: None,
             cached_models: HashMap::new(),
             cached_mcp_servers: HashMap::new(),
             cached_commands: HashMap::new(),
+            cached_task_templates: Vec::new(),
+            cached_template_workspace: None,
+            workspace_config: None,
             cached_upload_dir: PathBuf::from("."),
             cached_proxy: crate::core::config::ProxyConfig::default(),
             mcp_json_editor,
@@ -119,11 +147,15 @@ impl SettingsPanel {
                 let commands = service.list_commands().await;
                 let upload_dir = service.get_upload_dir().await;
                 let proxy = service.proxy_config();
+                let pending_restarts = service.pending_restarts().await;
+                let config_error = service.config_error().await;
 
                 _ = window.update(|window, cx| {
                     if let Some(entity) = weak_entity.upgrade() {
                         entity.update(cx, |this, cx| {
                             this.cached_agents = agents.into_iter().collect();
+                            this.pending_restarts = pending_restarts.into_iter().collect();
+                            this.config_error = config_error;
                             this.cached_models = models.into_iter().collect();
                             this.cached_mcp_servers = mcp_servers.into_iter().collect();
                             this.cached_commands = commands.into_iter().collect();
@@ -139,6 +171,10 @@ impl SettingsPanel {
             .detach();
         }
 
+        Self::load_task_templates(cx);
+        Self::load_workspace_config(cx);
+        Self::load_agent_features(cx);
+
         // Subscribe to EventHub for dynamic updates
         let event_hub = AppState::global(cx).event_hub().clone();
         let weak_entity = cx.entity().downgrade();
@@ -151,7 +187,22 @@ impl SettingsPanel {
         cx.spawn_in(window, async move |_this, window| {
             while let Some(event) = rx.recv().await {
                 if let Some(entity) = weak_entity.upgrade() {
-                    _ = window.update(|_window, cx| {
+                    _ = window.update(|window, cx| {
+                        if let AgentConfigEvent::AgentRestartDeferred {
+                            name,
+                            active_sessions,
+                        } = &event
+                        {
+                            let note = Notification::warning(
+                                t!(
+                                    "settings.agents.restart_deferred",
+                                    name = name,
+                                    count = active_sessions
+                                )
+                                .to_string(),
+                            );
+                            window.push_notification(note, cx);
+                        }
                         entity.update(cx, |this, cx| {
                             this.on_agent_config_event(&event, cx);
                         });
@@ -163,17 +214,116 @@ impl SettingsPanel {
         })
         .detach();
 
+        // Workspace templates are stored with the workspace, not the config file
+        let weak_entity = cx.entity().downgrade();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        event_hub.subscribe_workspace_updates(move |event| {
+            if matches!(
+                event,
+                WorkspaceUpdateEvent::TemplatesUpdated { .. }
+                    | WorkspaceUpdateEvent::WorkspaceAdded { .. }
+                    | WorkspaceUpdateEvent::WorkspaceRemoved { .. }
+            ) {
+                let _ = tx.send(());
+            }
+        });
+
+        cx.spawn_in(window, async move |_this, window| {
+            while rx.recv().await.is_some() {
+                let Some(entity) = weak_entity.upgrade() else {
+                    break;
+                };
+                _ = window.update(|_window, cx| {
+                    entity.update(cx, |_this, cx| {
+                        Self::load_task_templates(cx);
+                        Self::load_workspace_config(cx);
+                    });
+                });
+            }
+        })
+        .detach();
+
         panel
     }
 
-    /// Handle agent configuration events
-    fn on_agent_config_event(
-        &mut self,
-        event: &crate::core::event_bus::AgentConfigEvent,
-        cx: &mut Context<Self>,
-    ) {
-        use crate::core::event_bus::AgentConfigEvent;
+    /// Load global task templates and those of the active workspace
+    pub(super) fn load_task_templates(cx: &mut Context<Self>) {
+        let Some(template_service) = AppState::global(cx).task_template_service().cloned() else {
+            return;
+        };
+        let Some(workspace_service) = AppState::global(cx).workspace_service().cloned() else {
+            return;
+        };
+
+        cx.spawn(async move |this, cx| {
+            let workspace = workspace_service.get_active_workspace().await;
+            let templates = template_service
+                .list_templates(workspace.as_ref().map(|ws| ws.id.as_str()))
+                .await;
+
+            _ = this.update(cx, |this, cx| {
+                this.cached_task_templates = templates;
+                this.cached_template_workspace = workspace.map(|ws| (ws.id, ws.name));
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Resolve the layered configuration of the active workspace
+    pub(super) fn load_workspace_config(cx: &mut Context<Self>) {
+        let Some(config_service) = AppState::global(cx).agent_config_service().cloned() else {
+            return;
+        };
+        let Some(workspace_service) = AppState::global(cx).workspace_service().cloned() else {
+            return;
+        };
+
+        cx.spawn(async move |this, cx| {
+            let workspace_config = match workspace_service.get_active_workspace().await {
+                Some(workspace) => Some((
+                    workspace.name.clone(),
+                    config_service.effective_config(&workspace, None).await,
+                )),
+                None => None,
+            };
+
+            _ = this.update(cx, |this, cx| {
+                this.workspace_config = workspace_config;
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Load the features of the running agents from their initialize responses
+    pub(super) fn load_agent_features(cx: &mut Context<Self>) {
+        let Some(agent_service) = AppState::global(cx).agent_service().cloned() else {
+            return;
+        };
+
+        cx.spawn(async move |this, cx| {
+            let features = agent_service
+                .list_agents_with_info()
+                .await
+                .into_iter()
+                .map(|(name, response)| {
+                    let features = response.as_ref().map(AgentFeatures::from_init_response);
+                    (name, features)
+                })
+                .collect();
 
+            _ = this.update(cx, |this, cx| {
+                this.cached_agent_features = features;
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Handle agent configuration events
+    fn on_agent_config_event(&mut self, event: &AgentConfigEvent, cx: &mut Context<Self>) {
         log::info!("[SettingsPanel] Processing config event: {:?}", event);
 
         // Update cache based on event type
@@ -184,9 +334,14 @@ impl SettingsPanel {
             }
             AgentConfigEvent::AgentUpdated { name, config } => {
                 self.cached_agents.insert(name.clone(), config.clone());
+                self.pending_restarts.remove(name);
             }
             AgentConfigEvent::AgentRemoved { name } => {
                 self.cached_agents.remove(name);
+                self.pending_restarts.remove(name);
+            }
+            AgentConfigEvent::AgentRestartDeferred { name, .. } => {
+                self.pending_restarts.insert(name.clone());
             }
 
             // Model events
@@ -222,15 +377,42 @@ impl SettingsPanel {
                 self.cached_commands.remove(name);
             }
 
+            // Task template events
+            AgentConfigEvent::TaskTemplateAdded { .. }
+            | AgentConfigEvent::TaskTemplateUpdated { .. }
+            | AgentConfigEvent::TaskTemplateRemoved { .. } => {
+                Self::load_task_templates(cx);
+            }
+
             // Full reload
             AgentConfigEvent::ConfigReloaded { config } => {
                 self.cached_agents = config.agent_servers.clone();
+                self.pending_restarts
+                    .retain(|name| config.agent_servers.contains_key(name));
                 self.cached_models = config.models.clone();
                 self.cached_mcp_servers = config.mcp_servers.clone();
                 self.cached_commands = config.commands.clone();
                 self.cached_upload_dir = config.upload_dir.clone();
                 self.cached_proxy = config.proxy.clone();
+                Self::load_task_templates(cx);
+            }
+            AgentConfigEvent::ConfigValidated { error } => {
+                self.config_error = error.clone();
             }
+            AgentConfigEvent::SecretsExternalized { .. } => {}
+        }
+
+        // Global values show through the workspace layers
+        Self::load_workspace_config(cx);
+        // Changed agents were restarted and may support other features
+        if matches!(
+            event,
+            AgentConfigEvent::AgentAdded { .. }
+                | AgentConfigEvent::AgentUpdated { .. }
+                | AgentConfigEvent::AgentRemoved { .. }
+                | AgentConfigEvent::ConfigReloaded { .. }
+        ) {
+            Self::load_agent_features(cx);
         }
 
         // Trigger re-render
@@ -250,6 +432,7 @@ impl SettingsPanel {
             self.prompt_page(&view),
             self.mcp_page(&view),
             self.command_page(&view),
+            self.layers_page(&view),
             super::about_page::about_page(resettable),
         ]
     }
@@ -30,7 +30,11 @@ use crate::core::services::WorkspaceService;
 use crate::core::{event_bus::WorkspaceUpdateEvent, services::SessionStatus};
 use crate::panels::dock_panel::DockPanel;
 use crate::schemas::workspace_schema::WorkspaceTask;
-use crate::{AppState, OpenSessionManager, PanelAction, StatusIndicator, utils};
+use crate::{
+    AppState, EditTaskTemplate, NewTaskFromTemplate, OpenComparison, OpenSessionManager,
+    PanelAction, QueueTasks, RunPipeline, StatusIndicator, utils,
+};
+use agentx_types::schemas::pipeline::{PipelineRun, PipelineStatus};
 
 // ============================================================================
 // Constants - Layout spacing
@@ -65,6 +69,8 @@ pub enum ViewMode {
 pub struct TaskPanel {
     focus_handle: FocusHandle,
     workspaces: Vec<WorkspaceGroup>,
+    /// Pipeline runs by ID (for the stage progress of pipeline tasks)
+    pipeline_runs: HashMap<String, PipelineRun>,
     selected_task_id: Option<String>,
     context_menu_task_id: Option<String>,
     view_mode: ViewMode,
@@ -130,6 +136,7 @@ impl TaskPanel {
         Self {
             focus_handle: cx.focus_handle(),
             workspaces: Vec::new(),
+            pipeline_runs: HashMap::new(),
             selected_task_id: None,
             context_menu_task_id: None,
             view_mode: ViewMode::Tree,
@@ -175,6 +182,11 @@ impl TaskPanel {
             let config = workspace_service.get_config().await;
             let workspaces_list = config.workspaces;
             let tasks = config.tasks;
+            let pipeline_runs: HashMap<String, PipelineRun> = config
+                .pipeline_runs
+                .into_iter()
+                .map(|run| (run.id.clone(), run))
+                .collect();
 
             let mut tasks_by_workspace: HashMap<String, Vec<Rc<WorkspaceTask>>> = HashMap::new();
             for task in tasks {
@@ -213,6 +225,7 @@ impl TaskPanel {
                             }
                         })
                         .collect();
+                    this.pipeline_runs = pipeline_runs;
 
                     this.ensure_selected_task_valid();
                     this.is_loading = false;
@@ -339,6 +352,34 @@ impl TaskPanel {
         .detach();
     }
 
+    /// Incremental update: Refresh a single pipeline run
+    fn update_pipeline_run_incremental(
+        entity: &Entity<Self>,
+        run_id: String,
+        workspace_service: std::sync::Arc<WorkspaceService>,
+        cx: &mut App,
+    ) {
+        let entity_clone = entity.clone();
+        cx.spawn(async move |cx| {
+            let run = workspace_service.get_pipeline_run(&run_id).await;
+            cx.update(|cx| {
+                entity_clone.update(cx, |this, cx| {
+                    match run {
+                        Some(run) => {
+                            this.pipeline_runs.insert(run_id.clone(), run);
+                        }
+                        None => {
+                            this.pipeline_runs.remove(&run_id);
+                        }
+                    }
+                    log::debug!("Incrementally updated pipeline run {}", run_id);
+                    cx.notify();
+                });
+            });
+        })
+        .detach();
+    }
+
     /// Incremental update: Add a single workspace
     fn add_workspace_incremental(
         entity: &Entity<Self>,
@@ -518,6 +559,25 @@ impl TaskPanel {
                             });
                         }
                     }
+                    WorkspaceUpdateEvent::TemplatesUpdated { .. } => {
+                        // Templates are loaded on demand when creating a task
+                    }
+                    WorkspaceUpdateEvent::ComparisonUpdated { .. } => {
+                        // Candidate tasks are reported through TaskCreated/TaskUpdated
+                    }
+                    WorkspaceUpdateEvent::PipelineUpdated { run_id, .. } => {
+                        log::debug!("TaskPanel received PipelineUpdated: {}", run_id);
+                        if let Some(entity) = entity_weak.upgrade() {
+                            cx.update(|cx| {
+                                Self::update_pipeline_run_incremental(
+                                    &entity,
+                                    run_id.clone(),
+                                    workspace_service.clone(),
+                                    cx,
+                                );
+                            });
+                        }
+                    }
                 }
             }
         })
@@ -570,7 +630,8 @@ impl TaskPanel {
                     continue;
                 }
 
-                if task.status == status {
+                // The task queue reports the status of its tasks via TaskUpdated
+                if task.is_queued() || task.status == status {
                     continue;
                 }
 
@@ -589,12 +650,13 @@ impl TaskPanel {
 
             // Persist status to JSON file
             if let Some(task_id) = task_id_to_update {
-                if let Some(workspace_service) = AppState::global(cx).workspace_service() {
-                    let workspace_service = workspace_service.clone();
+                if let Some(task_queue_service) = AppState::global(cx).task_queue_service() {
+                    let task_queue_service = task_queue_service.clone();
+                    let session_id = session_id.to_string();
                     let status_clone = status.clone();
                     cx.spawn(async move |_entity, _cx| {
-                        match workspace_service
-                            .update_task_status(&task_id, status_clone)
+                        match task_queue_service
+                            .update_session_status(&session_id, status_clone)
                             .await
                         {
                             Ok(_) => {
@@ -731,6 +793,37 @@ impl TaskPanel {
         .detach();
     }
 
+    fn retry_task(&mut self, task_id: String, cx: &mut Context<Self>) {
+        let workspace_service = match AppState::global(cx).workspace_service() {
+            Some(service) => service.clone(),
+            None => {
+                log::warn!("WorkspaceService not available");
+                return;
+            }
+        };
+
+        cx.spawn(async move |_entity, _cx| {
+            match workspace_service.retry_task(&task_id).await {
+                Ok(_) => {
+                    log::info!("Successfully re-queued task: {}", task_id);
+                    // The UI will be updated via the TaskUpdated event
+                }
+                Err(e) => {
+                    log::error!("Failed to re-queue task: {}", e);
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Find a task by ID across all workspaces
+    fn find_task(&self, task_id: &str) -> Option<&Rc<WorkspaceTask>> {
+        self.workspaces
+            .iter()
+            .flat_map(|w| w.tasks.iter())
+            .find(|t| t.id == task_id)
+    }
+
     fn select_task(&mut self, task_id: String, cx: &mut Context<Self>) {
         self.selected_task_id = Some(task_id);
         cx.notify();
@@ -1216,6 +1309,82 @@ impl TaskPanel {
                                 }),
                             );
 
+                            // Task template items
+                            menu = menu
+                                .separator()
+                                .item(
+                                    PopupMenuItem::new(
+                                        t!("task_panel.workspace.new_from_template").to_string(),
+                                    )
+                                    .icon(IconName::File)
+                                    .on_click({
+                                        let workspace_id = workspace_id.clone();
+                                        move |_, window, cx| {
+                                            window.dispatch_action(
+                                                Box::new(NewTaskFromTemplate {
+                                                    workspace_id: Some(workspace_id.clone()),
+                                                    selected_files: Vec::new(),
+                                                }),
+                                                cx,
+                                            );
+                                        }
+                                    }),
+                                )
+                                .item(
+                                    PopupMenuItem::new(
+                                        t!("task_panel.workspace.new_template").to_string(),
+                                    )
+                                    .icon(IconName::Plus)
+                                    .on_click({
+                                        let workspace_id = workspace_id.clone();
+                                        move |_, window, cx| {
+                                            window.dispatch_action(
+                                                Box::new(EditTaskTemplate {
+                                                    workspace_id: Some(workspace_id.clone()),
+                                                    name: None,
+                                                    scope: None,
+                                                    template: Default::default(),
+                                                }),
+                                                cx,
+                                            );
+                                        }
+                                    }),
+                                )
+                                .item(
+                                    PopupMenuItem::new(
+                                        t!("task_panel.workspace.run_pipeline").to_string(),
+                                    )
+                                    .icon(IconName::ArrowRight)
+                                    .on_click({
+                                        let workspace_id = workspace_id.clone();
+                                        move |_, window, cx| {
+                                            window.dispatch_action(
+                                                Box::new(RunPipeline {
+                                                    workspace_id: Some(workspace_id.clone()),
+                                                }),
+                                                cx,
+                                            );
+                                        }
+                                    }),
+                                )
+                                .item(
+                                    PopupMenuItem::new(
+                                        t!("task_panel.workspace.queue_tasks").to_string(),
+                                    )
+                                    .icon(IconName::Inbox)
+                                    .on_click({
+                                        let workspace_id = workspace_id.clone();
+                                        move |_, window, cx| {
+                                            window.dispatch_action(
+                                                Box::new(QueueTasks {
+                                                    workspace_id: Some(workspace_id.clone()),
+                                                }),
+                                                cx,
+                                            );
+                                        }
+                                    }),
+                                );
+
                             menu.separator().item(
                                 PopupMenuItem::new(t!("task_panel.workspace.remove").to_string())
                                     .icon(Icon::new(crate::assets::Icon::Trash2))
@@ -1382,8 +1551,131 @@ impl TaskPanel {
                                     }),
                             ),
                     )
-                    .child(self.render_status_badge(&task.status, cx)),
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(self.render_pipeline_stage(task, cx))
+                            .child(self.render_change_stats(task, cx))
+                            .child(self.render_status_badge(&task.status, cx)),
+                    ),
+            )
+            // Changed files list (only for the selected task)
+            .when(is_selected && task.changes.has_changes(), |this| {
+                this.child(self.render_changed_files(task, cx))
+            })
+    }
+
+    /// Render the pipeline stage of a task, e.g. "2/3" (empty for other tasks)
+    fn render_pipeline_stage(&self, task: &WorkspaceTask, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let run = task
+            .pipeline_run_id
+            .as_ref()
+            .and_then(|id| self.pipeline_runs.get(id));
+
+        h_flex().when_some(run.zip(task.pipeline_stage), |this, (run, stage)| {
+            let color = match run.status {
+                PipelineStatus::Running => theme.muted_foreground,
+                PipelineStatus::Completed => theme.green,
+                PipelineStatus::Failed => theme.red,
+            };
+            this.gap_1()
+                .items_center()
+                .text_xs()
+                .text_color(color)
+                .child(Icon::new(IconName::ArrowRight).size(px(12.)))
+                .child(
+                    t!(
+                        "task_panel.task.pipeline_stage",
+                        stage = stage + 1,
+                        total = run.definition.stages.len()
+                    )
+                    .to_string(),
+                )
+        })
+    }
+
+    /// Render the +/- line counts of a task (empty if nothing changed)
+    fn render_change_stats(&self, task: &WorkspaceTask, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let additions = task.changes.total_additions();
+        let deletions = task.changes.total_deletions();
+
+        h_flex()
+            .gap_1()
+            .items_center()
+            .text_xs()
+            .when(additions > 0, |this| {
+                this.child(
+                    div()
+                        .text_color(theme.green)
+                        .child(format!("+{}", additions)),
+                )
+            })
+            .when(deletions > 0, |this| {
+                this.child(div().text_color(theme.red).child(format!("-{}", deletions)))
+            })
+    }
+
+    /// Render the per-task "files changed" list
+    fn render_changed_files(&self, task: &WorkspaceTask, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let total_files = task.changes.total_files();
+
+        v_flex()
+            .w_full()
+            .gap_0p5()
+            .pt_1()
+            .pl(px(24.0)) // Align with task name (icon + gap)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(t!("task_panel.task.files_changed", count = total_files).to_string()),
             )
+            .children(task.changes.files.iter().map(|file| {
+                let filename = file
+                    .path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("unknown")
+                    .to_string();
+
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .items_center()
+                    .text_xs()
+                    .child(
+                        Icon::new(IconName::File)
+                            .size_3()
+                            .text_color(theme.muted_foreground),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .overflow_x_hidden()
+                            .text_ellipsis()
+                            .text_color(theme.foreground)
+                            .child(filename),
+                    )
+                    .when(file.additions > 0, |this| {
+                        this.child(
+                            div()
+                                .text_color(theme.green)
+                                .child(format!("+{}", file.additions)),
+                        )
+                    })
+                    .when(file.deletions > 0, |this| {
+                        this.child(
+                            div()
+                                .text_color(theme.red)
+                                .child(format!("-{}", file.deletions)),
+                        )
+                    })
+            }))
     }
 
     // ========================================================================
@@ -1718,6 +2010,50 @@ impl Render for TaskPanel {
                         let Some(task_id) = entity.read(cx).context_menu_task_id.clone() else {
                             return menu;
                         };
+                        let can_retry = entity.read(cx).find_task(&task_id).is_some_and(|task| {
+                            task.is_queued()
+                                && matches!(
+                                    task.status,
+                                    SessionStatus::Failed | SessionStatus::Completed
+                                )
+                        });
+
+                        let comparison_id = entity
+                            .read(cx)
+                            .find_task(&task_id)
+                            .and_then(|task| task.comparison_id.clone());
+
+                        let mut menu = menu;
+                        if let Some(comparison_id) = comparison_id {
+                            menu = menu.item(
+                                PopupMenuItem::new(
+                                    t!("task_panel.task.open_comparison").to_string(),
+                                )
+                                .icon(Icon::new(IconName::LayoutDashboard))
+                                .on_click(move |_, window, cx| {
+                                    window.dispatch_action(
+                                        Box::new(OpenComparison {
+                                            comparison_id: comparison_id.clone(),
+                                        }),
+                                        cx,
+                                    );
+                                }),
+                            );
+                        }
+                        if can_retry {
+                            let entity = entity.clone();
+                            let task_id = task_id.clone();
+                            menu = menu.item(
+                                PopupMenuItem::new(t!("task_panel.task.retry").to_string())
+                                    .icon(Icon::new(IconName::Redo))
+                                    .on_click(move |_, _, cx| {
+                                        entity.update(cx, |this, cx| {
+                                            this.retry_task(task_id.clone(), cx);
+                                        });
+                                    }),
+                            );
+                        }
+
                         let entity = entity.clone();
                         menu.item(
                             PopupMenuItem::new(t!("task_panel.task.delete").to_string())
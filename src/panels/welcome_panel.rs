@@ -1,12 +1,15 @@
 use gpui::{
     App, AppContext, ClipboardEntry, Context, Entity, FocusHandle, Focusable, InteractiveElement,
-    IntoElement, ParentElement, Render, SharedString, Styled, Subscription, Window, px,
+    IntoElement, ParentElement, Render, SharedString, Styled, Subscription, Window,
+    prelude::FluentBuilder, px,
 };
 use rust_i18n::t;
 use std::collections::HashSet;
 
 use gpui_component::{
-    ActiveTheme, IndexPath, StyledExt, WindowExt,
+    ActiveTheme, IconName, IndexPath, Sizable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex,
     input::InputState,
     list::ListState,
     notification::Notification,
@@ -17,13 +20,17 @@ use gpui_component::{
 use agent_client_protocol::{self as acp, AvailableCommand, ImageContent};
 
 use crate::{
-    AppState, CreateTaskFromWelcome, WelcomeSession,
+    AppState, CompareAgents, CreateTaskFromWelcome, EditTaskTemplate, NewTaskFromTemplate,
+    WelcomeSession,
     app::actions::AddCodeSelection,
     components::{
         AgentItem, ChatInputBox, FileItem, FilePickerDelegate, ModeSelectItem, ModelSelectItem,
     },
     core::{config::McpServerConfig, services::AgentSessionInfo},
+    workspace::actions::confirm_trust_folder,
 };
+use agentx_types::UntrustedFolder;
+use agentx_types::schemas::task_template::TaskTemplate;
 
 // File picker delegate is now imported from components module
 
@@ -330,7 +337,7 @@ impl WelcomePanel {
                             );
                             let new_root = this.working_directory.clone();
                             this.context_list.update(cx, |state, cx| {
-                                state.delegate_mut().reset_root(new_root);
+                                state.delegate_mut().reset_root(new_root, cx);
                                 cx.notify();
                             });
                             this.clear_file_suggestions(cx);
@@ -370,7 +377,7 @@ impl WelcomePanel {
             working_directory.unwrap_or_else(|| AppState::global(cx).current_working_dir().clone());
 
         let context_list = cx.new(|cx| {
-            let delegate = FilePickerDelegate::new(&working_dir);
+            let delegate = FilePickerDelegate::new(&working_dir, cx);
             ListState::new(delegate, window, cx).searchable(true)
         });
 
@@ -526,12 +533,19 @@ impl WelcomePanel {
             None => return,
         };
 
+        let agent_config_service = AppState::global(cx).agent_config_service().cloned();
         let agent_select = self.agent_select.clone();
         let current_selection = self.agent_select.read(cx).selected_value().cloned();
         let no_agents_label = Self::no_agents_label();
         let weak_self = cx.entity().downgrade();
         cx.spawn_in(window, async move |_this, window| {
-            let agents = agent_service.list_agents().await;
+            let mut agents = agent_service.list_agents().await;
+            // Profile agents (`agent@profile`) start on first use
+            if let Some(service) = agent_config_service {
+                agents.extend(service.list_agent_variants().await);
+                agents.sort();
+                agents.dedup();
+            }
 
             _ = window.update(|window, cx| {
                 if let Some(this) = weak_self.upgrade() {
@@ -597,7 +611,8 @@ impl WelcomePanel {
                 // Force refresh to remove deleted agent
                 self.has_agents = false;
             }
-            AgentConfigEvent::AgentUpdated { name, .. } => {
+            AgentConfigEvent::AgentUpdated { name, .. }
+            | AgentConfigEvent::AgentRestartDeferred { name, .. } => {
                 log::info!("[WelcomePanel] Agent updated: {}", name);
                 // No action needed - agent name hasn't changed
             }
@@ -620,13 +635,18 @@ impl WelcomePanel {
                 log::info!("[WelcomePanel] MCP server removed: {}", name);
                 self.remove_mcp_server(name);
             }
-            // Model and Command events don't affect WelcomePanel
+            // Model, Command and Task Template events don't affect WelcomePanel
             AgentConfigEvent::ModelAdded { .. }
             | AgentConfigEvent::ModelUpdated { .. }
             | AgentConfigEvent::ModelRemoved { .. }
             | AgentConfigEvent::CommandAdded { .. }
             | AgentConfigEvent::CommandUpdated { .. }
-            | AgentConfigEvent::CommandRemoved { .. } => {
+            | AgentConfigEvent::CommandRemoved { .. }
+            | AgentConfigEvent::TaskTemplateAdded { .. }
+            | AgentConfigEvent::TaskTemplateUpdated { .. }
+            | AgentConfigEvent::TaskTemplateRemoved { .. }
+            | AgentConfigEvent::ConfigValidated { .. }
+            | AgentConfigEvent::SecretsExternalized { .. } => {
                 // No action needed for non-agent config changes
             }
         }
@@ -974,66 +994,31 @@ impl WelcomePanel {
         let query = query.trim();
         self.last_file_query = query.to_string();
 
-        let (needs_scan, root_path) = {
-            let delegate = self.context_list.read(cx).delegate();
-            (delegate.needs_scan(), delegate.root_path().to_path_buf())
-        };
-
-        self.context_list.update(cx, |state, cx| {
+        let pending_index = self.context_list.update(cx, |state, cx| {
             let delegate = state.delegate_mut();
             delegate.set_search_query(query.to_string());
-            if needs_scan && !delegate.is_scanning() {
-                delegate.mark_scanning();
-            }
             cx.notify();
+            delegate.index().filter(|index| !index.is_ready()).cloned()
         });
 
-        if needs_scan {
-            let weak_self = cx.entity().downgrade();
-            cx.spawn(async move |_this, cx| {
-                let items = smol::unblock(move || {
-                    FilePickerDelegate::scan_directory(&root_path, &root_path)
-                })
-                .await;
-
-                _ = cx.update(|cx| {
-                    if let Some(this) = weak_self.upgrade() {
-                        this.update(cx, |this, cx| {
-                            let value = this.input_state.read(cx).value();
-                            let mention_query = value.rfind('@').and_then(|at_index| {
-                                let query = &value[at_index + 1..];
-                                if query.chars().any(char::is_whitespace) {
-                                    None
-                                } else {
-                                    Some(query.to_string())
-                                }
-                            });
-
-                            let Some(query) = mention_query else {
-                                this.clear_file_suggestions(cx);
-                                return;
-                            };
-
-                            this.last_file_query = query.clone();
-                            this.context_list.update(cx, |state, cx| {
-                                state.delegate_mut().set_items(items);
-                                state.delegate_mut().set_search_query(query);
-                                cx.notify();
-                            });
+        // Refresh the suggestions once the workspace index is built
+        if let Some(index) = pending_index {
+            cx.spawn(async move |this, cx| {
+                index.wait_until_ready().await;
+                _ = this.update(cx, |this, cx| {
+                    let value = this.input_state.read(cx).value();
+                    let mention_query = value.rfind('@').and_then(|at_index| {
+                        let query = &value[at_index + 1..];
+                        if query.chars().any(char::is_whitespace) {
+                            None
+                        } else {
+                            Some(query.to_string())
+                        }
+                    });
 
-                            let items = this
-                                .context_list
-                                .read(cx)
-                                .delegate()
-                                .filtered_items()
-                                .iter()
-                                .take(MAX_FILE_SUGGESTIONS)
-                                .cloned()
-                                .collect::<Vec<_>>();
-
-                            this.file_suggestions = items;
-                            cx.notify();
-                        });
+                    match mention_query {
+                        Some(query) => this.update_file_suggestions(&query, cx),
+                        None => this.clear_file_suggestions(cx),
                     }
                 });
             })
@@ -1294,15 +1279,55 @@ impl WelcomePanel {
             let mut mcp_servers =
                 Self::collect_mcp_servers_from_selection(&available_mcps, &selected_mcps);
 
-            if !mcp_selection_initialized {
-                if let Some(service) = agent_config_service {
-                    let defaults = service.list_mcp_servers().await;
-                    mcp_servers = defaults
+            let effective = match &agent_config_service {
+                Some(service) => Some(service.effective_config_at(&cwd, None).await),
+                None => None,
+            };
+            if let (Some(service), Some(effective)) = (&agent_config_service, &effective) {
+                if !mcp_selection_initialized {
+                    mcp_servers = effective
+                        .enabled_mcp_servers()
                         .into_iter()
-                        .filter(|(_, config)| config.enabled)
                         .map(|(name, config)| config.to_acp_mcp_server(name))
                         .collect();
                 }
+                // A profile agent only gets the MCP servers its profile allows
+                if let Some(profile) = effective.agent_profile(&agent_name_for_session) {
+                    mcp_servers.retain(|server| match server {
+                        acp::McpServer::Stdio(stdio) => profile.allows_mcp_server(&stdio.name),
+                        _ => true,
+                    });
+                }
+                if let Err(e) = service
+                    .prepare_agent(effective, &agent_name_for_session)
+                    .await
+                {
+                    log::error!(
+                        "[WelcomePanel] Failed to prepare agent '{}': {}",
+                        agent_name_for_session,
+                        e
+                    );
+                    _ = window.update(|window, cx| {
+                        if let Some(this) = weak_self.upgrade() {
+                            this.update(cx, |this, cx| {
+                                this.is_session_loading = false;
+                                cx.notify();
+                            });
+                            if let Some(untrusted) = e.downcast_ref::<UntrustedFolder>() {
+                                confirm_trust_folder(untrusted.clone(), window, cx);
+                                return;
+                            }
+                            struct SessionCreationError;
+                            let note = Notification::error(format!(
+                                "Failed to create session: {}",
+                                e
+                            ))
+                            .id::<SessionCreationError>();
+                            window.push_notification(note, cx);
+                        }
+                    });
+                    return;
+                }
             }
 
             log::info!(
@@ -1317,6 +1342,11 @@ impl WelcomePanel {
             {
                 Ok(session_id) => {
                     log::info!("[WelcomePanel] Created new session: {}", session_id);
+                    if let (Some(service), Some(effective)) = (&agent_config_service, &effective) {
+                        service
+                            .apply_profile_defaults(effective, &agent_name_for_session, &session_id)
+                            .await;
+                    }
                     _ = window.update(|window, cx| {
                         if let Some(this) = weak_self.upgrade() {
                             this.update(cx, |this, cx| {
@@ -1451,6 +1481,7 @@ impl WelcomePanel {
                 images,
                 code_selections,
                 workspace_id,
+                working_dir: Some(self.working_directory.clone()),
             };
 
             log::info!(
@@ -1463,6 +1494,111 @@ impl WelcomePanel {
     }
 }
 
+impl WelcomePanel {
+    /// Buttons to compare agents, start a task from a template or save the current input as one
+    fn render_template_actions(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .w_full()
+            .px(px(32.))
+            .gap_2()
+            .justify_end()
+            .child(
+                Button::new("welcome-compare-agents")
+                    .label(t!("welcome.compare").to_string())
+                    .icon(IconName::LayoutDashboard)
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.handle_compare_agents(window, cx);
+                    })),
+            )
+            .child(
+                Button::new("welcome-from-template")
+                    .label(t!("welcome.template.use").to_string())
+                    .icon(IconName::File)
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        window.dispatch_action(
+                            Box::new(NewTaskFromTemplate {
+                                workspace_id: this.workspace_id.clone(),
+                                selected_files: this.selected_files.clone(),
+                            }),
+                            cx,
+                        );
+                    })),
+            )
+            .child(
+                Button::new("welcome-save-template")
+                    .label(t!("welcome.template.save").to_string())
+                    .icon(IconName::Plus)
+                    .ghost()
+                    .small()
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        let template = this.template_from_input(cx);
+                        window.dispatch_action(
+                            Box::new(EditTaskTemplate {
+                                workspace_id: this.workspace_id.clone(),
+                                name: None,
+                                scope: None,
+                                template,
+                            }),
+                            cx,
+                        );
+                    })),
+            )
+    }
+
+    /// Send the current input and attachments to several agents side by side
+    fn handle_compare_agents(&self, window: &mut Window, cx: &mut Context<Self>) {
+        let prompt = self.input_state.read(cx).text().to_string();
+        if prompt.trim().is_empty() {
+            return;
+        }
+
+        let mode = if self.has_modes {
+            self.mode_select.read(cx).selected_value().cloned()
+        } else {
+            None
+        };
+
+        // Keep the input: the agent picker may still be cancelled
+        let action = CompareAgents {
+            workspace_id: self.workspace_id.clone(),
+            prompt,
+            mode,
+            images: self.pasted_images.clone(),
+            code_selections: self.code_selections.clone(),
+        };
+        window.dispatch_action(Box::new(action), cx);
+    }
+
+    /// Build a template prefilled from the current input, agent, mode and MCP selection
+    fn template_from_input(&self, cx: &Context<Self>) -> TaskTemplate {
+        let agent_name = self
+            .agent_select
+            .read(cx)
+            .selected_value()
+            .cloned()
+            .filter(|name| *name != Self::no_agents_label());
+        let mode = if self.has_modes {
+            self.mode_select.read(cx).selected_value().cloned()
+        } else {
+            None
+        };
+
+        TaskTemplate {
+            agent_name,
+            mode,
+            mcp_servers: self
+                .mcp_selection_overridden
+                .then(|| self.selected_mcps.clone()),
+            prompt: self.input_state.read(cx).text().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
 impl Focusable for WelcomePanel {
     fn focus_handle(&self, _cx: &gpui::App) -> FocusHandle {
         self.focus_handle.clone()
@@ -1475,34 +1611,56 @@ impl WelcomePanel {
     fn handle_paste(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
         log::info!("Handling paste in WelcomePanel");
 
-        let mut handled = false;
-        if let Some(clipboard_item) = cx.read_from_clipboard() {
-            for entry in clipboard_item.entries().iter() {
-                if let ClipboardEntry::Image(image) = entry {
-                    log::info!("Processing pasted image: {:?}", image.format);
-                    let image = image.clone();
-                    handled = true;
-
-                    cx.spawn_in(window, async move |this, cx| {
-                        match crate::utils::clipboard::image_to_content(image).await {
-                            Ok((image_content, filename)) => {
-                                _ = cx.update(move |_window, cx| {
-                                    let _ = this.update(cx, |this, cx| {
-                                        this.pasted_images.push((image_content, filename));
-                                        cx.notify();
-                                    });
-                                });
-                            }
-                            Err(e) => {
-                                log::error!("Failed to process pasted image: {}", e);
-                            }
-                        }
+        let images: Vec<_> = cx
+            .read_from_clipboard()
+            .map(|clipboard_item| {
+                clipboard_item
+                    .entries()
+                    .iter()
+                    .filter_map(|entry| match entry {
+                        ClipboardEntry::Image(image) => Some(image.clone()),
+                        _ => None,
                     })
-                    .detach();
+                    .collect()
+            })
+            .unwrap_or_default();
+        if images.is_empty() {
+            return false;
+        }
+
+        let agent_service = AppState::global(cx).agent_service().cloned();
+        let agent_name = self.current_agent_name.clone();
+        cx.spawn_in(window, async move |this, cx| {
+            // Don't attach images the agent would refuse
+            if let Some(agent_name) = agent_name
+                && !crate::utils::clipboard::agent_accepts_images(agent_service, &agent_name).await
+            {
+                _ = cx.update(|window, cx| {
+                    let note = Notification::warning(
+                        t!("conversation.image_unsupported", agent = agent_name).to_string(),
+                    );
+                    window.push_notification(note, cx);
+                });
+                return;
+            }
+
+            for image in images {
+                log::info!("Processing pasted image: {:?}", image.format);
+                match crate::utils::clipboard::image_to_content(image).await {
+                    Ok((image_content, filename)) => {
+                        _ = this.update(cx, |this, cx| {
+                            this.pasted_images.push((image_content, filename));
+                            cx.notify();
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Failed to process pasted image: {}", e);
+                    }
                 }
             }
-        }
-        handled
+        })
+        .detach();
+        true
     }
 }
 
@@ -1689,7 +1847,10 @@ impl Render for WelcomePanel {
                                     this.handle_send_task(window, cx);
                                 }))
                         },
-                    ),
+                    )
+                    .when(self.has_workspace, |this| {
+                        this.child(self.render_template_actions(cx))
+                    }),
             )
     }
 }
@@ -2,6 +2,7 @@ use gpui::SharedString;
 use serde::Deserialize;
 
 use crate::core::services::SessionStatus;
+use crate::schemas::workspace_schema::TaskChangeSummary;
 
 #[derive(Clone, Default, Deserialize)]
 pub struct AgentTask {
@@ -36,8 +37,8 @@ impl AgentTask {
         self
     }
 
-    /// Create a new task for a session
-    pub fn new_for_session(name: String, session_id: String) -> Self {
+    /// Create a new task for a session, with the changes tracked so far
+    pub fn new_for_session(name: String, session_id: String, changes: &TaskChangeSummary) -> Self {
         Self {
             name,
             task_type: "Default".to_string(),
@@ -51,6 +52,14 @@ impl AgentTask {
             add_new_code_lines_str: "+0".into(),
             delete_code_lines_str: "-0".into(),
         }
+        .with_changes(changes)
+    }
+
+    /// Take the line counts from a task's tracked changes
+    pub fn with_changes(mut self, changes: &TaskChangeSummary) -> Self {
+        self.add_new_code_lines = i16::try_from(changes.total_additions()).unwrap_or(i16::MAX);
+        self.delete_code_lines = i16::try_from(changes.total_deletions()).unwrap_or(i16::MAX);
+        self.prepare()
     }
 
     /// Update the subtitle with a message preview
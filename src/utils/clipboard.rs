@@ -1,6 +1,10 @@
+use std::sync::Arc;
+
 use agent_client_protocol::ImageContent;
 use gpui::{Image, ImageFormat};
 
+use crate::core::services::AgentService;
+
 pub async fn image_to_content(image: Image) -> anyhow::Result<(ImageContent, String)> {
     let temp_path = crate::utils::file::write_image_to_temp_file(&image).await?;
 
@@ -23,6 +27,23 @@ pub async fn image_to_content(image: Image) -> anyhow::Result<(ImageContent, Str
     Ok((image_content, filename))
 }
 
+/// Whether pasted images can be sent to an agent
+///
+/// Agents that haven't started yet are allowed, as prompts are checked
+/// again when they are sent.
+pub async fn agent_accepts_images(
+    agent_service: Option<Arc<AgentService>>,
+    agent_name: &str,
+) -> bool {
+    let Some(agent_service) = agent_service else {
+        return true;
+    };
+    agent_service
+        .get_agent_features(agent_name)
+        .await
+        .is_none_or(|features| features.images)
+}
+
 fn mime_type_for_format(format: ImageFormat) -> &'static str {
     match format {
         ImageFormat::Png => "image/png",
@@ -0,0 +1,301 @@
+use agent_client_protocol as acp;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, WindowExt, checkbox::Checkbox, dialog::DialogButtonProps, dock::DockPlacement,
+    label::Label, notification::Notification, v_flex,
+};
+use rust_i18n::t;
+use std::sync::Arc;
+
+use crate::{
+    AppState, CompareAgents, OpenComparison, PickComparisonWinner,
+    core::services::ComparisonRequest, panels::dock_panel::DockPanelContainer,
+};
+
+use super::session_actions::format_code_selection_as_context;
+use crate::workspace::DockWorkspace;
+
+impl DockWorkspace {
+    /// Handle CompareAgents action - pick the agents and start the comparison
+    pub(in crate::workspace) fn on_action_compare_agents(
+        &mut self,
+        action: &CompareAgents,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(workspace_service) = AppState::global(cx).workspace_service().cloned() else {
+            log::error!("WorkspaceService not initialized");
+            return;
+        };
+        let Some(agent_config_service) = AppState::global(cx).agent_config_service().cloned()
+        else {
+            log::error!("AgentConfigService not initialized");
+            return;
+        };
+
+        let action = action.clone();
+
+        cx.spawn_in(window, async move |_this, window| {
+            let workspace_id = match action.workspace_id.clone() {
+                Some(id) => Some(id),
+                None => workspace_service
+                    .get_active_workspace()
+                    .await
+                    .map(|ws| ws.id),
+            };
+            let Some(workspace_id) = workspace_id else {
+                _ = window.update(|window, cx| {
+                    struct NoWorkspaceForComparison;
+                    let note = Notification::error(t!("comparison.error.no_workspace").to_string())
+                        .id::<NoWorkspaceForComparison>();
+                    window.push_notification(note, cx);
+                });
+                return;
+            };
+
+            let agents: Vec<String> = agent_config_service
+                .list_agents()
+                .await
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+
+            _ = window.update(|window, cx| {
+                if agents.len() < 2 {
+                    struct NotEnoughAgents;
+                    let note =
+                        Notification::info(t!("comparison.error.not_enough_agents").to_string())
+                            .id::<NotEnoughAgents>();
+                    window.push_notification(note, cx);
+                    return;
+                }
+
+                open_agent_picker(workspace_id, action, agents, window, cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Handle OpenComparison action - show the candidates side by side
+    pub(in crate::workspace) fn on_action_open_comparison(
+        &mut self,
+        action: &OpenComparison,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let panel =
+            DockPanelContainer::panel_for_comparison(action.comparison_id.clone(), window, cx);
+
+        self.dock_area.update(cx, |dock_area, cx| {
+            dock_area.add_panel(Arc::new(panel), DockPlacement::Center, None, window, cx);
+        });
+    }
+
+    /// Handle PickComparisonWinner action - confirm, then merge the winner's branch
+    pub(in crate::workspace) fn on_action_pick_comparison_winner(
+        &mut self,
+        action: &PickComparisonWinner,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let action = action.clone();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .title(t!("comparison.dialog.winner.title").to_string())
+                .confirm()
+                .button_props(
+                    DialogButtonProps::default()
+                        .ok_text(t!("comparison.dialog.winner.ok").to_string())
+                        .cancel_text(t!("comparison.dialog.cancel").to_string()),
+                )
+                .on_ok({
+                    let action = action.clone();
+                    move |_, window, cx| {
+                        let Some(service) = AppState::global(cx).comparison_service().cloned()
+                        else {
+                            log::error!("ComparisonService not initialized");
+                            return true;
+                        };
+                        let action = action.clone();
+
+                        window
+                            .spawn(cx, async move |cx| {
+                                let result = service
+                                    .pick_winner(&action.comparison_id, &action.task_id)
+                                    .await;
+
+                                _ = cx.update(|window, cx| match result {
+                                    Ok(()) => {
+                                        struct ComparisonWinnerMerged;
+                                        let note = Notification::success(
+                                            t!("comparison.winner.merged").to_string(),
+                                        )
+                                        .id::<ComparisonWinnerMerged>();
+                                        window.push_notification(note, cx);
+                                    }
+                                    Err(e) => {
+                                        log::error!("Failed to pick comparison winner: {}", e);
+                                        struct ComparisonWinnerFailed;
+                                        let note = Notification::error(
+                                            t!("comparison.error.winner", error = e.to_string())
+                                                .to_string(),
+                                        )
+                                        .id::<ComparisonWinnerFailed>();
+                                        window.push_notification(note, cx);
+                                    }
+                                });
+                            })
+                            .detach();
+
+                        true
+                    }
+                })
+                .child(v_flex().w_full().p_4().child(Label::new(
+                    t!("comparison.dialog.winner.message").to_string(),
+                )))
+        });
+    }
+}
+
+/// Let the user choose which agents take part in the comparison
+fn open_agent_picker(
+    workspace_id: String,
+    action: CompareAgents,
+    agents: Vec<String>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let selected = cx.new(|_| agents.clone());
+    let use_worktrees = cx.new(|_| true);
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let mut content = v_flex().w_full().gap_3().p_4().child(
+            Label::new(t!("comparison.dialog.agents.hint").to_string())
+                .text_xs()
+                .text_color(cx.theme().muted_foreground),
+        );
+
+        for (idx, agent) in agents.iter().enumerate() {
+            content = content.child(
+                Checkbox::new(("comparison-agent", idx))
+                    .label(agent.clone())
+                    .checked(selected.read(cx).contains(agent))
+                    .on_click({
+                        let selected = selected.clone();
+                        let agent = agent.clone();
+                        move |checked, window, cx| {
+                            selected.update(cx, |selected, _| {
+                                selected.retain(|a| a != &agent);
+                                if *checked {
+                                    selected.push(agent.clone());
+                                }
+                            });
+                            window.refresh();
+                        }
+                    }),
+            );
+        }
+
+        content = content.child(
+            Checkbox::new("comparison-worktrees")
+                .label(t!("comparison.field.worktrees").to_string())
+                .checked(*use_worktrees.read(cx))
+                .on_click({
+                    let use_worktrees = use_worktrees.clone();
+                    move |checked, window, cx| {
+                        use_worktrees.update(cx, |value, _| *value = *checked);
+                        window.refresh();
+                    }
+                }),
+        );
+
+        dialog
+            .title(t!("comparison.dialog.agents.title").to_string())
+            .confirm()
+            .button_props(
+                DialogButtonProps::default()
+                    .ok_text(t!("comparison.dialog.agents.ok").to_string())
+                    .cancel_text(t!("comparison.dialog.cancel").to_string()),
+            )
+            .on_ok({
+                let workspace_id = workspace_id.clone();
+                let action = action.clone();
+                let agents = agents.clone();
+                let selected = selected.clone();
+                let use_worktrees = use_worktrees.clone();
+
+                move |_, window, cx| {
+                    // Keep the agents in list order rather than click order
+                    let selected: Vec<String> = agents
+                        .iter()
+                        .filter(|agent| selected.read(cx).contains(agent))
+                        .cloned()
+                        .collect();
+                    if selected.len() < 2 {
+                        log::warn!("Select at least two agents to compare");
+                        return false;
+                    }
+
+                    let Some(service) = AppState::global(cx).comparison_service().cloned() else {
+                        log::error!("ComparisonService not initialized");
+                        return true;
+                    };
+
+                    let request = ComparisonRequest {
+                        workspace_id: workspace_id.clone(),
+                        prompt: action.prompt.clone(),
+                        attachments: attachments_for(&action),
+                        agents: selected,
+                        mode: action.mode.clone(),
+                        use_worktrees: *use_worktrees.read(cx),
+                    };
+
+                    window
+                        .spawn(cx, async move |cx| {
+                            let result = service.start(request).await;
+
+                            _ = cx.update(|window, cx| match result {
+                                Ok(comparison) => {
+                                    window.dispatch_action(
+                                        Box::new(OpenComparison {
+                                            comparison_id: comparison.id,
+                                        }),
+                                        cx,
+                                    );
+                                }
+                                Err(e) => {
+                                    log::error!("Failed to start comparison: {}", e);
+                                    struct ComparisonStartFailed;
+                                    let note = Notification::error(
+                                        t!("comparison.error.start", error = e.to_string())
+                                            .to_string(),
+                                    )
+                                    .id::<ComparisonStartFailed>();
+                                    window.push_notification(note, cx);
+                                }
+                            });
+                        })
+                        .detach();
+
+                    true
+                }
+            })
+            .child(content)
+    });
+}
+
+/// Build the content blocks sent after the prompt (code selections, then images)
+fn attachments_for(action: &CompareAgents) -> Vec<acp::ContentBlock> {
+    action
+        .code_selections
+        .iter()
+        .map(|selection| format_code_selection_as_context(selection).into())
+        .chain(
+            action
+                .images
+                .iter()
+                .map(|(image, _filename)| acp::ContentBlock::Image(image.clone())),
+        )
+        .collect()
+}
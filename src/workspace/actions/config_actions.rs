@@ -1,4 +1,10 @@
+use agentx_types::{ConfigOverlay, UntrustedFolder};
 use gpui::*;
+use gpui_component::{
+    ActiveTheme, WindowExt, dialog::DialogButtonProps, label::Label, notification::Notification,
+    v_flex,
+};
+use rust_i18n::t;
 
 use crate::{
     AppState,
@@ -8,6 +14,70 @@ use crate::{
     },
 };
 
+/// Ask whether to trust the folder whose overlay defines an agent
+///
+/// The session is not retried: once trusted, the user starts it again.
+pub fn confirm_trust_folder(untrusted: UntrustedFolder, window: &mut Window, cx: &mut App) {
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let overlay = ConfigOverlay::path_in(&untrusted.folder);
+        let content = v_flex()
+            .w_full()
+            .gap_2()
+            .p_4()
+            .child(Label::new(
+                t!(
+                    "settings.layers.trust.prompt",
+                    agent = untrusted.agent.as_str(),
+                    path = overlay.display().to_string()
+                )
+                .to_string(),
+            ))
+            .child(
+                Label::new(t!("settings.layers.trust.hint").to_string())
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground),
+            );
+
+        dialog
+            .title(t!("settings.layers.trust.title").to_string())
+            .confirm()
+            .button_props(
+                DialogButtonProps::default()
+                    .ok_text(t!("settings.layers.trust.ok").to_string())
+                    .cancel_text(t!("settings.layers.trust.cancel").to_string()),
+            )
+            .on_ok({
+                let folder = untrusted.folder.clone();
+                move |_, window, cx| {
+                    let Some(service) = AppState::global(cx).agent_config_service().cloned() else {
+                        log::error!("AgentConfigService not initialized");
+                        return true;
+                    };
+                    let folder = folder.clone();
+                    window
+                        .spawn(cx, async move |cx| {
+                            let result = service.trust_folder(folder).await;
+                            _ = cx.update(|window, cx| {
+                                let note = match result {
+                                    Ok(()) => Notification::success(
+                                        t!("settings.layers.trust.done").to_string(),
+                                    ),
+                                    Err(e) => Notification::error(
+                                        t!("settings.layers.trust.error", error = e.to_string())
+                                            .to_string(),
+                                    ),
+                                };
+                                window.push_notification(note, cx);
+                            });
+                        })
+                        .detach();
+                    true
+                }
+            })
+            .child(content)
+    });
+}
+
 pub fn add_agent(action: &AddAgent, cx: &mut App) {
     let agent_config_service = match AppState::global(cx).agent_config_service() {
         Some(service) => service.clone(),
@@ -22,6 +92,9 @@ pub fn add_agent(action: &AddAgent, cx: &mut App) {
         command: action.command.clone(),
         args: action.args.clone(),
         env: action.env.clone(),
+        profiles: Default::default(),
+        cwd_policy: Default::default(),
+        node_version: None,
         nodejs_path: None,
     };
 
@@ -49,24 +122,40 @@ pub fn update_agent(action: &UpdateAgent, cx: &mut App) {
     };
 
     let name = action.name.clone();
-    let config = crate::core::config::AgentProcessConfig {
-        command: action.command.clone(),
-        args: action.args.clone(),
-        env: action.env.clone(),
-        nodejs_path: None,
-    };
+    let command = action.command.clone();
+    let args = action.args.clone();
+    let env = action.env.clone();
 
     let _ = cx
-        .spawn(
-            async move |_cx| match agent_config_service.update_agent(&name, config).await {
+        .spawn(async move |_cx| {
+            // The dialog doesn't edit profiles, the cwd policy or the Node.js
+            // requirement, keep them
+            let existing = agent_config_service.get_agent(&name).await;
+            let config = crate::core::config::AgentProcessConfig {
+                command,
+                args,
+                env,
+                profiles: existing
+                    .as_ref()
+                    .map(|config| config.profiles.clone())
+                    .unwrap_or_default(),
+                cwd_policy: existing
+                    .as_ref()
+                    .map(|config| config.cwd_policy)
+                    .unwrap_or_default(),
+                node_version: existing.and_then(|config| config.node_version),
+                nodejs_path: None,
+            };
+
+            match agent_config_service.update_agent(&name, config).await {
                 Ok(()) => {
                     log::info!("Successfully updated agent: {}", name);
                 }
                 Err(e) => {
                     log::error!("Failed to update agent '{}': {}", name, e);
                 }
-            },
-        )
+            }
+        })
         .detach();
 }
 
@@ -184,7 +273,7 @@ pub fn change_config_path(action: &ChangeConfigPath, cx: &mut App) {
 
     let config_result = std::fs::read_to_string(&new_path);
     match config_result {
-        Ok(json) => match serde_json::from_str::<crate::core::config::Config>(&json) {
+        Ok(json) => match crate::core::config::parse_config(&json) {
             Ok(_config) => {
                 log::info!("Config file validated successfully: {:?}", new_path);
 
@@ -1,5 +1,9 @@
+mod comparison_actions;
 mod config_actions;
 mod panel_actions;
+mod pipeline_actions;
+mod queue_actions;
 mod session_actions;
+mod template_actions;
 
 pub use config_actions::*;
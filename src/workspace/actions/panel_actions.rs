@@ -2,12 +2,16 @@ use gpui::*;
 use gpui_component::{
     WindowExt,
     dock::{DockItem, DockPlacement, Panel, PanelInfo, PanelState, PanelView, TabPanel},
+    input::Position,
+    notification::Notification,
 };
+use rust_i18n::t;
 use std::sync::Arc;
 
 use crate::{
-    AppState, ConversationPanel, OpenSessionManager, PanelAction, SessionManagerPanel,
-    SettingsPanel, ToggleDockToggleButton, TogglePanelVisible, WelcomePanel,
+    AppState, CodeEditorPanel, ConversationPanel, EventJournalPanel, OpenEventJournal,
+    OpenFileInEditor, OpenSessionManager, PanelAction, SearchInWorkspace, SearchPanel,
+    SessionManagerPanel, SettingsPanel, ToggleDockToggleButton, TogglePanelVisible, WelcomePanel,
     app::actions::{PanelCommand, PanelKind, Submit},
     panels::{
         DockPanel,
@@ -693,6 +697,179 @@ impl DockWorkspace {
         });
     }
 
+    /// The root item of a dock
+    fn dock_item(&self, placement: DockPlacement, cx: &App) -> Option<DockItem> {
+        let dock_area = self.dock_area.read(cx);
+        let dock = match placement {
+            DockPlacement::Center => return Some(dock_area.center().clone()),
+            DockPlacement::Left => dock_area.left_dock(),
+            DockPlacement::Right => dock_area.right_dock(),
+            DockPlacement::Bottom => dock_area.bottom_dock(),
+        };
+        dock.map(|dock| dock.read(cx).panel().clone())
+    }
+
+    fn open_dock(&mut self, placement: DockPlacement, window: &mut Window, cx: &mut Context<Self>) {
+        if placement == DockPlacement::Center {
+            return;
+        }
+        self.dock_area.update(cx, |dock_area, cx| {
+            if !dock_area.is_dock_open(placement, cx) {
+                dock_area.toggle_dock(placement, window, cx);
+            }
+        });
+    }
+
+    pub(in crate::workspace) fn on_action_search_in_workspace(
+        &mut self,
+        _: &SearchInWorkspace,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(item) = self.dock_item(DockPlacement::Left, cx)
+            && Self::activate_panel_by_klass(&item, SearchPanel::klass(), window, cx)
+        {
+            self.open_dock(DockPlacement::Left, window, cx);
+            return;
+        }
+
+        let panel = Arc::new(DockPanelContainer::panel::<SearchPanel>(window, cx));
+        self.dock_area.update(cx, |dock_area, cx| {
+            dock_area.add_panel(panel, DockPlacement::Left, None, window, cx);
+        });
+        self.open_dock(DockPlacement::Left, window, cx);
+    }
+
+    pub(in crate::workspace) fn on_action_open_event_journal(
+        &mut self,
+        _: &OpenEventJournal,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(item) = self.dock_item(DockPlacement::Bottom, cx)
+            && Self::activate_panel_by_klass(&item, EventJournalPanel::klass(), window, cx)
+        {
+            self.open_dock(DockPlacement::Bottom, window, cx);
+            return;
+        }
+
+        let panel = Arc::new(DockPanelContainer::panel::<EventJournalPanel>(window, cx));
+        self.dock_area.update(cx, |dock_area, cx| {
+            dock_area.add_panel(panel, DockPlacement::Bottom, None, window, cx);
+        });
+        self.open_dock(DockPlacement::Bottom, window, cx);
+    }
+
+    /// Activate the first code editor in a dock item and return it
+    fn activate_code_editor_in(
+        item: &DockItem,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Entity<CodeEditorPanel>> {
+        let panel = match item {
+            DockItem::Tabs { view, .. } => {
+                if !Self::activate_panel_by_klass(item, CodeEditorPanel::klass(), window, cx) {
+                    return None;
+                }
+                view.read(cx).active_panel(cx)?
+            }
+            DockItem::Split { items, .. } => {
+                return items
+                    .iter()
+                    .find_map(|item| Self::activate_code_editor_in(item, window, cx));
+            }
+            DockItem::Panel { view, .. } => {
+                if !Self::activate_panel_by_klass(item, CodeEditorPanel::klass(), window, cx) {
+                    return None;
+                }
+                view.clone()
+            }
+            DockItem::Tiles { .. } => return None,
+        };
+
+        Self::code_editor_of(panel.view(), cx)
+    }
+
+    fn code_editor_of(view: AnyView, cx: &App) -> Option<Entity<CodeEditorPanel>> {
+        view.downcast::<DockPanelContainer>()
+            .ok()?
+            .read(cx)
+            .agent_studio
+            .clone()?
+            .downcast::<CodeEditorPanel>()
+            .ok()
+    }
+
+    /// Handle OpenFileInEditor - open a file at a position in a code editor,
+    /// reusing an open editor when there is one
+    pub(in crate::workspace) fn on_action_open_file_in_editor(
+        &mut self,
+        action: &OpenFileInEditor,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut editor = None;
+        for placement in [
+            DockPlacement::Right,
+            DockPlacement::Center,
+            DockPlacement::Left,
+            DockPlacement::Bottom,
+        ] {
+            let Some(item) = self.dock_item(placement, cx) else {
+                continue;
+            };
+            if let Some(found) = Self::activate_code_editor_in(&item, window, cx) {
+                self.open_dock(placement, window, cx);
+                editor = Some(found);
+                break;
+            }
+        }
+
+        let editor = match editor {
+            Some(editor) => editor,
+            None => {
+                let working_directory = AppState::global(cx).current_working_dir().clone();
+                let container = DockPanelContainer::panel_for_code_editor_with_cwd(
+                    working_directory,
+                    window,
+                    cx,
+                );
+                let Some(editor) = Self::code_editor_of(container.clone().into(), cx) else {
+                    return;
+                };
+                self.dock_area.update(cx, |dock_area, cx| {
+                    dock_area.add_panel(
+                        Arc::new(container),
+                        DockPlacement::Right,
+                        None,
+                        window,
+                        cx,
+                    );
+                });
+                self.open_dock(DockPlacement::Right, window, cx);
+                editor
+            }
+        };
+
+        let position = Position::new(action.line, action.column);
+        if let Err(e) =
+            CodeEditorPanel::open_file_at(editor, action.path.clone(), Some(position), window, cx)
+        {
+            log::warn!("Failed to open {}: {}", action.path.display(), e);
+            struct OpenFileFailed;
+            let note = Notification::error(
+                t!(
+                    "code_editor.error.open",
+                    path = action.path.display().to_string(),
+                    error = e.to_string()
+                )
+                .to_string(),
+            )
+            .id::<OpenFileFailed>();
+            window.push_notification(note, cx);
+        }
+    }
+
     pub(in crate::workspace) fn show_welcome_panel(
         &mut self,
         workspace_id: Option<String>,
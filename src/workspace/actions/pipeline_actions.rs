@@ -0,0 +1,247 @@
+use gpui::{prelude::FluentBuilder as _, *};
+use gpui_component::{
+    ActiveTheme, WindowExt,
+    dialog::DialogButtonProps,
+    h_flex,
+    input::{Input, InputState},
+    label::Label,
+    notification::Notification,
+    v_flex,
+};
+use rust_i18n::t;
+
+use crate::{AppState, RunPipeline};
+use agentx_types::schemas::pipeline::PipelineDefinition;
+
+use crate::workspace::DockWorkspace;
+
+impl DockWorkspace {
+    /// Handle RunPipeline action - pick a pipeline, enter its input and start it
+    pub(in crate::workspace) fn on_action_run_pipeline(
+        &mut self,
+        action: &RunPipeline,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pipeline_service) = AppState::global(cx).pipeline_service().cloned() else {
+            log::error!("PipelineService not initialized");
+            return;
+        };
+        let Some(workspace_service) = AppState::global(cx).workspace_service().cloned() else {
+            log::error!("WorkspaceService not initialized");
+            return;
+        };
+
+        let workspace_id = action.workspace_id.clone();
+
+        cx.spawn_in(window, async move |_this, window| {
+            let workspace_id = match workspace_id {
+                Some(id) => Some(id),
+                None => workspace_service
+                    .get_active_workspace()
+                    .await
+                    .map(|ws| ws.id),
+            };
+            let Some(workspace_id) = workspace_id else {
+                _ = window.update(|window, cx| {
+                    struct NoWorkspaceForPipeline;
+                    let note = Notification::error(t!("pipeline.error.no_workspace").to_string())
+                        .id::<NoWorkspaceForPipeline>();
+                    window.push_notification(note, cx);
+                });
+                return;
+            };
+
+            let pipelines = pipeline_service.list_pipelines().await;
+
+            _ = window.update(|window, cx| {
+                if pipelines.is_empty() {
+                    struct NoPipelines;
+                    let note =
+                        Notification::info(t!("pipeline.empty").to_string()).id::<NoPipelines>();
+                    window.push_notification(note, cx);
+                    return;
+                }
+
+                open_pipeline_picker(workspace_id, pipelines, window, cx);
+            });
+        })
+        .detach();
+    }
+}
+
+/// Show the list of configured pipelines
+fn open_pipeline_picker(
+    workspace_id: String,
+    pipelines: Vec<(String, PipelineDefinition)>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let mut list = v_flex().w_full().gap_2().p_4();
+
+        for (idx, (name, pipeline)) in pipelines.iter().enumerate() {
+            list = list.child(
+                v_flex()
+                    .id(("pipeline-item", idx))
+                    .w_full()
+                    .gap_1()
+                    .p_3()
+                    .rounded(px(6.))
+                    .bg(cx.theme().secondary)
+                    .border_1()
+                    .border_color(cx.theme().border)
+                    .cursor_pointer()
+                    .hover(|s| s.bg(cx.theme().accent.opacity(0.3)))
+                    .on_click({
+                        let workspace_id = workspace_id.clone();
+                        let name = name.clone();
+                        let pipeline = pipeline.clone();
+                        move |_, window, cx| {
+                            window.close_dialog(cx);
+                            open_pipeline_run_dialog(
+                                workspace_id.clone(),
+                                name.clone(),
+                                pipeline.clone(),
+                                window,
+                                cx,
+                            );
+                        }
+                    })
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .justify_between()
+                            .child(
+                                Label::new(name.clone())
+                                    .text_sm()
+                                    .font_weight(FontWeight::SEMIBOLD),
+                            )
+                            .child(
+                                Label::new(
+                                    t!("pipeline.stage_count", count = pipeline.stages.len())
+                                        .to_string(),
+                                )
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground),
+                            ),
+                    )
+                    .when(!pipeline.description.is_empty(), |this| {
+                        this.child(
+                            Label::new(pipeline.description.clone())
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground),
+                        )
+                    })
+                    .child(
+                        Label::new(stage_summary(pipeline))
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground),
+                    ),
+            );
+        }
+
+        dialog
+            .title(t!("pipeline.dialog.pick.title").to_string())
+            .child(list)
+    });
+}
+
+/// Ask for the pipeline input and start the run
+fn open_pipeline_run_dialog(
+    workspace_id: String,
+    name: String,
+    pipeline: PipelineDefinition,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let input = cx.new(|cx| {
+        InputState::new(window, cx)
+            .multi_line(true)
+            .auto_grow(4, 12)
+            .placeholder(t!("pipeline.input.placeholder").to_string())
+    });
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let content = v_flex()
+            .w_full()
+            .gap_3()
+            .p_4()
+            .child(
+                Label::new(stage_summary(&pipeline))
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground),
+            )
+            .child(Input::new(&input));
+
+        dialog
+            .title(t!("pipeline.dialog.run.title", name = name).to_string())
+            .confirm()
+            .button_props(
+                DialogButtonProps::default()
+                    .ok_text(t!("pipeline.dialog.run.ok").to_string())
+                    .cancel_text(t!("pipeline.dialog.cancel").to_string()),
+            )
+            .on_ok({
+                let workspace_id = workspace_id.clone();
+                let name = name.clone();
+                let input = input.clone();
+
+                move |_, window, cx| {
+                    let text = input.read(cx).text().to_string().trim().to_string();
+                    if text.is_empty() {
+                        log::warn!("Pipeline input cannot be empty");
+                        return false;
+                    }
+
+                    let Some(service) = AppState::global(cx).pipeline_service().cloned() else {
+                        log::error!("PipelineService not initialized");
+                        return true;
+                    };
+                    let workspace_id = workspace_id.clone();
+                    let name = name.clone();
+
+                    window
+                        .spawn(cx, async move |cx| {
+                            let result = service.start(&workspace_id, &name, text).await;
+
+                            _ = cx.update(|window, cx| match result {
+                                Ok(run) => {
+                                    log::info!("Started pipeline '{}' (run {})", name, run.id);
+                                    struct PipelineStarted;
+                                    let note = Notification::success(
+                                        t!("pipeline.started", name = name).to_string(),
+                                    )
+                                    .id::<PipelineStarted>();
+                                    window.push_notification(note, cx);
+                                }
+                                Err(e) => {
+                                    log::error!("Failed to start pipeline '{}': {}", name, e);
+                                    struct PipelineStartFailed;
+                                    let note = Notification::error(
+                                        t!("pipeline.error.start", error = e.to_string())
+                                            .to_string(),
+                                    )
+                                    .id::<PipelineStartFailed>();
+                                    window.push_notification(note, cx);
+                                }
+                            });
+                        })
+                        .detach();
+
+                    true
+                }
+            })
+            .child(content)
+    });
+}
+
+/// Describe the stages of a pipeline, e.g. "plan (claude) → review (codex)"
+fn stage_summary(pipeline: &PipelineDefinition) -> String {
+    pipeline
+        .stages
+        .iter()
+        .map(|stage| format!("{} ({})", stage.name, stage.agent_name))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
@@ -0,0 +1,249 @@
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, IndexPath, WindowExt,
+    dialog::DialogButtonProps,
+    input::{Input, InputState},
+    label::Label,
+    notification::Notification,
+    select::{Select, SelectState},
+    v_flex,
+};
+use rust_i18n::t;
+
+use crate::{AppState, QueueTasks, schemas::workspace_schema::TaskQueueSettings};
+
+use crate::workspace::DockWorkspace;
+
+/// Longest task name taken from a prompt
+const MAX_TASK_NAME_CHARS: usize = 60;
+
+/// Mode recorded on queued tasks
+const DEFAULT_TASK_MODE: &str = "default";
+
+impl DockWorkspace {
+    /// Handle QueueTasks action - enter one prompt per line and queue a task for each
+    pub(in crate::workspace) fn on_action_queue_tasks(
+        &mut self,
+        action: &QueueTasks,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(workspace_service) = AppState::global(cx).workspace_service().cloned() else {
+            log::error!("WorkspaceService not initialized");
+            return;
+        };
+        let Some(agent_config_service) = AppState::global(cx).agent_config_service().cloned()
+        else {
+            log::error!("AgentConfigService not initialized");
+            return;
+        };
+
+        let workspace_id = action.workspace_id.clone();
+
+        cx.spawn_in(window, async move |_this, window| {
+            let workspace_id = match workspace_id {
+                Some(id) => Some(id),
+                None => workspace_service
+                    .get_active_workspace()
+                    .await
+                    .map(|ws| ws.id),
+            };
+            let Some(workspace_id) = workspace_id else {
+                _ = window.update(|window, cx| {
+                    struct NoWorkspaceForQueue;
+                    let note = Notification::error(t!("task_queue.error.no_workspace").to_string())
+                        .id::<NoWorkspaceForQueue>();
+                    window.push_notification(note, cx);
+                });
+                return;
+            };
+
+            let agents = agent_config_service.list_agent_variants().await;
+            let settings = workspace_service.get_queue_settings().await;
+
+            _ = window.update(|window, cx| {
+                if agents.is_empty() {
+                    struct NoAgentsForQueue;
+                    let note = Notification::info(t!("task_queue.error.no_agents").to_string())
+                        .id::<NoAgentsForQueue>();
+                    window.push_notification(note, cx);
+                    return;
+                }
+
+                open_queue_dialog(workspace_id, agents, settings, window, cx);
+            });
+        })
+        .detach();
+    }
+}
+
+/// Ask for the prompts, the agent and the queue limits
+fn open_queue_dialog(
+    workspace_id: String,
+    agents: Vec<String>,
+    settings: TaskQueueSettings,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let agent_select =
+        cx.new(|cx| SelectState::new(agents, Some(IndexPath::default()), window, cx));
+    let prompts = cx.new(|cx| {
+        InputState::new(window, cx)
+            .multi_line(true)
+            .auto_grow(4, 12)
+            .placeholder(t!("task_queue.prompts.placeholder").to_string())
+    });
+    let concurrency = cx.new(|cx| {
+        let mut state = InputState::new(window, cx);
+        state.set_value(settings.max_concurrent_per_agent.to_string(), window, cx);
+        state
+    });
+    let retries = cx.new(|cx| {
+        let mut state = InputState::new(window, cx);
+        state.set_value(settings.max_retries.to_string(), window, cx);
+        state
+    });
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let content = v_flex()
+            .w_full()
+            .gap_3()
+            .p_4()
+            .child(
+                Label::new(t!("task_queue.prompts.hint").to_string())
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground),
+            )
+            .child(Input::new(&prompts))
+            .child(
+                v_flex()
+                    .gap_2()
+                    .child(Label::new(t!("task_queue.agent").to_string()))
+                    .child(Select::new(&agent_select)),
+            )
+            .child(
+                v_flex()
+                    .gap_2()
+                    .child(Label::new(t!("task_queue.concurrency").to_string()))
+                    .child(Input::new(&concurrency)),
+            )
+            .child(
+                v_flex()
+                    .gap_2()
+                    .child(Label::new(t!("task_queue.retries").to_string()))
+                    .child(Input::new(&retries)),
+            );
+
+        dialog
+            .title(t!("task_queue.dialog.title").to_string())
+            .confirm()
+            .button_props(
+                DialogButtonProps::default()
+                    .ok_text(t!("task_queue.dialog.ok").to_string())
+                    .cancel_text(t!("task_queue.dialog.cancel").to_string()),
+            )
+            .on_ok({
+                let workspace_id = workspace_id.clone();
+                let settings = settings.clone();
+                let agent_select = agent_select.clone();
+                let prompts = prompts.clone();
+                let concurrency = concurrency.clone();
+                let retries = retries.clone();
+
+                move |_, window, cx| {
+                    let items: Vec<(String, String)> = prompts
+                        .read(cx)
+                        .text()
+                        .to_string()
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(|prompt| (task_name(prompt), prompt.to_string()))
+                        .collect();
+                    if items.is_empty() {
+                        log::warn!("Queued tasks need at least one prompt");
+                        return false;
+                    }
+                    let Some(agent_name) = agent_select.read(cx).selected_value().cloned() else {
+                        log::warn!("Queued tasks need an agent");
+                        return false;
+                    };
+                    let (Ok(max_concurrent), Ok(max_retries)) = (
+                        concurrency
+                            .read(cx)
+                            .text()
+                            .to_string()
+                            .trim()
+                            .parse::<usize>(),
+                        retries.read(cx).text().to_string().trim().parse::<u32>(),
+                    ) else {
+                        log::warn!("Queue limits must be whole numbers");
+                        return false;
+                    };
+
+                    let Some(service) = AppState::global(cx).workspace_service().cloned() else {
+                        log::error!("WorkspaceService not initialized");
+                        return true;
+                    };
+                    let workspace_id = workspace_id.clone();
+                    let mut settings = settings.clone();
+                    settings.max_concurrent_per_agent = max_concurrent.max(1);
+                    settings.max_retries = max_retries;
+
+                    window
+                        .spawn(cx, async move |cx| {
+                            let result = async {
+                                service.set_queue_settings(settings).await?;
+                                service
+                                    .enqueue_batch(
+                                        &workspace_id,
+                                        agent_name,
+                                        DEFAULT_TASK_MODE.to_string(),
+                                        items,
+                                        None,
+                                    )
+                                    .await
+                            }
+                            .await;
+
+                            _ = cx.update(|window, cx| match result {
+                                Ok(tasks) => {
+                                    struct TasksQueued;
+                                    let note = Notification::success(
+                                        t!("task_queue.queued", count = tasks.len()).to_string(),
+                                    )
+                                    .id::<TasksQueued>();
+                                    window.push_notification(note, cx);
+                                }
+                                Err(e) => {
+                                    log::error!("Failed to queue tasks: {}", e);
+                                    struct QueueTasksFailed;
+                                    let note = Notification::error(
+                                        t!("task_queue.error.queue", error = e.to_string())
+                                            .to_string(),
+                                    )
+                                    .id::<QueueTasksFailed>();
+                                    window.push_notification(note, cx);
+                                }
+                            });
+                        })
+                        .detach();
+
+                    true
+                }
+            })
+            .child(content)
+    });
+}
+
+/// Task name for a queued prompt: its start, cut at a word boundary
+fn task_name(prompt: &str) -> String {
+    if prompt.chars().count() <= MAX_TASK_NAME_CHARS {
+        return prompt.to_string();
+    }
+    let cut: String = prompt.chars().take(MAX_TASK_NAME_CHARS).collect();
+    let cut = cut
+        .rsplit_once(' ')
+        .map_or(cut.as_str(), |(start, _)| start);
+    format!("{}…", cut.trim_end())
+}
@@ -1,4 +1,5 @@
 use agent_client_protocol as acp;
+use agentx_types::UntrustedFolder;
 use gpui::*;
 use gpui_component::{
     WindowExt,
@@ -14,6 +15,7 @@ use crate::{
     panels::{DockPanel, dock_panel::DockPanelContainer},
 };
 
+use super::confirm_trust_folder;
 use crate::workspace::DockWorkspace;
 
 impl DockWorkspace {
@@ -81,6 +83,7 @@ impl DockWorkspace {
 
         let dock_area = self.dock_area.clone();
         let target_workspace_id = action.workspace_id.clone();
+        let working_dir = action.working_dir.clone();
 
         cx.spawn_in(window, async move |_this, window| {
             // Step 1: Get target workspace
@@ -122,7 +125,11 @@ impl DockWorkspace {
             };
 
             let workspace_id = workspace.id.clone();
-            let workspace_cwd = workspace.path.clone();
+            // A folder inside the workspace (e.g. a worktree or a package)
+            // runs the session, and its own config layers apply
+            let workspace_cwd = working_dir
+                .filter(|dir| dir.starts_with(&workspace.path))
+                .unwrap_or_else(|| workspace.path.clone());
 
             log::info!(
                 "Creating task in workspace: {} ({}), cwd: {:?}",
@@ -140,17 +147,44 @@ impl DockWorkspace {
                 );
                 ws.session_id
             } else {
-                let mcp_servers = if let Some(service) = agent_config_service {
-                    service
-                        .list_mcp_servers()
-                        .await
-                        .into_iter()
-                        .filter(|(_, config)| config.enabled)
-                        .map(|(name, config)| config.to_acp_mcp_server(name))
-                        .collect()
+                let effective = if let Some(service) = &agent_config_service {
+                    // Apply the `.agentx/config.json` overrides of the workspace
+                    // and of the folders down to the session's cwd
+                    let effective = service
+                        .effective_config(&workspace, Some(&workspace_cwd))
+                        .await;
+                    if let Err(e) = service.prepare_agent(&effective, &agent_name).await {
+                        log::error!("Failed to prepare agent '{}': {}", agent_name, e);
+                        _ = window.update(|window, cx| {
+                            if let Some(untrusted) = e.downcast_ref::<UntrustedFolder>() {
+                                confirm_trust_folder(untrusted.clone(), window, cx);
+                                return;
+                            }
+                            struct TaskCreationError;
+                            let note = Notification::error(format!(
+                                "Failed to create task: {}",
+                                e
+                            ))
+                            .id::<TaskCreationError>();
+                            window.push_notification(note, cx);
+                        });
+                        return;
+                    }
+                    Some(effective)
                 } else {
-                    Vec::new()
+                    None
                 };
+                // A profile agent only gets the MCP servers its profile allows
+                let mcp_servers = effective
+                    .as_ref()
+                    .map(|effective| {
+                        effective
+                            .mcp_servers_for(&agent_name)
+                            .into_iter()
+                            .map(|(name, config)| config.to_acp_mcp_server(name))
+                            .collect()
+                    })
+                    .unwrap_or_default();
 
                 log::info!(
                     "Creating new session for agent '{}' with cwd: {:?}",
@@ -172,6 +206,12 @@ impl DockWorkspace {
                             session_id,
                             agent_name
                         );
+                        if let (Some(service), Some(effective)) = (&agent_config_service, &effective)
+                        {
+                            service
+                                .apply_profile_defaults(effective, &agent_name, &session_id)
+                                .await;
+                        }
                         session_id
                     }
                     Err(e) => {
@@ -482,7 +522,7 @@ impl DockWorkspace {
 ///
 /// Produces a markdown-style code block with file path and line range metadata,
 /// suitable for inclusion as a `ContentBlock::Text` in the prompt.
-fn format_code_selection_as_context(selection: &AddCodeSelection) -> String {
+pub(super) fn format_code_selection_as_context(selection: &AddCodeSelection) -> String {
     let line_range = if selection.start_line == selection.end_line {
         format!("Line {}", selection.start_line)
     } else {
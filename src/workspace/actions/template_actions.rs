@@ -0,0 +1,521 @@
+use gpui::{prelude::FluentBuilder as _, *};
+use gpui_component::{
+    ActiveTheme, WindowExt,
+    checkbox::Checkbox,
+    dialog::DialogButtonProps,
+    h_flex,
+    input::{Input, InputState},
+    label::Label,
+    notification::Notification,
+    v_flex,
+};
+use rust_i18n::t;
+use std::collections::HashMap;
+
+use crate::{
+    AppState, EditTaskTemplate, NewTaskFromTemplate,
+    core::services::{ScopedTemplate, TemplateInput},
+};
+use agentx_types::schemas::task_template::{TaskTemplate, TemplateField, TemplateScope};
+
+use crate::workspace::DockWorkspace;
+
+impl DockWorkspace {
+    /// Handle NewTaskFromTemplate action - pick a template, fill in its fields and queue the task
+    pub(in crate::workspace) fn on_action_new_task_from_template(
+        &mut self,
+        action: &NewTaskFromTemplate,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(template_service) = AppState::global(cx).task_template_service().cloned() else {
+            log::error!("TaskTemplateService not initialized");
+            return;
+        };
+        let Some(workspace_service) = AppState::global(cx).workspace_service().cloned() else {
+            log::error!("WorkspaceService not initialized");
+            return;
+        };
+
+        let workspace_id = action.workspace_id.clone();
+        let selected_files = action.selected_files.clone();
+
+        cx.spawn_in(window, async move |_this, window| {
+            let workspace_id = match workspace_id {
+                Some(id) => Some(id),
+                None => workspace_service
+                    .get_active_workspace()
+                    .await
+                    .map(|ws| ws.id),
+            };
+            let Some(workspace_id) = workspace_id else {
+                _ = window.update(|window, cx| {
+                    struct NoWorkspaceForTemplate;
+                    let note =
+                        Notification::error(t!("task_template.error.no_workspace").to_string())
+                            .id::<NoWorkspaceForTemplate>();
+                    window.push_notification(note, cx);
+                });
+                return;
+            };
+
+            let templates = template_service.list_templates(Some(&workspace_id)).await;
+
+            _ = window.update(|window, cx| {
+                if templates.is_empty() {
+                    struct NoTaskTemplates;
+                    let note = Notification::info(t!("task_template.empty").to_string())
+                        .id::<NoTaskTemplates>();
+                    window.push_notification(note, cx);
+                    return;
+                }
+
+                open_template_picker(workspace_id, templates, selected_files, window, cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Handle EditTaskTemplate action - create or edit a task template
+    pub(in crate::workspace) fn on_action_edit_task_template(
+        &mut self,
+        action: &EditTaskTemplate,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        open_template_editor(action.clone(), window, cx);
+    }
+}
+
+/// Show the list of templates available in a workspace
+fn open_template_picker(
+    workspace_id: String,
+    templates: Vec<ScopedTemplate>,
+    selected_files: Vec<String>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let mut list = v_flex().w_full().gap_2().p_4();
+
+        for (idx, scoped) in templates.iter().enumerate() {
+            let scope_label = match scoped.scope {
+                TemplateScope::Global => t!("task_template.scope.global"),
+                TemplateScope::Workspace(_) => t!("task_template.scope.workspace"),
+            };
+
+            list = list.child(
+                v_flex()
+                    .id(("task-template-item", idx))
+                    .w_full()
+                    .gap_1()
+                    .p_3()
+                    .rounded(px(6.))
+                    .bg(cx.theme().secondary)
+                    .border_1()
+                    .border_color(cx.theme().border)
+                    .cursor_pointer()
+                    .hover(|s| s.bg(cx.theme().accent.opacity(0.3)))
+                    .on_click({
+                        let workspace_id = workspace_id.clone();
+                        let scoped = scoped.clone();
+                        let selected_files = selected_files.clone();
+                        move |_, window, cx| {
+                            window.close_dialog(cx);
+                            open_template_run_dialog(
+                                workspace_id.clone(),
+                                scoped.clone(),
+                                selected_files.clone(),
+                                window,
+                                cx,
+                            );
+                        }
+                    })
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .justify_between()
+                            .child(
+                                Label::new(scoped.name.clone())
+                                    .text_sm()
+                                    .font_weight(FontWeight::SEMIBOLD),
+                            )
+                            .child(
+                                Label::new(scope_label.to_string())
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground),
+                            ),
+                    )
+                    .when(!scoped.template.description.is_empty(), |this| {
+                        this.child(
+                            Label::new(scoped.template.description.clone())
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground),
+                        )
+                    }),
+            );
+        }
+
+        dialog
+            .title(t!("task_template.dialog.pick.title").to_string())
+            .child(list)
+    });
+}
+
+/// Ask for the template's user fields and queue the task
+fn open_template_run_dialog(
+    workspace_id: String,
+    scoped: ScopedTemplate,
+    selected_files: Vec<String>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let inputs: Vec<(TemplateField, Entity<InputState>)> = scoped
+        .template
+        .input_fields()
+        .into_iter()
+        .map(|field| {
+            let input = cx.new(|cx| {
+                let mut state =
+                    InputState::new(window, cx).placeholder(field.display_label().to_string());
+                if let Some(default) = &field.default {
+                    state.set_value(default.clone(), window, cx);
+                }
+                state
+            });
+            (field, input)
+        })
+        .collect();
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let template = &scoped.template;
+        let agent = template
+            .agent_name
+            .clone()
+            .unwrap_or_else(|| t!("task_template.value.default_agent").to_string());
+
+        let mut content = v_flex().w_full().gap_3().p_4().child(
+            Label::new(
+                t!(
+                    "task_template.summary",
+                    agent = agent,
+                    mode = template.mode.clone().unwrap_or_else(|| "-".to_string())
+                )
+                .to_string(),
+            )
+            .text_xs()
+            .text_color(cx.theme().muted_foreground),
+        );
+        if template.use_worktree {
+            content = content.child(
+                Label::new(t!("task_template.summary.worktree").to_string())
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground),
+            );
+        }
+        for (field, input) in &inputs {
+            content = content.child(
+                v_flex()
+                    .gap_2()
+                    .child(Label::new(field.display_label().to_string()))
+                    .child(Input::new(input)),
+            );
+        }
+
+        dialog
+            .title(t!("task_template.dialog.run.title", name = scoped.name).to_string())
+            .confirm()
+            .button_props(
+                DialogButtonProps::default()
+                    .ok_text(t!("task_template.dialog.run.ok").to_string())
+                    .cancel_text(t!("task_template.dialog.cancel").to_string()),
+            )
+            .on_ok({
+                let workspace_id = workspace_id.clone();
+                let scoped = scoped.clone();
+                let selected_files = selected_files.clone();
+                let inputs = inputs.clone();
+
+                move |_, window, cx| {
+                    let values: HashMap<String, String> = inputs
+                        .iter()
+                        .map(|(field, input)| {
+                            let value = input.read(cx).text().to_string().trim().to_string();
+                            (field.name.clone(), value)
+                        })
+                        .filter(|(_, value)| !value.is_empty())
+                        .collect();
+
+                    if let Some(missing) = inputs.iter().find(|(field, _)| {
+                        field.default.is_none() && !values.contains_key(&field.name)
+                    }) {
+                        log::warn!("Template field '{}' is required", missing.0.name);
+                        return false;
+                    }
+
+                    let Some(service) = AppState::global(cx).task_template_service().cloned()
+                    else {
+                        log::error!("TaskTemplateService not initialized");
+                        return true;
+                    };
+
+                    let input = TemplateInput {
+                        selected_files: selected_files.clone(),
+                        clipboard: cx.read_from_clipboard().and_then(|item| item.text()),
+                        values,
+                    };
+                    let workspace_id = workspace_id.clone();
+                    let scoped = scoped.clone();
+
+                    window
+                        .spawn(cx, async move |cx| {
+                            let result = service
+                                .instantiate(&workspace_id, &scoped.name, &scoped.template, input)
+                                .await;
+
+                            _ = cx.update(|window, cx| match result {
+                                Ok(task) => {
+                                    log::info!(
+                                        "Queued task {} from template '{}'",
+                                        task.id,
+                                        scoped.name
+                                    );
+                                    struct TemplateTaskQueued;
+                                    let note = Notification::success(
+                                        t!("task_template.queued", name = scoped.name).to_string(),
+                                    )
+                                    .id::<TemplateTaskQueued>();
+                                    window.push_notification(note, cx);
+                                }
+                                Err(e) => {
+                                    log::error!("Failed to create task from template: {}", e);
+                                    struct TemplateTaskFailed;
+                                    let note = Notification::error(
+                                        t!("task_template.error.create", error = e.to_string())
+                                            .to_string(),
+                                    )
+                                    .id::<TemplateTaskFailed>();
+                                    window.push_notification(note, cx);
+                                }
+                            });
+                        })
+                        .detach();
+
+                    true
+                }
+            })
+            .child(content)
+    });
+}
+
+/// Create or edit a template
+///
+/// New templates are saved to the workspace when "save to workspace" is
+/// checked, otherwise globally. Edited templates keep their scope.
+fn open_template_editor(action: EditTaskTemplate, window: &mut Window, cx: &mut App) {
+    let template = action.template.clone();
+    let is_new = action.name.is_none();
+
+    let new_input =
+        |value: Option<String>, placeholder: String, window: &mut Window, cx: &mut App| {
+            cx.new(|cx| {
+                let mut state = InputState::new(window, cx).placeholder(placeholder);
+                if let Some(value) = value.filter(|v| !v.is_empty()) {
+                    state.set_value(value, window, cx);
+                }
+                state
+            })
+        };
+
+    let name_input = new_input(
+        action.name.clone(),
+        t!("task_template.input.name.placeholder").to_string(),
+        window,
+        cx,
+    );
+    let desc_input = new_input(
+        Some(template.description.clone()),
+        t!("task_template.input.description.placeholder").to_string(),
+        window,
+        cx,
+    );
+    let agent_input = new_input(
+        template.agent_name.clone(),
+        t!("task_template.input.agent.placeholder").to_string(),
+        window,
+        cx,
+    );
+    let mode_input = new_input(
+        template.mode.clone(),
+        t!("task_template.input.mode.placeholder").to_string(),
+        window,
+        cx,
+    );
+    let mcp_input = new_input(
+        template
+            .mcp_servers
+            .as_ref()
+            .map(|servers| servers.join(", ")),
+        t!("task_template.input.mcp.placeholder").to_string(),
+        window,
+        cx,
+    );
+    let prompt_input = cx.new(|cx| {
+        let mut state = InputState::new(window, cx)
+            .multi_line(true)
+            .auto_grow(4, 12)
+            .placeholder(t!("task_template.input.prompt.placeholder").to_string());
+        state.set_value(template.prompt.clone(), window, cx);
+        state
+    });
+    let use_worktree = cx.new(|_| template.use_worktree);
+    let save_to_workspace = cx.new(|_| action.workspace_id.is_some());
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let field = |label: String, input: &Entity<InputState>| {
+            v_flex()
+                .gap_2()
+                .child(Label::new(label))
+                .child(Input::new(input))
+        };
+
+        let mut content = v_flex().w_full().gap_3().p_4();
+        if is_new {
+            content = content.child(field(
+                t!("task_template.field.name").to_string(),
+                &name_input,
+            ));
+        }
+        content = content
+            .child(field(
+                t!("task_template.field.description").to_string(),
+                &desc_input,
+            ))
+            .child(
+                h_flex()
+                    .gap_3()
+                    .child(
+                        field(t!("task_template.field.agent").to_string(), &agent_input).flex_1(),
+                    )
+                    .child(field(t!("task_template.field.mode").to_string(), &mode_input).flex_1()),
+            )
+            .child(field(t!("task_template.field.mcp").to_string(), &mcp_input))
+            .child(field(
+                t!("task_template.field.prompt").to_string(),
+                &prompt_input,
+            ))
+            .child(
+                Label::new(t!("task_template.field.prompt.help").to_string())
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground),
+            )
+            .child(
+                Checkbox::new("task-template-worktree")
+                    .label(t!("task_template.field.worktree").to_string())
+                    .checked(*use_worktree.read(cx))
+                    .on_click({
+                        let use_worktree = use_worktree.clone();
+                        move |checked, window, cx| {
+                            use_worktree.update(cx, |value, _| *value = *checked);
+                            window.refresh();
+                        }
+                    }),
+            );
+        if is_new && action.workspace_id.is_some() {
+            content = content.child(
+                Checkbox::new("task-template-workspace-scope")
+                    .label(t!("task_template.field.save_to_workspace").to_string())
+                    .checked(*save_to_workspace.read(cx))
+                    .on_click({
+                        let save_to_workspace = save_to_workspace.clone();
+                        move |checked, window, cx| {
+                            save_to_workspace.update(cx, |value, _| *value = *checked);
+                            window.refresh();
+                        }
+                    }),
+            );
+        }
+
+        let title = match &action.name {
+            Some(name) => t!("task_template.dialog.edit.title", name = name).to_string(),
+            None => t!("task_template.dialog.add.title").to_string(),
+        };
+
+        dialog
+            .title(title)
+            .confirm()
+            .button_props(
+                DialogButtonProps::default()
+                    .ok_text(t!("task_template.dialog.save").to_string())
+                    .cancel_text(t!("task_template.dialog.cancel").to_string()),
+            )
+            .on_ok({
+                let action = action.clone();
+                let name_input = name_input.clone();
+                let desc_input = desc_input.clone();
+                let agent_input = agent_input.clone();
+                let mode_input = mode_input.clone();
+                let mcp_input = mcp_input.clone();
+                let prompt_input = prompt_input.clone();
+                let use_worktree = use_worktree.clone();
+                let save_to_workspace = save_to_workspace.clone();
+
+                move |_, _window, cx| {
+                    let text = |input: &Entity<InputState>, cx: &App| {
+                        input.read(cx).text().to_string().trim().to_string()
+                    };
+                    let optional = |value: String| (!value.is_empty()).then_some(value);
+
+                    let name = action.name.clone().unwrap_or_else(|| text(&name_input, cx));
+                    let prompt = text(&prompt_input, cx);
+                    if name.is_empty() || prompt.is_empty() {
+                        log::warn!("Template name and prompt cannot be empty");
+                        return false;
+                    }
+
+                    let mcp_servers = optional(text(&mcp_input, cx)).map(|value| {
+                        value
+                            .split(',')
+                            .map(|s| s.trim().to_string())
+                            .filter(|s| !s.is_empty())
+                            .collect()
+                    });
+
+                    let template = TaskTemplate {
+                        description: text(&desc_input, cx),
+                        agent_name: optional(text(&agent_input, cx)),
+                        mode: optional(text(&mode_input, cx)),
+                        mcp_servers,
+                        prompt,
+                        use_worktree: *use_worktree.read(cx),
+                        fields: action.template.fields.clone(),
+                    };
+
+                    let scope = match (&action.scope, &action.workspace_id) {
+                        (Some(scope), _) => scope.clone(),
+                        (None, Some(workspace_id)) if *save_to_workspace.read(cx) => {
+                            TemplateScope::Workspace(workspace_id.clone())
+                        }
+                        _ => TemplateScope::Global,
+                    };
+
+                    let Some(service) = AppState::global(cx).task_template_service().cloned()
+                    else {
+                        log::error!("TaskTemplateService not initialized");
+                        return true;
+                    };
+
+                    cx.spawn(async move |_cx| {
+                        match service.save_template(&scope, name.clone(), template).await {
+                            Ok(_) => log::info!("Saved task template '{}'", name),
+                            Err(e) => log::error!("Failed to save task template: {}", e),
+                        }
+                    })
+                    .detach();
+
+                    true
+                }
+            })
+            .child(content)
+    });
+}
@@ -1,9 +1,10 @@
 use anyhow::{Context as _, Result};
 use gpui::*;
-use gpui_component::Root;
 use gpui_component::dock::{
     DockArea, DockAreaState, DockEvent, DockItem, DockPlacement, PanelState,
 };
+use gpui_component::{Root, WindowExt as _, notification::Notification};
+use rust_i18n::t;
 use smol::Timer;
 use std::{sync::Arc, time::Duration};
 
@@ -13,7 +14,10 @@ const EXCLUDED_PANELS: &[&str] = &["CodeEditorPanel", "ToolCallDetailPanel"];
 use crate::{
     AppSettings, AppTitleBar, CodeEditorPanel, ConversationPanel, SessionManagerPanel, TaskPanel,
     TerminalPanel,
-    core::updater::{UpdateCheckResult, UpdateManager},
+    core::{
+        event_bus::AgentConfigEvent,
+        updater::{UpdateCheckResult, UpdateManager},
+    },
     panels::dock_panel::DockPanelContainer,
 };
 
@@ -84,6 +88,8 @@ impl DockWorkspace {
         })
         .detach();
 
+        Self::notify_config_events(window, cx);
+
         cx.on_release(|this, cx| {
             this.flush_layout_state(cx);
             crate::themes::save_state(cx);
@@ -172,6 +178,47 @@ impl DockWorkspace {
         }
     }
 
+    /// Show a notification whenever config.json fails validation or its
+    /// plaintext secrets were moved into the vault
+    fn notify_config_events(window: &mut Window, cx: &mut Context<Self>) {
+        let event_hub = crate::AppState::global(cx).event_hub().clone();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        event_hub.subscribe_agent_config_updates(move |event| {
+            if matches!(
+                event,
+                AgentConfigEvent::ConfigValidated { error: Some(_) }
+                    | AgentConfigEvent::SecretsExternalized { .. }
+            ) {
+                let _ = tx.send(event.clone());
+            }
+        });
+
+        cx.spawn_in(window, async move |_this, window| {
+            while let Some(event) = rx.recv().await {
+                let result = window.update(|window, cx| {
+                    let note = match event {
+                        AgentConfigEvent::SecretsExternalized { vault_path } => Notification::info(
+                            t!("settings.secrets.externalized", path = vault_path).to_string(),
+                        ),
+                        AgentConfigEvent::ConfigValidated { error: Some(error) } => {
+                            Notification::error(
+                                t!("settings.agents.config.invalid", error = error.to_string())
+                                    .to_string(),
+                            )
+                        }
+                        _ => return,
+                    };
+                    window.push_notification(note, cx);
+                });
+                if result.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+    }
+
     /// Check for updates on startup if auto-check is enabled
     fn maybe_check_updates_on_startup(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         if self.update_checked_on_startup {
@@ -472,8 +519,18 @@ impl Render for DockWorkspace {
             .on_action(cx.listener(Self::on_action_toggle_dock_toggle_button))
             .on_action(cx.listener(Self::on_action_open_setting_panel))
             .on_action(cx.listener(Self::on_action_open_session_manager))
+            .on_action(cx.listener(Self::on_action_search_in_workspace))
+            .on_action(cx.listener(Self::on_action_open_event_journal))
+            .on_action(cx.listener(Self::on_action_open_file_in_editor))
             .on_action(cx.listener(Self::on_action_new_session_conversation_panel))
             .on_action(cx.listener(Self::on_action_create_task_from_welcome))
+            .on_action(cx.listener(Self::on_action_new_task_from_template))
+            .on_action(cx.listener(Self::on_action_edit_task_template))
+            .on_action(cx.listener(Self::on_action_compare_agents))
+            .on_action(cx.listener(Self::on_action_open_comparison))
+            .on_action(cx.listener(Self::on_action_pick_comparison_winner))
+            .on_action(cx.listener(Self::on_action_run_pipeline))
+            .on_action(cx.listener(Self::on_action_queue_tasks))
             .on_action(cx.listener(Self::on_action_send_message_to_session))
             .on_action(cx.listener(Self::on_action_cancel_session))
             .on_action(cx.listener(Self::on_action_open))
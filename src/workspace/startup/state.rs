@@ -3,7 +3,7 @@ use std::path::PathBuf;
 
 use gpui::*;
 
-use crate::core::config::{AgentProcessConfig, Config};
+use crate::core::config::AgentProcessConfig;
 
 #[derive(Clone, Debug)]
 pub(in crate::workspace) struct AgentChoice {
@@ -151,30 +151,19 @@ impl StartupState {
         HashMap<String, AgentProcessConfig>,
         Option<String>,
     ) {
-        let raw = match crate::assets::get_default_config() {
-            Some(raw) => raw,
-            None => {
-                return (
-                    Vec::new(),
-                    HashMap::new(),
-                    Some("Embedded config.json not found.".to_string()),
-                );
-            }
-        };
-
-        let config: Config = match serde_json::from_str(&raw) {
-            Ok(config) => config,
+        let catalog = match crate::core::config_manager::load_agent_catalog() {
+            Ok(catalog) => catalog,
             Err(err) => {
-                log::error!("Failed to parse embedded config.json: {}", err);
-                return (
-                    Vec::new(),
-                    HashMap::new(),
-                    Some(format!("Failed to parse embedded config.json: {}", err)),
-                );
+                log::error!("Failed to load agent catalog: {}", err);
+                return (Vec::new(), HashMap::new(), Some(err.to_string()));
             }
         };
 
-        let mut agent_entries: Vec<_> = config.agent_servers.into_iter().collect();
+        let mut agent_entries: Vec<_> = catalog
+            .agents
+            .iter()
+            .map(|agent| (agent.name.clone(), agent.default_config()))
+            .collect();
         agent_entries.sort_by(|a, b| a.0.cmp(&b.0));
 
         let mut agent_choices = Vec::new();